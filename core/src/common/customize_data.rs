@@ -32,7 +32,17 @@ pub struct CustomizeData {
     pub face_paint_color: u8,
 }
 
+/// The highest race ID, currently Viera.
+const MAX_RACE: u8 = 8;
+
 impl CustomizeData {
+    /// Whether the race, tribe and gender are a combination that can exist. Each race has two tribes, numbered in order.
+    pub fn has_valid_race(&self) -> bool {
+        (1..=MAX_RACE).contains(&self.race)
+            && self.subrace.div_ceil(2) == self.race
+            && self.gender <= 1
+    }
+
     pub fn to_json(&self) -> Value {
         json!([
             self.race.to_string(),
@@ -133,6 +143,49 @@ mod tests {
         assert_eq!(customize.face_paint_color, 5);
     }
 
+    #[test]
+    fn valid_race_combinations() {
+        // Hyur Highlander
+        let customize = CustomizeData {
+            race: 1,
+            subrace: 2,
+            gender: 1,
+            ..Default::default()
+        };
+        assert!(customize.has_valid_race());
+
+        // Elezen Wildwood
+        let customize = CustomizeData {
+            race: 2,
+            subrace: 3,
+            ..Default::default()
+        };
+        assert!(customize.has_valid_race());
+
+        // Highlander isn't an Elezen tribe
+        let customize = CustomizeData {
+            race: 2,
+            subrace: 2,
+            ..Default::default()
+        };
+        assert!(!customize.has_valid_race());
+
+        let customize = CustomizeData {
+            race: 9,
+            subrace: 17,
+            ..Default::default()
+        };
+        assert!(!customize.has_valid_race());
+
+        let customize = CustomizeData {
+            race: 1,
+            subrace: 1,
+            gender: 2,
+            ..Default::default()
+        };
+        assert!(!customize.has_valid_race());
+    }
+
     #[test]
     fn roundtrip_customize_data() {
         let json = "[\"1\",\"1\",\"1\",\"46\",\"2\",\"2\",\"122\",\"0\",\"0\",\"137\",\"98\",\"0\",\"1\",\"0\",\"2\",\"137\",\"5\",\"5\",\"0\",\"131\",\"169\",\"0\",\"0\",\"100\",\"1\",\"5\"]";
@@ -62,9 +62,18 @@ pub const DEAD_FADE_OUT_TIME: Duration = Duration::from_secs(8);
 /// Time until a dead actor despawns while faded away. Estimated from retail.
 pub const DEAD_DESPAWN_TIME: Duration = Duration::from_secs(2);
 
+/// The Weakness status effect, gained after returning to your home point.
+pub const WEAKNESS_STATUS_ID: u16 = 43;
+
+/// How long Weakness lasts, in seconds.
+pub const WEAKNESS_DURATION: f32 = 100.0;
+
 /// Maximum number of seconds until rested EXP is full. This is 7 days.
 pub const MAXIMUM_RESTED_EXP: i32 = 604800;
 
+/// The level cap for the current expansion.
+pub const MAXIMUM_LEVEL: u16 = 100;
+
 /// Number of seconds between an enemy's auto attacks. No idea how close this is to retail.
 pub const ENEMY_AUTO_ATTACK_RATE: i64 = 3;
 
@@ -519,7 +528,10 @@ impl diesel::deserialize::FromSql<diesel::sql_types::Integer, diesel::sqlite::Sq
 #[binrw]
 #[brw(little)]
 #[brw(repr = u16)]
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Display, Deserialize, Serialize)]
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Display, Deserialize, Serialize, FromRepr,
+)]
+#[repr(u16)]
 pub enum ContainerType {
     #[default]
     Inventory0 = 0,
@@ -676,6 +688,8 @@ pub enum LogMessageType {
     PlayerAlreadyFriend = 0x138, // That player is already a friend or has been sent a request. TODO: unclear if this one is sent by the server or the client keeps track of that itself
     PlayerAlreadyInAnotherParty = 0x146, // That player is already in another party.
     UnableToAcceptLSInvite = 0x1DD, // Unable to accept linkshell invite.
+    ItemObtained = 0x2EE,        // You obtained a <item name>.
+    ItemsObtained = 0x2EF,       // You obtained <quantity> <item name>.
     ItemBought = 0x697,
     ItemSold = 0x698,
     ItemBoughtBack = 0x699,
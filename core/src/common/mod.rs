@@ -65,6 +65,20 @@ pub fn determine_initial_starting_zone(citystate_id: u8) -> u16 {
     }
 }
 
+/// Gets the zone and position a brand new character is placed at, unless `starting_position` overrides it. Without an override, the position is later decided by their city-state's pop range.
+pub fn determine_initial_placement(
+    citystate_id: u8,
+    starting_position: Option<(u16, Position)>,
+) -> (u16, Position) {
+    match starting_position {
+        Some(starting_position) => starting_position,
+        None => (
+            determine_initial_starting_zone(citystate_id),
+            Position::default(),
+        ),
+    }
+}
+
 /// Gets the homepoint for a given city-state id.
 pub fn determine_initial_homepoint(citystate_id: u8) -> u8 {
     match citystate_id {
@@ -183,6 +197,16 @@ pub fn adjust_quest_id(quest_id: u32) -> u32 {
     quest_id.saturating_sub(65536)
 }
 
+/// Cuts `text` down to at most `max_length` bytes, without splitting a character in half.
+pub fn truncate_at_char_boundary(text: &str, max_length: usize) -> &str {
+    let mut end = text.len().min(max_length);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    &text[..end]
+}
+
 #[macro_export]
 macro_rules! web_static_dir {
     ($rel_path:literal) => {
@@ -242,6 +266,21 @@ mod tests {
         assert_eq!(value_to_flag_byte_index_value(64), (1, 8));
     }
 
+    #[test]
+    fn test_initial_placement() {
+        // Gridania, and also where CNJ, ARC and LNC start
+        assert_eq!(
+            determine_initial_placement(2, None),
+            (183, Position::default())
+        );
+
+        let starting_position = (132, Position(glam::Vec3::new(1.0, 2.0, 3.0)));
+        assert_eq!(
+            determine_initial_placement(2, Some(starting_position)),
+            starting_position
+        );
+    }
+
     #[test]
     fn test_max_ex_levels() {
         assert_eq!(calculate_max_level(0), 50); // A Realm Reborn
@@ -252,6 +291,14 @@ mod tests {
         assert_eq!(calculate_max_level(5), 100); // Dawntrail
     }
 
+    #[test]
+    fn test_truncate_at_char_boundary() {
+        assert_eq!(truncate_at_char_boundary("Kawari", 10), "Kawari");
+        assert_eq!(truncate_at_char_boundary("Kawari", 3), "Kaw");
+        // "é" is two bytes, so cutting at 2 would land in the middle of it
+        assert_eq!(truncate_at_char_boundary("aé", 2), "a");
+    }
+
     #[test]
     fn test_euler_to_direction() {
         assert_eq!(euler_to_direction((0.0, 0.0, 0.0)), 0.0);
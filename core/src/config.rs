@@ -6,6 +6,12 @@ use std::{
 use physis::Language;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    common::{Position, RECEIVE_BUFFER_SIZE},
+    ipc::zone::GameMasterRank,
+    packet::CompressionType,
+};
+
 fn default_listen_address() -> String {
     "0.0.0.0".to_string()
 }
@@ -306,6 +312,43 @@ impl WebConfig {
     }
 }
 
+/// Algorithms available for compressing packets sent to clients.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum PacketCompressionMode {
+    /// Oodle, which is what retail uses.
+    #[default]
+    Oodle,
+    /// ZLib, which the client also understands.
+    ZLib,
+}
+
+/// An item given to every new character.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct StartingItem {
+    /// Index into the Item Excel sheet.
+    pub id: u32,
+    /// How many of this item to give.
+    pub quantity: u32,
+}
+
+/// Where new characters are placed instead of their city-state.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct StartingPosition {
+    /// Index into the TerritoryType Excel sheet.
+    pub zone_id: u16,
+    /// Where in the zone they appear.
+    pub position: Position,
+}
+
+/// A service account that's granted a GM rank whenever it logs in.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct GmAccount {
+    /// The service account's id.
+    pub service_account_id: u64,
+    /// Which rank all of its characters have, e.g. `GameMaster` or `Debug`.
+    pub rank: GameMasterRank,
+}
+
 /// Configuration for the world server.
 #[derive(Serialize, Deserialize)]
 pub struct WorldConfig {
@@ -333,7 +376,16 @@ pub struct WorldConfig {
     #[serde(default = "WorldConfig::default_packet_compression")]
     pub enable_packet_compression: bool,
 
-    /// Default message received when logging into the world.
+    /// Which algorithm to compress packets with, if compression is enabled. Use ZLib if Oodle isn't available in your build.
+    #[serde(default)]
+    pub packet_compression_mode: PacketCompressionMode,
+
+    /// If set, every packet sent and received by the world server is appended to this file for debugging.
+    #[serde(default)]
+    pub packet_capture_path: String,
+
+    /// Default message received when logging into the world. `{name}` is replaced with the character's name, and each line is sent separately.
+    /// Leave empty to not send anything.
     #[serde(default = "WorldConfig::default_login_message")]
     pub login_message: String,
 
@@ -354,8 +406,83 @@ pub struct WorldConfig {
     pub exp_bonus: bool,
 
     /// The language to read game data as, should have no effect on regular gameplay but definitely does affect a lot of debug/GM commands.
+    /// Falls back to English if the game data doesn't contain Excel sheets in this language.
     #[serde(default = "WorldConfig::default_language")]
     pub language: String,
+
+    /// How long (in seconds) it takes for a defeated enemy to respawn in the overworld. Set to zero to disable respawning.
+    #[serde(default = "WorldConfig::default_enemy_respawn_time")]
+    pub enemy_respawn_time: u64,
+
+    /// How far (in yalms) /say and custom emotes can be heard.
+    #[serde(default = "WorldConfig::default_say_radius")]
+    pub say_radius: f32,
+
+    /// How far (in yalms) /yell can be heard. Set to zero to reach the entire zone.
+    #[serde(default = "WorldConfig::default_yell_radius")]
+    pub yell_radius: f32,
+
+    /// How far (in yalms) /shout can be heard. Set to zero to reach the entire zone.
+    #[serde(default = "WorldConfig::default_shout_radius")]
+    pub shout_radius: f32,
+
+    /// How much gil teleporting to an aetheryte costs. Set to zero to make teleporting free.
+    #[serde(default = "WorldConfig::default_teleport_cost")]
+    pub teleport_cost: u32,
+
+    /// Multiplies all EXP gained by players. Set to zero to stop players from gaining EXP.
+    #[serde(default = "WorldConfig::default_exp_multiplier")]
+    pub exp_multiplier: f32,
+
+    /// Items placed into the inventory of newly created characters, in addition to their class and racial gear.
+    #[serde(default)]
+    pub starting_items: Vec<StartingItem>,
+
+    /// How much gil newly created characters start with.
+    #[serde(default)]
+    pub starting_gil: u32,
+
+    /// Where newly created characters first appear, overriding their city-state. Useful for test servers that want to skip the opening!
+    #[serde(default)]
+    pub starting_position: Option<StartingPosition>,
+
+    /// How long (in seconds) a client can go without sending anything before it's disconnected.
+    #[serde(default = "WorldConfig::default_keep_alive_timeout")]
+    pub keep_alive_timeout: u64,
+
+    /// The most movement updates sent per second for each actor. Set to zero to send every update.
+    #[serde(default = "WorldConfig::default_movement_updates_per_second")]
+    pub movement_updates_per_second: u32,
+
+    /// The furthest (in yalms) another actor can be before it's despawned for a player, regardless of their client's view distance. Set to zero to only use the client's setting.
+    #[serde(default = "WorldConfig::default_max_spawn_distance")]
+    pub max_spawn_distance: f32,
+
+    /// The fastest (in yalms per second) a player can move before they're snapped back to where they were. GMs are exempt. Set to zero to trust all movement.
+    #[serde(default = "WorldConfig::default_max_movement_speed")]
+    pub max_movement_speed: f32,
+
+    /// How many bytes are read at once when waiting for a response from the world server. Larger responses are still reassembled from multiple reads.
+    #[serde(default = "WorldConfig::default_receive_buffer_size")]
+    pub receive_buffer_size: usize,
+
+    /// Shared secret the other servers send along with custom IPC, which the world server checks before doing anything. Segments with a missing or wrong token are rejected.
+    /// This is unset by default, and all custom IPC (such as creating characters) is rejected until it's configured. Pick something nobody else can guess!
+    #[serde(default = "WorldConfig::default_custom_ipc_token")]
+    pub custom_ipc_token: u64,
+
+    /// Whether players are told in chat when an action or status effect has no script, instead of it silently doing nothing. Useful when testing!
+    #[serde(default)]
+    pub verbose_scripting: bool,
+
+    /// Service accounts whose characters are made GMs when they log in. Once any are listed, every other account logs in without a GM rank.
+    /// If this is left empty, the ranks stored in the database are used instead.
+    #[serde(default)]
+    pub gm_accounts: Vec<GmAccount>,
+
+    /// Zones whose layer data is read when the server starts, instead of when the first player enters them.
+    #[serde(default)]
+    pub preload_zones: Vec<u16>,
 }
 
 impl Default for WorldConfig {
@@ -368,12 +495,32 @@ impl Default for WorldConfig {
             world_id: Self::default_world_id(),
             enable_packet_obsfucation: Self::default_packet_obsfucation(),
             enable_packet_compression: Self::default_packet_compression(),
+            packet_compression_mode: PacketCompressionMode::default(),
+            packet_capture_path: String::default(),
             login_message: Self::default_login_message(),
             generate_navmesh: Self::default_generate_navmesh(),
             active_festivals: Self::default_active_festivals(),
             accept_new_characters: Self::default_accept_new_characters(),
             exp_bonus: Self::default_exp_bonus(),
             language: Self::default_language(),
+            enemy_respawn_time: Self::default_enemy_respawn_time(),
+            say_radius: Self::default_say_radius(),
+            yell_radius: Self::default_yell_radius(),
+            shout_radius: Self::default_shout_radius(),
+            teleport_cost: Self::default_teleport_cost(),
+            exp_multiplier: Self::default_exp_multiplier(),
+            starting_items: Vec::default(),
+            starting_gil: 0,
+            starting_position: None,
+            keep_alive_timeout: Self::default_keep_alive_timeout(),
+            movement_updates_per_second: Self::default_movement_updates_per_second(),
+            max_spawn_distance: Self::default_max_spawn_distance(),
+            max_movement_speed: Self::default_max_movement_speed(),
+            receive_buffer_size: Self::default_receive_buffer_size(),
+            custom_ipc_token: Self::default_custom_ipc_token(),
+            verbose_scripting: false,
+            gm_accounts: Vec::default(),
+            preload_zones: Vec::default(),
         }
     }
 }
@@ -403,6 +550,18 @@ impl WorldConfig {
         true
     }
 
+    /// Returns the compression type used for packets sent to clients.
+    pub fn packet_compression_type(&self) -> CompressionType {
+        if !self.enable_packet_compression {
+            return CompressionType::Uncompressed;
+        }
+
+        match self.packet_compression_mode {
+            PacketCompressionMode::Oodle => CompressionType::Oodle,
+            PacketCompressionMode::ZLib => CompressionType::ZLib,
+        }
+    }
+
     fn default_login_message() -> String {
         "Welcome to Kawari!".to_string()
     }
@@ -427,6 +586,54 @@ impl WorldConfig {
         "en".to_string()
     }
 
+    fn default_enemy_respawn_time() -> u64 {
+        30
+    }
+
+    fn default_say_radius() -> f32 {
+        20.0
+    }
+
+    fn default_yell_radius() -> f32 {
+        0.0
+    }
+
+    fn default_shout_radius() -> f32 {
+        0.0
+    }
+
+    fn default_teleport_cost() -> u32 {
+        100
+    }
+
+    fn default_exp_multiplier() -> f32 {
+        1.0
+    }
+
+    fn default_keep_alive_timeout() -> u64 {
+        60
+    }
+
+    fn default_movement_updates_per_second() -> u32 {
+        10
+    }
+
+    fn default_max_spawn_distance() -> f32 {
+        0.0
+    }
+
+    fn default_max_movement_speed() -> f32 {
+        30.0 // Well above running or flying on a mount
+    }
+
+    fn default_receive_buffer_size() -> usize {
+        RECEIVE_BUFFER_SIZE
+    }
+
+    fn default_custom_ipc_token() -> u64 {
+        0 // Unset, so custom IPC is rejected until one is configured
+    }
+
     pub fn language(&self) -> Language {
         // TODO: possibly de-duplicate this in Physis?
         match self.language.as_str() {
@@ -454,6 +661,21 @@ impl WorldConfig {
             self.port,
         ))
     }
+
+    /// The GM rank `service_account_id` should log in with, or None if `gm_accounts` isn't used.
+    pub fn gm_rank_for_account(&self, service_account_id: u64) -> Option<GameMasterRank> {
+        if self.gm_accounts.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.gm_accounts
+                .iter()
+                .find(|account| account.service_account_id == service_account_id)
+                .map(|account| account.rank)
+                .unwrap_or_default(),
+        )
+    }
 }
 
 /// Configuration for the launcher server.
@@ -632,3 +854,27 @@ pub fn get_config() -> Config {
         Config::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gm_rank_for_account() {
+        let mut config = WorldConfig::default();
+        assert_eq!(config.gm_rank_for_account(1), None);
+
+        config.gm_accounts.push(GmAccount {
+            service_account_id: 1,
+            rank: GameMasterRank::GameMaster,
+        });
+        assert_eq!(
+            config.gm_rank_for_account(1),
+            Some(GameMasterRank::GameMaster)
+        );
+        assert_eq!(
+            config.gm_rank_for_account(2),
+            Some(GameMasterRank::NormalUser)
+        );
+    }
+}
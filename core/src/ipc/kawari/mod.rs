@@ -3,15 +3,58 @@ use kawari_core_macro::opcode_data;
 
 use crate::{
     common::{
-        CHAR_NAME_MAX_LENGTH, ObjectId, read_bool_from, read_string, write_bool_as, write_string,
+        CHAR_NAME_MAX_LENGTH, ObjectId, read_bool_from, read_string, timestamp_secs, write_bool_as,
+        write_string,
     },
     ipc::lobby::CharacterDetails,
     opcodes::CustomIpcType,
-    packet::{IpcSegment, ServerlessIpcSegmentHeader},
+    packet::{IpcSegment, IpcSegmentHeader},
 };
 
-pub type CustomIpcSegment =
-    IpcSegment<ServerlessIpcSegmentHeader<CustomIpcType>, CustomIpcType, CustomIpcData>;
+pub type CustomIpcSegment = IpcSegment<CustomIpcSegmentHeader, CustomIpcType, CustomIpcData>;
+
+/// Only used between our own servers. Same size as the other IPC headers, but carries the shared token instead of padding.
+#[binrw]
+#[derive(Debug, Clone)]
+#[brw(magic = 0x14u16)]
+pub struct CustomIpcSegmentHeader {
+    /// The opcode for this segment.
+    pub op_code: CustomIpcType,
+    /// The timestamp of this packet in seconds since UNIX epoch.
+    pub timestamp: u32,
+    /// The shared secret from the config, zero if missing.
+    pub token: u64,
+}
+
+impl Default for CustomIpcSegmentHeader {
+    fn default() -> Self {
+        Self {
+            op_code: CustomIpcType::default(),
+            timestamp: timestamp_secs(),
+            token: 0,
+        }
+    }
+}
+
+impl IpcSegmentHeader<CustomIpcType> for CustomIpcSegmentHeader {
+    fn from_opcode(opcode: CustomIpcType) -> Self {
+        Self {
+            op_code: opcode,
+            ..Default::default()
+        }
+    }
+
+    fn opcode(&self) -> &CustomIpcType {
+        &self.op_code
+    }
+}
+
+impl CustomIpcSegment {
+    /// Returns true if this segment carries `token`. A missing token is never accepted.
+    pub fn has_valid_token(&self, token: u64) -> bool {
+        self.header.token != 0 && self.header.token == token
+    }
+}
 
 #[opcode_data(CustomIpcType)]
 #[binrw]
@@ -119,4 +162,24 @@ mod tests {
     fn custom_ipc_sizes() {
         test_opcodes::<CustomIpcSegment>();
     }
+
+    #[test]
+    fn test_accepted_token() {
+        let mut segment = CustomIpcSegment::new(CustomIpcData::RequestFullCharacterList {});
+        segment.header.token = 1234;
+
+        assert!(segment.has_valid_token(1234));
+    }
+
+    #[test]
+    fn test_rejected_token() {
+        let mut segment = CustomIpcSegment::new(CustomIpcData::RequestFullCharacterList {});
+        assert!(!segment.has_valid_token(1234));
+
+        // a missing token isn't accepted, even if the config ends up with one too
+        assert!(!segment.has_valid_token(0));
+
+        segment.header.token = 4321;
+        assert!(!segment.has_valid_token(1234));
+    }
 }
@@ -1,11 +1,21 @@
 use crate::{
-    common::{read_string, value_to_flag_byte_index_value, write_string},
+    common::{
+        read_string, truncate_at_char_boundary, value_to_flag_byte_index_value, write_string,
+    },
     constants::AVAILABLE_CLASSJOBS,
     ipc::zone::{GrandCompany, OnlineStatusMask, SocialListUILanguages},
 };
 use binrw::binrw;
 use bitflags::bitflags;
 
+/// The most bytes a search comment can take up, leaving room for the null terminator.
+pub const SEARCH_COMMENT_MAX_LENGTH: usize = 59;
+
+/// Cuts `comment` down to `SEARCH_COMMENT_MAX_LENGTH` bytes, without splitting any characters.
+pub fn truncate_search_comment(comment: &str) -> String {
+    truncate_at_char_boundary(comment, SEARCH_COMMENT_MAX_LENGTH).to_string()
+}
+
 #[binrw]
 #[brw(little)]
 #[derive(Clone, Default, Debug)]
@@ -150,4 +160,20 @@ mod tests {
         let mask: [u8; 8] = [16, 0, 0, 0, 0, 0, 0, 0];
         assert_eq!(SearchUIClassJobMask::from(mask).mask(), vec![4]);
     }
+
+    #[test]
+    fn test_truncate_search_comment() {
+        assert_eq!(
+            truncate_search_comment("Looking for a static!"),
+            "Looking for a static!"
+        );
+        assert_eq!(
+            truncate_search_comment(&"a".repeat(100)).len(),
+            SEARCH_COMMENT_MAX_LENGTH
+        );
+
+        // Each of these takes up three bytes, and can't be split in half
+        let truncated = truncate_search_comment(&"あ".repeat(30));
+        assert_eq!(truncated, "あ".repeat(19));
+    }
 }
@@ -1,4 +1,5 @@
 use binrw::binrw;
+use serde::{Deserialize, Serialize};
 use strum_macros::{Display, EnumIter, FromRepr};
 
 use crate::common::{
@@ -85,7 +86,9 @@ pub enum ObjectKind {
 #[brw(little)]
 #[brw(repr = u8)]
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, Default, PartialEq, Display, EnumIter, FromRepr)]
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Display, EnumIter, FromRepr, Serialize, Deserialize,
+)]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 #[cfg_attr(
     feature = "server",
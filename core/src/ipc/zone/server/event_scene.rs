@@ -95,6 +95,9 @@ pub struct EventScene {
 }
 
 impl EventScene {
+    /// The most parameters a scene can be played with.
+    pub const MAX_PARAMS: usize = 255;
+
     pub fn package(&self) -> Option<ServerZoneIpcSegment> {
         match self.params.len() {
             0..=2 => Some(ServerZoneIpcSegment::new(ServerZoneIpcData::EventScene2 {
@@ -118,7 +121,7 @@ impl EventScene {
             65..=128 => Some(ServerZoneIpcSegment::new(
                 ServerZoneIpcData::EventScene128 { data: self.clone() },
             )),
-            129..=255 => Some(ServerZoneIpcSegment::new(
+            129..=Self::MAX_PARAMS => Some(ServerZoneIpcSegment::new(
                 ServerZoneIpcData::EventScene255 { data: self.clone() },
             )),
             _ => None,
@@ -0,0 +1,251 @@
+use std::{
+    fs::File,
+    io::{Cursor, Write},
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use binrw::{BinRead, BinWrite, binrw};
+
+use crate::common::timestamp_msecs;
+
+use super::{ConnectionType, parse_packet_header};
+
+/// The file we're currently capturing packets to, if capturing was started.
+/// Every connection writes to it, so it's locked to keep their packets from interleaving.
+static CAPTURE_FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Which way a captured packet was travelling.
+#[binrw]
+#[brw(repr = u8)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PacketDirection {
+    /// Sent from the client to the server.
+    Serverbound = 0,
+    /// Sent from the server to the client.
+    Clientbound = 1,
+}
+
+/// A single packet in a capture file. These are simply written one after another.
+#[binrw]
+#[brw(little)]
+#[derive(Debug)]
+pub struct CapturedPacket {
+    /// When this packet was sent or received, in milliseconds since the UNIX epoch.
+    pub timestamp: u64,
+    pub direction: PacketDirection,
+    pub connection_type: ConnectionType,
+    #[bw(calc = data.len() as u32)]
+    size: u32,
+    /// The raw bytes of the packet, including its header.
+    #[br(count = size)]
+    pub data: Vec<u8>,
+}
+
+/// Starts capturing every packet sent or received by this process to `path`. The file is appended to if it already exists.
+pub fn start_packet_capture(path: &str) -> std::io::Result<()> {
+    let file = File::options().create(true).append(true).open(path)?;
+    if CAPTURE_FILE.set(Mutex::new(file)).is_err() {
+        tracing::warn!("Packet capture was already started, ignoring {path}.");
+    }
+
+    Ok(())
+}
+
+/// Writes this packet to the capture file, if capturing is enabled.
+pub(crate) fn capture_packet(direction: PacketDirection, data: &[u8]) {
+    let Some(file) = CAPTURE_FILE.get() else {
+        return;
+    };
+
+    let encoded = encode_captured_packet(direction, data);
+    let mut file = file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Err(err) = file.write_all(&encoded) {
+        tracing::warn!("Failed to capture packet: {err}");
+    }
+}
+
+fn encode_captured_packet(direction: PacketDirection, data: &[u8]) -> Vec<u8> {
+    let packet = CapturedPacket {
+        timestamp: timestamp_msecs(),
+        direction,
+        connection_type: parse_packet_header(data).connection_type,
+        data: data.to_vec(),
+    };
+
+    let mut cursor = Cursor::new(Vec::new());
+    packet.write(&mut cursor).unwrap();
+
+    cursor.into_inner()
+}
+
+/// Reads back the packets stored in a capture file. Trailing data that can't be read (e.g. from the server stopping mid-write) is ignored.
+pub fn read_packet_capture(data: &[u8]) -> Vec<CapturedPacket> {
+    let mut cursor = Cursor::new(data);
+    let mut packets = Vec::new();
+
+    while (cursor.position() as usize) < data.len() {
+        match CapturedPacket::read(&mut cursor) {
+            Ok(packet) => packets.push(packet),
+            Err(err) => {
+                tracing::warn!("Stopped reading packet capture early: {err}");
+                break;
+            }
+        }
+    }
+
+    packets
+}
+
+/// Options for replaying a packet capture back to a client.
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayOptions {
+    /// Multiplier for the original timing between packets. A speed of zero pauses the replay.
+    pub speed: f32,
+    /// Whether to start over again once the last packet is sent.
+    pub loop_forever: bool,
+    /// How far into the capture to begin replaying from.
+    pub start_offset: Duration,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            loop_forever: false,
+            start_offset: Duration::ZERO,
+        }
+    }
+}
+
+/// Figures out when each clientbound packet in a capture should be sent, relative to the start of the replay.
+/// If the replay is paused, nothing is scheduled.
+pub fn replay_schedule<'a>(
+    packets: &'a [CapturedPacket],
+    options: &ReplayOptions,
+) -> Vec<(Duration, &'a CapturedPacket)> {
+    if options.speed <= 0.0 {
+        return Vec::new();
+    }
+
+    let Some(first_timestamp) = packets.first().map(|packet| packet.timestamp) else {
+        return Vec::new();
+    };
+
+    packets
+        .iter()
+        .filter(|packet| packet.direction == PacketDirection::Clientbound)
+        .filter_map(|packet| {
+            let elapsed = Duration::from_millis(packet.timestamp.saturating_sub(first_timestamp));
+            let elapsed = elapsed.checked_sub(options.start_offset)?;
+
+            Some((elapsed.div_f32(options.speed), packet))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ipc::kawari::CustomIpcSegment,
+        packet::{
+            CompressionType, ConnectionState, PacketHeader, PacketSegment, SegmentData,
+            SegmentType, compression::compress, parse_packet,
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_capture_round_trip() {
+        let mut state = ConnectionState::None;
+
+        let segments: Vec<PacketSegment<CustomIpcSegment>> = (0..2)
+            .map(|id| PacketSegment {
+                segment_type: SegmentType::KeepAliveResponse,
+                data: SegmentData::KeepAliveResponse { id, timestamp: 0 },
+                ..Default::default()
+            })
+            .collect();
+
+        let (data, uncompressed_size) =
+            compress(&mut state, &CompressionType::Uncompressed, &segments);
+        let header = PacketHeader {
+            size: (std::mem::size_of::<PacketHeader>() + data.len()) as u32,
+            connection_type: ConnectionType::Zone,
+            segment_count: segments.len() as u16,
+            uncompressed_size: uncompressed_size as u32,
+            ..Default::default()
+        };
+
+        let mut packet = Vec::new();
+        header.write_le(&mut Cursor::new(&mut packet)).unwrap();
+        packet.extend_from_slice(&data);
+
+        let mut capture = encode_captured_packet(PacketDirection::Clientbound, &packet);
+        capture.extend(encode_captured_packet(
+            PacketDirection::Serverbound,
+            &packet,
+        ));
+
+        let captured = read_packet_capture(&capture);
+        assert_eq!(captured.len(), 2);
+        assert_eq!(captured[0].direction, PacketDirection::Clientbound);
+        assert_eq!(captured[1].direction, PacketDirection::Serverbound);
+        assert_eq!(captured[0].connection_type, ConnectionType::Zone);
+
+        let parsed = parse_packet::<CustomIpcSegment>(&captured[0].data, &mut state);
+        assert_eq!(parsed.len(), segments.len());
+    }
+
+    #[test]
+    fn test_replay_schedule() {
+        let packet_at = |timestamp, direction| CapturedPacket {
+            timestamp,
+            direction,
+            connection_type: ConnectionType::Zone,
+            data: Vec::new(),
+        };
+        let packets = vec![
+            packet_at(1000, PacketDirection::Clientbound),
+            packet_at(1200, PacketDirection::Serverbound),
+            packet_at(1500, PacketDirection::Clientbound),
+            packet_at(3000, PacketDirection::Clientbound),
+        ];
+
+        // At 2x, the original timing is halved and packets from the client are skipped
+        let options = ReplayOptions {
+            speed: 2.0,
+            ..Default::default()
+        };
+        let delays: Vec<Duration> = replay_schedule(&packets, &options)
+            .iter()
+            .map(|(delay, _)| *delay)
+            .collect();
+        assert_eq!(
+            delays,
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(250),
+                Duration::from_millis(1000)
+            ]
+        );
+
+        // Starting later skips the earlier packets
+        let options = ReplayOptions {
+            start_offset: Duration::from_millis(500),
+            ..Default::default()
+        };
+        let schedule = replay_schedule(&packets, &options);
+        assert_eq!(schedule.len(), 2);
+        assert_eq!(schedule[0].0, Duration::ZERO);
+        assert_eq!(schedule[1].0, Duration::from_millis(1500));
+
+        // A speed of zero pauses
+        let options = ReplayOptions {
+            speed: 0.0,
+            ..Default::default()
+        };
+        assert!(replay_schedule(&packets, &options).is_empty());
+    }
+}
@@ -1,5 +1,5 @@
 use binrw::binrw;
-use std::io::Cursor;
+use std::io::{Cursor, Read, Write};
 
 use binrw::{BinRead, BinResult};
 
@@ -10,7 +10,7 @@ use super::{ReadWriteIpcSegment, SegmentData, parsing::ConnectionState};
 /// Various types of compression available for packets.
 #[binrw]
 #[brw(repr = u8)]
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum CompressionType {
     /// The packet data is uncompressed.
     #[default]
@@ -35,7 +35,12 @@ pub(crate) fn decompress<T: ReadWriteIpcSegment>(
 
     let data = match header.compression_type {
         CompressionType::Uncompressed => data,
-        CompressionType::ZLib => unimplemented!(),
+        CompressionType::ZLib => {
+            let mut decompressed = Vec::with_capacity(header.uncompressed_size as usize);
+            flate2::read::ZlibDecoder::new(data.as_slice()).read_to_end(&mut decompressed)?;
+
+            decompressed
+        }
         CompressionType::Oodle => {
             let ConnectionState::Zone {
                 serverbound_oodle, ..
@@ -50,7 +55,7 @@ pub(crate) fn decompress<T: ReadWriteIpcSegment>(
         }
     };
 
-    if header.compression_type == CompressionType::Oodle {
+    if header.compression_type != CompressionType::Uncompressed {
         assert_eq!(
             data.len(),
             header.uncompressed_size as usize,
@@ -160,7 +165,13 @@ pub(crate) fn compress<T: ReadWriteIpcSegment>(
 
     match compression_type {
         CompressionType::Uncompressed => (segments_buffer, segments_buffer_len),
-        CompressionType::ZLib => unimplemented!(),
+        CompressionType::ZLib => {
+            let mut encoder =
+                flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&segments_buffer).unwrap();
+
+            (encoder.finish().unwrap(), segments_buffer_len)
+        }
         CompressionType::Oodle => {
             let ConnectionState::Zone {
                 clientbound_oodle, ..
@@ -178,3 +189,51 @@ pub(crate) fn compress<T: ReadWriteIpcSegment>(
         }
     }
 }
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use binrw::BinWrite;
+
+    use crate::{
+        ipc::kawari::CustomIpcSegment,
+        packet::{SegmentType, parse_packet},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_zlib_round_trip() {
+        let mut state = ConnectionState::None;
+
+        let segments: Vec<PacketSegment<CustomIpcSegment>> = (0..3)
+            .map(|id| PacketSegment {
+                segment_type: SegmentType::KeepAliveRequest,
+                data: SegmentData::KeepAliveRequest { id, timestamp: 0 },
+                ..Default::default()
+            })
+            .collect();
+
+        let (data, uncompressed_size) = compress(&mut state, &CompressionType::ZLib, &segments);
+
+        let header = PacketHeader {
+            size: (std::mem::size_of::<PacketHeader>() + data.len()) as u32,
+            segment_count: segments.len() as u16,
+            compression_type: CompressionType::ZLib,
+            uncompressed_size: uncompressed_size as u32,
+            ..Default::default()
+        };
+
+        let mut buffer = Vec::new();
+        header.write_le(&mut Cursor::new(&mut buffer)).unwrap();
+        buffer.extend_from_slice(&data);
+
+        let parsed = parse_packet::<CustomIpcSegment>(&buffer, &mut state);
+        assert_eq!(parsed.len(), segments.len());
+        for (expected_id, segment) in parsed.iter().enumerate() {
+            let SegmentData::KeepAliveRequest { id, .. } = segment.data else {
+                panic!("Expected a keep alive request!");
+            };
+            assert_eq!(id, expected_id as u32);
+        }
+    }
+}
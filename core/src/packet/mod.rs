@@ -19,6 +19,15 @@ pub use ipc::{
 /// Bindings for Oodle network compression.
 pub mod oodle;
 
+/// Capturing packets to disk for debugging.
+#[cfg(feature = "server")]
+mod capture;
+#[cfg(feature = "server")]
+pub use capture::{
+    CapturedPacket, PacketDirection, ReplayOptions, read_packet_capture, replay_schedule,
+    start_packet_capture,
+};
+
 /// Send packet helpers.
 #[cfg(feature = "server")]
 mod send_helpers;
@@ -26,4 +35,4 @@ mod send_helpers;
 pub use send_helpers::{send_custom_world_packet, send_keep_alive, send_packet};
 
 mod scrambler;
-pub use scrambler::{ScramblerKeyGenerator, ScramblerKeys, scramble_packet};
+pub use scrambler::{ScramblerKeyGenerator, ScramblerKeys, scramble_packet, unscramble_packet};
@@ -222,6 +222,9 @@ pub fn parse_packet<T: ReadWriteIpcSegment>(
     data: &[u8],
     state: &mut ConnectionState,
 ) -> Vec<PacketSegment<T>> {
+    #[cfg(feature = "server")]
+    super::capture::capture_packet(super::capture::PacketDirection::Serverbound, data);
+
     let mut cursor = Cursor::new(data);
 
     match Packet::read_le_args(&mut cursor, (state,)) {
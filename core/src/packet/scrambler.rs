@@ -140,6 +140,21 @@ where
     }
 }
 
+unsafe fn unscramble_sub<T: Copy + Sized>(data: &mut [u8], offset: usize, key: T)
+where
+    Wrapping<T>: std::ops::Sub<Wrapping<T>, Output = Wrapping<T>>,
+{
+    unsafe {
+        let ptr = data.as_mut_ptr().add(offset);
+
+        let ptr_casted = ptr as *mut T;
+        let wrapped_key = Wrapping(key);
+        let wrapped_value = Wrapping(*ptr_casted);
+
+        *ptr_casted = (wrapped_value - wrapped_key).0;
+    }
+}
+
 unsafe fn unscramble_xor<T: Copy + Sized>(data: &mut [u8], offset: usize, key: T)
 where
     Wrapping<T>: std::ops::BitXor<Wrapping<T>, Output = Wrapping<T>>,
@@ -158,20 +173,46 @@ where
 /// Scrambles the packet in just the right ways.
 /// Not the greatest thing I ever implemented, it just copies what Unscrambler does. There might be a better way of doing this.
 pub fn scramble_packet(opcode_name: &str, base_key: u8, opcode_based_key: i32, data: &mut [u8]) {
+    apply_scrambling(opcode_name, base_key, opcode_based_key, data, false);
+}
+
+/// Reverses `scramble_packet`, given the same keys. Only the server obsfucates packets, but this is useful for reading our own.
+pub fn unscramble_packet(opcode_name: &str, base_key: u8, opcode_based_key: i32, data: &mut [u8]) {
+    apply_scrambling(opcode_name, base_key, opcode_based_key, data, true);
+}
+
+fn apply_scrambling(
+    opcode_name: &str,
+    base_key: u8,
+    opcode_based_key: i32,
+    data: &mut [u8],
+    reverse: bool,
+) {
+    // Adding is reversed by subtracting the same key, while XOR is its own inverse.
+    macro_rules! add {
+        ($t:ty, $offset:expr, $key:expr) => {
+            if reverse {
+                unscramble_sub::<$t>(data, $offset, $key)
+            } else {
+                unscramble_add::<$t>(data, $offset, $key)
+            }
+        };
+    }
+
     unsafe {
         match opcode_name {
             "SpawnPlayer" => {
                 // Content ID
-                unscramble_add::<u64>(data, 24, base_key as u64);
+                add!(u64, 24, base_key as u64);
                 // Current World ID
-                unscramble_add::<u16>(data, 36, base_key as u16);
+                add!(u16, 36, base_key as u16);
                 // Home World ID
-                unscramble_add::<u16>(data, 38, base_key as u16);
+                add!(u16, 38, base_key as u16);
 
                 // Name
                 let name_offset = 610;
                 for i in 0..CHAR_NAME_MAX_LENGTH {
-                    unscramble_add::<u8>(data, name_offset + i, base_key);
+                    add!(u8, name_offset + i, base_key);
                 }
 
                 // Equipment model IDs
@@ -184,15 +225,15 @@ pub fn scramble_packet(opcode_name: &str, base_key: u8, opcode_based_key: i32, d
             }
             "SpawnNpc" => {
                 // BNPC Base ID
-                unscramble_add::<u32>(data, 80, base_key as u32);
+                add!(u32, 80, base_key as u32);
                 // BNPC Name ID
-                unscramble_add::<u32>(data, 84, base_key as u32);
+                add!(u32, 84, base_key as u32);
                 // Unused, seems to be a level ID
-                unscramble_add::<u32>(data, 88, base_key as u32);
+                add!(u32, 88, base_key as u32);
                 // Companion Owner ID
-                unscramble_add::<u32>(data, 96, base_key as u32);
+                add!(u32, 96, base_key as u32);
                 // Event ID
-                unscramble_add::<u32>(data, 100, base_key as u32);
+                add!(u32, 100, base_key as u32);
 
                 // Tether ID
                 unscramble_xor::<u32>(data, 108, opcode_based_key as u32);
@@ -201,7 +242,7 @@ pub fn scramble_packet(opcode_name: &str, base_key: u8, opcode_based_key: i32, d
                 let op_offset = 168;
                 for i in 0..30 {
                     let offset = op_offset + i * 22;
-                    unscramble_add::<u16>(data, offset, base_key as u16);
+                    add!(u16, offset, base_key as u16);
                 }
             }
             "Equip" => {
@@ -216,14 +257,14 @@ pub fn scramble_packet(opcode_name: &str, base_key: u8, opcode_based_key: i32, d
                 let op_offset = 36;
                 for i in 0..30 {
                     let offset = op_offset + i * 12;
-                    unscramble_add::<u16>(data, offset, base_key as u16);
+                    add!(u16, offset, base_key as u16);
                 }
             }
             "StatusEffectList3" => {
                 let op_offset = 16;
                 for i in 0..30 {
                     let offset = op_offset + i * 12;
-                    unscramble_add::<u16>(data, offset, base_key as u16);
+                    add!(u16, offset, base_key as u16);
                 }
             }
             _ => {}
@@ -260,4 +301,16 @@ mod tests {
         }
         assert_eq!(bytes, [0, 0, 0, 0, 0, 0, 0, 0]);
     }
+
+    #[test]
+    fn test_scramble_round_trip() {
+        let original: Vec<u8> = (0..1024).map(|i| (i % 251) as u8).collect();
+
+        let mut data = original.clone();
+        scramble_packet("SpawnPlayer", 123, -456789, &mut data);
+        assert_ne!(data, original);
+
+        unscramble_packet("SpawnPlayer", 123, -456789, &mut data);
+        assert_eq!(data, original);
+    }
 }
@@ -1,4 +1,4 @@
-use std::io::Cursor;
+use std::{io::Cursor, net::SocketAddr};
 
 use binrw::BinWrite;
 use tokio::{
@@ -6,14 +6,14 @@ use tokio::{
     net::TcpStream,
 };
 
-use crate::{
-    common::RECEIVE_BUFFER_SIZE, common::timestamp_msecs, config::get_config,
-    ipc::kawari::CustomIpcSegment,
-};
+use crate::{common::timestamp_msecs, config::get_config, ipc::kawari::CustomIpcSegment};
 
 use super::{
     CompressionType, ConnectionState, ConnectionType, PacketHeader, PacketSegment,
-    ReadWriteIpcSegment, SegmentData, SegmentType, compression::compress, parse_packet,
+    ReadWriteIpcSegment, SegmentData, SegmentType,
+    capture::{PacketDirection, capture_packet},
+    compression::compress,
+    parse_packet, parse_packet_header,
 };
 
 pub async fn send_packet<T: ReadWriteIpcSegment>(
@@ -42,6 +42,8 @@ pub async fn send_packet<T: ReadWriteIpcSegment>(
 
     let buffer = cursor.into_inner();
 
+    capture_packet(PacketDirection::Clientbound, &buffer);
+
     if let Err(e) = socket.write_all(&buffer).await {
         tracing::warn!("Failed to send packet: {e}");
     }
@@ -70,13 +72,29 @@ pub async fn send_keep_alive<T: ReadWriteIpcSegment>(
 }
 
 /// Sends a custom IPC packet to the world server, meant for private server-to-server communication.
-/// Returns the first custom IPC segment returned.
-pub async fn send_custom_world_packet(segment: CustomIpcSegment) -> Option<CustomIpcSegment> {
+/// The token from the config is included in the segment, otherwise the world server would reject it.
+/// Returns the first custom IPC segment returned, or None if the world server didn't respond with one.
+pub async fn send_custom_world_packet(
+    mut segment: CustomIpcSegment,
+) -> Result<Option<CustomIpcSegment>, std::io::Error> {
     let config = get_config();
 
-    let addr = config.world.get_public_socketaddr();
+    segment.header.token = config.world.custom_ipc_token;
+
+    send_custom_packet(
+        config.world.get_public_socketaddr(),
+        config.world.receive_buffer_size,
+        segment,
+    )
+    .await
+}
 
-    let mut stream = TcpStream::connect(addr).await.ok()?;
+async fn send_custom_packet(
+    addr: SocketAddr,
+    receive_buffer_size: usize,
+    segment: CustomIpcSegment,
+) -> Result<Option<CustomIpcSegment>, std::io::Error> {
+    let mut stream = TcpStream::connect(addr).await?;
 
     let mut packet_state = ConnectionState::None;
 
@@ -95,17 +113,88 @@ pub async fn send_custom_world_packet(segment: CustomIpcSegment) -> Option<Custo
     )
     .await;
 
-    // read response
-    let mut buf = vec![0; RECEIVE_BUFFER_SIZE];
-    let n = stream.read(&mut buf).await.expect("Failed to read data!");
-    if n != 0 {
-        let segments = parse_packet::<CustomIpcSegment>(&buf[..n], &mut packet_state);
+    // read response, which may be split across multiple reads
+    let mut buf = vec![0; receive_buffer_size.max(1)];
+    let mut data = Vec::new();
+    let packet_len = loop {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            if data.is_empty() {
+                return Ok(None);
+            }
+
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "World server closed the connection before sending the whole response",
+            ));
+        }
+
+        data.extend_from_slice(&buf[..n]);
+        if let Some(packet_len) = complete_packet_len(&data) {
+            break packet_len;
+        }
+    };
+
+    let segments = parse_packet::<CustomIpcSegment>(&data[..packet_len], &mut packet_state);
 
-        return match &segments[0].data {
-            SegmentData::KawariIpc(data) => Some(data.clone()),
-            _ => None,
-        };
+    Ok(match segments.first().map(|segment| &segment.data) {
+        Some(SegmentData::KawariIpc(data)) => Some(data.clone()),
+        _ => None,
+    })
+}
+
+/// Returns the size of the first packet in `data` if all of it has been received, otherwise None.
+fn complete_packet_len(data: &[u8]) -> Option<usize> {
+    let header_size = std::mem::size_of::<PacketHeader>();
+    if data.len() < header_size {
+        return None;
     }
 
-    None
+    let packet_len = (parse_packet_header(data).size as usize).max(header_size);
+    (data.len() >= packet_len).then_some(packet_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ipc::kawari::CustomIpcData;
+
+    #[tokio::test]
+    async fn test_connection_failure() {
+        // Grab a free port, and then make sure nothing is listening on it
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let segment = CustomIpcSegment::new(CustomIpcData::RequestFullCharacterList {});
+        let result = send_custom_packet(addr, 1024, segment).await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_reassemble_split_response() {
+        let header = PacketHeader {
+            size: 64,
+            ..Default::default()
+        };
+
+        let mut cursor = Cursor::new(Vec::new());
+        header.write_le(&mut cursor).unwrap();
+        let mut packet = cursor.into_inner();
+        packet.resize(64, 0xAB);
+
+        // The first read only has part of the header, then part of the body
+        let mut data = Vec::new();
+        data.extend_from_slice(&packet[..10]);
+        assert_eq!(complete_packet_len(&data), None);
+
+        data.extend_from_slice(&packet[10..50]);
+        assert_eq!(complete_packet_len(&data), None);
+
+        // And the second read completes it
+        data.extend_from_slice(&packet[50..]);
+        assert_eq!(complete_packet_len(&data), Some(64));
+        assert_eq!(&data[..64], packet.as_slice());
+    }
 }
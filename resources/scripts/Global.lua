@@ -37,6 +37,16 @@ function printf(player, fmt_str, ...)
     end
 end
 
+-- Returns a table describing the item (name, price_mid, price_low, item_level, stack_size and equip_category), or nil if it doesn't exist.
+function get_item_info(id)
+    return GAME_DATA:get_item_info(id)
+end
+
+-- Returns a list of every item sold in this gil shop, each described like get_item_info.
+function get_shop_items(shop_id)
+    return GAME_DATA:get_gilshop_items(shop_id)
+end
+
 function has_value(tab, val)
     for index, value in ipairs(tab) do
         if value == val then
@@ -42,45 +42,45 @@ GM_JUMP = 606
 GM_WORLD = 609
 GM_IMMEDIATELY_ACTION = 612
 
-registerGMCommand(GM_SET_POS,            GM_DIR.."SetPos.lua")
-registerGMCommand(GM_SET_LEVEL,          GM_DIR.."SetLevel.lua")
-registerGMCommand(GM_SET_RACE,           GM_DIR.."SetRace.lua")
-registerGMCommand(GM_SET_TRIBE,          GM_DIR.."SetTribe.lua")
-registerGMCommand(GM_SET_SEX,            GM_DIR.."SetSex.lua")
-registerGMCommand(GM_CHANGE_WEATHER,     GM_DIR.."ChangeWeather.lua")
-registerGMCommand(GM_CALL,               GM_DIR.."Call.lua")
-registerGMCommand(GM_SPEED,              GM_DIR.."SetSpeed.lua")
-registerGMCommand(GM_INVISIBILITY,       GM_DIR.."ToggleInvisibility.lua")
-registerGMCommand(GM_KILL,               GM_DIR.."Kill.lua")
-registerGMCommand(GM_ICON,               GM_DIR.."Icon.lua")
-registerGMCommand(GM_SET_HP,             GM_DIR.."SetHP.lua")
-registerGMCommand(GM_SET_MP,             GM_DIR.."SetMP.lua")
+registerGMCommand(GM_SET_POS,            GM_DIR.."SetPos.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_SET_LEVEL,          GM_DIR.."SetLevel.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_SET_RACE,           GM_DIR.."SetRace.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_SET_TRIBE,          GM_DIR.."SetTribe.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_SET_SEX,            GM_DIR.."SetSex.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_CHANGE_WEATHER,     GM_DIR.."ChangeWeather.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_CALL,               GM_DIR.."Call.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_SPEED,              GM_DIR.."SetSpeed.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_INVISIBILITY,       GM_DIR.."ToggleInvisibility.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_KILL,               GM_DIR.."Kill.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_ICON,               GM_DIR.."Icon.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_SET_HP,             GM_DIR.."SetHP.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_SET_MP,             GM_DIR.."SetMP.lua", GM_RANK_DEBUG)
 -- TODO: Implement the GMInspect IPC opcode, it's completely different than the normal GMCommand opcode
 --registerGMCommand(GM_INSPECT,           GM_DIR.."InspectPlayer.lua")
-registerGMCommand(GM_EXP,                GM_DIR.."Exp.lua")
-registerGMCommand(GM_GET_REST,           GM_DIR.."GetRest.lua")
-registerGMCommand(GM_ORCHESTRION,        GM_DIR.."Orchestrion.lua")
-registerGMCommand(GM_GIVE_ITEM,          GM_DIR.."GiveItem.lua")
-registerGMCommand(GM_GIL,                GM_DIR.."Gil.lua")
-registerGMCommand(GM_COLLECT,            GM_DIR.."Collect.lua")
-registerGMCommand(GM_QUEST_ACCEPT,       GM_DIR.."QuestAccept.lua")
-registerGMCommand(GM_QUEST_CANCEL,       GM_DIR.."QuestCancel.lua")
-registerGMCommand(GM_QUEST_COMPLETE,     GM_DIR.."QuestComplete.lua")
-registerGMCommand(GM_QUEST_INCOMPLETE,   GM_DIR.."QuestIncomplete.lua")
-registerGMCommand(GM_QUEST_SEQUENCE,     GM_DIR.."QuestSequence.lua")
-registerGMCommand(GM_QUEST_INSPECT,      GM_DIR.."QuestInspect.lua")
-registerGMCommand(GM_GC,                 GM_DIR.."SetGrandCompany.lua")
-registerGMCommand(GM_GCRANK,             GM_DIR.."SetGrandCompanyRank.lua")
-registerGMCommand(GM_COLLECT,            GM_DIR.."Collect.lua")
-registerGMCommand(GM_HOWTO,              GM_DIR.."HowTo.lua")
-registerGMCommand(GM_AETHERYTE,          GM_DIR.."UnlockAetheryte.lua")
-registerGMCommand(GM_CUTFLG,             GM_DIR.."CutsceneFlag.lua")
-registerGMCommand(GM_WIREFRAME,          GM_DIR.."ToggleWireframe.lua")
-registerGMCommand(GM_TERRITORY,          GM_DIR.."ChangeTerritory.lua")
-registerGMCommand(GM_TERRITORY_INFO,     GM_DIR.."TerritoryInfo.lua")
-registerGMCommand(GM_JUMP,               GM_DIR.."Jump.lua")
-registerGMCommand(GM_WORLD,              GM_DIR.."World.lua")
-registerGMCommand(GM_IMMEDIATELY_ACTION, GM_DIR.."ImmediatelyAction.lua")
+registerGMCommand(GM_EXP,                GM_DIR.."Exp.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_GET_REST,           GM_DIR.."GetRest.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_ORCHESTRION,        GM_DIR.."Orchestrion.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_GIVE_ITEM,          GM_DIR.."GiveItem.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_GIL,                GM_DIR.."Gil.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_COLLECT,            GM_DIR.."Collect.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_QUEST_ACCEPT,       GM_DIR.."QuestAccept.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_QUEST_CANCEL,       GM_DIR.."QuestCancel.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_QUEST_COMPLETE,     GM_DIR.."QuestComplete.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_QUEST_INCOMPLETE,   GM_DIR.."QuestIncomplete.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_QUEST_SEQUENCE,     GM_DIR.."QuestSequence.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_QUEST_INSPECT,      GM_DIR.."QuestInspect.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_GC,                 GM_DIR.."SetGrandCompany.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_GCRANK,             GM_DIR.."SetGrandCompanyRank.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_COLLECT,            GM_DIR.."Collect.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_HOWTO,              GM_DIR.."HowTo.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_AETHERYTE,          GM_DIR.."UnlockAetheryte.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_CUTFLG,             GM_DIR.."CutsceneFlag.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_WIREFRAME,          GM_DIR.."ToggleWireframe.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_TERRITORY,          GM_DIR.."ChangeTerritory.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_TERRITORY_INFO,     GM_DIR.."TerritoryInfo.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_JUMP,               GM_DIR.."Jump.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_WORLD,              GM_DIR.."World.lua", GM_RANK_DEBUG)
+registerGMCommand(GM_IMMEDIATELY_ACTION, GM_DIR.."ImmediatelyAction.lua", GM_RANK_DEBUG)
 
 -- Debug commands
 -- Please keep these in alphabetical order!
@@ -94,6 +94,7 @@ registerCommand("monies",                           DBG_DIR.."Monies.lua")
 registerCommand("nudge",                            DBG_DIR.."Nudge.lua")
 registerCommand("ost",                              DBG_DIR.."OnScreenTest.lua")
 registerCommand("permtest",                         DBG_DIR.."PermissionTest.lua")
+registerCommand("revive",                           DBG_DIR.."Revive.lua")
 registerCommand("toggleadventure",                  DBG_DIR.."ToggleAdventure.lua")
 registerCommand("toggleaethercurrent",              DBG_DIR.."ToggleAetherCurrent.lua")
 registerCommand("toggleaethercurrentcompflgset",    DBG_DIR.."ToggleAetherCurrentCompFlgSet.lua")
@@ -104,6 +105,7 @@ registerCommand("togglecutsceneseen",               DBG_DIR.."ToggleCutsceneSeen
 registerCommand("toggleglassesstyle",               DBG_DIR.."ToggleGlassesStyle.lua")
 registerCommand("toggleminion",                     DBG_DIR.."ToggleMinion.lua")
 registerCommand("togglemount",                      DBG_DIR.."ToggleMount.lua")
+registerCommand("toggleorchestrion",                DBG_DIR.."ToggleOrchestrion.lua")
 registerCommand("toggleornament",                   DBG_DIR.."ToggleOrnament.lua")
 registerCommand("toggletripletriadcard",            DBG_DIR.."ToggleTripleTriadCard.lua")
 registerCommand("unlock",                           DBG_DIR.."Unlock.lua")
@@ -0,0 +1,20 @@
+required_rank = GM_RANK_DEBUG
+command_sender = "[revive] "
+
+function onCommand(player, args, name)
+    local argc = #args
+    if argc ~= 1 then
+        printf(player, "Incorrect arguments given!")
+        return
+    end
+
+    local kind = args[1]
+
+    if kind == "home" then
+        player:revive(0)
+    elseif kind == "raise" then
+        player:revive(1)
+    else
+        printf(player, "Incorrect arguments given!")
+    end
+end
@@ -0,0 +1,18 @@
+required_rank = GM_RANK_DEBUG
+command_sender = "[toggleorchestrion] "
+
+function onCommand(player, args, name)
+    local argc = #args
+    if argc ~= 1 then
+        printf(player, "Incorrect arguments given!")
+        return
+    end
+
+    local id = args[1]
+
+    if id == "all" then
+        player:unlock_orchestrion_all()
+    else
+        player:toggle_orchestrion(tonumber(id))
+    end
+end
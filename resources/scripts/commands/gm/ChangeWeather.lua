@@ -5,5 +5,9 @@ function onCommand(player, args, name)
     local id = args[1]
 
     player:change_weather(id)
-    printf(player, "Changing weather to %s.", id)
+    if tonumber(id) == 0 then
+        printf(player, "Weather will now change on its own.")
+    else
+        printf(player, "Changing weather to %s.", id)
+    end
 end
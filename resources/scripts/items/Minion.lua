@@ -1,7 +1,7 @@
 function doAction(player, arg)
     effects = EffectsBuilder()
 
-    player:toggle_minion(arg)
+    player:unlock_minion(arg)
 
     return effects
 end
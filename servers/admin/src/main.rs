@@ -56,7 +56,7 @@ async fn characters() -> Html<String> {
 
     let ipc_segment = CustomIpcSegment::new(CustomIpcData::RequestFullCharacterList {});
 
-    if let Some(response) = send_custom_world_packet(ipc_segment).await
+    if let Ok(Some(response)) = send_custom_world_packet(ipc_segment).await
         && let CustomIpcData::FullCharacterListResponse { json } = response.data
     {
         let characters: Option<Vec<BasicCharacterData>> = serde_json::from_str(&json).ok();
@@ -308,7 +308,7 @@ impl LobbyConnection {
                 service_account_id: self.selected_service_account.unwrap(),
             });
 
-            let Some(name_response) = send_custom_world_packet(charlist_request).await else {
+            let Ok(Some(name_response)) = send_custom_world_packet(charlist_request).await else {
                 // "World data could not be obtained. Please try logging in later."
                 self.send_error(sequence, 2002, 13201).await;
                 return;
@@ -429,7 +429,7 @@ impl LobbyConnection {
                 });
 
                 let is_free;
-                if let Some(name_response) = send_custom_world_packet(name_request).await {
+                if let Ok(Some(name_response)) = send_custom_world_packet(name_request).await {
                     let CustomIpcData::NameIsAvailableResponse { free } = &name_response.data
                     else {
                         panic!("Unexpedted custom IPC type!")
@@ -494,7 +494,8 @@ impl LobbyConnection {
                             chara_make_json: character_action.json.clone(),
                         });
 
-                    if let Some(response_segment) = send_custom_world_packet(ipc_segment).await {
+                    if let Ok(Some(response_segment)) = send_custom_world_packet(ipc_segment).await
+                    {
                         match &response_segment.data {
                             CustomIpcData::CharacterCreated {
                                 actor_id,
@@ -550,7 +551,9 @@ impl LobbyConnection {
                         content_id: character_action.content_id,
                     });
 
-                    let _ = send_custom_world_packet(ipc_segment).await;
+                    if let Err(err) = send_custom_world_packet(ipc_segment).await {
+                        tracing::warn!("Failed to contact World server: {err}");
+                    }
 
                     // we intentionally don't care about the response right now, it's not expected to fail
                 }
@@ -590,7 +593,9 @@ impl LobbyConnection {
                         chara_make_json: character_action.json.clone(),
                     });
 
-                    let _ = send_custom_world_packet(ipc_segment).await;
+                    if let Err(err) = send_custom_world_packet(ipc_segment).await {
+                        tracing::warn!("Failed to contact World server: {err}");
+                    }
 
                     // we intentionally don't care about the response right now, it's not expected to fail
                 }
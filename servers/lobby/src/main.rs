@@ -168,7 +168,7 @@ async fn main() {
                                                     },
                                                 );
 
-                                                if let Some(response_segment) =
+                                                if let Ok(Some(response_segment)) =
                                                     send_custom_world_packet(ipc_segment).await
                                                 {
                                                     match &response_segment.data {
@@ -314,7 +314,7 @@ async fn upload_character_backup(
                 path: "temp.zip".to_string(),
             });
 
-            if let Some(response) = send_custom_world_packet(ipc_segment).await
+            if let Ok(Some(response)) = send_custom_world_packet(ipc_segment).await
                 && let CustomIpcData::CharacterImported { message } = response.data
             {
                 return restore_backup_with_message(message).await.into_response();
@@ -377,7 +377,10 @@ async fn cancel_account_perform(
         let ipc_segment =
             CustomIpcSegment::new(CustomIpcData::DeleteServiceAccount { service_account_id });
 
-        let _ = send_custom_world_packet(ipc_segment).await; // we don't care about the response, for now.
+        // we don't care about the response, for now.
+        if let Err(err) = send_custom_world_packet(ipc_segment).await {
+            tracing::warn!("Failed to contact World server: {err}");
+        }
     }
 
     (jar.remove("cis_sessid"), Redirect::to("/"))
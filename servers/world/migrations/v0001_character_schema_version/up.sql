@@ -0,0 +1 @@
+ALTER TABLE `character` ADD COLUMN `schema_version` INTEGER NOT NULL DEFAULT 0;
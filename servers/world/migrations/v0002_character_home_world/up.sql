@@ -0,0 +1 @@
+ALTER TABLE `character` ADD COLUMN `home_world_id` INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE `unlock` ADD COLUMN `learned_actions` TEXT NOT NULL DEFAULT '[]';
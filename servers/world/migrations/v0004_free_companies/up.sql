@@ -0,0 +1,13 @@
+CREATE TABLE `free_company`(
+	`id` BIGINT NOT NULL PRIMARY KEY,
+	`name` TEXT NOT NULL,
+	`tag` TEXT NOT NULL,
+	`creation_time` BIGINT NOT NULL
+);
+
+CREATE TABLE `free_company_members`(
+	`content_id` BIGINT NOT NULL PRIMARY KEY,
+	`free_company_id` BIGINT NOT NULL,
+	`join_time` BIGINT NOT NULL,
+	FOREIGN KEY (`content_id`) REFERENCES `character`(`content_id`)
+);
@@ -0,0 +1,5 @@
+CREATE TABLE `blacklist`(
+	`id` BIGINT NOT NULL PRIMARY KEY,
+	`content_id` BIGINT NOT NULL,
+	`blocked_content_id` BIGINT NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE `volatile` ADD COLUMN `recast_timers` TEXT NOT NULL DEFAULT '[]';
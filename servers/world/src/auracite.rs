@@ -8,6 +8,7 @@ use crate::{
 };
 use kawari::{
     common::{CustomizeData, Position},
+    config::get_config,
     ipc::zone::GrandCompany,
 };
 
@@ -274,10 +275,12 @@ impl WorldDatabase {
 
         let (_, actor_id) = self.create_player_data(
             service_account_id,
+            get_config().world.world_id,
             &character.name,
             &chara_make.to_json(),
             character.city_state.value as u8,
             character.zone_id,
+            Position::default(),
             Inventory::default(),
             game_data,
         );
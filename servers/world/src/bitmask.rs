@@ -53,11 +53,14 @@ impl<const N: usize, T: BitmaskTransformation + std::fmt::Debug> deserialize::Fr
     for GenericBitmask<N, T>
 {
     fn from_sql(mut bytes: <Sqlite as Backend>::RawValue<'_>) -> deserialize::Result<Self> {
-        Ok(Self::from(
-            serde_json::from_str::<Vec<u8>>(bytes.read_text())
-                .ok()
-                .unwrap(),
-        ))
+        let mut data = serde_json::from_str::<Vec<u8>>(bytes.read_text())?;
+
+        // Bitmasks saved before they grew are padded out, so the new bits start cleared.
+        if data.len() < N {
+            data.resize(N, 0);
+        }
+
+        Ok(Self::from(data))
     }
 }
 
@@ -117,6 +120,19 @@ impl<const N: usize, T: BitmaskTransformation + std::fmt::Debug> GenericBitmask<
         (self.data[index as usize] & value) == value
     }
 
+    /// Sets every value in `values`, returning the ones that weren't already set.
+    pub fn set_many(&mut self, values: impl IntoIterator<Item = u32>) -> Vec<u32> {
+        let mut newly_set = Vec::new();
+        for value in values {
+            if !self.contains(value) {
+                self.set(value);
+                newly_set.push(value);
+            }
+        }
+
+        newly_set
+    }
+
     /// Sets all bits of this mask to 0xFF (255)
     pub fn set_all(&mut self) {
         self.data = vec![0xFF; N];
@@ -189,6 +205,19 @@ mod tests {
         assert_eq!(bitmask.contains(1), true);
     }
 
+    #[test]
+    fn set_many_bitmask() {
+        let mut bitmask: Bitmask<4> = Bitmask::default();
+
+        bitmask.set(1);
+        assert_eq!(bitmask.set_many(0..4), vec![0, 2, 3]);
+        assert_eq!(bitmask.data, vec![15, 0, 0, 0]);
+
+        // Setting everything should fill the whole mask, and anything already set isn't returned again.
+        assert_eq!(bitmask.set_many(0..32).len(), 28);
+        assert_eq!(bitmask.data, vec![0xFF; 4]);
+    }
+
     #[test]
     fn set_quest_bitmask() {
         let mut bitmask: QuestBitmask<4> = QuestBitmask::default();
@@ -4,7 +4,10 @@ use parking_lot::Mutex;
 use tokio::net::TcpStream;
 
 use super::common::ClientId;
-use crate::{ServerHandle, ToServer, WorldDatabase, database::Character};
+use crate::{
+    ServerHandle, ToServer, WorldDatabase,
+    database::{Character, TellDelivery},
+};
 use kawari::{
     common::{ObjectId, timestamp_secs},
     config::WorldConfig,
@@ -14,13 +17,12 @@ use kawari::{
             SendCWLinkshellMessage, SendPartyMessage, SendTellMessage, ServerChatIpcData,
             ServerChatIpcSegment, TellMessage, TellNotFoundError,
         },
-        zone::{CrossworldLinkshellEx, OnlineStatus},
+        zone::CrossworldLinkshellEx,
     },
     opcodes::ServerChatIpcType,
     packet::{
-        CompressionType, ConnectionState, ConnectionType, IpcSegmentHeader, PacketSegment,
-        SegmentData, SegmentType, ServerIpcSegmentHeader, parse_packet, send_keep_alive,
-        send_packet,
+        ConnectionState, ConnectionType, IpcSegmentHeader, PacketSegment, SegmentData, SegmentType,
+        ServerIpcSegmentHeader, parse_packet, send_keep_alive, send_packet,
     },
 };
 
@@ -87,11 +89,7 @@ impl ChatConnection {
             &mut self.socket,
             &mut self.state,
             ConnectionType::Chat,
-            if self.config.enable_packet_compression {
-                CompressionType::Oodle
-            } else {
-                CompressionType::Uncompressed
-            },
+            self.config.packet_compression_type(),
             &[segment],
         ))
         .await;
@@ -117,7 +115,7 @@ impl ChatConnection {
                 &mut self.socket,
                 &mut self.state,
                 ConnectionType::Chat,
-                CompressionType::Oodle,
+                self.config.packet_compression_type(),
                 &[response],
             )
             .await;
@@ -137,7 +135,7 @@ impl ChatConnection {
                 &mut self.socket,
                 &mut self.state,
                 ConnectionType::Chat,
-                CompressionType::Oodle,
+                self.config.packet_compression_type(),
                 &[response],
             )
             .await;
@@ -159,7 +157,7 @@ impl ChatConnection {
                 &mut self.socket,
                 &mut self.state,
                 ConnectionType::Chat,
-                CompressionType::Oodle,
+                self.config.packet_compression_type(),
                 &[response],
             )
             .await;
@@ -182,23 +180,27 @@ impl ChatConnection {
 
     pub async fn send_tell_message(&mut self, tell_data: &SendTellMessage) {
         // Start with the assumption that the recipient doesn't exist or is offline.
-        let mut recipient_ids = Character::default();
-        let mut recipient_is_online = false;
-        {
-            let mut db = self.database.lock();
+        let mut delivery = TellDelivery::NotFound;
 
-            // Tells only give us the recipient's name which isn't very helpful, so we need further info.
-            if let Some(recipient_id) =
-                db.find_character_ids(None, Some(tell_data.recipient_name.clone()))
-            {
-                recipient_ids = recipient_id;
-                let mask = db.determine_online_status_mask(recipient_ids.content_id);
-                recipient_is_online = mask.has_status(OnlineStatus::Online);
-            }
+        // Tells only give us the recipient's name which isn't very helpful, so we need further info.
+        // We don't support other worlds yet, so anyone there is treated as not found.
+        if tell_data.recipient_world_id == self.config.world_id {
+            let mut db = self.database.lock();
+            delivery = db.find_tell_delivery(
+                self.player_data.content_id as i64,
+                &tell_data.recipient_name,
+            );
         }
 
+        let recipient_ids = match delivery {
+            TellDelivery::Deliver(recipient) => recipient,
+            // The sender isn't told they're blacklisted, it looks like it went through to them.
+            TellDelivery::Drop => return,
+            TellDelivery::NotFound => Character::default(),
+        };
+
         // Next, if they do exist and are online, tell the server where to send the message.
-        if recipient_is_online && recipient_ids.actor_id != ObjectId::default() {
+        if recipient_ids.actor_id != ObjectId::default() {
             self.handle
                 .send(ToServer::TellMessageSent(
                     self.player_data.actor_id,
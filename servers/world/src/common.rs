@@ -9,10 +9,10 @@ use std::{
 use tokio::sync::mpsc::Sender;
 
 use crate::{
-    StatusEffects,
+    RecastTimers, StatusEffects,
     lua::LuaTask,
     server::Party,
-    zone_connection::{BaseParameters, TeleportQuery},
+    zone_connection::{BaseParameters, ReviveKind, TeleportQuery},
 };
 use kawari::{
     common::{
@@ -45,6 +45,14 @@ impl std::fmt::Debug for ClientId {
     }
 }
 
+#[cfg(test)]
+impl ClientId {
+    /// Creates a client id directly, for use in tests.
+    pub fn from_raw(id: usize) -> Self {
+        Self(id)
+    }
+}
+
 /// A type encapsulating the different spawn types.
 /// Note that event object (eobj) spawning is handled elsewhere in connection.rs.
 #[derive(Clone, Debug)]
@@ -67,6 +75,18 @@ pub struct PartyUpdateTargets {
     pub target_name: String,
 }
 
+/// A player in the online player list, as seen by /who.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OnlinePlayer {
+    pub name: String,
+    /// The place name of the zone they're in.
+    pub zone_name: String,
+    pub classjob_id: u8,
+    /// The abbreviation for their class/job, e.g. "WHM".
+    pub classjob_name: String,
+    pub level: u8,
+}
+
 /// A type encapsulating various information about a zone chat mesage to be sent.
 #[derive(Clone, Debug, Default)]
 pub struct MessageInfo {
@@ -74,11 +94,13 @@ pub struct MessageInfo {
     pub sender_actor_id: ObjectId,
     /// The sender's account id. Likely used by the client to know to ignore the message if this player is blocked.
     pub sender_account_id: u64,
+    /// The sender's content id, so listeners that blacklisted them can drop the message.
+    pub sender_content_id: u64,
     /// The sender's home world id. Used for purposes of displaying their home world in the chat window.
     pub sender_world_id: u16,
     /// The sender's name.
     pub sender_name: String,
-    /// The sender's position in the zone, used for creating a radius around which the message is heard (not yet implemented on Kawari).
+    /// The sender's position in the zone, used for creating a radius around which the message is heard.
     pub sender_position: Position,
     /// The channel the message is intended for (say, shout, yell, custom emote (/em)).
     pub channel: ChatChannelType,
@@ -129,6 +151,12 @@ pub enum FromServer {
     LoseEffect(u16, u16, ObjectId),
     // TODO: temporary
     Conditions(Conditions),
+    /// The weather in the player's zone changed.
+    WeatherChanged(u16),
+    /// An action went on recast, so the player's recast timers are now these.
+    RecastTimersChanged(RecastTimers),
+    /// The players online, as requested with ListOnlinePlayers.
+    OnlinePlayers(Vec<OnlinePlayer>),
     /// To inform the connection of the zone they're loading into.
     ChangeZone(
         u16,
@@ -281,6 +309,8 @@ pub enum ToServer {
     NewChatClient(ClientHandle),
     /// The connection sent a message.
     Message(ClientId, ObjectId, MessageInfo),
+    /// The connection's player rolled a random number (up to the given max) for those nearby to see.
+    RandomRoll(ObjectId, String, Position, u32),
     /// The connection's player moved.
     ActorMoved(
         ObjectId,
@@ -314,6 +344,10 @@ pub enum ToServer {
     FatalError(std::io::Error),
     /// Request to perform an action
     ActionRequest(ClientId, ObjectId, ActionRequest),
+    /// An actor has finished dying, and should be removed from its instance. NPCs are respawned later if they're meant to.
+    ActorDespawned(ObjectId),
+    /// Asks which (alive) actors are within a radius of a position, in the same instance as this actor. The answer is sent back through the channel.
+    ActorsInRadius(ObjectId, Position, f32, Sender<Vec<ObjectId>>),
     /// We want to update our own equip display flags.
     Config(ClientId, ObjectId, Config),
     /// Tell the server what models IDs we have equipped.
@@ -333,7 +367,7 @@ pub enum ToServer {
     /// Warp with the specified aetheryte id.
     WarpAetheryte(ClientId, ObjectId, u32, bool),
     /// Ready to spawn the player (this happens during initrequest)
-    ReadySpawnPlayer(ClientId, ObjectId, u16, Position, f32, Option<u8>),
+    ReadySpawnPlayer(ClientId, ObjectId, u16, Position, f32, Option<u32>),
     /// Ready to send the ZoneIn ACS
     ZoneIn(ClientId, ObjectId, bool),
     /// We need to summon a player's minion, and tell other clients
@@ -399,8 +433,22 @@ pub enum ToServer {
     Kill(ClientId, ObjectId),
     /// Inform the server to update our HP to this value.
     SetHP(ClientId, ObjectId, u32),
+    /// Changes the SharedGroupTimelineState of an EObj (by its base ID) in our instance, e.g. to open a door.
+    SetEObjState(ObjectId, u32, u32),
+    /// Forces the weather in our instance for everyone, or lets it change on its own again if None.
+    ForceWeather(ObjectId, Option<u16>),
+    /// Lists who's online for the actor, filtered by a name or zone name if given. GM invisible players are only seen by other GMs.
+    ListOnlinePlayers(ObjectId, Option<String>),
+    /// The connection's player toggled GM invisibility.
+    SetGmInvisible(ObjectId, bool),
+    /// The connection's recast timers, since the server's copy is lost on every zone change.
+    SetRecastTimers(ObjectId, RecastTimers),
+    /// Announces a message to everyone online. If a countdown (in minutes) is given, it's repeated every minute until it runs out.
+    Broadcast(String, Option<u32>),
     /// Inform the server to update our MP to this value.
     SetMP(ClientId, ObjectId, u16),
+    /// Inform the server to update both our HP and MP to these values.
+    SetHpMp(ClientId, ObjectId, u32, u16),
     /// The client discovered a new location in this zone.
     NewLocationDiscovered(ClientId, u32, Position, u16),
     /// The client is sharing a strategy board with their party.
@@ -461,12 +509,20 @@ pub enum ToServer {
     FriendRemoved(ObjectId, u64, String, ObjectId, u64, String),
     /// The client sends a letter to a friend.
     SendLetterTo(ObjectId),
+    /// The client wants to examine another player.
+    ExaminePlayer(ObjectId, ObjectId),
     /// Teleport to this player.
     Jump(ClientId, String),
     /// Teleport the player to this one.
     Call(ObjectId, String),
     /// Spawns an NPC defined by the layout or drop-in.
     SpawnLayoutNpc(ObjectId, u32),
+    /// Spawns this NPC in the same instance as the actor.
+    SpawnNpc(ObjectId, SpawnNpc),
+    /// Makes this NPC walk through the waypoints at the given speed (in yalms per second), replacing any path it was already on.
+    MoveActor(ObjectId, Vec<Position>, f32),
+    /// Brings this dead player back to life.
+    Revive(ClientId, ObjectId, ReviveKind),
     /// The client places a piece of furniture.
     PlaceFurniture(
         ObjectId,
@@ -1,6 +1,6 @@
 use crate::{CharaMake, GameData, RemakeMode, WorldDatabase, inventory::Inventory};
 use kawari::{
-    common::determine_initial_starting_zone,
+    common::determine_initial_placement,
     config::get_config,
     ipc::kawari::{CustomIpcData, CustomIpcSegment},
     packet::{
@@ -40,6 +40,14 @@ impl CustomIpcConnection {
     }
 
     pub async fn handle_custom_ipc(&mut self, data: &CustomIpcSegment) {
+        if !data.has_valid_token(get_config().world.custom_ipc_token) {
+            tracing::warn!(
+                "Rejecting custom IPC {:?} with a missing or wrong token!",
+                data.header.op_code
+            );
+            return;
+        }
+
         match &data.data {
             CustomIpcData::RequestCreateCharacter {
                 service_account_id,
@@ -64,22 +72,45 @@ impl CustomIpcConnection {
                 {
                     let mut game_data = self.gamedata.lock();
 
-                    inventory.equip_classjob_items(chara_make.classjob_id as u16, &mut game_data);
+                    let language = game_data.default_language;
+                    inventory.equip_classjob_items(
+                        chara_make.classjob_id as u16,
+                        &mut game_data,
+                        language,
+                    );
 
                     // fill inventory
                     inventory.equip_racial_items(
                         chara_make.customize.race,
                         chara_make.customize.gender,
                         &mut game_data,
+                        language,
+                    );
+
+                    let config = get_config();
+                    inventory.add_starting_items(
+                        &config.world.starting_items,
+                        config.world.starting_gil,
+                        &mut game_data,
+                    );
+
+                    let (zone_id, position) = determine_initial_placement(
+                        city_state,
+                        config
+                            .world
+                            .starting_position
+                            .map(|starting| (starting.zone_id, starting.position)),
                     );
 
                     let mut database = self.database.lock();
                     (content_id, actor_id) = database.create_player_data(
                         *service_account_id,
+                        config.world.world_id,
                         name,
                         chara_make_json,
                         city_state,
-                        determine_initial_starting_zone(city_state),
+                        zone_id,
+                        position,
                         inventory,
                         &mut game_data,
                     );
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+use diesel::prelude::*;
+
+use super::{Character, WorldDatabase, models::Blacklist, schema::blacklist::dsl::*};
+use kawari::ipc::zone::OnlineStatus;
+
+/// What should happen to a tell sent to someone by name.
+#[derive(Debug, Clone)]
+pub enum TellDelivery {
+    /// The recipient is online and willing to hear from the sender.
+    Deliver(Character),
+    /// The recipient blacklisted the sender, so it's silently dropped.
+    Drop,
+    /// The recipient doesn't exist or is offline.
+    NotFound,
+}
+
+impl WorldDatabase {
+    /// Whether `for_content_id` has blacklisted `their_content_id`.
+    pub fn is_blacklisted(&mut self, for_content_id: i64, their_content_id: i64) -> bool {
+        blacklist
+            .filter(content_id.eq(for_content_id))
+            .filter(blocked_content_id.eq(their_content_id))
+            .count()
+            .get_result::<i64>(&mut self.connection)
+            .unwrap_or_default()
+            > 0
+    }
+
+    /// Returns everyone `for_content_id` has blacklisted.
+    pub fn get_blacklist(&mut self, for_content_id: i64) -> HashSet<i64> {
+        blacklist
+            .filter(content_id.eq(for_content_id))
+            .select(blocked_content_id)
+            .load::<i64>(&mut self.connection)
+            .unwrap_or_default()
+            .into_iter()
+            .collect()
+    }
+
+    pub fn add_to_blacklist(&mut self, their_content_id: i64, my_content_id: i64) {
+        if my_content_id == their_content_id {
+            tracing::error!(
+                "Player with content id {my_content_id} attempted to add themselves to their blacklist. Ignoring request."
+            );
+            return;
+        }
+
+        if self.is_blacklisted(my_content_id, their_content_id) {
+            return;
+        }
+
+        let entry = Blacklist {
+            id: fastrand::i64(..),
+            content_id: my_content_id,
+            blocked_content_id: their_content_id,
+        };
+
+        diesel::insert_into(blacklist)
+            .values(entry)
+            .execute(&mut self.connection)
+            .unwrap();
+    }
+
+    pub fn remove_from_blacklist(&mut self, their_content_id: i64, my_content_id: i64) {
+        diesel::delete(
+            blacklist
+                .filter(content_id.eq(my_content_id))
+                .filter(blocked_content_id.eq(their_content_id)),
+        )
+        .execute(&mut self.connection)
+        .unwrap();
+    }
+
+    /// Decides what to do with a tell from `sender_content_id` to the character named `recipient_name`.
+    pub fn find_tell_delivery(
+        &mut self,
+        sender_content_id: i64,
+        recipient_name: &str,
+    ) -> TellDelivery {
+        let Some(recipient) = self.find_character_ids(None, Some(recipient_name.to_string()))
+        else {
+            return TellDelivery::NotFound;
+        };
+
+        if !self
+            .determine_online_status_mask(recipient.content_id)
+            .has_status(OnlineStatus::Online)
+        {
+            return TellDelivery::NotFound;
+        }
+
+        if self.is_blacklisted(recipient.content_id, sender_content_id) {
+            return TellDelivery::Drop;
+        }
+
+        TellDelivery::Deliver(recipient)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blacklisted_tell_not_delivered() {
+        let mut database = WorldDatabase::in_memory();
+        database.insert_test_character(1, true);
+        let recipient = database.insert_test_character(2, true);
+
+        database.add_to_blacklist(1, 2);
+        assert!(database.is_blacklisted(2, 1));
+        assert!(matches!(
+            database.find_tell_delivery(1, &recipient.name),
+            TellDelivery::Drop
+        ));
+
+        // Blacklisting only goes one way
+        assert!(!database.is_blacklisted(1, 2));
+        assert_eq!(database.get_blacklist(2), HashSet::from([1]));
+        assert!(database.get_blacklist(1).is_empty());
+
+        database.remove_from_blacklist(1, 2);
+        assert!(database.get_blacklist(2).is_empty());
+        assert!(matches!(
+            database.find_tell_delivery(1, &recipient.name),
+            TellDelivery::Deliver(character) if character.content_id == recipient.content_id
+        ));
+    }
+}
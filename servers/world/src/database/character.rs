@@ -1,13 +1,14 @@
 use diesel::prelude::*;
 
-use super::{Character, WorldDatabase, models, schema};
+use super::{CHARACTER_SCHEMA_VERSION, Character, WorldDatabase, models, schema};
 use crate::{
     CharaMake, ClassLevels, ClientSelectData, GameData, GrandCompanyRanks, PlayerData, RemakeMode,
     inventory::Inventory,
 };
 use kawari::{
     common::{
-        BasicCharacterData, ObjectId, WORLD_NAME, WeaponModelId, determine_initial_homepoint,
+        BasicCharacterData, ObjectId, Position, WORLD_NAME, WeaponModelId,
+        determine_initial_homepoint,
     },
     ipc::{
         lobby::{CharacterDetails, CharacterFlag},
@@ -18,7 +19,7 @@ use kawari::{
 };
 
 impl WorldDatabase {
-    /// Returns a row from the Character table, searching either with a content id or a character's name. When sending tells, the ChatConnection is only given a name from the game client, so it needs to pull data in this fashion.
+    /// Returns a row from the Character table, searching either with a content id or a character's name. When sending tells, the ChatConnection is only given a name from the game client, so it needs to pull data in this fashion. Names are matched case-insensitively.
     // TODO: What's a better name for this function?
     pub fn find_character_ids(
         &mut self,
@@ -35,7 +36,7 @@ impl WorldDatabase {
             return Some(data);
         } else if let Some(for_name) = for_name
             && let Ok(data) = character
-                .filter(name.eq(for_name))
+                .filter(super::lower(name).eq(for_name.to_lowercase()))
                 .select(Character::as_select())
                 .first(&mut self.connection)
         {
@@ -68,7 +69,7 @@ impl WorldDatabase {
         for_actor_id: ObjectId,
         game_data: &mut GameData,
     ) -> PlayerData {
-        let found_character;
+        let mut found_character;
         {
             use models::*;
             use schema::character::dsl::*;
@@ -80,6 +81,9 @@ impl WorldDatabase {
                 .unwrap();
         }
 
+        // Older characters may be missing rows or data the current layout expects.
+        self.upgrade_character(&mut found_character);
+
         let mut player_data;
         {
             use models::*;
@@ -342,10 +346,12 @@ impl WorldDatabase {
     pub fn create_player_data(
         &mut self,
         service_account_id: u64,
+        home_world_id: u16,
         name: &str,
         chara_make_str: &str,
         city_state: u8,
         zone_id: u16,
+        position: Position,
         inventory: Inventory,
         game_data: &mut GameData,
     ) -> (u64, ObjectId) {
@@ -373,6 +379,8 @@ impl WorldDatabase {
             gm_rank: GameMasterRank::Debug,
             name: name.to_string(),
             time_played_minutes: 0,
+            schema_version: CHARACTER_SCHEMA_VERSION,
+            home_world_id: home_world_id as i32,
         };
         diesel::insert_into(schema::character::table)
             .values(character)
@@ -424,6 +432,7 @@ impl WorldDatabase {
         let volatile = Volatile {
             content_id: content_id as i64,
             zone_id: zone_id as i32,
+            position,
             ..Default::default()
         };
         diesel::insert_into(schema::volatile::table)
@@ -627,6 +636,25 @@ impl WorldDatabase {
                 .unwrap();
         }
 
+        {
+            use schema::blacklist::dsl::*;
+            // Nobody needs to block this character anymore.
+            diesel::delete(blacklist.filter(blocked_content_id.eq(for_content_id as i64)))
+                .execute(&mut self.connection)
+                .unwrap();
+
+            diesel::delete(blacklist.filter(content_id.eq(for_content_id as i64)))
+                .execute(&mut self.connection)
+                .unwrap();
+        }
+
+        {
+            use schema::free_company_members::dsl::*;
+            diesel::delete(free_company_members.filter(content_id.eq(for_content_id as i64)))
+                .execute(&mut self.connection)
+                .unwrap();
+        }
+
         // Since linkshell management is a little more complex than just deleting all rows with this content id, we do it the slightly slower way. We want orphaned linkshells with zero members to auto-disband.
         // TODO: Implement the ToServer protocol for CustomIpcConnection so we can notify the global server about this character's departures from their linkshells
         if let Some(linkshells) = self.find_linkshells(for_content_id as i64) {
@@ -732,6 +760,16 @@ impl WorldDatabase {
         }
     }
 
+    /// Sets the total playtime (in minutes) for a character.
+    pub fn commit_playtime(&mut self, for_content_id: u64, minutes: i64) {
+        use schema::character::dsl::*;
+
+        diesel::update(character.filter(content_id.eq(for_content_id as i64)))
+            .set(time_played_minutes.eq(minutes))
+            .execute(&mut self.connection)
+            .unwrap();
+    }
+
     pub fn find_playtime(&mut self, for_content_id: u64) -> i64 {
         use schema::character::dsl::*;
 
@@ -742,3 +780,119 @@ impl WorldDatabase {
             .unwrap_or_default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use glam::Vec3;
+
+    use super::*;
+
+    #[test]
+    fn test_commit_playtime() {
+        let mut database = WorldDatabase::in_memory();
+        database.insert_test_character(1, false);
+        assert_eq!(database.find_playtime(1), 0);
+
+        // Two sessions in a row
+        let first = database.find_playtime(1) + 30;
+        database.commit_playtime(1, first);
+        let second = database.find_playtime(1) + 15;
+        database.commit_playtime(1, second);
+
+        assert_eq!(database.find_playtime(1), 45);
+    }
+
+    #[test]
+    fn test_volatile_round_trip() {
+        use models::*;
+
+        let mut database = WorldDatabase::in_memory();
+        let character = database.insert_test_character(1, false);
+        let volatile = Volatile::belonging_to(&character)
+            .select(Volatile::as_select())
+            .first(&mut database.connection)
+            .unwrap();
+
+        let mut data = PlayerData {
+            character: character.clone(),
+            volatile,
+            ..Default::default()
+        };
+        data.volatile.position = Position(Vec3::new(1.0, 2.0, 3.0));
+        data.volatile.rotation = 1.5;
+        data.volatile.zone_id = 132;
+        data.volatile
+            .recast_timers
+            .start(9, Duration::from_secs(60), 1000);
+        database.commit_volatile(&data);
+
+        let saved = Volatile::belonging_to(&character)
+            .select(Volatile::as_select())
+            .first(&mut database.connection)
+            .unwrap();
+        assert_eq!(saved.position, data.volatile.position);
+        assert_eq!(saved.rotation, 1.5);
+        assert_eq!(saved.zone_id, 132);
+
+        // Cooldowns carry over to the next login
+        assert!(!saved.recast_timers.is_ready(9, 1000));
+        assert!(saved.recast_timers.is_ready(9, 61_000));
+    }
+
+    #[test]
+    fn test_unlock_round_trip() {
+        use models::*;
+
+        let mut database = WorldDatabase::in_memory();
+        let character = database.insert_test_character(1, false);
+
+        let mut unlock = Unlock {
+            content_id: character.content_id,
+            ..Default::default()
+        };
+        diesel::insert_into(schema::unlock::table)
+            .values(&unlock)
+            .execute(&mut database.connection)
+            .unwrap();
+
+        unlock.mounts.set(1);
+        unlock.minions.set(2);
+        unlock.orchestrion_rolls.set(3);
+        unlock.learned_actions.learn(9);
+        unlock
+            .save_changes::<Unlock>(&mut database.connection)
+            .unwrap();
+
+        let saved = Unlock::belonging_to(&character)
+            .select(Unlock::as_select())
+            .first(&mut database.connection)
+            .unwrap();
+        assert!(saved.mounts.contains(1));
+        assert!(saved.minions.contains(2));
+        assert!(saved.orchestrion_rolls.contains(3));
+        assert!(!saved.orchestrion_rolls.contains(4));
+        assert_eq!(saved.mounts.data, unlock.mounts.data);
+        assert_eq!(saved.learned_actions.0, vec![9]);
+    }
+
+    #[test]
+    fn test_find_character_by_name() {
+        let mut database = WorldDatabase::in_memory();
+        database.insert_test_character(1, true);
+        database.insert_test_character(2, false);
+
+        let found = database
+            .find_character_ids(None, Some("test character 2".to_string()))
+            .unwrap();
+        assert_eq!(found.content_id, 2);
+        assert_eq!(found.actor_id, ObjectId(2));
+
+        assert!(
+            database
+                .find_character_ids(None, Some("Nobody".to_string()))
+                .is_none()
+        );
+    }
+}
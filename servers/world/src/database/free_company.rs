@@ -0,0 +1,168 @@
+use diesel::prelude::*;
+use kawari::common::truncate_at_char_boundary;
+
+use super::{WorldDatabase, models, schema, unixepoch};
+
+/// The longest tag (in bytes) a Free Company can have. The spawn and social list packets have six bytes for it, including the terminator.
+pub const FC_TAG_MAX_LENGTH: usize = 5;
+
+/// Cuts `tag` down to `FC_TAG_MAX_LENGTH` bytes, without splitting a character in half.
+fn truncate_fc_tag(tag: &str) -> String {
+    truncate_at_char_boundary(tag, FC_TAG_MAX_LENGTH).to_string()
+}
+
+impl WorldDatabase {
+    /// Creates a new Free Company and makes `leader_content_id` its first member, returning its id.
+    /// Returns None if there's already a Free Company with this name.
+    pub fn create_free_company(
+        &mut self,
+        fc_name: &str,
+        fc_tag: &str,
+        leader_content_id: i64,
+    ) -> Option<i64> {
+        if self.find_free_company_id(fc_name).is_some() {
+            return None;
+        }
+
+        use schema::free_company::dsl::*;
+
+        let time = diesel::select(unixepoch())
+            .get_result::<i64>(&mut self.connection)
+            .unwrap();
+
+        let company = models::FreeCompany {
+            id: fastrand::i64(1..),
+            name: fc_name.to_string(),
+            tag: truncate_fc_tag(fc_tag),
+            creation_time: time,
+        };
+
+        diesel::insert_into(free_company)
+            .values(&company)
+            .execute(&mut self.connection)
+            .unwrap();
+
+        self.join_free_company(company.id, leader_content_id);
+
+        Some(company.id)
+    }
+
+    /// Returns the id of the Free Company called `fc_name`, if it exists.
+    pub fn find_free_company_id(&mut self, fc_name: &str) -> Option<i64> {
+        use schema::free_company::dsl::*;
+
+        free_company
+            .select(id)
+            .filter(super::lower(name).eq(fc_name.to_lowercase()))
+            .first::<i64>(&mut self.connection)
+            .ok()
+    }
+
+    /// Makes `for_content_id` a member of `fc_id`, leaving any Free Company they were in before. Returns false if there's no such Free Company.
+    pub fn join_free_company(&mut self, fc_id: i64, for_content_id: i64) -> bool {
+        {
+            use schema::free_company::dsl::*;
+
+            let exists = free_company
+                .filter(id.eq(fc_id))
+                .count()
+                .get_result::<i64>(&mut self.connection)
+                .unwrap_or_default()
+                > 0;
+            if !exists {
+                return false;
+            }
+        }
+
+        use schema::free_company_members::dsl::*;
+
+        let time = diesel::select(unixepoch())
+            .get_result::<i64>(&mut self.connection)
+            .unwrap();
+
+        let member = models::FreeCompanyMembers {
+            content_id: for_content_id,
+            free_company_id: fc_id,
+            join_time: time,
+        };
+
+        diesel::replace_into(free_company_members)
+            .values(&member)
+            .execute(&mut self.connection)
+            .unwrap();
+
+        true
+    }
+
+    /// Returns the tag of the Free Company `for_content_id` belongs to, or an empty string if they aren't in one.
+    pub fn find_fc_tag(&mut self, for_content_id: i64) -> String {
+        schema::free_company_members::table
+            .inner_join(
+                schema::free_company::table
+                    .on(schema::free_company::id.eq(schema::free_company_members::free_company_id)),
+            )
+            .filter(schema::free_company_members::content_id.eq(for_content_id))
+            .select(schema::free_company::tag)
+            .first::<String>(&mut self.connection)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fc_tag_truncated() {
+        assert_eq!(truncate_fc_tag("KAWA"), "KAWA");
+        assert_eq!(truncate_fc_tag("KAWARI"), "KAWAR");
+
+        // Multi-byte characters are never split
+        assert_eq!(truncate_fc_tag("ＫＡＷ"), "Ｋ");
+        assert_eq!(truncate_fc_tag("KAWＡ"), "KAW");
+    }
+
+    #[test]
+    fn test_fc_member_tag() {
+        let mut database = WorldDatabase::in_memory();
+        database.insert_test_character(1, true);
+        database.insert_test_character(2, true);
+        database.insert_test_character(3, true);
+
+        let fc_id = database
+            .create_free_company("Test Company", "TEST", 1)
+            .unwrap();
+        assert_eq!(database.find_free_company_id("test company"), Some(fc_id));
+        assert!(database.join_free_company(fc_id, 2));
+
+        // This is what ends up in their PlayerEntry
+        assert_eq!(database.find_fc_tag(1), "TEST");
+        assert_eq!(database.find_fc_tag(2), "TEST");
+        assert_eq!(database.find_fc_tag(3), "");
+
+        // Joining a Free Company that doesn't exist does nothing
+        assert!(!database.join_free_company(0, 3));
+        assert_eq!(database.find_fc_tag(3), "");
+    }
+
+    #[test]
+    fn test_fc_name_taken() {
+        let mut database = WorldDatabase::in_memory();
+        database.insert_test_character(1, true);
+        database.insert_test_character(2, true);
+
+        assert!(
+            database
+                .create_free_company("Test Company", "TEST", 1)
+                .is_some()
+        );
+        assert!(
+            database
+                .create_free_company("TEST COMPANY", "OTHER", 2)
+                .is_none()
+        );
+
+        // The second leader is left where they were
+        assert_eq!(database.find_fc_tag(2), "");
+    }
+}
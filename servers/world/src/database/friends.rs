@@ -115,3 +115,43 @@ impl WorldDatabase {
         time as u32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use kawari::ipc::zone::OnlineStatus;
+
+    use super::*;
+
+    #[test]
+    fn test_friend_list() {
+        let mut database = WorldDatabase::in_memory();
+        database.insert_test_character(1, true);
+        database.insert_test_character(2, true);
+        database.insert_test_character(3, false);
+
+        database.add_to_friend_list(2, 1, 32);
+        database.add_to_friend_list(3, 1, 32);
+
+        // Adding yourself is ignored
+        database.add_to_friend_list(1, 1, 32);
+
+        let mut friend_ids = database.get_friend_content_ids(1);
+        friend_ids.sort();
+        assert_eq!(friend_ids, vec![2, 3]);
+
+        // Offline friends are still listed, but without any online status
+        assert!(
+            database
+                .determine_online_status_mask(2)
+                .has_status(OnlineStatus::Online)
+        );
+        assert!(
+            !database
+                .determine_online_status_mask(3)
+                .has_status(OnlineStatus::Online)
+        );
+
+        database.remove_from_friend_list(2, 1);
+        assert_eq!(database.get_friend_content_ids(1), vec![3]);
+    }
+}
@@ -226,7 +226,7 @@ impl WorldDatabase {
                     members.push(CWLSMemberListEntry {
                         content_id: member.content_id as u64,
                         unk_timestamp: member.invite_time as u32,
-                        home_world_id: config.world.world_id,
+                        home_world_id: player_info.home_world_id,
                         current_world_id: config.world.world_id,
                         name: player_info.name.clone(),
                         is_online,
@@ -0,0 +1,167 @@
+//! Upgrading rows saved by older versions of Kawari.
+//!
+//! Adding or removing columns is handled by the SQL migrations, but those can't know what a sensible value for existing rows is. Whenever the layout of a character's rows changes in a way older rows can't be loaded as-is, bump `CHARACTER_SCHEMA_VERSION` and add an upgrade step below.
+
+use diesel::prelude::*;
+
+use super::{Character, WorldDatabase, models, schema};
+use kawari::ipc::zone::OnlineStatus;
+
+/// The current layout version of a character's rows.
+pub const CHARACTER_SCHEMA_VERSION: i32 = 1;
+
+impl WorldDatabase {
+    /// Brings `found_character`'s rows up to `CHARACTER_SCHEMA_VERSION`, filling in anything they're missing with defaults.
+    pub fn upgrade_character(&mut self, found_character: &mut Character) {
+        if found_character.schema_version >= CHARACTER_SCHEMA_VERSION {
+            return;
+        }
+
+        tracing::info!(
+            "Upgrading {} from schema version {} to {CHARACTER_SCHEMA_VERSION}...",
+            found_character.name,
+            found_character.schema_version
+        );
+
+        if found_character.schema_version < 1 {
+            self.upgrade_character_to_v1(found_character.content_id);
+        }
+
+        found_character.schema_version = CHARACTER_SCHEMA_VERSION;
+
+        use schema::character::dsl::*;
+        diesel::update(character.filter(content_id.eq(found_character.content_id)))
+            .set(schema_version.eq(CHARACTER_SCHEMA_VERSION))
+            .execute(&mut self.connection)
+            .unwrap();
+    }
+
+    /// Version 1 is the first versioned layout. Characters from before then may be missing rows for tables that were added later, and have unlocks saved before their bitmasks grew.
+    fn upgrade_character_to_v1(&mut self, for_content_id: i64) {
+        diesel::insert_or_ignore_into(schema::mentor::table)
+            .values(models::Mentor {
+                content_id: for_content_id,
+                ..Default::default()
+            })
+            .execute(&mut self.connection)
+            .unwrap();
+        diesel::insert_or_ignore_into(schema::search_info::table)
+            .values(models::SearchInfo {
+                content_id: for_content_id,
+                online_status: OnlineStatus::Online,
+                ..Default::default()
+            })
+            .execute(&mut self.connection)
+            .unwrap();
+        diesel::insert_or_ignore_into(schema::grand_company::table)
+            .values(models::GrandCompany {
+                content_id: for_content_id,
+                ..Default::default()
+            })
+            .execute(&mut self.connection)
+            .unwrap();
+        diesel::insert_or_ignore_into(schema::companion::table)
+            .values(models::Companion {
+                content_id: for_content_id,
+                ..Default::default()
+            })
+            .execute(&mut self.connection)
+            .unwrap();
+        diesel::insert_or_ignore_into(schema::aether_current::table)
+            .values(models::AetherCurrent {
+                content_id: for_content_id,
+                ..Default::default()
+            })
+            .execute(&mut self.connection)
+            .unwrap();
+        diesel::insert_or_ignore_into(schema::content::table)
+            .values(models::Content {
+                content_id: for_content_id,
+                ..Default::default()
+            })
+            .execute(&mut self.connection)
+            .unwrap();
+        diesel::insert_or_ignore_into(schema::unlock::table)
+            .values(models::Unlock {
+                content_id: for_content_id,
+                ..Default::default()
+            })
+            .execute(&mut self.connection)
+            .unwrap();
+
+        // Bitmasks are padded to their current size when loaded, so saving them again is enough to upgrade them. If any of them can't be parsed, the row is left alone instead of being saved back cleared.
+        if let Ok(unlock) = schema::unlock::table
+            .filter(schema::unlock::content_id.eq(for_content_id))
+            .select(models::Unlock::as_select())
+            .first(&mut self.connection)
+        {
+            unlock
+                .save_changes::<models::Unlock>(&mut self.connection)
+                .unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kawari::constants::{
+        ADVENTURE_BITMASK_SIZE, CAUGHT_FISH_BITMASK_SIZE, CUTSCENE_SEEN_BITMASK_SIZE,
+        MINION_BITMASK_SIZE, MOUNT_BITMASK_SIZE, ORCHESTRION_ROLL_BITMASK_SIZE,
+        UNLOCK_BITMASK_SIZE,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_upgrade_old_character() {
+        let mut database = WorldDatabase::in_memory();
+        let mut character = database.insert_test_character(1, true);
+        assert_eq!(character.schema_version, 0);
+
+        // Simulate unlocks saved back when there were fewer mounts and minions.
+        diesel::sql_query(
+            "INSERT INTO unlock VALUES (1, '[1]', '[]', '[2]', '[4]', '[]', '[]', '[]', '[]', '[]', '[]', '[]', '[]', '[]', '[]', '[]')",
+        )
+        .execute(&mut database.connection)
+        .unwrap();
+
+        database.upgrade_character(&mut character);
+        assert_eq!(character.schema_version, CHARACTER_SCHEMA_VERSION);
+
+        let unlock = schema::unlock::table
+            .filter(schema::unlock::content_id.eq(1))
+            .select(models::Unlock::as_select())
+            .first(&mut database.connection)
+            .unwrap();
+        assert_eq!(unlock.unlocks.data.len(), UNLOCK_BITMASK_SIZE);
+        assert_eq!(unlock.unlocks.data[0], 1);
+        assert_eq!(unlock.minions.data.len(), MINION_BITMASK_SIZE);
+        assert_eq!(unlock.minions.data[0], 2);
+        assert_eq!(unlock.mounts.data.len(), MOUNT_BITMASK_SIZE);
+        assert_eq!(unlock.mounts.data[0], 4);
+        assert_eq!(
+            unlock.orchestrion_rolls.data.len(),
+            ORCHESTRION_ROLL_BITMASK_SIZE
+        );
+        assert_eq!(unlock.cutscene_seen.data.len(), CUTSCENE_SEEN_BITMASK_SIZE);
+        assert_eq!(unlock.caught_fish.data.len(), CAUGHT_FISH_BITMASK_SIZE);
+        assert_eq!(unlock.adventures.data.len(), ADVENTURE_BITMASK_SIZE);
+
+        // The rows it was missing are filled in.
+        assert!(
+            schema::grand_company::table
+                .filter(schema::grand_company::content_id.eq(1))
+                .select(models::GrandCompany::as_select())
+                .first(&mut database.connection)
+                .is_ok()
+        );
+
+        // It's only upgraded once.
+        let stored_version = schema::character::table
+            .filter(schema::character::content_id.eq(1))
+            .select(schema::character::schema_version)
+            .first::<i32>(&mut database.connection)
+            .unwrap();
+        assert_eq!(stored_version, CHARACTER_SCHEMA_VERSION);
+    }
+}
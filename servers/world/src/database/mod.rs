@@ -1,8 +1,15 @@
+mod blacklist;
+pub use blacklist::TellDelivery;
+
 mod character;
+mod free_company;
 mod friends;
 mod linkshell;
 mod mail;
 
+mod migration;
+pub use migration::CHARACTER_SCHEMA_VERSION;
+
 mod models;
 pub use models::{
     AetherCurrent, Aetheryte, Character, ClassJob, Companion, Content, Friends, GrandCompany,
@@ -30,8 +37,12 @@ impl Default for WorldDatabase {
 
 impl WorldDatabase {
     pub fn new() -> Self {
-        let mut connection =
-            SqliteConnection::establish("world.db").expect("Failed to open database!");
+        Self::open("world.db")
+    }
+
+    /// Opens the database at `path`, and runs any pending migrations.
+    fn open(path: &str) -> Self {
+        let mut connection = SqliteConnection::establish(path).expect("Failed to open database!");
 
         connection.run_pending_migrations(MIGRATIONS).unwrap();
 
@@ -92,6 +103,50 @@ impl WorldDatabase {
     }
 }
 
+#[cfg(test)]
+impl WorldDatabase {
+    /// Opens an empty database in memory.
+    fn in_memory() -> Self {
+        Self::open(":memory:")
+    }
+
+    /// Inserts the bare minimum rows needed for a character to show up in the social lists.
+    fn insert_test_character(&mut self, for_content_id: i64, online: bool) -> Character {
+        let character = Character {
+            content_id: for_content_id,
+            service_account_id: for_content_id,
+            actor_id: ObjectId(for_content_id as u32),
+            name: format!("Test Character {for_content_id}"),
+            ..Default::default()
+        };
+        diesel::insert_into(schema::character::table)
+            .values(&character)
+            .execute(&mut self.connection)
+            .unwrap();
+
+        let volatile = Volatile {
+            content_id: for_content_id,
+            is_online: online,
+            ..Default::default()
+        };
+        diesel::insert_into(schema::volatile::table)
+            .values(&volatile)
+            .execute(&mut self.connection)
+            .unwrap();
+
+        let search_info = SearchInfo {
+            content_id: for_content_id,
+            ..Default::default()
+        };
+        diesel::insert_into(schema::search_info::table)
+            .values(&search_info)
+            .execute(&mut self.connection)
+            .unwrap();
+
+        character
+    }
+}
+
 #[declare_sql_function]
 extern "SQL" {
     fn datetime() -> diesel::sql_types::Text;
@@ -101,3 +156,8 @@ extern "SQL" {
 extern "SQL" {
     fn unixepoch() -> diesel::sql_types::BigInt;
 }
+
+#[declare_sql_function]
+extern "SQL" {
+    fn lower(x: diesel::sql_types::Text) -> diesel::sql_types::Text;
+}
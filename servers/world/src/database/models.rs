@@ -1,6 +1,6 @@
 use diesel::prelude::*;
 use kawari::{
-    common::{ClientLanguage, EquipDisplayFlag, ObjectId, Position},
+    common::{ClientLanguage, EquipDisplayFlag, InstanceContentType, ObjectId, Position},
     constants::{
         ACTIVE_HELP_BITMASK_SIZE, ADVENTURE_BITMASK_SIZE, AETHER_CURRENT_BITMASK_SIZE,
         AETHER_CURRENT_COMP_FLG_SET_BITMASK_SIZE, AETHERYTE_UNLOCK_BITMASK_SIZE,
@@ -18,7 +18,7 @@ use kawari::{
 
 use crate::{
     ActiveQuests, Bitmask, CharaMake, ClassExperience, ClassLevels, FavoriteAetherytes,
-    GrandCompanyRanks, PartyMembers, QuestBitmask,
+    GrandCompanyRanks, LearnedActions, PartyMembers, QuestBitmask, RecastTimers,
 };
 
 #[derive(Insertable, Identifiable, Queryable, Selectable, AsChangeset, Debug, Default, Clone)]
@@ -32,6 +32,20 @@ pub struct Character {
     pub gm_rank: GameMasterRank,
     pub name: String,
     pub time_played_minutes: i64,
+    /// Which layout this character's rows were last saved in, see `CHARACTER_SCHEMA_VERSION`.
+    pub schema_version: i32,
+    /// The world this character lives on, or zero if they were created before this was recorded.
+    pub home_world_id: i32,
+}
+
+impl Character {
+    /// The world this character lives on, falling back to `configured_world_id` if it wasn't recorded.
+    pub fn home_world_id_or(&self, configured_world_id: u16) -> u16 {
+        u16::try_from(self.home_world_id)
+            .ok()
+            .filter(|id| *id != 0)
+            .unwrap_or(configured_world_id)
+    }
 }
 
 #[derive(
@@ -124,6 +138,15 @@ pub struct Aetheryte {
     pub free_aetheryte: i32,
 }
 
+impl Aetheryte {
+    /// Whether teleporting to `aetheryte_id` is free, which is the case for the homepoint and favorite aetherytes.
+    pub fn is_free_destination(&self, aetheryte_id: u32) -> bool {
+        aetheryte_id as i32 == self.homepoint
+            || aetheryte_id as i32 == self.free_aetheryte
+            || self.favorite_aetherytes.0.contains(&(aetheryte_id as u16))
+    }
+}
+
 #[derive(
     Insertable,
     Identifiable,
@@ -149,6 +172,7 @@ pub struct Volatile {
     pub is_online: bool,
     pub client_language: ClientLanguage,
     pub current_mount: i32,
+    pub recast_timers: RecastTimers,
 }
 
 #[derive(
@@ -247,6 +271,54 @@ pub struct Content {
     pub cleared_misc_content: Bitmask<MISC_CONTENT_ARRAY_SIZE>,
 }
 
+impl Content {
+    /// Each id has to be subtracted by it's offset in the InstanceContent Excel sheet. For example, all guildheists start at ID 10000.
+    fn bitmask_index(kind: &InstanceContentType, content_id: u16) -> Option<u32> {
+        let offset = match kind {
+            InstanceContentType::Dungeon => 1,
+            InstanceContentType::Raid => 30001,
+            InstanceContentType::Guildhests => 10001,
+            InstanceContentType::Trial => 20001,
+            _ => return None,
+        };
+
+        (content_id as u32).checked_sub(offset)
+    }
+
+    /// Marks this content as unlocked, returning false if we don't know where to store this kind of content.
+    pub fn unlock(&mut self, kind: &InstanceContentType, content_id: u16) -> bool {
+        let Some(index) = Self::bitmask_index(kind, content_id) else {
+            return false;
+        };
+
+        match kind {
+            InstanceContentType::Dungeon => self.unlocked_dungeons.set(index),
+            InstanceContentType::Raid => self.unlocked_raids.set(index),
+            InstanceContentType::Guildhests => self.unlocked_guildhests.set(index),
+            InstanceContentType::Trial => self.unlocked_trials.set(index),
+            _ => unreachable!(),
+        }
+
+        true
+    }
+
+    /// Marks this content as cleared, returning whether this is the first time it was cleared.
+    /// Returns None if we don't know where to store this kind of content.
+    pub fn clear(&mut self, kind: &InstanceContentType, content_id: u16) -> Option<bool> {
+        let index = Self::bitmask_index(kind, content_id)?;
+
+        let newly_cleared = match kind {
+            InstanceContentType::Dungeon => self.cleared_dungeons.set_many([index]),
+            InstanceContentType::Raid => self.cleared_raids.set_many([index]),
+            InstanceContentType::Guildhests => self.cleared_guildhests.set_many([index]),
+            InstanceContentType::Trial => self.cleared_trials.set_many([index]),
+            _ => unreachable!(),
+        };
+
+        Some(!newly_cleared.is_empty())
+    }
+}
+
 #[derive(
     Insertable,
     Identifiable,
@@ -278,6 +350,7 @@ pub struct Unlock {
     pub glasses_styles: Bitmask<GLASSES_STYLES_BITMASK_SIZE>,
     pub chocobo_taxi_stands: Bitmask<CHOCOBO_TAXI_STANDS_BITMASK_SIZE>,
     pub titles: Bitmask<TITLE_UNLOCK_BITMASK_SIZE>,
+    pub learned_actions: LearnedActions,
 }
 
 #[derive(
@@ -340,6 +413,39 @@ pub struct Friends {
     pub is_pending: i32,
 }
 
+#[derive(Insertable, Identifiable, Queryable, Selectable, AsChangeset, Debug, Default, Clone)]
+#[diesel(table_name = super::schema::free_company)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(primary_key(id))]
+pub struct FreeCompany {
+    pub id: i64,
+    pub name: String,
+    /// The short tag shown next to members' names, up to `FC_TAG_MAX_LENGTH` characters.
+    pub tag: String,
+    pub creation_time: i64,
+}
+
+#[derive(Insertable, Identifiable, Queryable, Selectable, AsChangeset, Debug, Default, Clone)]
+#[diesel(table_name = super::schema::free_company_members)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(primary_key(content_id))]
+pub struct FreeCompanyMembers {
+    pub content_id: i64,
+    pub free_company_id: i64,
+    pub join_time: i64,
+}
+
+#[derive(Insertable, Identifiable, Queryable, Selectable, AsChangeset, Debug, Default, Clone)]
+#[diesel(table_name = super::schema::blacklist)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(primary_key(id))]
+pub struct Blacklist {
+    // Fake ID because diesel doesn't support tables without primary IDs
+    pub id: i64,
+    pub content_id: i64,
+    pub blocked_content_id: i64,
+}
+
 #[derive(Insertable, Queryable, Selectable, AsChangeset, Debug, Default, Clone)]
 #[diesel(table_name = super::schema::party)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
@@ -410,3 +516,29 @@ pub struct GrandCompany {
     pub active_company: kawari::ipc::zone::GrandCompany,
     pub company_ranks: GrandCompanyRanks,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clear_dungeon() {
+        let mut content = Content::default();
+
+        // Sastasha
+        assert_eq!(content.clear(&InstanceContentType::Dungeon, 4), Some(true));
+        assert!(content.cleared_dungeons.contains(3));
+        assert!(!content.cleared_dungeons.contains(4));
+        assert!(!content.cleared_raids.contains(3));
+
+        // Clearing it again isn't a first clear
+        assert_eq!(content.clear(&InstanceContentType::Dungeon, 4), Some(false));
+    }
+
+    #[test]
+    fn test_clear_unknown_content() {
+        let mut content = Content::default();
+        assert_eq!(content.clear(&InstanceContentType::Mahjong, 1), None);
+        assert_eq!(content.clear(&InstanceContentType::Raid, 5), None);
+    }
+}
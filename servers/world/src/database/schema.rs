@@ -6,6 +6,8 @@ diesel::table! {
         gm_rank -> Integer,
         name -> Text,
         time_played_minutes -> BigInt,
+        schema_version -> Integer,
+        home_world_id -> Integer,
     }
 }
 
@@ -66,6 +68,7 @@ diesel::table! {
         is_online -> Bool,
         client_language -> Integer,
         current_mount -> Integer,
+        recast_timers -> Text,
     }
 }
 
@@ -140,6 +143,7 @@ diesel::table! {
         glasses_styles -> Text,
         chocobo_taxi_stands -> Text,
         titles -> Text,
+        learned_actions -> Text,
     }
 }
 
@@ -220,6 +224,33 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    free_company (id) {
+        id -> BigInt,
+        name -> Text,
+        tag -> Text,
+        creation_time -> BigInt,
+    }
+}
+
+diesel::table! {
+    free_company_members (content_id) {
+        content_id -> BigInt,
+        free_company_id -> BigInt,
+        join_time -> BigInt,
+    }
+}
+
+diesel::joinable!(free_company_members -> character (content_id));
+
+diesel::table! {
+    blacklist (id) {
+        id -> BigInt,
+        content_id -> BigInt,
+        blocked_content_id -> BigInt,
+    }
+}
+
 diesel::table! {
     grand_company (content_id) {
         content_id -> BigInt,
@@ -245,4 +276,6 @@ diesel::allow_tables_to_appear_in_same_query!(
     mentor,
     search_info,
     grand_company,
+    free_company,
+    free_company_members,
 );
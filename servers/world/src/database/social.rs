@@ -98,7 +98,7 @@ impl WorldDatabase {
         PartyMember {
             actor_id: found_character.actor_id,
             content_id: for_content_id,
-            world_id: config.world.world_id,
+            world_id: found_character.home_world_id_or(config.world.world_id),
             account_id: found_character.service_account_id as u64,
             name: found_character.name,
             ..Default::default()
@@ -186,6 +186,15 @@ impl WorldDatabase {
         new_status_mask
     }
 
+    /// Gets the search comment of `for_content_id`, which is empty if they never set one.
+    pub fn get_search_comment(&mut self, for_content_id: i64) -> String {
+        schema::search_info::dsl::search_info
+            .select(schema::search_info::dsl::comment)
+            .filter(schema::search_info::dsl::content_id.eq(for_content_id))
+            .first::<String>(&mut self.connection)
+            .unwrap_or_default()
+    }
+
     pub fn get_search_info(
         &mut self,
         game_data: &mut GameData,
@@ -193,11 +202,7 @@ impl WorldDatabase {
     ) -> ServerZoneIpcData {
         let config = get_config();
 
-        let comment = schema::search_info::dsl::search_info
-            .select(schema::search_info::dsl::comment)
-            .filter(schema::search_info::dsl::content_id.eq(for_content_id))
-            .first::<String>(&mut self.connection)
-            .unwrap_or_default();
+        let comment = self.get_search_comment(for_content_id);
 
         let levels = schema::classjob::dsl::classjob
             .select(schema::classjob::dsl::levels)
@@ -316,22 +321,36 @@ impl WorldDatabase {
             };
         }
 
-        let character_name;
+        let found_character;
         {
             use schema::character::dsl::*;
 
-            character_name = character
-                .select(name)
+            found_character = character
+                .select(models::Character::as_select())
                 .filter(content_id.eq(for_content_id))
-                .first::<String>(&mut self.connection)
+                .first(&mut self.connection)
                 .unwrap();
         }
 
         let config = get_config();
+        let mut home_world_id = found_character.home_world_id_or(config.world.world_id);
+
+        // The client can't show a world it doesn't know about.
+        if home_world_id != config.world.world_id
+            && game_data.get_world_name(home_world_id).is_none()
+        {
+            tracing::warn!(
+                "{} lives on world {home_world_id}, which isn't in the World Excel sheet!",
+                found_character.name
+            );
+            home_world_id = config.world.world_id;
+        }
+
+        let (current_world_id, home_world_id) = entry_world_ids(home_world_id, online);
 
         PlayerEntry {
             content_id: for_content_id as u64,
-            current_world_id: if online { config.world.world_id } else { 0 },
+            current_world_id,
             ui_flags: SocialListUIFlags::ENABLE_CONTEXT_MENU,
             unk2: [
                 0,
@@ -352,10 +371,86 @@ impl WorldDatabase {
             online_status_mask,
             classjob_id,
             classjob_level,
-            home_world_id: config.world.world_id,
-            name: character_name,
+            home_world_id,
+            name: found_character.name,
             grand_company,
+            fc_tag: self.find_fc_tag(for_content_id),
             ..Default::default()
         }
     }
 }
+
+/// Returns the current and home world of a social list entry. Travelling between worlds isn't supported yet, so anyone online is on their home world, and the client only wants a current world if they're online.
+fn entry_world_ids(home_world_id: u16, online: bool) -> (u16, u16) {
+    (if online { home_world_id } else { 0 }, home_world_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PlayerData;
+
+    use super::*;
+
+    #[test]
+    fn test_afk_online_status() {
+        let mut database = WorldDatabase::in_memory();
+        database.insert_test_character(1, true);
+
+        let mut data = PlayerData::default();
+        data.search_info.content_id = 1;
+        data.search_info.online_status = OnlineStatus::AwayFromKeyboard;
+        database.commit_search_info(&data);
+
+        // This is the mask that ends up in their social list entry.
+        let mask = database.determine_online_status_mask(1);
+        assert!(mask.has_status(OnlineStatus::Online));
+        assert!(mask.has_status(OnlineStatus::AwayFromKeyboard));
+    }
+
+    #[test]
+    fn test_entry_world_ids() {
+        let world_id = 63; // Gilgamesh
+
+        assert_eq!(entry_world_ids(world_id, true), (world_id, world_id));
+        assert_eq!(entry_world_ids(world_id, false), (0, world_id));
+    }
+
+    #[test]
+    fn test_home_world_id() {
+        let mut database = WorldDatabase::in_memory();
+        let configured_world_id = 63; // Gilgamesh
+
+        // Characters from before home worlds were recorded live on the configured world
+        database.insert_test_character(1, true);
+        let found = database.find_character_ids(Some(1), None).unwrap();
+        assert_eq!(
+            found.home_world_id_or(configured_world_id),
+            configured_world_id
+        );
+
+        // But everyone else keeps their own, so the client shows the world next to their name
+        let other_world_id = 79; // Cactuar
+        diesel::update(schema::character::table)
+            .filter(schema::character::content_id.eq(1))
+            .set(schema::character::home_world_id.eq(other_world_id as i32))
+            .execute(&mut database.connection)
+            .unwrap();
+        let found = database.find_character_ids(Some(1), None).unwrap();
+        assert_eq!(found.home_world_id_or(configured_world_id), other_world_id);
+        assert_eq!(database.find_party_member(1).world_id, other_world_id);
+    }
+
+    #[test]
+    fn test_search_comment_round_trip() {
+        let mut database = WorldDatabase::in_memory();
+        database.insert_test_character(1, true);
+
+        let mut data = PlayerData::default();
+        data.search_info.content_id = 1;
+        data.search_info.comment = "Looking for FATE buddies!".to_string();
+        database.commit_search_info(&data);
+
+        assert_eq!(database.get_search_comment(1), "Looking for FATE buddies!");
+        assert_eq!(database.get_search_comment(2), "");
+    }
+}
@@ -64,6 +64,14 @@ impl ShopEventHandler {
             bb_item = *tmp_bb_item;
         }
 
+        let cost = bb_item.quantity.saturating_mul(bb_item.price_low);
+        if connection.player_data.inventory.currency.gil.quantity < cost {
+            let error = "You don't have enough gil to buy back this item.";
+            connection.send_notice(error).await;
+            tracing::warn!(error);
+            return;
+        }
+
         let Some(item_dst_info) = connection
             .player_data
             .inventory
@@ -83,15 +91,18 @@ impl ShopEventHandler {
             .remove_item(shop_id, buyback_index);
 
         // Queue up the player's adjusted gil, but we're not going to send an entire inventory update to the client.
-        let cost = item_dst_info.quantity * bb_item.price_low;
-        let new_gil = connection.player_data.inventory.currency.gil.quantity - cost;
-        connection.player_data.inventory.currency.gil.quantity = new_gil;
+        connection
+            .player_data
+            .inventory
+            .currency
+            .try_spend_gil(cost);
+        let new_gil = connection.player_data.inventory.currency.gil.quantity;
 
         let shop_packets_to_send = [
             ServerZoneIpcSegment::new(ServerZoneIpcData::UpdateInventorySlot(ItemInfo {
                 sequence: connection.player_data.shop_sequence,
                 container: ContainerType::Currency,
-                slot: CurrencyStorage::get_slot_for_id(CurrencyKind::Gil),
+                slot: CurrencyStorage::GIL_SLOT,
                 quantity: new_gil,
                 item_id: CurrencyKind::Gil as u32,
                 ..Default::default()
@@ -109,7 +120,7 @@ impl ShopEventHandler {
                 params_count: 3,
                 item_id: bb_item.item_id,
                 item_quantity: item_dst_info.quantity,
-                total_sale_cost: item_dst_info.quantity * bb_item.price_low,
+                total_sale_cost: cost,
             }),
         ];
 
@@ -144,21 +155,23 @@ impl ShopEventHandler {
             }
 
             if let Some(item_info) = result {
-                if connection.player_data.inventory.currency.gil.quantity
-                    >= item_quantity * item_info.price_mid
-                {
+                let cost = item_quantity.saturating_mul(item_info.price_mid);
+                if connection.player_data.inventory.currency.gil.quantity >= cost {
                     if let Some(add_result) = connection
                         .player_data
                         .inventory
                         .add_in_next_free_slot(Item::new(&item_info, item_quantity))
                     {
-                        connection.player_data.inventory.currency.gil.quantity -=
-                            item_quantity * item_info.price_mid;
+                        connection
+                            .player_data
+                            .inventory
+                            .currency
+                            .try_spend_gil(cost);
                         Self::send_gilshop_item_update(
                             connection,
                             ItemInfo {
                                 container: ContainerType::Currency,
-                                slot: CurrencyStorage::get_slot_for_id(CurrencyKind::Gil),
+                                slot: CurrencyStorage::GIL_SLOT,
                                 ..connection.player_data.inventory.currency.gil.into()
                             },
                         )
@@ -169,7 +182,7 @@ impl ShopEventHandler {
                             .await;
 
                         Self::send_gilshop_item_update(connection, add_result).await;
-                        Self::send_gilshop_ack(
+                        Self::send_shop_log_message(
                             connection,
                             event.id,
                             item_info.id,
@@ -204,7 +217,15 @@ impl ShopEventHandler {
                     .await;
             }
         } else if buy_sell_mode == SELL {
-            let storage = get_container_type(item_index as u32).unwrap();
+            // Key items can never be sold.
+            let Some(storage) = get_container_type(item_index as u32)
+                .filter(|storage| *storage != ContainerType::KeyItems)
+            else {
+                tracing::warn!(
+                    "Client attempted to sell an item from unsellable container {item_index}! Rejecting request!"
+                );
+                return;
+            };
             let index = item_quantity;
             let result;
             let quantity;
@@ -225,19 +246,24 @@ impl ShopEventHandler {
             }
 
             if let Some(item_info) = result {
+                let sale_price = connection.preview_sale(storage, index as u16, quantity);
+
                 let bb_item = Item::new(&item_info, quantity);
                 connection
                     .player_data
                     .buyback_list
                     .push_item(event.id, bb_item);
 
-                connection.player_data.inventory.currency.gil.quantity +=
-                    quantity * item_info.price_low;
+                connection
+                    .player_data
+                    .inventory
+                    .currency
+                    .add_gil(sale_price);
                 Self::send_gilshop_item_update(
                     connection,
                     ItemInfo {
                         container: ContainerType::Currency,
-                        slot: CurrencyStorage::get_slot_for_id(CurrencyKind::Gil),
+                        slot: CurrencyStorage::GIL_SLOT,
                         ..connection.player_data.inventory.currency.gil.into()
                     },
                 )
@@ -300,7 +326,7 @@ impl ShopEventHandler {
                     .send_inventory_transaction_finish(0x100, 0x300)
                     .await;
 
-                Self::send_gilshop_ack(
+                Self::send_shop_log_message(
                     connection,
                     event.id,
                     item_info.id,
@@ -329,8 +355,8 @@ impl ShopEventHandler {
         }
     }
 
-    // TODO: When we add support for ItemObtainedLogMessage, rename this and update this
-    async fn send_gilshop_ack(
+    /// Tells the player what they bought, sold or bought back in their chat log.
+    async fn send_shop_log_message(
         connection: &mut ZoneConnection,
         event_id: u32,
         item_id: u32,
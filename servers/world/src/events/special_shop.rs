@@ -82,7 +82,7 @@ impl SpecialShopEventHandler {
                         .await;
 
                     ShopEventHandler::send_gilshop_item_update(connection, add_result).await;
-                    Self::send_gilshop_ack(
+                    Self::send_shop_log_message(
                         connection,
                         event.id,
                         item_info.id,
@@ -121,8 +121,8 @@ impl SpecialShopEventHandler {
         false
     }
 
-    // TODO: When we add support for ItemObtainedLogMessage, rename this and update this
-    async fn send_gilshop_ack(
+    /// Tells the player what they bought, sold or bought back in their chat log.
+    async fn send_shop_log_message(
         connection: &mut ZoneConnection,
         event_id: u32,
         item_id: u32,
@@ -1,5 +1,9 @@
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+use parking_lot::Mutex;
 
 use icarus::Action::ActionSheet;
 use icarus::AetherCurrentCompFlgSet::AetherCurrentCompFlgSetSheet;
@@ -49,21 +53,30 @@ use icarus::TerritoryType::TerritoryTypeSheet;
 use icarus::TopicSelect::TopicSelectSheet;
 use icarus::WarpLogic::WarpLogicSheet;
 use icarus::WeatherRate::WeatherRateSheet;
+use icarus::World::WorldSheet;
 use icarus::{Tribe::TribeSheet, Warp::WarpSheet};
-use kawari::ipc::zone::{CommonSpawn, DamageElement, PlotSize};
+use kawari::ipc::zone::{CommonSpawn, DamageElement, ObjectKind, PlotSize, SpawnNpc};
 use physis::equipment::EquipSlotCategory;
 use physis::resource::{Resource, ResourceResolver, SqPackResource, UnpackedResource};
 use physis::{Language, TerritoryIntendedUse};
 
-use kawari::common::{CustomizeData, LegacyEquipmentModelId, WeaponModelId, timestamp_secs};
+use kawari::common::{
+    CustomizeData, LegacyEquipmentModelId, Position, WeaponModelId, timestamp_secs,
+};
 use kawari::common::{InstanceContentType, get_aether_current_comp_flg_set_to_screenimage};
-use kawari::config::get_config;
+use kawari::config::{FilesystemConfig, get_config};
+use kawari::constants::AETHERYTE_UNLOCK_BITMASK_SIZE;
 use strum::FromRepr;
 
+use crate::bitmask::Bitmask;
+use crate::server::ZoneCache;
+
 /// Convenient methods built on top of Physis to access data relevant to the server
 #[derive(Clone)]
 pub struct GameData {
     pub resource: ResourceResolver,
+    /// The language used for reading sheets that contain text. Language-agnostic sheets still use `Language::None`.
+    pub default_language: Language,
 
     // Remember to keep frequently accessed or large sheets here, until we have a better caching solution.
     pub item_sheet: ItemSheet,
@@ -88,12 +101,7 @@ pub struct GameData {
     pub battalion_sheet: BattalionSheet,
     pub enpc_base_sheet: ENpcBaseSheet,
     pub equip_slot_category_sheet: EquipSlotCategorySheet,
-}
-
-impl Default for GameData {
-    fn default() -> Self {
-        Self::new()
-    }
+    pub zone_cache: ZoneCache,
 }
 
 #[derive(Debug)]
@@ -131,6 +139,8 @@ pub struct ItemRow {
     pub item_level: u16,
     /// The item's ClassJobCategory.
     pub classjob_category: u8,
+    /// The item's ItemUICategory, which is what the client uses to group items.
+    pub ui_category: u8,
 
     /// Stat modifier stuff
     pub base_param_ids: [u8; 6],
@@ -140,6 +150,38 @@ pub struct ItemRow {
     pub defense: u16,
     /// Magic defense;
     pub magic_defense: u16,
+    /// The equipment slot this item goes into, if it's equipment.
+    pub equip_slot: Option<u16>,
+}
+
+/// The appearance of an Event NPC, pulled from the ENpcBase and NpcEquip sheets.
+#[derive(Debug, Default, Clone)]
+pub struct NpcAppearance {
+    /// Index into the ModelChara sheet.
+    pub model_chara: u16,
+    /// Their customize data, only used for humanoid models.
+    pub look: CustomizeData,
+    /// Their equipment, only the model fields are filled in.
+    pub equip: CommonSpawn,
+}
+
+impl NpcAppearance {
+    /// Builds a spawn for the Event NPC `enpc_id` with this appearance.
+    pub fn to_spawn(&self, enpc_id: u32, position: Position, rotation: f32) -> SpawnNpc {
+        SpawnNpc {
+            common: CommonSpawn {
+                base_id: enpc_id,
+                name_id: enpc_id,
+                model_chara: self.model_chara,
+                object_kind: ObjectKind::EventNpc,
+                position,
+                rotation,
+                look: self.look.clone(),
+                ..self.equip.clone()
+            },
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -194,6 +236,45 @@ impl Modifiers {
     }
 }
 
+/// Errors that can occur while loading the game data.
+#[derive(Debug)]
+pub enum GameDataError {
+    /// A required Excel sheet couldn't be read, which usually means the game path is wrong.
+    MissingSheet(&'static str),
+}
+
+/// Why a zone couldn't be found by name, see `GameData::find_territory_by_name`.
+#[derive(Debug, PartialEq)]
+pub enum TerritoryLookupError {
+    /// No zone has this name.
+    NotFound,
+    /// More than one zone has this place name, these are their ids.
+    Ambiguous(Vec<u16>),
+}
+
+impl std::fmt::Display for TerritoryLookupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TerritoryLookupError::NotFound => write!(f, "no zone has this name"),
+            TerritoryLookupError::Ambiguous(ids) => write!(
+                f,
+                "more than one zone has this name ({ids:?}), use its internal name instead"
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for GameDataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GameDataError::MissingSheet(name) => write!(
+                f,
+                "Failed to read the {name} Excel sheet. Check that game_path in the filesystem config points to your game directory!"
+            ),
+        }
+    }
+}
+
 #[repr(u8)]
 #[derive(FromRepr)]
 pub enum Roulette {
@@ -213,18 +294,38 @@ pub enum Roulette {
 }
 
 impl GameData {
-    pub fn new() -> Self {
+    /// Returns `requested` if the Excel sheets exist in that language, otherwise falls back to English.
+    fn resolve_language(resource: &mut ResourceResolver, requested: Language) -> Language {
+        if requested == Language::English
+            || OnlineStatusSheet::read_from(resource, requested).is_ok()
+        {
+            return requested;
+        }
+
+        tracing::warn!(
+            "The game data doesn't contain {requested:?} Excel sheets, falling back to English!"
+        );
+        Language::English
+    }
+
+    pub fn new() -> Result<Self, GameDataError> {
         let config = get_config();
 
+        Self::load(&config.filesystem, config.world.language())
+    }
+
+    /// Reads the game data from the paths in `filesystem`, returning an error if any of the required Excel sheets are missing.
+    pub fn load(filesystem: &FilesystemConfig, language: Language) -> Result<Self, GameDataError> {
         let mut sqpack_resource = SqPackResourceSpy::from(
-            SqPackResource::from_existing(&config.filesystem.game_path),
-            &config.filesystem.unpack_path,
+            SqPackResource::from_existing(&filesystem.game_path),
+            &filesystem.unpack_path,
         );
 
+        // This isn't fatal yet, since the sheets could still be found in the additional search paths.
         if sqpack_resource.sqpack_resource.repositories.is_empty() {
             tracing::warn!(
                 "You have an empty game directory ({:?}). This may be a configuration issue, you may want to read the usage documentation.",
-                config.filesystem.game_path
+                filesystem.game_path
             );
         }
 
@@ -234,90 +335,92 @@ impl GameData {
         sqpack_resource.sqpack_resource.preload_index_files();
 
         let mut resource_resolver = ResourceResolver::new();
-        for path in config.filesystem.additional_search_paths {
-            let unpacked_resource = UnpackedResource::from_existing(&path);
+        for path in &filesystem.additional_search_paths {
+            let unpacked_resource = UnpackedResource::from_existing(path);
             resource_resolver.add_source(unpacked_resource);
         }
         resource_resolver.add_source(sqpack_resource);
 
+        let default_language = Self::resolve_language(&mut resource_resolver, language);
+
         let mut classjob_exp_indexes = Vec::new();
 
-        let classjob_sheet =
-            ClassJobSheet::read_from(&mut resource_resolver, config.world.language())
-                .expect("Failed to read ClassJobSheet, does the Excel files exist?");
+        let classjob_sheet = ClassJobSheet::read_from(&mut resource_resolver, default_language)
+            .map_err(|_| GameDataError::MissingSheet("ClassJob"))?;
         for (_, row) in classjob_sheet.into_iter().flatten_subrows() {
             classjob_exp_indexes.push(row.ExpArrayIndex);
         }
 
-        let item_sheet = ItemSheet::read_from(&mut resource_resolver, config.world.language())
-            .expect("Failed to read ItemSheet, does the Excel files exist?");
+        let item_sheet = ItemSheet::read_from(&mut resource_resolver, default_language)
+            .map_err(|_| GameDataError::MissingSheet("Item"))?;
 
         let weather_rate_sheet =
             WeatherRateSheet::read_from(&mut resource_resolver, Language::None)
-                .expect("Failed to read WeatherRateSheet, does the Excel files exist?");
+                .map_err(|_| GameDataError::MissingSheet("WeatherRate"))?;
 
-        let quest_sheet = QuestSheet::read_from(&mut resource_resolver, config.world.language())
-            .expect("Failed to read Quest, does the Excel files exist?");
+        let quest_sheet = QuestSheet::read_from(&mut resource_resolver, default_language)
+            .map_err(|_| GameDataError::MissingSheet("Quest"))?;
 
         let territory_type_sheet =
             TerritoryTypeSheet::read_from(&mut resource_resolver, Language::None)
-                .expect("Failed to read TerritoryTypeSheet, does the Excel files exist?");
+                .map_err(|_| GameDataError::MissingSheet("TerritoryType"))?;
 
-        let warp_sheet = WarpSheet::read_from(&mut resource_resolver, config.world.language())
-            .expect("Failed to read Warp, does the Excel files exist?");
+        let warp_sheet = WarpSheet::read_from(&mut resource_resolver, default_language)
+            .map_err(|_| GameDataError::MissingSheet("Warp"))?;
 
-        let action_sheet = ActionSheet::read_from(&mut resource_resolver, config.world.language())
-            .expect("Failed to read Action, does the Excel files exist?");
+        let action_sheet = ActionSheet::read_from(&mut resource_resolver, default_language)
+            .map_err(|_| GameDataError::MissingSheet("Action"))?;
 
-        let place_name_sheet =
-            PlaceNameSheet::read_from(&mut resource_resolver, config.world.language())
-                .expect("Failed to read PlaceName, does the Excel files exist?");
+        let place_name_sheet = PlaceNameSheet::read_from(&mut resource_resolver, default_language)
+            .map_err(|_| GameDataError::MissingSheet("PlaceName"))?;
 
         let custom_talk_sheet =
-            CustomTalkSheet::read_from(&mut resource_resolver, config.world.language())
-                .expect("Failed to read CustomTalk, does the Excel files exist?");
+            CustomTalkSheet::read_from(&mut resource_resolver, default_language)
+                .map_err(|_| GameDataError::MissingSheet("CustomTalk"))?;
 
-        let tribe_sheet = TribeSheet::read_from(&mut resource_resolver, config.world.language())
-            .expect("Failed to read Tribe, does the Excel files exist?");
+        let tribe_sheet = TribeSheet::read_from(&mut resource_resolver, default_language)
+            .map_err(|_| GameDataError::MissingSheet("Tribe"))?;
 
         let eobj_sheet = EObjSheet::read_from(&mut resource_resolver, Language::None)
-            .expect("Failed to read EObj, does the Excel files exist?");
+            .map_err(|_| GameDataError::MissingSheet("EObj"))?;
 
         let switch_talk_sheet =
-            SwitchTalkVariationSheet::read_from(&mut resource_resolver, Language::None).unwrap();
+            SwitchTalkVariationSheet::read_from(&mut resource_resolver, Language::None)
+                .map_err(|_| GameDataError::MissingSheet("SwitchTalkVariation"))?;
 
-        let param_grow_sheet =
-            ParamGrowSheet::read_from(&mut resource_resolver, Language::None).unwrap();
+        let param_grow_sheet = ParamGrowSheet::read_from(&mut resource_resolver, Language::None)
+            .map_err(|_| GameDataError::MissingSheet("ParamGrow"))?;
 
-        let bnpc_base_sheet =
-            BNpcBaseSheet::read_from(&mut resource_resolver, Language::None).unwrap();
+        let bnpc_base_sheet = BNpcBaseSheet::read_from(&mut resource_resolver, Language::None)
+            .map_err(|_| GameDataError::MissingSheet("BNpcBase"))?;
 
         let bnpc_customize_sheet =
-            BNpcCustomizeSheet::read_from(&mut resource_resolver, Language::None).unwrap();
+            BNpcCustomizeSheet::read_from(&mut resource_resolver, Language::None)
+                .map_err(|_| GameDataError::MissingSheet("BNpcCustomize"))?;
 
-        let item_level_sheet =
-            ItemLevelSheet::read_from(&mut resource_resolver, Language::None).unwrap();
+        let item_level_sheet = ItemLevelSheet::read_from(&mut resource_resolver, Language::None)
+            .map_err(|_| GameDataError::MissingSheet("ItemLevel"))?;
 
         let gimmick_rect_sheet =
-            GimmickRectSheet::read_from(&mut resource_resolver, Language::None).unwrap();
+            GimmickRectSheet::read_from(&mut resource_resolver, Language::None)
+                .map_err(|_| GameDataError::MissingSheet("GimmickRect"))?;
 
-        let base_param_sheet =
-            BaseParamSheet::read_from(&mut resource_resolver, config.world.language())
-                .ok()
-                .unwrap();
+        let base_param_sheet = BaseParamSheet::read_from(&mut resource_resolver, default_language)
+            .map_err(|_| GameDataError::MissingSheet("BaseParam"))?;
 
         let battalion_sheet = BattalionSheet::read_from(&mut resource_resolver, Language::None)
-            .ok()
-            .unwrap();
+            .map_err(|_| GameDataError::MissingSheet("Battalion"))?;
 
-        let enpc_base_sheet =
-            ENpcBaseSheet::read_from(&mut resource_resolver, Language::None).unwrap();
+        let enpc_base_sheet = ENpcBaseSheet::read_from(&mut resource_resolver, Language::None)
+            .map_err(|_| GameDataError::MissingSheet("ENpcBase"))?;
 
         let equip_slot_category_sheet =
-            EquipSlotCategorySheet::read_from(&mut resource_resolver, Language::None).unwrap();
+            EquipSlotCategorySheet::read_from(&mut resource_resolver, Language::None)
+                .map_err(|_| GameDataError::MissingSheet("EquipSlotCategory"))?;
 
-        Self {
+        Ok(Self {
             resource: resource_resolver,
+            default_language,
             item_sheet,
             classjob_exp_indexes,
             weather_rate_sheet,
@@ -340,7 +443,8 @@ impl GameData {
             battalion_sheet,
             enpc_base_sheet,
             equip_slot_category_sheet,
-        }
+            zone_cache: ZoneCache::default(),
+        })
     }
 
     /// Gets the starting city-state from a given class/job id.
@@ -349,6 +453,11 @@ impl GameData {
         Some(row.StartingTown)
     }
 
+    /// Whether `tribe_id` exists in the Tribe sheet.
+    pub fn is_valid_tribe(&mut self, tribe_id: u8) -> bool {
+        tribe_id != 0 && self.tribe_sheet.row(tribe_id as u32).is_some()
+    }
+
     pub fn get_racial_base_attributes(&mut self, tribe_id: u8) -> Option<Attributes> {
         let row = self.tribe_sheet.row(tribe_id as u32)?;
 
@@ -373,15 +482,16 @@ impl GameData {
             }
 
             ItemInfoQuery::ByName(ref query_item_name) => {
-                for (id, row) in self.item_sheet.into_iter().flatten_subrows() {
-                    if row
-                        .Name
-                        .to_lowercase()
-                        .contains(&query_item_name.to_lowercase())
-                    {
-                        result = Some((row.clone(), id));
-                        break;
-                    }
+                let names = self
+                    .item_sheet
+                    .into_iter()
+                    .flatten_subrows()
+                    .map(|(id, row)| (id, row.Name.clone()));
+
+                if let Some(id) = best_name_match(names, query_item_name)
+                    && let Some(row) = self.item_sheet.row(id)
+                {
+                    result = Some((row, id));
                 }
             }
         }
@@ -399,6 +509,7 @@ impl GameData {
                 stack_size: matched_row.StackSize,
                 item_level: matched_row.LevelItem,
                 classjob_category: matched_row.ClassJobCategory,
+                ui_category: matched_row.ItemUICategory as u8,
                 base_param_ids: matched_row.BaseParam,
                 base_param_values: matched_row.BaseParamValue,
                 defense: matched_row.DefensePhys,
@@ -406,6 +517,7 @@ impl GameData {
                 equip_restrictions: self
                     .get_equipslot_restrictions(matched_row.EquipSlotCategory)
                     .unwrap(),
+                equip_slot: self.get_equipslot_category(matched_row.EquipSlotCategory),
             };
 
             return Some(item_info);
@@ -414,6 +526,20 @@ impl GameData {
         None
     }
 
+    /// Gets information on every item whose name contains `name`, with the best matches first. See `get_item_info`.
+    pub fn get_all_items_matching(&mut self, name: &str) -> Vec<ItemRow> {
+        let names = self
+            .item_sheet
+            .into_iter()
+            .flatten_subrows()
+            .map(|(id, row)| (id, row.Name.clone()));
+
+        rank_name_matches(names, name)
+            .into_iter()
+            .filter_map(|id| self.get_item_info(ItemInfoQuery::ById(id)))
+            .collect()
+    }
+
     /// Gets the primary model ID for a given item ID.
     pub fn get_primary_model_id(&mut self, item_id: u32) -> Option<u64> {
         if let Some(item_info) = self.get_item_info(ItemInfoQuery::ById(item_id)) {
@@ -448,9 +574,8 @@ impl GameData {
 
         let warp_logic_id = row.WarpLogic;
 
-        let config = get_config();
         let warp_logic_sheet =
-            WarpLogicSheet::read_from(&mut self.resource, config.world.language()).unwrap();
+            WarpLogicSheet::read_from(&mut self.resource, self.default_language).unwrap();
         let warp_logic_row = warp_logic_sheet.row(warp_logic_id as u32).unwrap();
 
         warp_logic_row.WarpName
@@ -461,11 +586,9 @@ impl GameData {
         aetheryte_id: u32,
         housing_aethernet: bool,
     ) -> Option<(u32, u16)> {
-        let config = get_config();
-
         if !housing_aethernet {
             let sheet =
-                AetheryteSheet::read_from(&mut self.resource, config.world.language()).ok()?;
+                AetheryteSheet::read_from(&mut self.resource, self.default_language).ok()?;
             let row = sheet.row(aetheryte_id)?;
 
             // TODO: just look in the level sheet?
@@ -489,13 +612,53 @@ impl GameData {
 
     /// Checks if it's a big Aetheryte (true) or just a shard (false.)
     pub fn is_aetheryte(&mut self, aetheryte_id: u32) -> bool {
-        let config = get_config();
-        let sheet = AetheryteSheet::read_from(&mut self.resource, config.world.language()).unwrap();
+        let sheet = AetheryteSheet::read_from(&mut self.resource, self.default_language).unwrap();
         let row = sheet.row(aetheryte_id).unwrap();
 
         row.IsAetheryte
     }
 
+    /// Finds a safe aetheryte to send someone to in `zone_id`, preferring a big Aetheryte over any shards. Returns None if the zone has no aetherytes.
+    pub fn get_default_aetheryte_for_zone(&mut self, zone_id: u16) -> Option<u32> {
+        let sheet = AetheryteSheet::read_from(&mut self.resource, self.default_language).ok()?;
+
+        let aetherytes: Vec<(u32, bool, u16)> = sheet
+            .into_iter()
+            .flatten_subrows()
+            .map(|(id, row)| (id, row.IsAetheryte, row.Territory))
+            .collect();
+
+        pick_default_aetheryte(&aetherytes, zone_id)
+    }
+
+    /// Lists the aetherytes unlocked in `unlocked` as a teleport destination menu, returning their ID, place name and gil cost.
+    /// The cost is currently a flat `teleport_cost` since we don't have the positions to scale it by distance.
+    pub fn list_aetherytes(
+        &mut self,
+        unlocked: &Bitmask<AETHERYTE_UNLOCK_BITMASK_SIZE>,
+        teleport_cost: u32,
+    ) -> Vec<(u32, String, u32)> {
+        let Ok(sheet) = AetheryteSheet::read_from(&mut self.resource, self.default_language) else {
+            return Vec::new();
+        };
+
+        let aetherytes: Vec<(u32, bool, u16)> = sheet
+            .into_iter()
+            .flatten_subrows()
+            .map(|(id, row)| (id, row.IsAetheryte, row.Territory))
+            .collect();
+
+        filter_unlocked_aetherytes(&aetherytes, unlocked)
+            .into_iter()
+            .map(|(id, zone_id)| {
+                let name = self
+                    .get_territory_name(zone_id as u32, TerritoryNameKind::Place)
+                    .unwrap_or_default();
+                (id, name, teleport_cost)
+            })
+            .collect()
+    }
+
     /// Retrieves a zone's internal name, place name or parent region name.
     pub fn get_territory_name(&mut self, zone_id: u32, which: TerritoryNameKind) -> Option<String> {
         let row = self.territory_type_sheet.row(zone_id)?;
@@ -512,6 +675,30 @@ impl GameData {
         Some(row.Name)
     }
 
+    /// Finds the zone with this internal (e.g. "s1t1") or place (e.g. "Limsa Lominsa Upper Decks") name, ignoring case.
+    pub fn find_territory_by_name(&mut self, name: &str) -> Result<u16, TerritoryLookupError> {
+        let rows: Vec<(u32, String, u32)> = self
+            .territory_type_sheet
+            .into_iter()
+            .flatten_subrows()
+            .map(|(id, row)| (id, row.Name.clone(), row.PlaceName as u32))
+            .collect();
+
+        let territories: Vec<(u32, String, String)> = rows
+            .into_iter()
+            .map(|(id, internal_name, place_name_id)| {
+                let place_name = self
+                    .place_name_sheet
+                    .row(place_name_id)
+                    .map(|row| row.Name)
+                    .unwrap_or_default();
+                (id, internal_name, place_name)
+            })
+            .collect();
+
+        match_territory_name(territories, name)
+    }
+
     /// Turn an equip slot category id into a slot for the equipped inventory
     pub fn get_equipslot_category(&mut self, equipslot_id: u8) -> Option<u16> {
         let row = self.equip_slot_category_sheet.row(equipslot_id as u32)?;
@@ -606,9 +793,70 @@ impl GameData {
         Some(row.Cast100ms)
     }
 
+    /// Returns the MP cost of this action, or zero if it costs something else (or nothing at all.)
+    pub fn get_action_cost(&mut self, action_id: u32) -> Option<u16> {
+        let row = self.action_sheet.row(action_id)?;
+
+        // A cost type of 3 is MP, and the value is stored in hundreds.
+        if row.PrimaryCostType == 3 {
+            Some((row.PrimaryCostValue as u16).saturating_mul(100))
+        } else {
+            Some(0)
+        }
+    }
+
+    /// Returns the cast type (the shape of the area of effect), effect range and width (for lines) of this action.
+    pub fn get_action_effect_range(&mut self, action_id: u32) -> Option<(u8, u8, u8)> {
+        let row = self.action_sheet.row(action_id)?;
+
+        Some((row.CastType, row.EffectRange, row.XAxisModifier))
+    }
+
+    /// Returns how far away (in yalms) the target of this action can be, zero if it can only be used on yourself.
+    pub fn get_action_range(&mut self, action_id: u32) -> Option<u8> {
+        let row = self.action_sheet.row(action_id)?;
+
+        // Melee actions have a range of -1 in the sheet.
+        if row.Range < 0 {
+            return Some(MELEE_ACTION_RANGE);
+        }
+
+        Some(row.Range as u8)
+    }
+
+    /// Returns whether this action is aimed at a location on the ground, instead of an actor.
+    pub fn get_action_targets_area(&mut self, action_id: u32) -> Option<bool> {
+        let row = self.action_sheet.row(action_id)?;
+
+        Some(row.TargetArea)
+    }
+
+    /// Gets the UnlockLink gating this action, zero if there is none.
+    pub fn get_action_unlock_link(&mut self, action_id: u32) -> Option<u32> {
+        let row = self.action_sheet.row(action_id)?;
+
+        Some(row.UnlockLink as u32)
+    }
+
+    /// Returns the recast time of this action, in 100ms units.
+    pub fn get_action_recast(&mut self, action_id: u32) -> Option<u16> {
+        let row = self.action_sheet.row(action_id)?;
+
+        Some(row.Recast100ms)
+    }
+
     /// Calculates the current weather at the current time
-    // TODO: instead allow targetting a specific time to calculate forcecasts
     pub fn get_weather_rate(&mut self, weather_rate_id: u32) -> Option<i32> {
+        self.get_weather_rate_at(weather_rate_id, timestamp_secs())
+    }
+
+    /// Returns which weather period `unix_seconds` falls in. The weather can only change when this does.
+    pub fn get_weather_period(unix_seconds: u32) -> u32 {
+        unix_seconds / WEATHER_PERIOD_SECONDS
+    }
+
+    /// Calculates the weather at `unix_seconds`.
+    fn get_weather_rate_at(&mut self, weather_rate_id: u32, unix_seconds: u32) -> Option<i32> {
         let row = self.weather_rate_sheet.row(weather_rate_id)?;
 
         // sum up the rates
@@ -619,7 +867,7 @@ impl GameData {
             *rate = sum;
         }
 
-        let target = Self::calculate_target();
+        let target = calculate_weather_target(unix_seconds);
         let weather_and_rates: Vec<(i32, i32)> = row
             .Weather
             .iter()
@@ -640,22 +888,6 @@ impl GameData {
         )
     }
 
-    /// Calculate target window for weather calculations
-    fn calculate_target() -> i32 {
-        let unix_seconds = timestamp_secs();
-        let eorzean_hours = f32::floor(unix_seconds as f32 / 175.0) as u32;
-        let eorzean_days = f32::floor(eorzean_hours as f32 / 24.0) as u32;
-
-        let time_chunk = (eorzean_hours % 24) - (eorzean_hours % 8);
-        let time_chunk = (time_chunk + 8) % 24;
-        let calc_base = (eorzean_days * 100) + time_chunk;
-
-        let step1 = (calc_base << 0xB) ^ calc_base;
-        let step2 = (step1 >> 8) ^ step1;
-
-        (step2 % 100) as i32
-    }
-
     /// Gets the current weather for the given zone id
     pub fn get_weather(&mut self, zone_id: u32) -> Option<i32> {
         let row = self.territory_type_sheet.row(zone_id)?;
@@ -664,6 +896,29 @@ impl GameData {
         self.get_weather_rate(weather_rate_id as u32)
     }
 
+    /// Gets the weather for the given zone id at `unix_seconds`.
+    pub fn get_weather_at(&mut self, zone_id: u32, unix_seconds: u32) -> Option<i32> {
+        let row = self.territory_type_sheet.row(zone_id)?;
+
+        self.get_weather_rate_at(row.WeatherRate as u32, unix_seconds)
+    }
+
+    /// Gets the next `count` weathers for the given zone id, not including the current one.
+    pub fn get_weather_forecast(&mut self, zone_id: u32, count: u32) -> Vec<i32> {
+        let Some(row) = self.territory_type_sheet.row(zone_id) else {
+            return Vec::new();
+        };
+
+        let weather_rate_id = row.WeatherRate as u32;
+        let now = timestamp_secs();
+
+        (1..=count)
+            .filter_map(|i| {
+                self.get_weather_rate_at(weather_rate_id, now + i * WEATHER_PERIOD_SECONDS)
+            })
+            .collect()
+    }
+
     /// Gets the array index used in EXP & levels.
     pub fn get_exp_array_index(&self, classjob_id: u16) -> Option<i8> {
         self.classjob_exp_indexes
@@ -672,6 +927,21 @@ impl GameData {
             .filter(|x| *x != -1)
     }
 
+    /// Gets the abbreviation for a given class/job, e.g. "WHM".
+    pub fn get_classjob_abbreviation(&mut self, classjob_id: u8) -> Option<String> {
+        let row = self.classjob_sheet.row(classjob_id as u32)?;
+
+        Some(row.Abbreviation)
+    }
+
+    /// Gets the name of a world, as shown by the client.
+    pub fn get_world_name(&mut self, world_id: u16) -> Option<String> {
+        let sheet = WorldSheet::read_from(&mut self.resource, Language::None).ok()?;
+        let row = sheet.row(world_id as u32)?;
+
+        Some(row.Name)
+    }
+
     /// Gets the job index for a given class.
     pub fn get_job_index(&mut self, classjob_id: u16) -> Option<u8> {
         let row = self.classjob_sheet.row(classjob_id as u32)?;
@@ -688,11 +958,30 @@ impl GameData {
         self.get_item_info(ItemInfoQuery::ById(item_id as u32))
     }
 
+    /// Gets every item and its cost from the specified shop, in the order they're listed.
+    pub fn get_gilshop_items(&mut self, gilshop_id: u32) -> Vec<ItemRow> {
+        let Ok(sheet) = GilShopItemSheet::read_from(&mut self.resource, Language::None) else {
+            return Vec::new();
+        };
+        let Some((_, subrows)) = sheet.into_iter().find(|(row_id, _)| *row_id == gilshop_id) else {
+            return Vec::new();
+        };
+
+        let item_ids = list_shop_item_ids(
+            subrows
+                .iter()
+                .map(|(index, row)| (*index, row.Item as u32))
+                .collect(),
+        );
+        item_ids
+            .into_iter()
+            .filter_map(|item_id| self.get_item_info(ItemInfoQuery::ById(item_id)))
+            .collect()
+    }
+
     /// Gets the item and its cost from the specified SpecialShop.
     pub fn get_specialshop_item(&mut self, gilshop_id: u32, index: u16) -> Option<ItemRow> {
-        let config = get_config();
-        let sheet =
-            SpecialShopSheet::read_from(&mut self.resource, config.world.language()).ok()?;
+        let sheet = SpecialShopSheet::read_from(&mut self.resource, self.default_language).ok()?;
         let row = sheet.row(gilshop_id)?;
         let item_id = row.Item[index as usize].Item[0]; // TODO: why are there two items?
 
@@ -701,9 +990,8 @@ impl GameData {
 
     /// Gets the zone id for the given ContentFinderCondition ID.
     pub fn find_zone_for_content(&mut self, content_id: u16) -> Option<u16> {
-        let config = get_config();
         let content_finder_sheet =
-            ContentFinderConditionSheet::read_from(&mut self.resource, config.world.language())
+            ContentFinderConditionSheet::read_from(&mut self.resource, self.default_language)
                 .unwrap();
         let content_finder_row = content_finder_sheet.row(content_id as u32)?;
 
@@ -792,6 +1080,17 @@ impl GameData {
         Some((model_row_id, customize, row.NpcEquip))
     }
 
+    /// Gets how the Event NPC `enpc_id` looks, including their equipment.
+    pub fn get_npc_appearance(&mut self, enpc_id: u32) -> Option<NpcAppearance> {
+        let (model_chara, look, equip) = self.find_enpc(enpc_id)?;
+
+        Some(NpcAppearance {
+            model_chara,
+            look,
+            equip: self.get_npc_equip(equip as u32).unwrap_or_default(),
+        })
+    }
+
     /// Gets the content type for the given InstanceContent.
     pub fn find_type_for_content(&mut self, content_id: u16) -> Option<InstanceContentType> {
         let instance_content_sheet =
@@ -803,9 +1102,8 @@ impl GameData {
 
     /// Gets the order of the mount.
     pub fn find_mount_order(&mut self, mount_id: u32) -> Option<i16> {
-        let config = get_config();
         let instance_content_sheet =
-            MountSheet::read_from(&mut self.resource, config.world.language()).unwrap();
+            MountSheet::read_from(&mut self.resource, self.default_language).unwrap();
         let mount_row = instance_content_sheet.row(mount_id)?;
 
         Some(mount_row.Order)
@@ -902,8 +1200,7 @@ impl GameData {
 
     /// Returns the target event for a given PreHandler event.
     pub fn get_pre_handler_target(&mut self, pre_handler_id: u32) -> Option<u32> {
-        let config = get_config();
-        let sheet = PreHandlerSheet::read_from(&mut self.resource, config.world.language()).ok()?;
+        let sheet = PreHandlerSheet::read_from(&mut self.resource, self.default_language).ok()?;
         let row = sheet.row(pre_handler_id)?;
 
         Some(row.Target)
@@ -925,9 +1222,8 @@ impl GameData {
 
     /// Returns the target Transform Row ID for a given selected NPC. (Only applicable to the Halloween Transform NPC.)
     pub fn get_halloween_npc_transform(&mut self, npc_id: u32) -> Option<u16> {
-        let config = get_config();
         let sheet =
-            HalloweenNpcSelectSheet::read_from(&mut self.resource, config.world.language()).ok()?;
+            HalloweenNpcSelectSheet::read_from(&mut self.resource, self.default_language).ok()?;
         let row = sheet.row(npc_id)?;
 
         Some(row.Transformation)
@@ -946,9 +1242,7 @@ impl GameData {
         topic_select_id: u32,
         selected_index: usize,
     ) -> Option<u32> {
-        let config = get_config();
-        let sheet =
-            TopicSelectSheet::read_from(&mut self.resource, config.world.language()).ok()?;
+        let sheet = TopicSelectSheet::read_from(&mut self.resource, self.default_language).ok()?;
         let row = sheet.row(topic_select_id)?;
 
         Some(row.Shop[selected_index])
@@ -962,17 +1256,18 @@ impl GameData {
     }
 
     /// Returns the max EXP or the exp "needed to grow" for a given level.
-    pub fn get_max_exp(&mut self, level: u32) -> i32 {
-        let row = self.param_grow_sheet.row(level).unwrap();
-
-        row.ExpToNext
+    /// Returns how much EXP is needed to reach the next level, or zero if `level` can't be leveled up from.
+    pub fn get_exp_to_next_level(&mut self, level: u16) -> u32 {
+        self.param_grow_sheet
+            .row(level as u32)
+            .map(|row| row.ExpToNext.max(0) as u32)
+            .unwrap_or_default()
     }
 
     /// Gets the short name for a given content finder condition.
     pub fn get_content_short_name(&mut self, content_finder_row_id: u16) -> Option<String> {
-        let config = get_config();
         let content_finder_sheet =
-            ContentFinderConditionSheet::read_from(&mut self.resource, config.world.language())
+            ContentFinderConditionSheet::read_from(&mut self.resource, self.default_language)
                 .unwrap();
         let content_finder_row = content_finder_sheet.row(content_finder_row_id as u32)?;
 
@@ -999,9 +1294,8 @@ impl GameData {
         &mut self,
         content_finder_row_id: u16,
     ) -> Option<u16> {
-        let config = get_config();
         let content_finder_sheet =
-            ContentFinderConditionSheet::read_from(&mut self.resource, config.world.language())
+            ContentFinderConditionSheet::read_from(&mut self.resource, self.default_language)
                 .unwrap();
         let content_finder_row = content_finder_sheet.row(content_finder_row_id as u32)?;
 
@@ -1050,9 +1344,8 @@ impl GameData {
 
     /// Returns the entrance ID for this content finder condition.
     pub fn get_content_entrance_id(&mut self, content_finder_id: u16) -> Option<u32> {
-        let config = get_config();
         let content_finder_sheet =
-            ContentFinderConditionSheet::read_from(&mut self.resource, config.world.language())
+            ContentFinderConditionSheet::read_from(&mut self.resource, self.default_language)
                 .unwrap();
         let content_finder_row = content_finder_sheet.row(content_finder_id as u32)?;
 
@@ -1065,15 +1358,13 @@ impl GameData {
 
     /// Returns the list of applicable classjob IDs based on the ClassJobCategory.
     pub fn get_applicable_classjobs(&mut self, classjob_category_id: u16) -> Vec<u8> {
-        let config = get_config();
-
         let exh = self
             .resource
             .read_excel_sheet_header("ClassJobCategory")
             .unwrap();
         let sheet = self
             .resource
-            .read_excel_sheet(&exh, "ClassJobCategory", config.world.language())
+            .read_excel_sheet(&exh, "ClassJobCategory", self.default_language)
             .unwrap();
 
         let mut classjobs = Vec::new();
@@ -1089,6 +1380,45 @@ impl GameData {
         classjobs
     }
 
+    /// Lists the player actions `classjob_id` learns after `from_level`, up to and including `to_level`.
+    /// Returned as the action ID and its UnlockLink, which is zero if the action isn't gated behind one.
+    pub fn get_actions_learned_between(
+        &mut self,
+        classjob_id: u8,
+        from_level: u16,
+        to_level: u16,
+    ) -> Vec<(u32, u32)> {
+        let player_actions: Vec<(u32, u16, u8, u32)> = self
+            .action_sheet
+            .into_iter()
+            .flatten_subrows()
+            .filter(|(_, row)| row.IsPlayerAction)
+            .map(|(id, row)| {
+                (
+                    id,
+                    row.ClassJobCategory as u16,
+                    row.ClassJobLevel,
+                    row.UnlockLink as u32,
+                )
+            })
+            .collect();
+
+        // Many actions share the same few categories, so avoid reading them more than once.
+        let mut applicable_categories: HashMap<u16, bool> = HashMap::new();
+        let mut actions = Vec::new();
+        for (id, category, level, unlock_link) in player_actions {
+            let applicable = *applicable_categories.entry(category).or_insert_with(|| {
+                self.get_applicable_classjobs(category)
+                    .contains(&classjob_id)
+            });
+            if applicable {
+                actions.push((id, level, unlock_link));
+            }
+        }
+
+        actions_learned_between(&actions, from_level, to_level)
+    }
+
     /// Gets the soul crystal item ID for the classjob, if applicable.
     pub fn get_soul_crystal_item_id(&mut self, classjob_id: u16) -> Option<u32> {
         let row = self.classjob_sheet.row(classjob_id as u32)?;
@@ -1133,6 +1463,29 @@ impl GameData {
         })
     }
 
+    /// Calculates the max HP at this level, given the total vitality and classjob `modifiers` (if any). See `max_hp_for`.
+    pub fn calculate_max_hp(
+        &mut self,
+        level: u32,
+        modifiers: Option<&Modifiers>,
+        vitality: u32,
+    ) -> Option<u32> {
+        let param_grow = self.get_param_grow(level)?;
+
+        Some(max_hp_for(
+            param_grow.HpModifier as u32,
+            param_grow.LevelModifier as u32,
+            param_grow.BaseSpeed as u32,
+            vitality,
+            modifiers,
+        ))
+    }
+
+    /// Calculates the max MP at this level, which is the same for every classjob.
+    pub fn calculate_max_mp(&mut self, level: u32) -> Option<u32> {
+        Some(self.get_param_grow(level)?.MpModifier as u32)
+    }
+
     /// Gets the classjob ID associated with this soul crystal item ID.
     pub fn get_applicable_classjob(&mut self, soul_crystal_id: u32) -> Option<u32> {
         for (id, row) in self.classjob_sheet.into_iter().flatten_subrows() {
@@ -1330,7 +1683,7 @@ impl GameData {
 
     /// Returns a CraftAction's animation start/end.
     pub fn get_craft_action_animations(&mut self, id: u32) -> (u16, u16) {
-        let sheet = CraftActionSheet::read_from(&mut self.resource, Language::English).unwrap();
+        let sheet = CraftActionSheet::read_from(&mut self.resource, self.default_language).unwrap();
         let row = sheet.row(id).unwrap();
 
         (row.AnimationStart, row.AnimationEnd)
@@ -1340,7 +1693,8 @@ impl GameData {
     pub fn online_status_priorities(&mut self) -> Vec<u8> {
         let mut priorities = Vec::new();
 
-        let sheet = OnlineStatusSheet::read_from(&mut self.resource, Language::English).unwrap();
+        let sheet =
+            OnlineStatusSheet::read_from(&mut self.resource, self.default_language).unwrap();
         for (_, row) in sheet.into_iter().flatten_subrows() {
             priorities.push(row.Priority);
         }
@@ -1350,9 +1704,8 @@ impl GameData {
 
     /// Returns the synced level for this content.
     pub fn find_content_synced_level(&mut self, content_finder_row_id: u16) -> Option<u8> {
-        let config = get_config();
         let content_finder_sheet =
-            ContentFinderConditionSheet::read_from(&mut self.resource, config.world.language())
+            ContentFinderConditionSheet::read_from(&mut self.resource, self.default_language)
                 .unwrap();
         let content_finder_row = content_finder_sheet.row(content_finder_row_id as u32)?;
 
@@ -1373,10 +1726,10 @@ impl GameData {
         ]
     }
 
-    pub fn get_action_cooldown_group(&mut self, id: u32) -> u8 {
-        let row = self.action_sheet.row(id).unwrap();
+    pub fn get_action_cooldown_group(&mut self, id: u32) -> Option<u8> {
+        let row = self.action_sheet.row(id)?;
 
-        row.CooldownGroup
+        Some(row.CooldownGroup)
     }
 
     /// Checks if this zone is associated with a ContentFinderCondition.
@@ -1409,8 +1762,7 @@ impl GameData {
 
     /// Returns the emote mode (if any), really only relevant for persistent/loopable emotes.
     pub fn get_emote_mode(&mut self, emote_id: u32) -> Option<u8> {
-        let config = get_config();
-        let sheet = EmoteSheet::read_from(&mut self.resource, config.world.language()).ok()?;
+        let sheet = EmoteSheet::read_from(&mut self.resource, self.default_language).ok()?;
         let row = sheet.row(emote_id)?;
 
         let mode = row.EmoteMode;
@@ -1420,9 +1772,17 @@ impl GameData {
         None
     }
 
+    /// Checks if this emote exists and can be performed.
+    pub fn is_emote_valid(&mut self, emote_id: u32) -> bool {
+        let Ok(sheet) = EmoteSheet::read_from(&mut self.resource, self.default_language) else {
+            return false;
+        };
+
+        is_valid_emote(emote_id, sheet.row(emote_id).map(|row| row.Name).as_deref())
+    }
+
     pub fn get_mount_id_from_name(&mut self, mount_name: String) -> Option<u16> {
-        let config = get_config();
-        let sheet = MountSheet::read_from(&mut self.resource, config.world.language()).ok()?;
+        let sheet = MountSheet::read_from(&mut self.resource, self.default_language).ok()?;
         for (id, row) in sheet.into_iter().flatten_subrows() {
             if row
                 .Singular
@@ -1446,9 +1806,8 @@ impl GameData {
 
     /// Returns a ContentFinderCondition for a given roulette.
     pub fn pick_roulette_duty(&mut self, roulette: Roulette) -> u32 {
-        let config = get_config();
         let content_finder_sheet =
-            ContentFinderConditionSheet::read_from(&mut self.resource, config.world.language())
+            ContentFinderConditionSheet::read_from(&mut self.resource, self.default_language)
                 .unwrap();
 
         let rows: Vec<u32> = content_finder_sheet
@@ -1477,8 +1836,7 @@ impl GameData {
 
     /// Returns the name ID for a given NpcYell.
     pub fn get_npc_yell_name_id(&mut self, npc_yell_id: u32) -> Option<u32> {
-        let config = get_config();
-        let sheet = NpcYellSheet::read_from(&mut self.resource, config.world.language()).ok()?;
+        let sheet = NpcYellSheet::read_from(&mut self.resource, self.default_language).ok()?;
         let row = sheet.row(npc_yell_id)?;
 
         Some(row.Name)
@@ -1565,8 +1923,7 @@ impl GameData {
 
     /// Returns the ContentFinderCondition for a given IKDRoute.
     pub fn lookup_ikd_route_content(&mut self, id: u32) -> u32 {
-        let config = get_config();
-        let sheet = IKDRouteSheet::read_from(&mut self.resource, config.world.language()).unwrap();
+        let sheet = IKDRouteSheet::read_from(&mut self.resource, self.default_language).unwrap();
         let row = sheet.row(id).unwrap();
 
         row.Instance
@@ -1574,8 +1931,7 @@ impl GameData {
 
     /// Returns the IKDRoute's spots for a given ContentFinderCondition id.
     pub fn lookup_ikd_route_spots_via_content(&mut self, id: u32) -> Option<[u32; 3]> {
-        let config = get_config();
-        let sheet = IKDRouteSheet::read_from(&mut self.resource, config.world.language()).unwrap();
+        let sheet = IKDRouteSheet::read_from(&mut self.resource, self.default_language).unwrap();
 
         sheet
             .into_iter()
@@ -1608,6 +1964,30 @@ impl mlua::UserData for GameData {
         methods.add_method_mut("lookup_ikd_route_content", |_, this, id: u32| {
             Ok(this.lookup_ikd_route_content(id))
         });
+        methods.add_method_mut(
+            "get_weather_forecast",
+            |_, this, (zone_id, count): (u32, u32)| Ok(this.get_weather_forecast(zone_id, count)),
+        );
+        methods.add_method_mut("get_item_info", |_, this, item_id: u32| {
+            Ok(this.get_item_info(ItemInfoQuery::ById(item_id)))
+        });
+        methods.add_method_mut("get_gilshop_items", |_, this, gilshop_id: u32| {
+            Ok(this.get_gilshop_items(gilshop_id))
+        });
+    }
+}
+
+impl mlua::IntoLua for ItemRow {
+    fn into_lua(self, lua: &mlua::Lua) -> mlua::Result<mlua::Value> {
+        let table = lua.create_table()?;
+        table.set("name", self.name)?;
+        table.set("price_mid", self.price_mid)?;
+        table.set("price_low", self.price_low)?;
+        table.set("item_level", self.item_level)?;
+        table.set("stack_size", self.stack_size)?;
+        table.set("equip_category", self.equip_category as u8)?;
+
+        Ok(mlua::Value::Table(table))
     }
 }
 
@@ -1623,6 +2003,8 @@ pub enum TerritoryNameKind {
 struct SqPackResourceSpy {
     sqpack_resource: SqPackResource,
     output_directory: String,
+    /// Only exists if `output_directory` isn't empty.
+    extractor: Option<FileExtractor>,
 }
 
 impl SqPackResourceSpy {
@@ -1630,27 +2012,83 @@ impl SqPackResourceSpy {
         Self {
             sqpack_resource,
             output_directory: output_directory.to_string(),
+            extractor: (!output_directory.is_empty()).then(FileExtractor::new),
+        }
+    }
+}
+
+/// Writes extracted files to disk on a background thread, so reading them isn't slowed down by the filesystem.
+#[derive(Clone)]
+struct FileExtractor {
+    sender: Sender<(PathBuf, physis::ByteBuffer)>,
+    /// Paths that were already sent to the background thread, so the same file isn't queued up more than once.
+    queued: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl FileExtractor {
+    fn new() -> Self {
+        let (extractor, receiver) = Self::without_thread();
+        std::thread::spawn(move || write_extracted_files(receiver));
+
+        extractor
+    }
+
+    /// Creates an extractor whose files have to be written by passing the returned receiver to `write_extracted_files`.
+    fn without_thread() -> (Self, Receiver<(PathBuf, physis::ByteBuffer)>) {
+        let (sender, receiver) = channel();
+
+        (
+            Self {
+                sender,
+                queued: Arc::new(Mutex::new(HashSet::new())),
+            },
+            receiver,
+        )
+    }
+
+    /// Queues `buffer` to be written to `path`, unless it was already queued before.
+    fn extract(&self, path: PathBuf, buffer: &physis::ByteBuffer) {
+        if self.queued.lock().insert(path.clone()) {
+            let _ = self.sender.send((path, buffer.clone()));
         }
     }
 }
 
+/// Waits for the next file, and then writes it along with anything else that was queued up in the meantime. Returns once every extractor is gone.
+fn write_extracted_files(receiver: Receiver<(PathBuf, physis::ByteBuffer)>) {
+    while let Ok(file) = receiver.recv() {
+        for (path, buffer) in std::iter::once(file).chain(receiver.try_iter()) {
+            write_extracted_file(&path, &buffer);
+        }
+    }
+}
+
+/// Writes `buffer` to `path`, creating the parent directories if needed. Existing files are never overwritten.
+fn write_extracted_file(path: &Path, buffer: &[u8]) {
+    if std::fs::exists(path).unwrap_or_default() {
+        return;
+    }
+
+    if let Some(parent_directory) = path.parent()
+        && let Err(err) = std::fs::create_dir_all(parent_directory)
+    {
+        tracing::warn!("Couldn't create directory for extracting {path:?}: {err}");
+        return;
+    }
+
+    if let Err(err) = std::fs::write(path, buffer) {
+        tracing::warn!("Couldn't extract {path:?}: {err}");
+    }
+}
+
 impl Resource for SqPackResourceSpy {
     fn read(&mut self, path: &str) -> Option<physis::ByteBuffer> {
         if let Some(buffer) = self.sqpack_resource.read(path) {
-            if !self.output_directory.is_empty() {
+            if let Some(extractor) = &self.extractor {
                 let mut new_path = PathBuf::from(&self.output_directory);
                 new_path.push(path.to_lowercase());
 
-                if !std::fs::exists(&new_path).unwrap_or_default() {
-                    // create directory if it doesn't exist'
-                    let parent_directory = new_path.parent().unwrap();
-                    if !std::fs::exists(parent_directory).unwrap_or_default() {
-                        std::fs::create_dir_all(parent_directory)
-                            .expect("Couldn't create directory for extraction?!");
-                    }
-
-                    std::fs::write(new_path, &buffer).expect("Couldn't extract file!!");
-                }
+                extractor.extract(new_path, &buffer);
             }
 
             return Some(buffer);
@@ -1663,3 +2101,475 @@ impl Resource for SqPackResourceSpy {
         self.sqpack_resource.exists(path)
     }
 }
+
+/// How long (in real seconds) each weather lasts, which is eight Eorzean hours.
+const WEATHER_PERIOD_SECONDS: u32 = 8 * 175;
+
+/// How far away (in yalms) the target of a melee action can be.
+pub const MELEE_ACTION_RANGE: u8 = 3;
+
+/// Calculate target window for weather calculations
+fn calculate_weather_target(unix_seconds: u32) -> i32 {
+    let eorzean_hours = f32::floor(unix_seconds as f32 / 175.0) as u32;
+    let eorzean_days = f32::floor(eorzean_hours as f32 / 24.0) as u32;
+
+    let time_chunk = (eorzean_hours % 24) - (eorzean_hours % 8);
+    let time_chunk = (time_chunk + 8) % 24;
+    let calc_base = (eorzean_days * 100) + time_chunk;
+
+    let step1 = (calc_base << 0xB) ^ calc_base;
+    let step2 = (step1 >> 8) ^ step1;
+
+    (step2 % 100) as i32
+}
+
+/// Picks out the big aetherytes from `aetherytes` (ID, is aetheryte, zone ID) that are set in `unlocked`.
+fn filter_unlocked_aetherytes(
+    aetherytes: &[(u32, bool, u16)],
+    unlocked: &Bitmask<AETHERYTE_UNLOCK_BITMASK_SIZE>,
+) -> Vec<(u32, u16)> {
+    aetherytes
+        .iter()
+        .filter(|(id, is_aetheryte, _)| {
+            // Guard against rows beyond what the unlock bitmask can hold.
+            *is_aetheryte && (*id as usize) < unlocked.data.len() * 8 && unlocked.contains(*id)
+        })
+        .map(|(id, _, zone_id)| (*id, *zone_id))
+        .collect()
+}
+
+/// Orders a shop's `subrows` (subrow index, item ID) by their index, skipping any empty slots. Indices aren't always contiguous.
+fn list_shop_item_ids(mut subrows: Vec<(u16, u32)>) -> Vec<u32> {
+    subrows.sort_by_key(|(index, _)| *index);
+    subrows
+        .into_iter()
+        .filter(|(_, item_id)| *item_id != 0)
+        .map(|(_, item_id)| item_id)
+        .collect()
+}
+
+/// Picks the aetheryte in `aetherytes` (ID, is a big Aetheryte, zone ID) to use for `zone_id`. Big Aetherytes win over shards, and then the lowest ID.
+fn pick_default_aetheryte(aetherytes: &[(u32, bool, u16)], zone_id: u16) -> Option<u32> {
+    aetherytes
+        .iter()
+        .filter(|(id, _, territory)| *id != 0 && *territory == zone_id)
+        .min_by_key(|(id, is_aetheryte, _)| (!is_aetheryte, *id))
+        .map(|(id, _, _)| *id)
+}
+
+/// Emote rows without a name are unused placeholders, and the zeroth one is never a real emote.
+fn is_valid_emote(emote_id: u32, name: Option<&str>) -> bool {
+    emote_id != 0 && name.is_some_and(|name| !name.is_empty())
+}
+
+/// Picks out the actions (ID, required level, UnlockLink) learned after `from_level`, up to and including `to_level`.
+fn actions_learned_between(
+    actions: &[(u32, u8, u32)],
+    from_level: u16,
+    to_level: u16,
+) -> Vec<(u32, u32)> {
+    actions
+        .iter()
+        .filter(|(_, level, _)| (*level as u16) > from_level && (*level as u16) <= to_level)
+        .map(|(id, _, unlock_link)| (*id, *unlock_link))
+        .collect()
+}
+
+/// Calculates max HP from a level's ParamGrow columns (HpModifier, LevelModifier and BaseSpeed), the total vitality and the classjob modifiers.
+/// In clearer terms without all the casts: (100 + hp_modifier + (total_vit - base_vit) * (level_mod / 100)) * job_hp_modifier
+fn max_hp_for(
+    hp_modifier: u32,
+    level_modifier: u32,
+    base_speed: u32,
+    vitality: u32,
+    modifiers: Option<&Modifiers>,
+) -> u32 {
+    // This formula is loosely inspired by Akh Morning and takes some liberties to keep it fairly simple, at least for now.
+    // TODO: This formula isn't the greatest for 1-50, as near the end of that range it's fairly low compared to retail. For level 80+ though it's pretty close.
+    let classjob_hp_mod;
+    let classjob_vit_mod;
+
+    if let Some(modifiers) = modifiers {
+        classjob_hp_mod = modifiers.hp as f32 / 100.0;
+        classjob_vit_mod = modifiers.vitality as f32 / 100.0;
+    } else {
+        classjob_hp_mod = 1.0;
+        classjob_vit_mod = 1.0;
+    };
+
+    let hp_mod = hp_modifier as f32;
+    let base_vit = (base_speed as f32) * classjob_vit_mod; // TODO: Tribe adjustments, if we care about such a minimal change?
+    let lv_mod = level_modifier as f32;
+
+    (100.0 + hp_mod + ((vitality as f32 - base_vit) * (lv_mod / 100.0)) * classjob_hp_mod).round()
+        as u32
+}
+
+/// Finds the IDs of every name in `names` (ID, name) containing `query` ignoring case, with the best matches first.
+/// Exact matches come first, followed by the shorter names. Ties keep their original order.
+fn rank_name_matches(names: impl IntoIterator<Item = (u32, String)>, query: &str) -> Vec<u32> {
+    let query = query.to_lowercase();
+
+    let mut candidates: Vec<(u32, String)> = names
+        .into_iter()
+        .map(|(id, name)| (id, name.to_lowercase()))
+        .filter(|(_, name)| name.contains(&query))
+        .collect();
+    candidates.sort_by_key(|(_, name)| (*name != query, name.len()));
+
+    candidates.into_iter().map(|(id, _)| id).collect()
+}
+
+/// Finds the zone in `territories` (ID, internal name, place name) called `query`, ignoring case.
+/// Internal names are unique, but many zones share a place name so those have to be too.
+fn match_territory_name(
+    territories: impl IntoIterator<Item = (u32, String, String)>,
+    query: &str,
+) -> Result<u16, TerritoryLookupError> {
+    let mut place_matches = Vec::new();
+    for (id, internal_name, place_name) in territories {
+        if internal_name.eq_ignore_ascii_case(query) {
+            return Ok(id as u16);
+        }
+
+        if !place_name.is_empty() && place_name.to_lowercase() == query.to_lowercase() {
+            place_matches.push(id as u16);
+        }
+    }
+
+    match place_matches.as_slice() {
+        [] => Err(TerritoryLookupError::NotFound),
+        [id] => Ok(*id),
+        _ => Err(TerritoryLookupError::Ambiguous(place_matches)),
+    }
+}
+
+/// Finds the ID of the name in `names` (ID, name) that best matches `query`, see `rank_name_matches`.
+fn best_name_match(names: impl IntoIterator<Item = (u32, String)>, query: &str) -> Option<u32> {
+    rank_name_matches(names, query).first().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_shop_item_ids() {
+        // Mimics a shop with gaps in its subrows, and an empty slot
+        let subrows = vec![(5, 5057), (0, 4551), (1, 4552), (3, 0)];
+        assert_eq!(list_shop_item_ids(subrows), vec![4551, 4552, 5057]);
+
+        assert!(list_shop_item_ids(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_item_info_into_lua() {
+        let lua = mlua::Lua::new();
+        let item = ItemRow {
+            name: "Potion".to_string(),
+            id: 4551,
+            price_mid: 38,
+            price_low: 2,
+            stack_size: 999,
+            ..Default::default()
+        };
+        lua.globals().set("item", item).unwrap();
+        lua.globals().set("missing", None::<ItemRow>).unwrap();
+
+        assert_eq!(
+            lua.load("return item.price_mid").call::<u32>(()).unwrap(),
+            38
+        );
+        assert_eq!(
+            lua.load("return item.name").call::<String>(()).unwrap(),
+            "Potion"
+        );
+        assert!(lua.load("return missing == nil").call::<bool>(()).unwrap());
+    }
+
+    #[test]
+    fn test_weather_forecast_windows() {
+        // Weather only changes on the eight bell boundaries
+        let start = WEATHER_PERIOD_SECONDS * 1000;
+        assert_eq!(
+            calculate_weather_target(start),
+            calculate_weather_target(start + WEATHER_PERIOD_SECONDS - 1)
+        );
+
+        for i in 0..10 {
+            assert!(calculate_weather_target(start + i * WEATHER_PERIOD_SECONDS) < 100);
+        }
+    }
+
+    #[test]
+    fn test_find_territory_by_name() {
+        let territories = [
+            (128, "s1t1", "Limsa Lominsa Upper Decks"),
+            (129, "s1t2", "Limsa Lominsa Lower Decks"),
+            (177, "s1t1_i", "Mizzenmast Inn"),
+            (1010, "s1t1_q", "Limsa Lominsa Upper Decks"),
+        ]
+        .map(|(id, internal, place)| (id, internal.to_string(), place.to_string()));
+
+        assert_eq!(
+            match_territory_name(territories.clone(), "limsa lominsa lower decks"),
+            Ok(129)
+        );
+        assert_eq!(match_territory_name(territories.clone(), "S1T1"), Ok(128));
+        assert_eq!(
+            match_territory_name(territories.clone(), "Limsa Lominsa Upper Decks"),
+            Err(TerritoryLookupError::Ambiguous(vec![128, 1010]))
+        );
+        assert_eq!(
+            match_territory_name(territories, "Gridania"),
+            Err(TerritoryLookupError::NotFound)
+        );
+    }
+
+    #[test]
+    fn test_default_aetheryte_for_zone() {
+        let aetherytes = [
+            (0, false, 129), // unused row
+            (8, true, 129),  // Limsa Lominsa Aetheryte Plaza
+            (41, false, 129),
+            (42, false, 128),
+            (2, true, 132), // New Gridania
+        ];
+
+        assert_eq!(pick_default_aetheryte(&aetherytes, 129), Some(8));
+        assert_eq!(pick_default_aetheryte(&aetherytes, 132), Some(2));
+
+        // Only shards
+        assert_eq!(pick_default_aetheryte(&aetherytes, 128), Some(42));
+
+        // No aetherytes at all, like in an inn room
+        assert_eq!(pick_default_aetheryte(&aetherytes, 177), None);
+    }
+
+    #[test]
+    fn test_filter_unlocked_aetherytes() {
+        let aetherytes = [
+            (2, true, 132),
+            (3, true, 148),
+            (8, true, 129),
+            (9, true, 128),
+            (10, false, 128), // shard
+            (u32::MAX, true, 1),
+        ];
+
+        let mut unlocked = Bitmask::<AETHERYTE_UNLOCK_BITMASK_SIZE>::default();
+        assert!(filter_unlocked_aetherytes(&aetherytes, &unlocked).is_empty());
+
+        unlocked.set(2);
+        unlocked.set(9);
+        unlocked.set(10);
+        assert_eq!(
+            filter_unlocked_aetherytes(&aetherytes, &unlocked),
+            vec![(2, 132), (9, 128)]
+        );
+    }
+
+    #[test]
+    fn test_invalid_emote_rejected() {
+        assert!(is_valid_emote(1, Some("Surprised")));
+        assert!(!is_valid_emote(0, Some("")));
+        assert!(!is_valid_emote(5000, None));
+        assert!(!is_valid_emote(42, Some("")));
+    }
+
+    #[test]
+    fn test_actions_learned_between() {
+        // A sample of Gladiator's actions and their required levels.
+        let actions = [
+            (9, 1, 0),
+            (11, 4, 0),
+            (15, 10, 0),
+            (16, 15, 0),
+            (17, 18, 0),
+            (20, 22, 0),
+            (21, 26, 0),
+            (22, 30, 65),
+            (23, 35, 0),
+            (24, 40, 0),
+        ];
+
+        let learned = actions_learned_between(&actions, 1, 30);
+        assert_eq!(learned.len(), 7);
+        assert!(learned.contains(&(22, 65)));
+
+        // Nothing new is learned when the level doesn't change, or goes down.
+        assert!(actions_learned_between(&actions, 30, 30).is_empty());
+        assert!(actions_learned_between(&actions, 30, 1).is_empty());
+    }
+
+    fn item_names() -> Vec<(u32, String)> {
+        [
+            (4551, "Hi-Potion"),
+            (4552, "X-Potion"),
+            (4553, "Potion of Dexterity"),
+            (4554, "Potion"),
+            (4555, "Mega-Potion of Strength"),
+        ]
+        .into_iter()
+        .map(|(id, name)| (id, name.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_item_name_exact_match() {
+        assert_eq!(best_name_match(item_names(), "potion"), Some(4554));
+        assert_eq!(best_name_match(item_names(), "HI-POTION"), Some(4551));
+    }
+
+    #[test]
+    fn test_item_name_substring_match() {
+        // Several items match, so the shortest one wins.
+        assert_eq!(best_name_match(item_names(), "potion of"), Some(4553));
+        assert_eq!(best_name_match(item_names(), "strength"), Some(4555));
+    }
+
+    #[test]
+    fn test_item_name_ranking() {
+        // The exact match comes before any longer names that merely contain it.
+        assert_eq!(
+            rank_name_matches(item_names(), "potion"),
+            vec![4554, 4552, 4551, 4553, 4555]
+        );
+        assert_eq!(
+            rank_name_matches(item_names(), "Potion of"),
+            vec![4553, 4555]
+        );
+    }
+
+    #[test]
+    fn test_enpc_spawn() {
+        // Roughly what Baderon (1000972) looks like.
+        let appearance = NpcAppearance {
+            model_chara: 0,
+            look: CustomizeData {
+                race: 1,
+                gender: 0,
+                ..Default::default()
+            },
+            equip: CommonSpawn {
+                main_weapon_model: WeaponModelId {
+                    id: 201,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        };
+
+        let position = Position(glam::Vec3::new(1.0, 2.0, 3.0));
+        let spawn = appearance.to_spawn(1000972, position, 1.5);
+        assert_eq!(spawn.common.base_id, 1000972);
+        assert_eq!(spawn.common.name_id, 1000972);
+        assert_eq!(spawn.common.object_kind, ObjectKind::EventNpc);
+        assert_eq!(spawn.common.position, position);
+        assert_eq!(spawn.common.rotation, 1.5);
+        assert_eq!(spawn.common.look.race, 1);
+        assert_eq!(spawn.common.main_weapon_model.id, 201);
+    }
+
+    #[test]
+    fn test_item_name_no_match() {
+        assert_eq!(best_name_match(item_names(), "ether"), None);
+    }
+
+    fn paladin_modifiers() -> Modifiers {
+        Modifiers {
+            hp: 120,
+            mp: 59,
+            strength: 100,
+            vitality: 110,
+            dexterity: 95,
+            intelligence: 60,
+            mind: 100,
+            piety: 100,
+        }
+    }
+
+    #[test]
+    fn test_max_hp_at_base_vitality() {
+        // Level 1 ParamGrow row, with no vitality above the base value.
+        assert_eq!(max_hp_for(86, 56, 20, 20, None), 186);
+        assert_eq!(max_hp_for(86, 56, 20, 22, Some(&paladin_modifiers())), 186);
+    }
+
+    #[test]
+    fn test_max_hp_scales_with_vitality() {
+        // Level 90 ParamGrow row, roughly what a geared tank has. Allow for rounding differences.
+        let hp = max_hp_for(3000, 1900, 390, 3000, Some(&paladin_modifiers()));
+        assert!(hp.abs_diff(61719) <= 1, "unexpected max HP {hp}");
+
+        let lower_hp = max_hp_for(3000, 1900, 390, 2000, Some(&paladin_modifiers()));
+        assert!(lower_hp < hp);
+    }
+
+    #[test]
+    fn test_max_hp_mp_from_game_data() {
+        // This needs the real game files, so it's skipped when they aren't installed.
+        let Ok(mut game_data) = GameData::load(&get_config().filesystem, Language::English) else {
+            return;
+        };
+
+        // Every classjob has had a flat 10,000 MP since Shadowbringers.
+        assert_eq!(game_data.calculate_max_mp(1), Some(10000));
+        assert_eq!(game_data.calculate_max_mp(90), Some(10000));
+
+        // Paladins are tankier than Black Mages with the same vitality.
+        let paladin = game_data.get_class_job_modifiers(19).unwrap();
+        let black_mage = game_data.get_class_job_modifiers(25).unwrap();
+        let paladin_hp = game_data
+            .calculate_max_hp(90, Some(&paladin), 3000)
+            .unwrap();
+        let black_mage_hp = game_data
+            .calculate_max_hp(90, Some(&black_mage), 3000)
+            .unwrap();
+        assert!(paladin_hp > black_mage_hp);
+
+        // There's no ParamGrow row past the level cap.
+        assert_eq!(game_data.calculate_max_mp(1000), None);
+    }
+
+    #[test]
+    fn test_bogus_game_path() {
+        let filesystem = FilesystemConfig {
+            game_path: "/this/path/does/not/exist".to_string(),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            GameData::load(&filesystem, Language::English),
+            Err(GameDataError::MissingSheet(_))
+        ));
+    }
+
+    #[test]
+    fn test_file_extractor() {
+        let directory = std::env::temp_dir().join(format!("kawari-extract-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+
+        let new_file = directory.join("exd/item.exh");
+        let existing_file = directory.join("existing.exh");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(&existing_file, [1]).unwrap();
+
+        let (extractor, receiver) = FileExtractor::without_thread();
+        extractor.extract(existing_file.clone(), &vec![2]);
+        extractor.extract(new_file.clone(), &vec![1, 2, 3]);
+        extractor.extract(new_file.clone(), &vec![4, 5, 6]); // Already queued, so this is ignored
+
+        // Nothing is written until the queue is processed, so extracting never waits on the filesystem.
+        assert!(!std::fs::exists(&new_file).unwrap());
+
+        // Write everything out here instead of on the background thread, which returns once the extractor is dropped.
+        drop(extractor);
+        write_extracted_files(receiver);
+
+        assert_eq!(std::fs::read(&new_file).unwrap(), vec![1, 2, 3]);
+        assert_eq!(std::fs::read(&existing_file).unwrap(), vec![1]);
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
@@ -69,6 +69,9 @@ pub struct CrystalsStorage {
 }
 
 impl CrystalsStorage {
+    /// How many of each shard, crystal or cluster can be held.
+    pub const MAX_QUANTITY: u32 = 9999;
+
     pub fn get_slot_for_id(id: CrystalKind) -> u16 {
         id as u16 - 2
     }
@@ -26,6 +26,25 @@ pub enum CurrencyKind {
     TrophyCrystal = 36656,
 }
 
+impl CurrencyKind {
+    /// The most of this currency a player can hold at once.
+    pub fn max_quantity(&self) -> u32 {
+        match self {
+            CurrencyKind::Gil => 999_999_999,
+            CurrencyKind::MGP => 9_999_999,
+            CurrencyKind::StormSeal | CurrencyKind::SerpentSeal | CurrencyKind::FlameSeal => 90_000,
+            CurrencyKind::WolfMark => 20_000,
+            CurrencyKind::AlliedSeal | CurrencyKind::CenturioSeal => 4_000,
+            CurrencyKind::TomestonePoetics
+            | CurrencyKind::TomestoneHelio
+            | CurrencyKind::TomestoneMaths => 2_000,
+            CurrencyKind::Venture => 65_000,
+            CurrencyKind::SackOfNuts => 4_000,
+            CurrencyKind::TrophyCrystal => 20_000,
+        }
+    }
+}
+
 impl mlua::IntoLua for CurrencyKind {
     fn into_lua(self, _: &mlua::Lua) -> mlua::Result<mlua::Value> {
         Ok(mlua::Value::Integer(self as i64))
@@ -44,7 +63,11 @@ impl mlua::FromLua for CurrencyKind {
 #[derive(Clone, Copy, Deserialize, Serialize, Debug)]
 pub struct CurrencyStorage {
     pub gil: Item,
-    pub flame_seal: Item, // TODO: rename to something company agnostic
+    #[serde(default = "default_storm_seal")]
+    pub storm_seal: Item,
+    #[serde(default = "default_serpent_seal")]
+    pub serpent_seal: Item,
+    pub flame_seal: Item,
     pub wolf_mark: Item,
     pub tomestone_poetics: Item,
     pub tomestone_mathematics: Item, // TODO: rename season agnostic
@@ -55,17 +78,74 @@ pub struct CurrencyStorage {
 }
 
 impl CurrencyStorage {
-    pub fn get_slot_for_id(id: CurrencyKind) -> u16 {
+    /// The slot gil is always stored in.
+    pub const GIL_SLOT: u16 = 0;
+
+    /// Returns the slot this currency is stored in, if it's one we have a slot for.
+    pub fn find_slot_for_id(id: CurrencyKind) -> Option<u16> {
         match id {
-            CurrencyKind::Gil => 0,
-            CurrencyKind::WolfMark => 4,
-            CurrencyKind::MGP => 9,
-            _ => unimplemented!(),
+            CurrencyKind::Gil => Some(0),
+            CurrencyKind::StormSeal => Some(1),
+            CurrencyKind::SerpentSeal => Some(2),
+            CurrencyKind::FlameSeal => Some(3),
+            CurrencyKind::WolfMark => Some(4),
+            CurrencyKind::TomestonePoetics => Some(6),
+            CurrencyKind::TomestoneMaths => Some(7),
+            CurrencyKind::AlliedSeal => Some(8),
+            CurrencyKind::MGP => Some(9),
+            CurrencyKind::TomestoneHelio => Some(10),
+            _ => None,
         }
     }
 
-    pub fn get_item_for_id(&mut self, id: CurrencyKind) -> &mut Item {
-        self.get_slot_mut(Self::get_slot_for_id(id))
+    /// Returns the item in this currency's slot, or None if we don't have a slot for it.
+    pub fn get_item_for_id(&mut self, id: CurrencyKind) -> Option<&mut Item> {
+        Some(self.get_slot_mut(Self::find_slot_for_id(id)?))
+    }
+
+    /// How much of this currency is held. Returns zero if we don't have a slot for it.
+    pub fn quantity_of(&self, id: CurrencyKind) -> u32 {
+        Self::find_slot_for_id(id)
+            .map(|slot_index| self.get_slot(slot_index).quantity)
+            .unwrap_or_default()
+    }
+
+    /// Adds `amount` of this currency (or removes it, if negative) while staying within its cap.
+    /// Returns the slot and its updated contents, or None if we don't have a slot for this currency.
+    pub fn modify(&mut self, id: CurrencyKind, amount: i32) -> Option<(u16, Item)> {
+        let slot_index = Self::find_slot_for_id(id)?;
+        let slot = self.get_slot_mut(slot_index);
+
+        if amount > 0 {
+            slot.quantity = slot
+                .quantity
+                .saturating_add(amount as u32)
+                .min(id.max_quantity());
+        } else {
+            slot.quantity = slot.quantity.saturating_sub(amount.unsigned_abs());
+        }
+
+        Some((slot_index, *slot))
+    }
+
+    /// Adds `amount` gil without going past the gil cap, returning how much was actually added.
+    pub fn add_gil(&mut self, amount: u32) -> u32 {
+        let previous = self.gil.quantity;
+        self.gil.quantity = previous
+            .saturating_add(amount)
+            .min(CurrencyKind::Gil.max_quantity());
+
+        self.gil.quantity.saturating_sub(previous)
+    }
+
+    /// Deducts `amount` gil if there's enough of it, returning false (and leaving the gil untouched) if there isn't.
+    pub fn try_spend_gil(&mut self, amount: u32) -> bool {
+        if self.gil.quantity < amount {
+            return false;
+        }
+
+        self.gil.quantity -= amount;
+        true
     }
 }
 
@@ -76,6 +156,8 @@ impl Default for CurrencyStorage {
                 item_id: CurrencyKind::Gil as u32,
                 ..Default::default()
             },
+            storm_seal: default_storm_seal(),
+            serpent_seal: default_serpent_seal(),
             flame_seal: Item {
                 item_id: CurrencyKind::FlameSeal as u32,
                 ..Default::default()
@@ -109,6 +191,20 @@ impl Default for CurrencyStorage {
     }
 }
 
+fn default_storm_seal() -> Item {
+    Item {
+        item_id: CurrencyKind::StormSeal as u32,
+        ..Default::default()
+    }
+}
+
+fn default_serpent_seal() -> Item {
+    Item {
+        item_id: CurrencyKind::SerpentSeal as u32,
+        ..Default::default()
+    }
+}
+
 impl Storage for CurrencyStorage {
     fn max_slots(&self) -> u32 {
         11
@@ -117,6 +213,8 @@ impl Storage for CurrencyStorage {
     fn get_slot_mut(&mut self, index: u16) -> &mut Item {
         match index {
             0 => &mut self.gil,
+            1 => &mut self.storm_seal,
+            2 => &mut self.serpent_seal,
             3 => &mut self.flame_seal,
             4 => &mut self.wolf_mark,
             6 => &mut self.tomestone_poetics,
@@ -131,6 +229,8 @@ impl Storage for CurrencyStorage {
     fn get_slot(&self, index: u16) -> &Item {
         match index {
             0 => &self.gil,
+            1 => &self.storm_seal,
+            2 => &self.serpent_seal,
             3 => &self.flame_seal,
             4 => &self.wolf_mark,
             6 => &self.tomestone_poetics,
@@ -142,3 +242,125 @@ impl Storage for CurrencyStorage {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modify_currency() {
+        let mut currency = CurrencyStorage::default();
+
+        assert_eq!(currency.modify(CurrencyKind::MGP, 500).unwrap().0, 9);
+        assert_eq!(currency.mgp.quantity, 500);
+
+        assert_eq!(
+            currency
+                .modify(CurrencyKind::TomestonePoetics, 150)
+                .unwrap()
+                .0,
+            6
+        );
+        assert_eq!(currency.tomestone_poetics.quantity, 150);
+        assert_eq!(
+            currency.modify(CurrencyKind::TomestoneHelio, 20).unwrap().0,
+            10
+        );
+        assert_eq!(currency.tomestone_heliometry.quantity, 20);
+
+        // Removing more than you have leaves you with nothing.
+        currency.modify(CurrencyKind::MGP, -1000);
+        assert_eq!(currency.mgp.quantity, 0);
+
+        // Nothing else should've been touched.
+        assert_eq!(currency.gil.quantity, 0);
+        assert_eq!(currency.dummy.quantity, 0);
+
+        assert!(currency.modify(CurrencyKind::Venture, 1).is_none());
+    }
+
+    #[test]
+    fn test_quantity_of_currency() {
+        let mut currency = CurrencyStorage::default();
+        currency.modify(CurrencyKind::Gil, 1000);
+        currency.modify(CurrencyKind::StormSeal, 300);
+
+        assert_eq!(currency.quantity_of(CurrencyKind::Gil), 1000);
+        assert_eq!(currency.quantity_of(CurrencyKind::StormSeal), 300);
+
+        assert_eq!(currency.quantity_of(CurrencyKind::FlameSeal), 0);
+        assert_eq!(currency.quantity_of(CurrencyKind::Venture), 0);
+    }
+
+    #[test]
+    fn test_seals_kept_per_company() {
+        let mut currency = CurrencyStorage::default();
+        currency.modify(CurrencyKind::StormSeal, 300);
+        currency.modify(CurrencyKind::FlameSeal, 50);
+        currency.modify(CurrencyKind::SerpentSeal, 10);
+
+        assert_eq!(currency.quantity_of(CurrencyKind::StormSeal), 300);
+        assert_eq!(currency.quantity_of(CurrencyKind::SerpentSeal), 10);
+        assert_eq!(currency.quantity_of(CurrencyKind::FlameSeal), 50);
+        assert_eq!(currency.storm_seal.item_id, CurrencyKind::StormSeal as u32);
+        assert_eq!(currency.flame_seal.item_id, CurrencyKind::FlameSeal as u32);
+    }
+
+    #[test]
+    fn test_unmapped_currency() {
+        let mut currency = CurrencyStorage::default();
+        assert!(currency.get_item_for_id(CurrencyKind::Venture).is_none());
+        assert!(currency.get_item_for_id(CurrencyKind::Gil).is_some());
+    }
+
+    #[test]
+    fn test_currency_cap() {
+        let mut currency = CurrencyStorage::default();
+
+        currency.modify(CurrencyKind::TomestonePoetics, 1500);
+        currency.modify(CurrencyKind::TomestonePoetics, 1500);
+        assert_eq!(currency.tomestone_poetics.quantity, 2000);
+
+        currency.modify(CurrencyKind::Gil, i32::MAX);
+        assert_eq!(currency.gil.quantity, 999_999_999);
+    }
+
+    #[test]
+    fn test_try_spend_gil() {
+        let mut currency = CurrencyStorage::default();
+        currency.gil.quantity = 150;
+
+        // Affordable
+        assert!(currency.try_spend_gil(100));
+        assert_eq!(currency.gil.quantity, 50);
+
+        // Unaffordable
+        assert!(!currency.try_spend_gil(100));
+        assert_eq!(currency.gil.quantity, 50);
+
+        // Free
+        assert!(currency.try_spend_gil(0));
+        assert_eq!(currency.gil.quantity, 50);
+    }
+
+    #[test]
+    fn test_add_gil_past_cap() {
+        let mut currency = CurrencyStorage::default();
+        currency.gil.quantity = 999_999_000;
+
+        assert_eq!(currency.add_gil(5000), 999);
+        assert_eq!(currency.gil.quantity, 999_999_999);
+
+        assert_eq!(currency.add_gil(u32::MAX), 0);
+        assert_eq!(currency.gil.quantity, 999_999_999);
+    }
+
+    #[test]
+    fn test_spend_more_gil_than_held() {
+        let mut currency = CurrencyStorage::default();
+        currency.gil.quantity = 10;
+
+        assert!(!currency.try_spend_gil(u32::MAX));
+        assert_eq!(currency.gil.quantity, 10);
+    }
+}
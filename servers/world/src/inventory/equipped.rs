@@ -24,12 +24,92 @@ pub struct EquippedStorage {
     pub soul_crystal: Item,
 }
 
+/// How much condition equipped items lose each time an action is used.
+pub const CONDITION_LOSS_PER_ACTION: u16 = 10;
+
 // On the EquipSlotCategory sheet, -1 means that item slot can't be equipped while another item restricts it.
 pub const EQUIP_RESTRICTED: i8 = -1;
 
+/// Checks if an item that belongs in `category_slot` (see `GameData::get_equipslot_category`) can be equipped into `equip_slot`.
+pub fn can_equip_in_slot(category_slot: u16, equip_slot: u16) -> bool {
+    // Rings can be worn on either hand.
+    const RING_SLOTS: [u16; 2] = [EquipSlot::RightRing as u16, EquipSlot::LeftRing as u16];
+
+    category_slot == equip_slot
+        || (RING_SLOTS.contains(&category_slot) && RING_SLOTS.contains(&equip_slot))
+}
+
+/// Checks if an item with the `glamour_category` EquipSlotCategory can be projected onto one with `item_category`.
+pub fn can_glamour(item_category: u8, glamour_category: u8) -> bool {
+    // Zero means the item isn't equipment at all.
+    item_category != 0 && item_category == glamour_category
+}
+
 impl EquippedStorage {
     /// Calculates the player's item level.
     pub fn calculate_item_level(&self, game_data: &mut GameData) -> u16 {
+        // If our main hand weapon is two-handed (i.e. restricts off-hands from being equipped), it counts one additional time.
+        let two_handed = game_data
+            .get_item_info(ItemInfoQuery::ById(self.main_hand.item_id))
+            .is_some_and(|info| info.equip_restrictions.off_hand == EQUIP_RESTRICTED);
+
+        // If our body equipment blocks head, hands, legs, or feet, it counts one addtional time per restricted slot.
+        let mut body_restricted_slots = 0;
+        if let Some(body_info) = game_data.get_item_info(ItemInfoQuery::ById(self.body.item_id)) {
+            let body_restrictions = [
+                body_info.equip_restrictions.head,
+                body_info.equip_restrictions.hands,
+                body_info.equip_restrictions.legs,
+                body_info.equip_restrictions.feet,
+            ];
+
+            body_restricted_slots = body_restrictions
+                .iter()
+                .filter(|slot| **slot == EQUIP_RESTRICTED)
+                .count() as u16;
+        }
+
+        self.item_level_with_restrictions(two_handed, body_restricted_slots)
+    }
+
+    /// Wears down the condition of everything equipped by `amount`, and returns the slots that changed.
+    pub fn decay_condition(&mut self, amount: u16) -> Vec<u16> {
+        let mut changed = Vec::new();
+        for index in EquipSlot::iter() {
+            // Soul crystals don't wear down.
+            if index == EquipSlot::SoulCrystal {
+                continue;
+            }
+
+            let item = self.get_slot_mut(index as u16);
+            if item.is_empty_slot() || item.is_broken() {
+                continue;
+            }
+
+            item.condition = item.condition.saturating_sub(amount);
+            changed.push(index as u16);
+        }
+        changed
+    }
+
+    /// Returns the slots holding equipment that has lost any condition.
+    pub fn damaged_slots(&self) -> Vec<u16> {
+        EquipSlot::iter()
+            .map(|index| index as u16)
+            .filter(|index| self.get_slot(*index).needs_repair())
+            .collect()
+    }
+
+    /// Restores the condition of everything equipped, and returns the slots that changed.
+    pub fn repair_all(&mut self) -> Vec<u16> {
+        EquipSlot::iter()
+            .map(|index| index as u16)
+            .filter(|index| self.get_slot_mut(*index).repair())
+            .collect()
+    }
+
+    /// Calculates the item level, after the equipment restrictions are already known.
+    fn item_level_with_restrictions(&self, two_handed: bool, body_restricted_slots: u16) -> u16 {
         const DIVISOR: u16 = 12;
 
         let mut level = 0;
@@ -41,34 +121,15 @@ impl EquippedStorage {
             }
 
             let item = self.get_slot(index as u16);
-            level += item.item_level;
+            level += item.effective_item_level();
         }
 
-        // Next, calculate additional item levels based off main hand and body equipment restrictions.
-        if let Some(main_hand_info) =
-            game_data.get_item_info(ItemInfoQuery::ById(self.main_hand.item_id))
-        {
-            // If our main hand weapon is two-handed (i.e. restricts off-hands from being equipped), it counts one additional time.
-            if main_hand_info.equip_restrictions.off_hand == EQUIP_RESTRICTED {
-                level += self.get_slot(EquipSlot::MainHand as u16).item_level;
-            }
+        // Next, add additional item levels based off main hand and body equipment restrictions.
+        if two_handed {
+            level += self.main_hand.effective_item_level();
         }
 
-        if let Some(body_info) = game_data.get_item_info(ItemInfoQuery::ById(self.body.item_id)) {
-            let body_restrictions = [
-                body_info.equip_restrictions.head,
-                body_info.equip_restrictions.hands,
-                body_info.equip_restrictions.legs,
-                body_info.equip_restrictions.feet,
-            ];
-
-            // If our body equipment blocks head, hands, legs, or feet, it counts one addtional time per restricted slot.
-            for slot in body_restrictions {
-                if slot == EQUIP_RESTRICTED {
-                    level += self.get_slot(EquipSlot::Body as u16).item_level;
-                }
-            }
-        }
+        level += self.body.effective_item_level() * body_restricted_slots;
 
         std::cmp::min(level / DIVISOR, 9999)
     }
@@ -128,13 +189,47 @@ impl Storage for EquippedStorage {
 
 #[cfg(test)]
 mod tests {
-    // TODO: Fix this test so it can run again, calculate_item_level needs GameData now to function correctly.
-    /*use super::*;
+    use kawari::common::ITEM_CONDITION_MAX;
+
+    use super::*;
+
+    #[test]
+    fn test_can_equip_in_slot() {
+        // Heads go on heads
+        assert!(can_equip_in_slot(
+            EquipSlot::Head as u16,
+            EquipSlot::Head as u16
+        ));
+
+        // Rings fit on either finger
+        assert!(can_equip_in_slot(
+            EquipSlot::RightRing as u16,
+            EquipSlot::LeftRing as u16
+        ));
+
+        // But a ring can't go on your head
+        assert!(!can_equip_in_slot(
+            EquipSlot::RightRing as u16,
+            EquipSlot::Head as u16
+        ));
+    }
+
+    #[test]
+    fn test_mismatched_glamour_rejected() {
+        // A head piece can become another head piece
+        assert!(can_glamour(3, 3));
+
+        // But not a body piece
+        assert!(!can_glamour(3, 4));
+
+        // And non-equipment can't be glamoured at all
+        assert!(!can_glamour(0, 0));
+    }
 
     #[test]
     fn test_item_level() {
         let equipped = EquippedStorage::default();
-        assert_eq!(equipped.calculate_item_level(), 0);
+        assert_eq!(equipped.item_level_with_restrictions(false, 0), 0);
 
         let base_item = Item {
             quantity: 1,
@@ -154,6 +249,76 @@ mod tests {
             left_ring: base_item,
             ..Default::default()
         };
-        assert_eq!(equipped.calculate_item_level(), 4);
-    }*/
+        assert_eq!(equipped.item_level_with_restrictions(false, 0), 4);
+
+        // Two-handed weapons and body pieces that block the head count multiple times
+        assert_eq!(equipped.item_level_with_restrictions(true, 1), 5);
+    }
+
+    fn equipment() -> EquippedStorage {
+        let base_item = Item {
+            quantity: 1,
+            item_id: 1,
+            item_level: 12,
+            condition: ITEM_CONDITION_MAX,
+            ..Default::default()
+        };
+        EquippedStorage {
+            main_hand: base_item,
+            head: base_item,
+            soul_crystal: base_item,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_condition_decay() {
+        let mut equipped = equipment();
+
+        let changed = equipped.decay_condition(CONDITION_LOSS_PER_ACTION);
+        assert_eq!(
+            changed,
+            vec![EquipSlot::MainHand as u16, EquipSlot::Head as u16]
+        );
+        assert_eq!(
+            equipped.main_hand.condition,
+            ITEM_CONDITION_MAX - CONDITION_LOSS_PER_ACTION
+        );
+        assert_eq!(equipped.soul_crystal.condition, ITEM_CONDITION_MAX);
+        assert_eq!(equipped.item_level_with_restrictions(false, 0), 2);
+
+        // Eventually the head piece breaks, and stops counting towards our item level
+        equipped.head.condition = 5;
+        equipped.decay_condition(CONDITION_LOSS_PER_ACTION);
+        assert!(equipped.head.is_broken());
+        assert_eq!(equipped.item_level_with_restrictions(false, 0), 1);
+
+        // Broken items can't decay any further
+        let changed = equipped.decay_condition(CONDITION_LOSS_PER_ACTION);
+        assert_eq!(changed, vec![EquipSlot::MainHand as u16]);
+    }
+
+    #[test]
+    fn test_repair_all() {
+        let mut equipped = equipment();
+        equipped.main_hand.condition = 0;
+        equipped.head.condition = 1234;
+
+        assert_eq!(
+            equipped.damaged_slots(),
+            vec![EquipSlot::MainHand as u16, EquipSlot::Head as u16]
+        );
+
+        let changed = equipped.repair_all();
+        assert_eq!(
+            changed,
+            vec![EquipSlot::MainHand as u16, EquipSlot::Head as u16]
+        );
+        assert_eq!(equipped.main_hand.condition, ITEM_CONDITION_MAX);
+        assert_eq!(equipped.head.condition, ITEM_CONDITION_MAX);
+
+        // Nothing left to repair
+        assert!(equipped.damaged_slots().is_empty());
+        assert!(equipped.repair_all().is_empty());
+    }
 }
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::ItemRow;
 
 /// Represents an item, or if the quantity is zero, an empty slot.
-#[derive(Default, Copy, Clone, Serialize, Deserialize, Debug)]
+#[derive(Default, Copy, Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct Item {
     /// How many of this item occupies it's slot.
     pub quantity: u32,
@@ -43,6 +43,11 @@ pub struct Item {
     pub defense: u16,
     #[serde(skip)]
     pub magic_defense: u16,
+    #[serde(skip)]
+    pub ui_category: u8,
+    /// The equipment slot this item goes into, if it's equipment.
+    #[serde(skip)]
+    pub equip_slot: Option<u16>,
 }
 
 impl Item {
@@ -58,6 +63,8 @@ impl Item {
             base_param_values: item_info.base_param_values,
             defense: item_info.defense,
             magic_defense: item_info.magic_defense,
+            ui_category: item_info.ui_category,
+            equip_slot: item_info.equip_slot,
             ..Default::default()
         }
     }
@@ -76,6 +83,33 @@ impl Item {
     pub fn is_empty_slot(&self) -> bool {
         self.quantity == 0 || self.item_id == 0
     }
+
+    /// Whether this item's condition has been worn down to zero.
+    pub fn is_broken(&self) -> bool {
+        !self.is_empty_slot() && self.condition == 0
+    }
+
+    /// The item level this item contributes, broken items don't count.
+    pub fn effective_item_level(&self) -> u16 {
+        if self.is_broken() {
+            return 0;
+        }
+        self.item_level
+    }
+
+    /// Whether this item has lost any condition.
+    pub fn needs_repair(&self) -> bool {
+        !self.is_empty_slot() && self.condition < ITEM_CONDITION_MAX
+    }
+
+    /// Restores this item's condition to the maximum. Returns false if there was nothing to repair.
+    pub fn repair(&mut self) -> bool {
+        if !self.needs_repair() {
+            return false;
+        }
+        self.condition = ITEM_CONDITION_MAX;
+        true
+    }
 }
 
 impl From<Item> for ItemInfo {
@@ -46,6 +46,8 @@ pub fn get_container_type(container_index: u32) -> Option<ContainerType> {
         17 => Some(ContainerType::Currency),
         // crystals
         18 => Some(ContainerType::Crystals),
+        // key items
+        19 => Some(ContainerType::KeyItems),
         _ => panic!(
             "Inventory iterator invalid or the client sent a very weird packet! {container_index}"
         ),
@@ -59,7 +61,7 @@ impl<'a> Iterator for InventoryIterator<'a> {
         let curr = self.curr;
         self.curr += 1;
 
-        if curr >= 19 {
+        if curr >= 20 {
             return None;
         }
 
@@ -1,10 +1,11 @@
 use icarus::{ClassJob::ClassJobSheet, Race::RaceSheet};
 use kawari::{
     common::{ContainerType, ItemOperationKind, LegacyEquipmentModelId, WeaponModelId},
-    config::get_config,
+    config::StartingItem,
     ipc::zone::ItemInfo,
 };
 use serde::{Deserialize, Serialize};
+use strum_macros::FromRepr;
 
 use kawari::ipc::zone::ItemOperation;
 
@@ -12,7 +13,9 @@ mod buyback;
 pub use buyback::BuyBackList;
 
 mod equipped;
-pub use equipped::{EQUIP_RESTRICTED, EquippedStorage};
+pub use equipped::{
+    CONDITION_LOSS_PER_ACTION, EQUIP_RESTRICTED, EquippedStorage, can_equip_in_slot, can_glamour,
+};
 
 mod generic;
 pub use generic::GenericStorage;
@@ -34,7 +37,33 @@ pub use crystals::{CrystalKind, CrystalsStorage};
 
 use crate::{GameData, ItemInfoQuery};
 
-use physis::TerritoryIntendedUse;
+use physis::{Language, TerritoryIntendedUse};
+
+/// How to order items when sorting a container.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, FromRepr)]
+pub enum SortKey {
+    /// By their index in the Item Excel sheet.
+    ItemId = 0,
+    /// Highest item level first.
+    ItemLevel = 1,
+    /// By their ItemUICategory, which matches how the client groups them.
+    Category = 2,
+}
+
+impl mlua::FromLua for SortKey {
+    fn from_lua(value: mlua::Value, _: &mlua::Lua) -> mlua::Result<Self> {
+        match value {
+            mlua::Value::Integer(integer) => {
+                Ok(Self::from_repr(integer as u8).unwrap_or(Self::ItemId))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Key items come from the EventItem Excel sheet, whose rows start here instead of sharing the Item sheet's ids.
+pub const KEY_ITEM_ID_START: u32 = 2_000_000;
 
 const MAX_NORMAL_STORAGE: usize = 35;
 pub const MAX_LARGE_STORAGE: usize = 50;
@@ -92,59 +121,94 @@ impl Default for Inventory {
 
 impl Inventory {
     /// Equip the starting items for a given classjob
-    pub fn equip_classjob_items(&mut self, classjob_id: u16, game_data: &mut GameData) {
-        let config = get_config();
-        let sheet =
-            ClassJobSheet::read_from(&mut game_data.resource, config.world.language()).unwrap();
-        let row = sheet.row(classjob_id as u32).unwrap();
-
-        let main_hand_id = row.ItemStartingWeaponMainHand as u32;
-        self.equipped.main_hand = Item::new(
-            &game_data
-                .get_item_info(ItemInfoQuery::ById(main_hand_id))
-                .unwrap(),
-            1,
-        );
+    pub fn equip_classjob_items(
+        &mut self,
+        classjob_id: u16,
+        game_data: &mut GameData,
+        language: Language,
+    ) {
+        let Some(row) = ClassJobSheet::read_from(&mut game_data.resource, language)
+            .ok()
+            .and_then(|sheet| sheet.row(classjob_id as u32))
+        else {
+            tracing::warn!("Classjob {classjob_id} doesn't exist, not equipping its items!");
+            return;
+        };
+
+        self.equipped.main_hand =
+            Self::starting_equipment(game_data, row.ItemStartingWeaponMainHand as u32);
 
         // TODO: don't hardcode
-        self.equipped.ears = Item::new(
-            &game_data
-                .get_item_info(ItemInfoQuery::ById(0x3b1b))
-                .unwrap(),
-            1,
-        );
-        self.equipped.neck = Item::new(
-            &game_data
-                .get_item_info(ItemInfoQuery::ById(0x3b1a))
-                .unwrap(),
-            1,
-        );
-        self.equipped.wrists = Item::new(
-            &game_data
-                .get_item_info(ItemInfoQuery::ById(0x3b1c))
-                .unwrap(),
-            1,
-        );
+        self.equipped.ears = Self::starting_equipment(game_data, 0x3b1b);
+        self.equipped.neck = Self::starting_equipment(game_data, 0x3b1a);
+        self.equipped.wrists = Self::starting_equipment(game_data, 0x3b1c);
         // FIXME: I think this is actually based on a choice in the opening, and also defined in OpeningSystemDefine Excel sheet:
-        self.equipped.right_ring = Item::new(
-            &game_data
-                .get_item_info(ItemInfoQuery::ById(0x114a))
-                .unwrap(),
-            1,
-        );
-        self.equipped.left_ring = Item::new(
-            &game_data
-                .get_item_info(ItemInfoQuery::ById(0x3b1d))
-                .unwrap(),
-            1,
-        );
+        self.equipped.right_ring = Self::starting_equipment(game_data, 0x114a);
+        self.equipped.left_ring = Self::starting_equipment(game_data, 0x3b1d);
+    }
+
+    /// Looks up a single piece of starting equipment, leaving the slot empty if the item doesn't exist.
+    fn starting_equipment(game_data: &mut GameData, item_id: u32) -> Item {
+        let Some(item_info) = game_data.get_item_info(ItemInfoQuery::ById(item_id)) else {
+            tracing::warn!("Starting equipment {item_id} doesn't exist, it won't be equipped!");
+            return Item::default();
+        };
+
+        Item::new(&item_info, 1)
+    }
+
+    /// Gives the configured starting items and gil, this should only be done once when the character is created.
+    pub fn add_starting_items(
+        &mut self,
+        items: &[StartingItem],
+        gil: u32,
+        game_data: &mut GameData,
+    ) {
+        let items: Vec<Item> = items
+            .iter()
+            .filter_map(|starting_item| {
+                let Some(item_info) =
+                    game_data.get_item_info(ItemInfoQuery::ById(starting_item.id))
+                else {
+                    tracing::warn!(
+                        "Starting item {} doesn't exist, it won't be given!",
+                        starting_item.id
+                    );
+                    return None;
+                };
+                Some(Item::new(&item_info, starting_item.quantity))
+            })
+            .collect();
+
+        self.add_initial_items(&items, gil);
+    }
+
+    /// Places `items` and `gil` into the inventory, see `add_starting_items`.
+    fn add_initial_items(&mut self, items: &[Item], gil: u32) {
+        for item in items {
+            if self.add_in_next_free_slot(*item).is_none() {
+                tracing::warn!("No room left for starting item {}!", item.item_id);
+            }
+        }
+
+        self.currency.gil.quantity = gil.min(CurrencyKind::Gil.max_quantity());
     }
 
     /// Equip the starting items for a given race
-    pub fn equip_racial_items(&mut self, race_id: u8, gender: u8, game_data: &mut GameData) {
-        let config = get_config();
-        let sheet = RaceSheet::read_from(&mut game_data.resource, config.world.language()).unwrap();
-        let row = sheet.row(race_id as u32).unwrap();
+    pub fn equip_racial_items(
+        &mut self,
+        race_id: u8,
+        gender: u8,
+        game_data: &mut GameData,
+        language: Language,
+    ) {
+        let Some(row) = RaceSheet::read_from(&mut game_data.resource, language)
+            .ok()
+            .and_then(|sheet| sheet.row(race_id as u32))
+        else {
+            tracing::warn!("Race {race_id} doesn't exist, not equipping its items!");
+            return;
+        };
 
         let ids = if gender == 0 {
             [
@@ -162,30 +226,10 @@ impl Inventory {
             ]
         };
 
-        self.equipped.body = Item::new(
-            &game_data
-                .get_item_info(ItemInfoQuery::ById(ids[0]))
-                .unwrap(),
-            1,
-        );
-        self.equipped.hands = Item::new(
-            &game_data
-                .get_item_info(ItemInfoQuery::ById(ids[1]))
-                .unwrap(),
-            1,
-        );
-        self.equipped.legs = Item::new(
-            &game_data
-                .get_item_info(ItemInfoQuery::ById(ids[2]))
-                .unwrap(),
-            1,
-        );
-        self.equipped.feet = Item::new(
-            &game_data
-                .get_item_info(ItemInfoQuery::ById(ids[3]))
-                .unwrap(),
-            1,
-        );
+        self.equipped.body = Self::starting_equipment(game_data, ids[0]);
+        self.equipped.hands = Self::starting_equipment(game_data, ids[1]);
+        self.equipped.legs = Self::starting_equipment(game_data, ids[2]);
+        self.equipped.feet = Self::starting_equipment(game_data, ids[3]);
     }
 
     /// Helper functions to reduce boilerplate
@@ -303,6 +347,142 @@ impl Inventory {
         }
     }
 
+    /// Swaps the items in two (possibly different) containers. Returns the items that are now in the source and destination slots, respectively.
+    pub fn swap_items(
+        &mut self,
+        src_container: ContainerType,
+        src_index: u16,
+        dst_container: ContainerType,
+        dst_index: u16,
+    ) -> Option<(Item, Item)> {
+        let src_item = self.get_item(src_container, src_index)?;
+        let dst_slot = self.get_item_mut(dst_container, dst_index)?;
+
+        // move src item into dst slot
+        let dst_item = *dst_slot;
+        dst_slot.clone_from(&src_item);
+
+        // move dst item into src slot
+        let src_slot = self.get_item_mut(src_container, src_index)?;
+        src_slot.clone_from(&dst_item);
+
+        Some((dst_item, src_item))
+    }
+
+    /// Gives the player the key item `id`. Key items never stack, so holding one already (or having no room) does nothing.
+    /// Returns the slot it was placed in.
+    pub fn add_key_item(&mut self, id: u32) -> Option<ItemInfo> {
+        if id < KEY_ITEM_ID_START || self.has_key_item(id) {
+            return None;
+        }
+
+        let index = self
+            .key_items
+            .slots
+            .iter()
+            .position(|slot| slot.is_empty_slot())?;
+        let slot = &mut self.key_items.slots[index];
+        *slot = Item {
+            item_id: id,
+            quantity: 1,
+            ..Default::default()
+        };
+
+        Some(ItemInfo {
+            container: ContainerType::KeyItems,
+            slot: index as u16,
+            ..(*slot).into()
+        })
+    }
+
+    /// Takes away the key item `id`, returning the slot it was removed from.
+    pub fn remove_key_item(&mut self, id: u32) -> Option<ItemInfo> {
+        let index = self
+            .key_items
+            .slots
+            .iter()
+            .position(|slot| slot.item_id == id && !slot.is_empty_slot())?;
+        self.key_items.slots[index] = Item::default();
+
+        Some(ItemInfo {
+            container: ContainerType::KeyItems,
+            slot: index as u16,
+            ..Item::default().into()
+        })
+    }
+
+    /// Whether the player holds the key item `id`.
+    pub fn has_key_item(&self, id: u32) -> bool {
+        count_in_container(&self.key_items, id) > 0
+    }
+
+    /// How many of `item_id` are held across every container, even if they're split over several stacks.
+    pub fn count_item(&self, item_id: u32) -> u32 {
+        self.into_iter()
+            .map(|(_, container)| count_in_container(container, item_id))
+            .sum()
+    }
+
+    /// Removes `quantity` of `item_id`, taking from as many stacks as needed. Equipped items are never removed.
+    /// Returns the slots that changed, or None (and nothing is removed) if there isn't enough of it.
+    pub fn remove_item(&mut self, item_id: u32, quantity: u32) -> Option<Vec<ItemInfo>> {
+        let containers: Vec<ContainerType> = self
+            .into_iter()
+            .map(|(container_type, _)| container_type)
+            .filter(|container_type| *container_type != ContainerType::Equipped)
+            .collect();
+
+        let held: u32 = containers
+            .iter()
+            .filter_map(|container_type| self.get_container(*container_type))
+            .map(|container| count_in_container(container, item_id))
+            .sum();
+        if held < quantity {
+            return None;
+        }
+
+        let mut remaining = quantity;
+        let mut changed = Vec::new();
+        for container_type in containers {
+            let Some(container) = self.get_container_mut(&container_type) else {
+                continue;
+            };
+
+            for index in 0..container.max_slots() as u16 {
+                if remaining == 0 {
+                    return Some(changed);
+                }
+
+                let slot = container.get_slot_mut(index);
+                if slot.item_id != item_id || slot.is_empty_slot() {
+                    continue;
+                }
+
+                let taken = slot.quantity.min(remaining);
+                slot.quantity -= taken;
+                remaining -= taken;
+
+                // Currency and crystal slots always keep their item id.
+                if slot.quantity == 0
+                    && !matches!(
+                        container_type,
+                        ContainerType::Currency | ContainerType::Crystals
+                    )
+                {
+                    *slot = Item::default();
+                }
+
+                changed.push(ItemInfo {
+                    container: container_type,
+                    slot: index,
+                    ..(*slot).into()
+                });
+            }
+        }
+
+        Some(changed)
+    }
+
     fn add_in_empty_slot(&mut self, item: Item) -> Option<ItemInfo> {
         for page in &mut self.pages {
             for (slot_index, slot) in page.slots.iter_mut().enumerate() {
@@ -320,14 +500,54 @@ impl Inventory {
     }
 
     pub fn add_in_next_free_slot(&mut self, item: Item) -> Option<ItemInfo> {
+        self.try_add_in_next_free_slot(item).ok()
+    }
+
+    /// Like `add_in_next_free_slot`, but explains why the item couldn't be added.
+    pub fn try_add_in_next_free_slot(&mut self, mut item: Item) -> Result<ItemInfo, AddItemError> {
+        // These have their own containers, which aren't filled in here.
+        if item.item_id == 0
+            || item.item_id >= KEY_ITEM_ID_START
+            || CurrencyKind::from_repr(item.item_id).is_some()
+        {
+            return Err(AddItemError::NoValidContainer);
+        }
+
+        // Shards, crystals and clusters live in their own container.
+        if let Some(kind) = CrystalKind::from_repr(item.item_id) {
+            let slot = self.crystals.get_item_for_id(kind);
+            let quantity = slot.quantity.saturating_add(item.quantity);
+            if quantity > CrystalsStorage::MAX_QUANTITY {
+                return Err(AddItemError::InventoryFull);
+            }
+
+            slot.quantity = quantity;
+            return Ok(ItemInfo {
+                slot: CrystalsStorage::get_slot_for_id(kind),
+                container: ContainerType::Crystals,
+                ..(*slot).into()
+            });
+        }
+
+        // Gear goes into the matching Armoury Chest, as long as there's room left.
+        if let Some(equip_slot) = item.equip_slot
+            && let Some(destination) = self.add_in_next_free_armory_slot(equip_slot)
+        {
+            self.add_in_slot(item, &destination.container, destination.slot);
+            return Ok(ItemInfo {
+                slot: destination.slot,
+                container: destination.container,
+                ..item.into()
+            });
+        }
+
         if item.stack_size > 1 {
             for page in &mut self.pages {
                 for (slot_index, slot) in page.slots.iter_mut().enumerate() {
-                    if slot.item_id == item.item_id
-                        && slot.quantity + item.quantity <= item.stack_size
-                    {
-                        slot.quantity += item.quantity;
-                        return Some(ItemInfo {
+                    let quantity = slot.quantity.saturating_add(item.quantity);
+                    if slot.item_id == item.item_id && quantity <= item.stack_size {
+                        slot.quantity = quantity;
+                        return Ok(ItemInfo {
                             slot: slot_index as u16,
                             container: page.kind,
                             ..(*slot).into()
@@ -337,8 +557,20 @@ impl Inventory {
             }
         }
 
+        // A new stack can't hold more than the item's stack size. (Items without a stack size are left alone.)
+        if item.stack_size > 0 && item.quantity > item.stack_size {
+            tracing::warn!(
+                "{} of item {} won't fit in a single stack, only adding {}!",
+                item.quantity,
+                item.item_id,
+                item.stack_size
+            );
+            item.quantity = item.stack_size;
+        }
+
         // If we didn't find any stacks, or the item isn't stackable, try again to find an empty inventory slot.
         self.add_in_empty_slot(item)
+            .ok_or(AddItemError::InventoryFull)
     }
 
     pub fn add_in_next_free_armory_slot(&self, equip_index: u16) -> Option<ItemInfo> {
@@ -420,6 +652,53 @@ impl Inventory {
         }
     }
 
+    /// Sorts the items inside of `container_type` by `key`, and returns the slots that changed.
+    ///
+    /// Empty slots always sink to the end. The equipped, currency and crystal containers can't be sorted since their slots have special meanings.
+    pub fn sort_container(&mut self, container_type: ContainerType, key: SortKey) -> Vec<u16> {
+        if matches!(
+            container_type,
+            ContainerType::Equipped | ContainerType::Currency | ContainerType::Crystals
+        ) {
+            return Vec::new();
+        }
+
+        let Some(container) = self.get_container_mut(&container_type) else {
+            return Vec::new();
+        };
+
+        let before: Vec<Item> = (0..container.max_slots() as u16)
+            .map(|i| *container.get_slot(i))
+            .collect();
+
+        let mut sorted = before.clone();
+        sorted.sort_by(|a, b| {
+            a.is_empty_slot()
+                .cmp(&b.is_empty_slot())
+                .then_with(|| match key {
+                    SortKey::ItemId => a.item_id.cmp(&b.item_id),
+                    SortKey::ItemLevel => b
+                        .item_level
+                        .cmp(&a.item_level)
+                        .then(a.item_id.cmp(&b.item_id)),
+                    SortKey::Category => a
+                        .ui_category
+                        .cmp(&b.ui_category)
+                        .then(a.item_id.cmp(&b.item_id)),
+                })
+        });
+
+        let mut changed = Vec::new();
+        for (index, (old, new)) in before.iter().zip(&sorted).enumerate() {
+            if old != new {
+                *container.get_slot_mut(index as u16) = *new;
+                changed.push(index as u16);
+            }
+        }
+
+        changed
+    }
+
     /// Returns the model ID for the equipped main weapon.
     pub fn get_main_weapon_id(&self, game_data: &mut GameData) -> WeaponModelId {
         let mut model = WeaponModelId::from(
@@ -605,6 +884,15 @@ impl Inventory {
     }
 }
 
+/// How many of `item_id` are in `container`, across all of its slots.
+fn count_in_container(container: &dyn Storage, item_id: u32) -> u32 {
+    (0..container.max_slots() as u16)
+        .map(|index| container.get_slot(index))
+        .filter(|slot| slot.item_id == item_id && !slot.is_empty_slot())
+        .map(|slot| slot.quantity)
+        .sum()
+}
+
 /// Represents a single housing plot's collective inventory, both inside and out.
 // TODO: This will need to adjustments in 7.5x
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -832,6 +1120,30 @@ impl HousingInventory {
     }
 }
 
+/// Why an item couldn't be given to a player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AddItemError {
+    /// There's no such item in the Item Excel sheet.
+    UnknownItem,
+    /// Every slot it could go into is taken, or its stack can't hold any more.
+    InventoryFull,
+    /// It doesn't belong in any container items are added to, like currencies and key items.
+    NoValidContainer,
+}
+
+impl std::fmt::Display for AddItemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownItem => write!(f, "This item doesn't exist!"),
+            Self::InventoryFull => write!(f, "Unable to add item to inventory, it's full!"),
+            Self::NoValidContainer => write!(
+                f,
+                "Unable to add item to inventory, it doesn't go in any container! Currencies and key items have their own commands."
+            ),
+        }
+    }
+}
+
 /// Used to decide which set of housing inventory pages to send.
 #[derive(Debug, Copy, Clone)]
 pub enum DesiredHousingInventoryPages {
@@ -841,3 +1153,255 @@ pub enum DesiredHousingInventoryPages {
     Interior,
     InteriorStoreroom,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(item_id: u32, quantity: u32, item_level: u16) -> Item {
+        Item {
+            item_id,
+            quantity,
+            item_level,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_add_crystals() {
+        let mut inventory = Inventory::default();
+
+        let info = inventory
+            .add_in_next_free_slot(item(CrystalKind::FireShard as u32, 50, 1))
+            .unwrap();
+        assert_eq!(info.container, ContainerType::Crystals);
+        assert_eq!(info.slot, 0);
+        assert_eq!(inventory.crystals.fire_shard.quantity, 50);
+        assert!(inventory.pages[0].slots[0].is_empty_slot());
+
+        // They stack up to 9999, but no further.
+        inventory.add_in_next_free_slot(item(CrystalKind::FireShard as u32, 9949, 1));
+        assert_eq!(inventory.crystals.fire_shard.quantity, 9999);
+        assert!(
+            inventory
+                .add_in_next_free_slot(item(CrystalKind::FireShard as u32, 1, 1))
+                .is_none()
+        );
+        assert!(inventory.pages[0].slots[0].is_empty_slot());
+
+        // Even absurd amounts are turned away instead of overflowing.
+        assert!(
+            inventory
+                .add_in_next_free_slot(item(CrystalKind::FireShard as u32, u32::MAX, 1))
+                .is_none()
+        );
+        assert_eq!(inventory.crystals.fire_shard.quantity, 9999);
+    }
+
+    #[test]
+    fn test_add_to_stack_without_overflow() {
+        let mut inventory = Inventory::default();
+        let potion = Item {
+            stack_size: 999,
+            ..item(4551, 10, 1)
+        };
+        inventory.add_in_next_free_slot(potion);
+
+        // Too many to fit on the existing stack, so they start a new one that's clamped to the stack size.
+        let info = inventory
+            .add_in_next_free_slot(Item {
+                quantity: u32::MAX,
+                ..potion
+            })
+            .unwrap();
+        assert_eq!(info.slot, 1);
+        assert_eq!(inventory.pages[0].slots[0].quantity, 10);
+        assert_eq!(inventory.pages[0].slots[1].quantity, 999);
+    }
+
+    #[test]
+    fn test_add_to_full_inventory() {
+        let mut inventory = Inventory::default();
+        for page in &mut inventory.pages {
+            for slot in page.slots.iter_mut() {
+                *slot = item(5000, 1, 1);
+            }
+        }
+
+        assert_eq!(
+            inventory.try_add_in_next_free_slot(item(6000, 1, 1)).err(),
+            Some(AddItemError::InventoryFull)
+        );
+
+        // A full crystal stack counts too.
+        inventory.crystals.fire_shard.quantity = CrystalsStorage::MAX_QUANTITY;
+        assert_eq!(
+            inventory
+                .try_add_in_next_free_slot(item(CrystalKind::FireShard as u32, 1, 1))
+                .err(),
+            Some(AddItemError::InventoryFull)
+        );
+    }
+
+    #[test]
+    fn test_add_without_valid_container() {
+        let mut inventory = Inventory::default();
+
+        assert_eq!(
+            inventory
+                .try_add_in_next_free_slot(item(CurrencyKind::Gil as u32, 100, 1))
+                .err(),
+            Some(AddItemError::NoValidContainer)
+        );
+        assert_eq!(
+            inventory
+                .try_add_in_next_free_slot(item(KEY_ITEM_ID_START + 500, 1, 1))
+                .err(),
+            Some(AddItemError::NoValidContainer)
+        );
+        assert!(inventory.pages[0].slots[0].is_empty_slot());
+    }
+
+    #[test]
+    fn test_remove_item_across_stacks() {
+        let mut inventory = Inventory::default();
+        inventory.pages[0].slots[0] = item(5000, 5, 1);
+        inventory.pages[1].slots[3] = item(5000, 10, 1);
+        inventory.pages[2].slots[0] = item(6000, 1, 1);
+        assert_eq!(inventory.count_item(5000), 15);
+
+        // Not enough, so nothing is touched.
+        assert!(inventory.remove_item(5000, 20).is_none());
+        assert_eq!(inventory.count_item(5000), 15);
+
+        let changed = inventory.remove_item(5000, 8).unwrap();
+        assert_eq!(changed.len(), 2);
+        assert_eq!(inventory.count_item(5000), 7);
+        assert!(inventory.pages[0].slots[0].is_empty_slot());
+        assert_eq!(inventory.pages[1].slots[3].quantity, 7);
+        assert_eq!(inventory.count_item(6000), 1);
+    }
+
+    #[test]
+    fn test_key_items() {
+        let mut inventory = Inventory::default();
+        let key_item = KEY_ITEM_ID_START + 500;
+
+        assert!(!inventory.has_key_item(key_item));
+        let info = inventory.add_key_item(key_item).unwrap();
+        assert_eq!(info.container, ContainerType::KeyItems);
+        assert_eq!(info.slot, 0);
+        assert!(inventory.has_key_item(key_item));
+
+        // They don't stack, and regular items can't be key items.
+        assert!(inventory.add_key_item(key_item).is_none());
+        assert_eq!(inventory.key_items.slots[0].quantity, 1);
+        assert!(inventory.key_items.slots[1].is_empty_slot());
+        assert!(inventory.add_key_item(5000).is_none());
+
+        assert_eq!(inventory.remove_key_item(key_item).unwrap().slot, 0);
+        assert!(!inventory.has_key_item(key_item));
+        assert!(inventory.remove_key_item(key_item).is_none());
+    }
+
+    #[test]
+    fn test_add_helmet_to_armory() {
+        let mut inventory = Inventory::default();
+
+        let helmet = Item {
+            equip_slot: Some(2),
+            ..item(3000, 1, 10)
+        };
+        let info = inventory.add_in_next_free_slot(helmet).unwrap();
+        assert_eq!(info.container, ContainerType::ArmoryHead);
+        assert_eq!(info.slot, 0);
+        assert_eq!(*inventory.armoury_head.get_slot(0), helmet);
+        assert!(inventory.pages[0].slots[0].is_empty_slot());
+
+        // Anything that isn't equipment still goes into the regular inventory.
+        let info = inventory.add_in_next_free_slot(item(4000, 1, 1)).unwrap();
+        assert_eq!(info.container, ContainerType::Inventory0);
+    }
+
+    #[test]
+    fn test_add_starting_items() {
+        let mut inventory = Inventory::default();
+
+        inventory.add_initial_items(&[item(4551, 5, 1), item(5057, 1, 1)], 1500);
+        assert_eq!(inventory.pages[0].slots[0], item(4551, 5, 1));
+        assert_eq!(inventory.pages[0].slots[1], item(5057, 1, 1));
+        assert!(inventory.pages[0].slots[2].is_empty_slot());
+        assert_eq!(inventory.currency.gil.quantity, 1500);
+
+        // Gil is still capped like usual
+        let mut inventory = Inventory::default();
+        inventory.add_initial_items(&[], u32::MAX);
+        assert_eq!(
+            inventory.currency.gil.quantity,
+            CurrencyKind::Gil.max_quantity()
+        );
+    }
+
+    #[test]
+    fn test_sort_container() {
+        let mut inventory = Inventory::default();
+        inventory.pages[0].slots[0] = item(5, 1, 10);
+        inventory.pages[0].slots[2] = item(2, 99, 30);
+        inventory.pages[0].slots[3] = item(3, 7, 20);
+
+        // By item id, with the empty slot sinking to the end
+        let changed = inventory.sort_container(ContainerType::Inventory0, SortKey::ItemId);
+        assert_eq!(changed, vec![0, 1, 2, 3]);
+        assert_eq!(inventory.pages[0].slots[0], item(2, 99, 30));
+        assert_eq!(inventory.pages[0].slots[1], item(3, 7, 20));
+        assert_eq!(inventory.pages[0].slots[2], item(5, 1, 10));
+        assert!(inventory.pages[0].slots[3].is_empty_slot());
+
+        // These also happen to be ordered by item level, so nothing should change
+        let changed = inventory.sort_container(ContainerType::Inventory0, SortKey::ItemLevel);
+        assert_eq!(changed, Vec::<u16>::new());
+
+        // By item level, highest first
+        inventory.pages[0].slots.swap(0, 2);
+        let changed = inventory.sort_container(ContainerType::Inventory0, SortKey::ItemLevel);
+        assert_eq!(changed, vec![0, 2]);
+
+        // Quantities should be preserved
+        let total: u32 = inventory.pages[0].slots.iter().map(|x| x.quantity).sum();
+        assert_eq!(total, 107);
+    }
+
+    #[test]
+    fn test_swap_items() {
+        let mut inventory = Inventory::default();
+        inventory.pages[0].slots[4] = item(1, 1, 5);
+        inventory.equipped.head = item(2, 1, 10);
+
+        // Equipping something over an existing piece should swap them
+        let (in_src, in_dst) = inventory
+            .swap_items(ContainerType::Inventory0, 4, ContainerType::Equipped, 2)
+            .unwrap();
+        assert_eq!(in_src, item(2, 1, 10));
+        assert_eq!(in_dst, item(1, 1, 5));
+        assert_eq!(inventory.equipped.head, item(1, 1, 5));
+        assert_eq!(inventory.pages[0].slots[4], item(2, 1, 10));
+
+        // Invalid containers are rejected
+        assert!(
+            inventory
+                .swap_items(ContainerType::Invalid, 0, ContainerType::Equipped, 2)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_sort_equipped() {
+        // Equipped slots have a meaning, so they can't be sorted
+        let mut inventory = Inventory::default();
+        assert!(
+            inventory
+                .sort_container(ContainerType::Equipped, SortKey::ItemId)
+                .is_empty()
+        );
+    }
+}
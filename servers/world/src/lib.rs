@@ -11,7 +11,8 @@ use diesel::sqlite::Sqlite;
 use kawari::constants::CLASSJOB_ARRAY_SIZE;
 use serde::{Deserialize, Serialize};
 pub use zone_connection::{
-    ObsfucationData, PlayerData, TeleportReason, ZoneConnection, spawn_allocator::SpawnAllocator,
+    ContentQueue, MoveThrottle, ObsfucationData, PlayerData, TeleportReason, ZoneConnection,
+    spawn_allocator::SpawnAllocator,
 };
 
 mod database;
@@ -32,7 +33,9 @@ mod custom_ipc_connection;
 pub use custom_ipc_connection::CustomIpcConnection;
 
 mod common;
-pub use common::{ClientHandle, ClientId, FromServer, MessageInfo, ServerHandle, ToServer};
+pub use common::{
+    ClientHandle, ClientId, FromServer, MessageInfo, OnlinePlayer, ServerHandle, ToServer,
+};
 
 mod navmesh;
 pub use navmesh::{Navmesh, NavmeshParams, NavmeshTile};
@@ -46,7 +49,10 @@ mod bitmask;
 pub use bitmask::{Bitmask, QuestBitmask};
 
 mod gamedata;
-pub use gamedata::{GameData, ItemInfoQuery, ItemRow, Recipe, Roulette, TerritoryNameKind};
+pub use gamedata::{
+    GameData, GameDataError, ItemInfoQuery, ItemRow, Recipe, Roulette, TerritoryLookupError,
+    TerritoryNameKind,
+};
 
 mod chara_make;
 pub use chara_make::CharaMake;
@@ -126,3 +132,47 @@ define_sql_array!(ActiveQuests, PersistentQuest);
 define_sql_array!(FavoriteAetherytes, u16);
 define_sql_array!(PartyMembers, i64, 8);
 define_sql_array!(GrandCompanyRanks, u8, 3);
+define_sql_array!(LearnedActions, u32);
+define_sql_array!(RecastTimers, RecastTimer);
+
+impl LearnedActions {
+    /// Records this action as learned, returning false if it already was.
+    pub fn learn(&mut self, action_id: u32) -> bool {
+        if self.0.contains(&action_id) {
+            return false;
+        }
+
+        self.0.push(action_id);
+        true
+    }
+}
+
+/// When an action can be used again.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RecastTimer {
+    pub action_id: u32,
+    /// In milliseconds since UNIX epoch, so it still means something after logging out.
+    pub ends_at: u64,
+}
+
+impl RecastTimers {
+    /// Returns true if `action_id` is not currently recasting.
+    pub fn is_ready(&self, action_id: u32, now: u64) -> bool {
+        self.0
+            .iter()
+            .find(|timer| timer.action_id == action_id)
+            .is_none_or(|timer| now >= timer.ends_at)
+    }
+
+    /// Starts the recast for `action_id`, ending `recast` from `now`. Timers that have already ended are dropped.
+    pub fn start(&mut self, action_id: u32, recast: std::time::Duration, now: u64) {
+        self.0
+            .retain(|timer| timer.action_id != action_id && timer.ends_at > now);
+        if !recast.is_zero() {
+            self.0.push(RecastTimer {
+                action_id,
+                ends_at: now + recast.as_millis() as u64,
+            });
+        }
+    }
+}
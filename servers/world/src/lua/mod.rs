@@ -5,7 +5,7 @@ mod inventory;
 
 mod player;
 use mlua::{UserData, UserDataFields};
-pub use player::{LuaContent, LuaPlayer};
+pub use player::{LuaContent, LuaPlayer, MessageChannel};
 
 mod state;
 pub use state::{KawariLua, KawariLuaState};
@@ -55,5 +55,6 @@ fn create_ipc_target<T: QueueSegments>(
 impl UserData for TeleportQuery {
     fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
         fields.add_field_method_get("aetheryte_id", |_, this| Ok(this.aetheryte_id));
+        fields.add_field_method_get("free", |_, this| Ok(this.free));
     }
 }
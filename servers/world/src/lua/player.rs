@@ -1,19 +1,28 @@
-use std::sync::Arc;
+use std::{f32::consts::TAU, io::Cursor, sync::Arc};
 
+use binrw::BinRead;
 use mlua::{LuaSerdeExt, UserData, UserDataFields, UserDataMethods, Value};
 use parking_lot::Mutex;
+use strum_macros::{Display, EnumIter, FromRepr};
 
 use crate::{
     GameData, PlayerData, RemakeMode, StatusEffects,
-    inventory::{CrystalKind, CurrencyKind},
-    zone_connection::BaseParameters,
+    inventory::{CrystalKind, CurrencyKind, SortKey},
+    zone_connection::{BaseParameters, ReviveKind},
 };
 use kawari::{
-    common::{HandlerId, ObjectTypeId, ObjectTypeKind, Position, adjust_quest_id},
-    ipc::zone::{
-        ActorControlCategory, ActorControlSelf, ActorSetPos, EventType, GrandCompany, OnlineStatus,
-        SceneFlags, ServerNoticeFlags, ServerNoticeMessage, ServerZoneIpcData,
-        ServerZoneIpcSegment,
+    common::{
+        ContainerType, CustomizeData, HandlerId, MESSAGE_MAX_LENGTH, ObjectId, ObjectTypeId,
+        ObjectTypeKind, Position, adjust_quest_id, truncate_at_char_boundary,
+    },
+    config::get_config,
+    ipc::{
+        chat::ChatChannelType,
+        zone::{
+            ActorControlCategory, ActorControlSelf, ActorSetPos, ChatMessage, EventScene,
+            EventType, GrandCompany, OnlineStatus, SceneFlags, ServerNoticeFlags,
+            ServerNoticeMessage, ServerZoneIpcData, ServerZoneIpcSegment,
+        },
     },
     packet::PacketSegment,
 };
@@ -36,6 +45,63 @@ impl UserData for LuaContent {
     }
 }
 
+/// The longest message that fits into a ServerNoticeMessage.
+const SERVER_NOTICE_MAX_LENGTH: usize = 775;
+
+/// Where a message from `print_message` shows up.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter, FromRepr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum MessageChannel {
+    /// A system message in the chat log.
+    System = 0,
+    /// In the middle of the screen, as well as in the chat log.
+    OnScreen,
+    /// Spoken aloud, like from /say.
+    Say,
+    /// Like from /shout.
+    Shout,
+    /// Like from /yell.
+    Yell,
+}
+
+impl mlua::IntoLua for MessageChannel {
+    fn into_lua(self, _: &mlua::Lua) -> mlua::Result<mlua::Value> {
+        Ok(mlua::Value::Integer(self as i64))
+    }
+}
+
+impl mlua::FromLua for MessageChannel {
+    fn from_lua(value: mlua::Value, _: &mlua::Lua) -> mlua::Result<Self> {
+        match value {
+            mlua::Value::Integer(integer) => Self::from_repr(integer as u8)
+                .ok_or_else(|| mlua::Error::runtime(format!("Unknown message channel {integer}"))),
+            _ => Err(mlua::Error::runtime("Expected a message channel")),
+        }
+    }
+}
+
+/// Splits `message` into pieces of at most `max_length` bytes, without breaking up any characters.
+fn split_message(message: &str, max_length: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut rest = message;
+    while rest.len() > max_length {
+        let piece = truncate_at_char_boundary(rest, max_length);
+        pieces.push(piece.to_string());
+        rest = &rest[piece.len()..];
+    }
+    pieces.push(rest.to_string());
+
+    pieces
+}
+
+/// Wraps `rotation` (in radians) into `[0, 2π)`.
+fn normalize_rotation(rotation: f32) -> f32 {
+    let rotation = rotation.rem_euclid(TAU);
+    // Tiny negative angles can round up to exactly 2π
+    if rotation >= TAU { 0.0 } else { rotation }
+}
+
 #[derive(Default)]
 pub struct LuaPlayer {
     pub player_data: PlayerData,
@@ -56,20 +122,59 @@ impl QueueSegments for LuaPlayer {
 impl LuaPlayer {
     fn send_message(&mut self, message: &str, param: u8) {
         // This is a completely arbitrary string, so we have to make sure it's the proper size.
-        let mut message = message.to_string();
-        message.truncate(775);
+        for message in split_message(message, SERVER_NOTICE_MAX_LENGTH) {
+            let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ServerNoticeMessage(
+                ServerNoticeMessage {
+                    message,
+                    flags: ServerNoticeFlags::from_bits(param).unwrap_or_default(),
+                },
+            ));
 
-        let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ServerNoticeMessage(
-            ServerNoticeMessage {
-                message,
-                flags: ServerNoticeFlags::from_bits(param).unwrap_or_default(),
-            },
-        ));
+            create_ipc_self(self, ipc, self.player_data.character.actor_id);
+        }
+    }
 
-        create_ipc_self(self, ipc, self.player_data.character.actor_id);
+    /// Sends `message` to the player on `channel`. `sender` is the name shown for spoken messages, and defaults to the player's own.
+    fn print_message(&mut self, channel: MessageChannel, message: &str, sender: Option<String>) {
+        let chat_channel = match channel {
+            MessageChannel::System => {
+                self.send_message(message, ServerNoticeFlags::CHAT_LOG.bits());
+                return;
+            }
+            MessageChannel::OnScreen => {
+                self.send_message(
+                    message,
+                    (ServerNoticeFlags::CHAT_LOG | ServerNoticeFlags::ON_SCREEN).bits(),
+                );
+                return;
+            }
+            MessageChannel::Say => ChatChannelType::Say,
+            MessageChannel::Shout => ChatChannelType::Shout,
+            MessageChannel::Yell => ChatChannelType::Yell,
+        };
+
+        let character = self.player_data.character.clone();
+        let sender_name = sender.unwrap_or_else(|| character.name.clone());
+        let sender_world_id = get_config().world.world_id;
+
+        for message in split_message(message, MESSAGE_MAX_LENGTH - 1) {
+            let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ChatMessage(ChatMessage {
+                sender_account_id: character.service_account_id as u64,
+                sender_content_id: character.content_id as u64,
+                sender_actor_id: character.actor_id,
+                sender_world_id,
+                channel: chat_channel,
+                sender_name: sender_name.clone(),
+                message: message.into(),
+            }));
+
+            create_ipc_self(self, ipc, self.player_data.character.actor_id);
+        }
     }
 
     fn give_status_effect(&mut self, effect_id: u16, effect_param: u16, duration: f32) {
+        // Keep our copy up to date, so the script can see it before the server responds.
+        self.status_effects.add(effect_id, effect_param, duration);
         self.queued_tasks.push(LuaTask::GainStatusEffect {
             effect_id,
             effect_param,
@@ -77,6 +182,20 @@ impl LuaPlayer {
         });
     }
 
+    fn lose_status_effect(&mut self, effect_id: u16) {
+        // Nothing to do if we don't have it
+        let Some(effect) = self.status_effects.get(effect_id) else {
+            return;
+        };
+
+        self.status_effects.remove(effect_id);
+        self.queued_tasks.push(LuaTask::LoseStatusEffect {
+            effect_id,
+            effect_param: effect.param,
+            effect_source_actor_id: effect.source_actor_id,
+        });
+    }
+
     pub fn play_scene(&mut self, scene: u16, scene_flags: SceneFlags, params: Vec<u32>) {
         self.queued_tasks.push(LuaTask::PlayScene {
             scene,
@@ -95,6 +214,34 @@ impl LuaPlayer {
         create_ipc_self(self, ipc, self.player_data.character.actor_id);
     }
 
+    fn set_rotation(&mut self, rotation: f32) {
+        let rotation = normalize_rotation(rotation);
+        self.player_data.volatile.rotation = rotation as f64;
+
+        // The client only turns if we warp them, so keep them where they are
+        self.set_position(self.player_data.volatile.position, rotation);
+    }
+
+    fn move_actor(&mut self, actor_id: ObjectId, waypoints: Vec<Position>, speed: f32) {
+        self.queued_tasks.push(LuaTask::MoveActor {
+            actor_id,
+            waypoints,
+            speed,
+        });
+    }
+
+    fn revive(&mut self, kind: ReviveKind) {
+        self.queued_tasks.push(LuaTask::Revive { kind });
+    }
+
+    fn spawn_npc(&mut self, enpc_id: u32, position: Position, rotation: f32) {
+        self.queued_tasks.push(LuaTask::SpawnNpc {
+            enpc_id,
+            position,
+            rotation,
+        });
+    }
+
     fn set_festival(&mut self, festival1: u32, festival2: u32, festival3: u32, festival4: u32) {
         let ipc =
             ServerZoneIpcSegment::new(ServerZoneIpcData::ActorControlSelf(ActorControlSelf {
@@ -113,6 +260,10 @@ impl LuaPlayer {
         self.queued_tasks.push(LuaTask::Unlock { id });
     }
 
+    fn unlock_action(&mut self, action_id: u32) {
+        self.queued_tasks.push(LuaTask::UnlockAction { action_id });
+    }
+
     fn unlock_all(&mut self) {
         self.queued_tasks.push(LuaTask::UnlockAll {});
     }
@@ -155,6 +306,28 @@ impl LuaPlayer {
         });
     }
 
+    /// Warps to the zone called `name`, appearing at `position`.
+    fn warp_to_zone(
+        &mut self,
+        game_data: mlua::Value,
+        name: &str,
+        position: Position,
+    ) -> mlua::Result<()> {
+        let game_data = match game_data {
+            mlua::Value::UserData(ud) => ud.borrow::<Arc<Mutex<GameData>>>().unwrap().clone(),
+            _ => unreachable!(),
+        };
+
+        let zone_id = game_data
+            .lock()
+            .find_territory_by_name(name)
+            .map_err(|err| mlua::Error::runtime(format!("Can't warp to {name}: {err}")))?;
+
+        self.change_territory(zone_id, Some(position), None);
+
+        Ok(())
+    }
+
     fn set_remake_mode(&mut self, mode: RemakeMode) {
         self.queued_tasks.push(LuaTask::SetRemakeMode(mode));
     }
@@ -168,7 +341,10 @@ impl LuaPlayer {
     }
 
     pub fn finish_event(&mut self) {
-        self.queued_tasks.push(LuaTask::FinishEvent {});
+        self.queued_tasks.push(LuaTask::FinishEvent {
+            result: 1,
+            arg: None,
+        });
     }
 
     fn unlock_classjob(&mut self, classjob_id: u8) {
@@ -183,6 +359,33 @@ impl LuaPlayer {
         });
     }
 
+    fn do_emote(&mut self, emote_id: u32, target: Option<ObjectTypeId>) {
+        self.queued_tasks.push(LuaTask::Emote {
+            emote_id,
+            target: target.map(|target| target.object_id),
+        });
+    }
+
+    fn repair_item(&mut self, container: ContainerType, index: u16, gil_cost: u32) {
+        self.queued_tasks.push(LuaTask::RepairItem {
+            container,
+            index,
+            gil_cost,
+        });
+    }
+
+    fn repair_all(&mut self, gil_cost: u32) {
+        self.queued_tasks.push(LuaTask::RepairAll { gil_cost });
+    }
+
+    fn equip_item(&mut self, container: ContainerType, index: u16, equip_slot: u16) {
+        self.queued_tasks.push(LuaTask::EquipItem {
+            container,
+            index,
+            equip_slot,
+        });
+    }
+
     fn toggle_invisiblity(&mut self) {
         self.queued_tasks.push(LuaTask::ToggleInvisibility {
             invisible: !self.player_data.gm_invisible,
@@ -194,10 +397,13 @@ impl LuaPlayer {
     }
 
     fn change_weather(&mut self, id: u8) {
+        self.zone_data.weather_id = id as u16;
         self.queued_tasks.push(LuaTask::ChangeWeather { id });
     }
 
     pub fn modify_currency(&mut self, id: CurrencyKind, amount: i32, send_client_update: bool) {
+        // Also apply it to our copy, so the script sees the new balance before the task is processed.
+        self.player_data.inventory.currency.modify(id, amount);
         self.queued_tasks.push(LuaTask::ModifyCurrency {
             id,
             amount,
@@ -222,18 +428,53 @@ impl LuaPlayer {
         self.queued_tasks.push(LuaTask::ToggleOrchestrion { id });
     }
 
-    pub fn add_item(&mut self, id: u32, quantity: u32, send_client_update: bool) {
+    fn unlock_orchestrion_all(&mut self) {
+        self.queued_tasks.push(LuaTask::UnlockOrchestrionAll {});
+    }
+
+    pub fn add_item(&mut self, id: u32, quantity: u32, send_client_update: bool, announce: bool) {
         self.queued_tasks.push(LuaTask::AddItem {
             id,
             quantity,
             send_client_update,
+            announce,
         });
     }
 
+    fn remove_item(&mut self, id: u32, quantity: u32) {
+        // Keep our copy in sync, so the script sees the new counts before the task is processed.
+        self.player_data.inventory.remove_item(id, quantity);
+        self.queued_tasks.push(LuaTask::RemoveItem { id, quantity });
+    }
+
+    fn add_key_item(&mut self, id: u32) {
+        self.player_data.inventory.add_key_item(id);
+        self.queued_tasks.push(LuaTask::AddKeyItem { id });
+    }
+
+    fn remove_key_item(&mut self, id: u32) {
+        self.player_data.inventory.remove_key_item(id);
+        self.queued_tasks.push(LuaTask::RemoveKeyItem { id });
+    }
+
+    fn add_item_by_name(&mut self, name: String, quantity: u32) {
+        self.queued_tasks
+            .push(LuaTask::AddItemByName { name, quantity });
+    }
+
+    fn sort_inventory(&mut self, container: ContainerType, key: SortKey) {
+        self.queued_tasks
+            .push(LuaTask::SortInventory { container, key });
+    }
+
     fn unlock_content(&mut self, id: u16) {
         self.queued_tasks.push(LuaTask::UnlockContent { id });
     }
 
+    fn complete_content(&mut self, id: u16) {
+        self.queued_tasks.push(LuaTask::CompleteContent { id });
+    }
+
     fn unlock_all_content(&mut self) {
         self.queued_tasks.push(LuaTask::UnlockAllContent {});
     }
@@ -298,6 +539,18 @@ impl LuaPlayer {
         self.queued_tasks.push(LuaTask::ToggleMount { id });
     }
 
+    fn unlock_mount(&mut self, id: u32) {
+        self.queued_tasks.push(LuaTask::UnlockMount { id });
+    }
+
+    fn set_mount(&mut self, mount_id: u16) {
+        self.queued_tasks.push(LuaTask::SetMount { mount_id });
+    }
+
+    fn summon_minion(&mut self, minion_id: u32) {
+        self.queued_tasks.push(LuaTask::SummonMinion { minion_id });
+    }
+
     fn toggle_glasses_style(&mut self, id: u32) {
         self.queued_tasks.push(LuaTask::ToggleGlassesStyle { id });
     }
@@ -383,6 +636,10 @@ impl LuaPlayer {
         self.queued_tasks.push(LuaTask::ToggleMinionAll {});
     }
 
+    fn unlock_minion(&mut self, id: u32) {
+        self.queued_tasks.push(LuaTask::UnlockMinion { id });
+    }
+
     fn toggle_aether_current(&mut self, id: u32) {
         self.queued_tasks.push(LuaTask::ToggleAetherCurrent { id });
     }
@@ -410,6 +667,11 @@ impl LuaPlayer {
         self.queued_tasks.push(LuaTask::SetHP { hp });
     }
 
+    fn set_eobj_state(&mut self, eobj_id: u32, state: u32) {
+        self.queued_tasks
+            .push(LuaTask::SetEObjState { eobj_id, state });
+    }
+
     fn set_mp(&mut self, mp: u16) {
         self.queued_tasks.push(LuaTask::SetMP { mp });
     }
@@ -426,6 +688,10 @@ impl LuaPlayer {
         self.queued_tasks.push(LuaTask::SetSex { sex });
     }
 
+    fn set_appearance(&mut self, customize: CustomizeData) {
+        self.queued_tasks.push(LuaTask::SetAppearance { customize });
+    }
+
     fn start_talk_event(&mut self) {
         self.queued_tasks.push(LuaTask::StartTalkEvent {});
     }
@@ -581,6 +847,10 @@ impl LuaPlayer {
         self.queued_tasks.push(LuaTask::SendMailboxStatus {});
     }
 
+    fn report_playtime(&mut self) {
+        self.queued_tasks.push(LuaTask::ReportPlaytime {});
+    }
+
     fn set_grand_company(&mut self, company: GrandCompany) {
         self.queued_tasks.push(LuaTask::SetGrandCompany { company });
     }
@@ -597,6 +867,38 @@ impl LuaPlayer {
     fn call(&mut self, name: String) {
         self.queued_tasks.push(LuaTask::Call { name });
     }
+
+    fn add_to_blacklist(&mut self, name: String) {
+        self.queued_tasks.push(LuaTask::AddToBlacklist { name });
+    }
+
+    fn remove_from_blacklist(&mut self, name: String) {
+        self.queued_tasks
+            .push(LuaTask::RemoveFromBlacklist { name });
+    }
+
+    fn toggle_online_status(&mut self, status: OnlineStatus) {
+        self.queued_tasks
+            .push(LuaTask::ToggleOnlineStatus { status });
+    }
+
+    fn create_free_company(&mut self, name: String, tag: String) {
+        self.queued_tasks
+            .push(LuaTask::CreateFreeCompany { name, tag });
+    }
+
+    fn join_free_company(&mut self, name: String) {
+        self.queued_tasks.push(LuaTask::JoinFreeCompany { name });
+    }
+
+    fn set_hp_mp(&mut self, hp: u32, mp: u16) {
+        self.queued_tasks.push(LuaTask::SetHpMp { hp, mp });
+    }
+
+    fn set_search_comment(&mut self, comment: String) {
+        self.queued_tasks
+            .push(LuaTask::SetSearchComment { comment });
+    }
 }
 
 impl UserData for LuaPlayer {
@@ -609,6 +911,13 @@ impl UserData for LuaPlayer {
                 Ok(())
             },
         );
+        methods.add_method_mut(
+            "print_message",
+            |_, this, (channel, message, sender): (MessageChannel, String, Option<String>)| {
+                this.print_message(channel, &message, sender);
+                Ok(())
+            },
+        );
         methods.add_method_mut(
             "gain_effect",
             |_, this, (effect_id, param, duration): (u16, u16, f32)| {
@@ -616,9 +925,29 @@ impl UserData for LuaPlayer {
                 Ok(())
             },
         );
+        methods.add_method_mut(
+            "add_status_effect",
+            |_, this, (effect_id, duration, param): (u16, f32, Option<u16>)| {
+                this.give_status_effect(effect_id, param.unwrap_or(0), duration);
+                Ok(())
+            },
+        );
+        methods.add_method_mut("remove_status_effect", |_, this, effect_id: u16| {
+            this.lose_status_effect(effect_id);
+            Ok(())
+        });
         methods.add_method_mut(
             "play_scene",
             |_, this, (scene, scene_flags, params): (u16, u32, Vec<u32>)| {
+                // Otherwise the scene can't be sent at all, and it's better to tell the script now.
+                if params.len() > EventScene::MAX_PARAMS {
+                    return Err(mlua::Error::runtime(format!(
+                        "Scene {scene} has {} params, but only up to {} are supported!",
+                        params.len(),
+                        EventScene::MAX_PARAMS
+                    )));
+                }
+
                 this.play_scene(
                     scene,
                     SceneFlags::from_bits(scene_flags).unwrap_or_default(),
@@ -636,6 +965,42 @@ impl UserData for LuaPlayer {
                 Ok(())
             },
         );
+        methods.add_method("get_rotation", |_, this, ()| {
+            Ok(this.player_data.volatile.rotation)
+        });
+        methods.add_method_mut("set_rotation", |_, this, rotation: f32| {
+            this.set_rotation(rotation);
+            Ok(())
+        });
+        methods.add_method_mut(
+            "move_actor",
+            |lua, this, (actor_id, waypoints, speed): (u32, Value, f32)| {
+                let waypoints: Vec<Position> = lua.from_value(waypoints)?;
+                // Otherwise the NPC would never get anywhere
+                if speed <= 0.0 {
+                    return Err(mlua::Error::runtime(format!(
+                        "Actor {actor_id} can't move at a speed of {speed}!"
+                    )));
+                }
+                this.move_actor(ObjectId(actor_id), waypoints, speed);
+                Ok(())
+            },
+        );
+        methods.add_method_mut("revive", |_, this, kind: u8| {
+            if let Some(kind) = ReviveKind::from_repr(kind) {
+                this.revive(kind);
+            }
+            Ok(())
+        });
+        methods.add_method_mut(
+            "spawn_npc",
+            |lua, this, (enpc_id, position, rotation): (u32, Value, Value)| {
+                let position: Position = lua.from_value(position).unwrap();
+                let rotation: f32 = lua.from_value(rotation).unwrap();
+                this.spawn_npc(enpc_id, position, rotation);
+                Ok(())
+            },
+        );
         methods.add_method_mut(
             "set_festival",
             |_, this, (festival1, festival2, festival3, festival4): (u32, u32, u32, u32)| {
@@ -651,6 +1016,10 @@ impl UserData for LuaPlayer {
             this.unlock(action_id);
             Ok(())
         });
+        methods.add_method_mut("unlock_action", |_, this, action_id: u32| {
+            this.unlock_action(action_id);
+            Ok(())
+        });
         methods.add_method_mut("unlock_all", |_, this, _: ()| {
             this.unlock_all();
             Ok(())
@@ -691,10 +1060,16 @@ impl UserData for LuaPlayer {
             this.begin_log_out();
             Ok(())
         });
-        methods.add_method_mut("finish_event", |_, this, _: ()| {
-            this.finish_event();
-            Ok(())
-        });
+        methods.add_method_mut(
+            "finish_event",
+            |_, this, (result, arg): (Option<u8>, Option<u32>)| {
+                this.queued_tasks.push(LuaTask::FinishEvent {
+                    result: result.unwrap_or(1),
+                    arg,
+                });
+                Ok(())
+            },
+        );
         methods.add_method_mut("unlock_classjob", |_, this, classjob_id: u8| {
             this.unlock_classjob(classjob_id);
             Ok(())
@@ -706,14 +1081,47 @@ impl UserData for LuaPlayer {
                 Ok(())
             },
         );
+        methods.add_method_mut(
+            "do_emote",
+            |_, this, (emote_id, target): (u32, Option<ObjectTypeId>)| {
+                this.do_emote(emote_id, target);
+                Ok(())
+            },
+        );
+        methods.add_method_mut(
+            "repair_item",
+            |_, this, (container, index, gil_cost): (u16, u16, Option<u32>)| {
+                if let Some(container) = ContainerType::from_repr(container) {
+                    this.repair_item(container, index, gil_cost.unwrap_or(0));
+                }
+                Ok(())
+            },
+        );
+        methods.add_method_mut("repair_all", |_, this, gil_cost: Option<u32>| {
+            this.repair_all(gil_cost.unwrap_or(0));
+            Ok(())
+        });
+        methods.add_method_mut(
+            "equip_item",
+            |_, this, (container, index, equip_slot): (u16, u16, u16)| {
+                if let Some(container) = ContainerType::from_repr(container) {
+                    this.equip_item(container, index, equip_slot);
+                }
+                Ok(())
+            },
+        );
         methods.add_method_mut("set_level", |_, this, level: u16| {
             this.set_level(level);
             Ok(())
         });
+        methods.add_method("get_weather", |_, this, ()| Ok(this.zone_data.weather_id));
         methods.add_method_mut("change_weather", |_, this, id: u8| {
             this.change_weather(id);
             Ok(())
         });
+        methods.add_method("get_currency", |_, this, id: CurrencyKind| {
+            Ok(this.player_data.inventory.currency.quantity_of(id))
+        });
         methods.add_method_mut(
             "modify_currency",
             |_, this, (id, amount): (CurrencyKind, i32)| {
@@ -736,15 +1144,66 @@ impl UserData for LuaPlayer {
             this.toggle_orchestrion(id);
             Ok(())
         });
-        methods.add_method_mut("add_item", |_, this, (id, quantity): (u32, u32)| {
-            // Can't think of any situations where we wouldn't want to force a client inventory update after using debug commands.
-            this.add_item(id, quantity, true);
+        methods.add_method_mut("unlock_orchestrion_all", |_, this, _: ()| {
+            this.unlock_orchestrion_all();
             Ok(())
         });
+        methods.add_method_mut(
+            "add_item",
+            |_, this, (id, quantity, announce): (u32, u32, Option<bool>)| {
+                // Can't think of any situations where we wouldn't want to force a client inventory update after using debug commands.
+                this.add_item(id, quantity, true, announce.unwrap_or_default());
+                Ok(())
+            },
+        );
+        methods.add_method_mut(
+            "remove_item",
+            |_, this, (id, quantity): (u32, Option<u32>)| {
+                this.remove_item(id, quantity.unwrap_or(1));
+                Ok(())
+            },
+        );
+        methods.add_method("has_item", |_, this, id: u32| {
+            Ok(this.player_data.inventory.count_item(id) > 0)
+        });
+        methods.add_method("get_item_count", |_, this, id: u32| {
+            Ok(this.player_data.inventory.count_item(id))
+        });
+        methods.add_method_mut("add_key_item", |_, this, id: u32| {
+            this.add_key_item(id);
+            Ok(())
+        });
+        methods.add_method_mut("remove_key_item", |_, this, id: u32| {
+            this.remove_key_item(id);
+            Ok(())
+        });
+        methods.add_method("has_key_item", |_, this, id: u32| {
+            Ok(this.player_data.inventory.has_key_item(id))
+        });
+        methods.add_method_mut(
+            "add_item_by_name",
+            |_, this, (name, quantity): (String, Option<u32>)| {
+                this.add_item_by_name(name, quantity.unwrap_or(1));
+                Ok(())
+            },
+        );
+        methods.add_method_mut(
+            "sort_inventory",
+            |_, this, (container, key): (u16, SortKey)| {
+                if let Some(container) = ContainerType::from_repr(container) {
+                    this.sort_inventory(container, key);
+                }
+                Ok(())
+            },
+        );
         methods.add_method_mut("unlock_content", |_, this, id: u16| {
             this.unlock_content(id);
             Ok(())
         });
+        methods.add_method_mut("complete_content", |_, this, id: u16| {
+            this.complete_content(id);
+            Ok(())
+        });
         methods.add_method_mut("unlock_all_content", |_, this, _: ()| {
             this.unlock_all_content();
             Ok(())
@@ -789,6 +1248,18 @@ impl UserData for LuaPlayer {
             this.toggle_mount(id);
             Ok(())
         });
+        methods.add_method_mut("unlock_mount", |_, this, id: u32| {
+            this.unlock_mount(id);
+            Ok(())
+        });
+        methods.add_method_mut("set_mount", |_, this, mount_id: u16| {
+            this.set_mount(mount_id);
+            Ok(())
+        });
+        methods.add_method_mut("summon_minion", |_, this, minion_id: u32| {
+            this.summon_minion(minion_id);
+            Ok(())
+        });
         methods.add_method_mut("toggle_glasses_style", |_, this, id: u32| {
             this.toggle_glasses_style(id);
             Ok(())
@@ -868,6 +1339,10 @@ impl UserData for LuaPlayer {
             this.toggle_minion(id);
             Ok(())
         });
+        methods.add_method_mut("unlock_minion", |_, this, id: u32| {
+            this.unlock_minion(id);
+            Ok(())
+        });
         methods.add_method_mut("toggle_minion_all", |_, this, _: ()| {
             this.toggle_minion_all();
             Ok(())
@@ -903,6 +1378,10 @@ impl UserData for LuaPlayer {
             this.set_hp(hp);
             Ok(())
         });
+        methods.add_method_mut("set_eobj_state", |_, this, (eobj_id, state): (u32, u32)| {
+            this.set_eobj_state(eobj_id, state);
+            Ok(())
+        });
         methods.add_method_mut("set_mp", |_, this, mp: u16| {
             this.set_mp(mp);
             Ok(())
@@ -919,6 +1398,12 @@ impl UserData for LuaPlayer {
             this.set_sex(sex);
             Ok(())
         });
+        methods.add_method_mut("set_appearance", |_, this, customize: Vec<u8>| {
+            let customize = CustomizeData::read_le(&mut Cursor::new(customize))
+                .map_err(|err| mlua::Error::runtime(format!("Invalid appearance: {err}")))?;
+            this.set_appearance(customize);
+            Ok(())
+        });
         methods.add_method("get_effect", |_, this, effect_id: u16| {
             Ok(this.status_effects.get(effect_id))
         });
@@ -951,6 +1436,16 @@ impl UserData for LuaPlayer {
             this.commence_duty(director_id);
             Ok(())
         });
+        methods.add_method_mut(
+            "warp_to_zone",
+            |lua, this, (name, x, y, z): (String, f32, f32, f32)| {
+                this.warp_to_zone(
+                    lua.globals().get("GAME_DATA").unwrap(),
+                    &name,
+                    Position(glam::Vec3::new(x, y, z)),
+                )
+            },
+        );
         methods.add_method_mut(
             "get_switch_talk_target",
             |lua, this, switch_talk_target: u32| {
@@ -1040,6 +1535,10 @@ impl UserData for LuaPlayer {
             this.send_mailbox_status();
             Ok(())
         });
+        methods.add_method_mut("report_playtime", |_, this, _: ()| {
+            this.report_playtime();
+            Ok(())
+        });
         methods.add_method_mut("set_grand_company", |_, this, company: u8| {
             this.set_grand_company(GrandCompany::from_repr(company as usize).unwrap_or_default());
             Ok(())
@@ -1056,6 +1555,42 @@ impl UserData for LuaPlayer {
             this.call(name);
             Ok(())
         });
+        methods.add_method_mut("add_to_blacklist", |_, this, name: String| {
+            this.add_to_blacklist(name);
+            Ok(())
+        });
+        methods.add_method_mut("remove_from_blacklist", |_, this, name: String| {
+            this.remove_from_blacklist(name);
+            Ok(())
+        });
+        methods.add_method_mut("toggle_online_status", |_, this, status_id: u8| {
+            let Some(status) = OnlineStatus::from_repr(status_id) else {
+                return Err(mlua::Error::runtime(format!(
+                    "{status_id} is not a valid online status!"
+                )));
+            };
+            this.toggle_online_status(status);
+            Ok(())
+        });
+        methods.add_method_mut(
+            "create_free_company",
+            |_, this, (name, tag): (String, String)| {
+                this.create_free_company(name, tag);
+                Ok(())
+            },
+        );
+        methods.add_method_mut("join_free_company", |_, this, name: String| {
+            this.join_free_company(name);
+            Ok(())
+        });
+        methods.add_method_mut("set_hp_mp", |_, this, (hp, mp): (u32, u16)| {
+            this.set_hp_mp(hp, mp);
+            Ok(())
+        });
+        methods.add_method_mut("set_search_comment", |_, this, comment: String| {
+            this.set_search_comment(comment);
+            Ok(())
+        });
     }
 
     fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
@@ -1093,3 +1628,276 @@ impl UserData for LuaPlayer {
         fields.add_field_method_get("city_state", |_, this| Ok(this.player_data.city_state));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use mlua::Lua;
+
+    use super::*;
+    use crate::inventory::Item;
+
+    #[test]
+    fn test_queue_two_scenes() {
+        let lua = Lua::new();
+        lua.globals().set("player", LuaPlayer::default()).unwrap();
+
+        lua.load(
+            "player:play_scene(0, 0, {}) player:play_scene(1, 0, {5, 6}) player:finish_event(2)",
+        )
+        .exec()
+        .unwrap();
+
+        let player = lua.globals().get::<mlua::AnyUserData>("player").unwrap();
+        let player = player.borrow::<LuaPlayer>().unwrap();
+        assert!(matches!(
+            player.queued_tasks.as_slice(),
+            [
+                LuaTask::PlayScene { scene: 0, .. },
+                LuaTask::PlayScene { scene: 1, params, .. },
+                LuaTask::FinishEvent { result: 2, arg: None },
+            ] if params == &[5, 6]
+        ));
+    }
+
+    #[test]
+    fn test_add_and_remove_status_effect() {
+        let lua = Lua::new();
+        lua.globals().set("player", LuaPlayer::default()).unwrap();
+
+        let (gained, lost): (bool, bool) = lua
+            .load(
+                "player:add_status_effect(50, 20.0, 30)
+                 local gained = player:get_effect(50) ~= nil
+                 player:remove_status_effect(50)
+                 player:remove_status_effect(50)
+                 return gained, player:get_effect(50) == nil",
+            )
+            .call(())
+            .unwrap();
+        assert!(gained);
+        assert!(lost);
+
+        // Losing it twice should only tell the server once.
+        let player = lua.globals().get::<mlua::AnyUserData>("player").unwrap();
+        let player = player.borrow::<LuaPlayer>().unwrap();
+        assert!(matches!(
+            player.queued_tasks.as_slice(),
+            [
+                LuaTask::GainStatusEffect {
+                    effect_id: 50,
+                    effect_param: 30,
+                    ..
+                },
+                LuaTask::LoseStatusEffect {
+                    effect_id: 50,
+                    effect_param: 30,
+                    ..
+                },
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_print_message_channels() {
+        let lua = Lua::new();
+        lua.globals().set("player", LuaPlayer::default()).unwrap();
+
+        lua.load("player:print_message(0, 'Hello!') player:print_message(2, 'Hi there!', 'Wedge')")
+            .exec()
+            .unwrap();
+
+        let player = lua.globals().get::<mlua::AnyUserData>("player").unwrap();
+        let player = player.borrow::<LuaPlayer>().unwrap();
+        let ipcs: Vec<&ServerZoneIpcData> = player
+            .queued_tasks
+            .iter()
+            .filter_map(|task| match task {
+                LuaTask::SendSegment { segment } => match &segment.data {
+                    kawari::packet::SegmentData::Ipc(ipc) => Some(&ipc.data),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        assert!(matches!(
+            ipcs.as_slice(),
+            [
+                ServerZoneIpcData::ServerNoticeMessage(notice),
+                ServerZoneIpcData::ChatMessage(chat),
+            ] if notice.message == "Hello!"
+                && chat.channel == ChatChannelType::Say
+                && chat.sender_name == "Wedge"
+        ));
+    }
+
+    #[test]
+    fn test_unknown_message_channel() {
+        let lua = Lua::new();
+        lua.globals().set("player", LuaPlayer::default()).unwrap();
+
+        assert!(
+            lua.load("player:print_message(99, 'Hello!')")
+                .exec()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_split_long_message() {
+        let message = "é".repeat(10);
+
+        let pieces = split_message(&message, 5);
+        assert_eq!(pieces, ["éé", "éé", "éé", "éé", "éé"]);
+        assert_eq!(split_message("short", 775), ["short"]);
+    }
+
+    #[test]
+    fn test_set_rotation() {
+        let lua = Lua::new();
+        lua.globals().set("player", LuaPlayer::default()).unwrap();
+
+        let (turned, wrapped): (f32, f32) = lua
+            .load(
+                "player:set_rotation(1.5)
+                 local turned = player:get_rotation()
+                 player:set_rotation(-math.pi / 2)
+                 return turned, player.rotation",
+            )
+            .call(())
+            .unwrap();
+        assert_eq!(turned, 1.5);
+        assert!((wrapped - 3.0 * std::f32::consts::FRAC_PI_2).abs() < 0.0001);
+
+        // The client has to be told both times, and stay where it was.
+        let player = lua.globals().get::<mlua::AnyUserData>("player").unwrap();
+        let player = player.borrow::<LuaPlayer>().unwrap();
+        assert_eq!(player.queued_tasks.len(), 2);
+        assert!(player.queued_tasks.iter().all(|task| matches!(
+            task,
+            LuaTask::SendSegment { segment } if matches!(
+                &segment.data,
+                kawari::packet::SegmentData::Ipc(ipc)
+                    if matches!(ipc.data, ServerZoneIpcData::ActorSetPos(_))
+            )
+        )));
+    }
+
+    #[test]
+    fn test_move_actor() {
+        let lua = Lua::new();
+        lua.globals().set("player", LuaPlayer::default()).unwrap();
+
+        lua.load("player:move_actor(5, {{x = 1, y = 0, z = 2}, {x = 3, y = 0, z = 4}}, 2.5)")
+            .exec()
+            .unwrap();
+        assert!(lua.load("player:move_actor(5, {}, 0)").exec().is_err());
+
+        let player = lua.globals().get::<mlua::AnyUserData>("player").unwrap();
+        let player = player.borrow::<LuaPlayer>().unwrap();
+        assert!(matches!(
+            player.queued_tasks.as_slice(),
+            [LuaTask::MoveActor { actor_id: ObjectId(5), waypoints, speed }]
+                if waypoints.len() == 2 && waypoints[1].0.z == 4.0 && *speed == 2.5
+        ));
+    }
+
+    #[test]
+    fn test_normalize_rotation() {
+        assert_eq!(normalize_rotation(0.0), 0.0);
+        assert_eq!(normalize_rotation(TAU), 0.0);
+        assert_eq!(normalize_rotation(-f32::EPSILON), 0.0);
+        assert!((normalize_rotation(-1.0) - (TAU - 1.0)).abs() < 0.0001);
+        assert!((normalize_rotation(TAU + 1.0) - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_too_many_scene_params() {
+        let lua = Lua::new();
+        lua.globals().set("player", LuaPlayer::default()).unwrap();
+
+        let result = lua
+            .load("local params = {} for i = 1, 256 do params[i] = i end player:play_scene(0, 0, params)")
+            .exec();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_weather_after_zone_change() {
+        let lua = Lua::new();
+        lua.globals()
+            .set(
+                "player",
+                LuaPlayer {
+                    // What the server sends along with a zone change
+                    zone_data: LuaZone {
+                        zone_id: 132,
+                        weather_id: 4,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let (weather, zone_weather, changed): (u16, u16, u16) = lua
+            .load(
+                "local before = player:get_weather()
+                local zone = player.zone.weather_id
+                player:change_weather(7)
+                return before, zone, player:get_weather()",
+            )
+            .call(())
+            .unwrap();
+        assert_eq!(weather, 4);
+        assert_eq!(zone_weather, 4);
+        assert_eq!(changed, 7);
+    }
+
+    #[test]
+    fn test_read_gil_after_modifying() {
+        let lua = Lua::new();
+        lua.globals().set("player", LuaPlayer::default()).unwrap();
+
+        let (gil, currency): (u32, u32) = lua
+            .load("player:modify_currency(1, 500) return player.gil, player:get_currency(1)")
+            .call(())
+            .unwrap();
+        assert_eq!(gil, 500);
+        assert_eq!(currency, 500);
+
+        // The client still needs to be told about it.
+        let player = lua.globals().get::<mlua::AnyUserData>("player").unwrap();
+        assert_eq!(player.borrow::<LuaPlayer>().unwrap().queued_tasks.len(), 1);
+    }
+
+    #[test]
+    fn test_count_and_remove_items() {
+        let mut player = LuaPlayer::default();
+        player.player_data.inventory.pages[0].slots[0] = Item {
+            item_id: 5000,
+            quantity: 3,
+            ..Default::default()
+        };
+        player.player_data.inventory.pages[0].slots[1] = Item {
+            item_id: 5000,
+            quantity: 2,
+            ..Default::default()
+        };
+
+        let lua = Lua::new();
+        lua.globals().set("player", player).unwrap();
+
+        let (had, before, after): (bool, u32, u32) = lua
+            .load(
+                "local before = player:get_item_count(5000)
+                 player:remove_item(5000, 4)
+                 return player:has_item(5000), before, player:get_item_count(5000)",
+            )
+            .call(())
+            .unwrap();
+        assert!(had);
+        assert_eq!(before, 5);
+        assert_eq!(after, 1);
+    }
+}
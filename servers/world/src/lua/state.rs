@@ -17,7 +17,7 @@ use kawari::{
     },
 };
 
-use super::EffectsBuilder;
+use super::{EffectsBuilder, MessageChannel};
 
 #[derive(Debug, Clone)]
 pub struct KawariLua(pub Lua);
@@ -44,6 +44,7 @@ impl KawariLua {
         Self::register_enum::<DamageKind>(&mut lua, "DAMAGE_KIND");
         Self::register_enum::<DamageType>(&mut lua, "DAMAGE_TYPE");
         Self::register_enum::<Condition>(&mut lua, "CONDITION");
+        Self::register_enum::<MessageChannel>(&mut lua, "MESSAGE_CHANNEL");
 
         let config = get_config();
         lua.globals()
@@ -78,14 +79,23 @@ impl KawariLua {
                 Ok(())
             })?;
 
-        let register_gm_command_func =
-            lua.create_function(|lua, (command_type, command_script): (u32, String)| {
+        let register_gm_command_func = lua.create_function(
+            |lua, (command_type, command_script, required_rank): (u32, String, Option<u8>)| {
                 let mut state = lua.app_data_mut::<KawariLuaState>().unwrap();
                 let _ = state
                     .gm_command_scripts
                     .insert(command_type, command_script);
+                if let Some(required_rank) = required_rank {
+                    let Some(required_rank) = GameMasterRank::from_repr(required_rank) else {
+                        return Err(mlua::Error::runtime(format!(
+                            "Invalid GM rank {required_rank} for GM command {command_type}!"
+                        )));
+                    };
+                    let _ = state.gm_command_ranks.insert(command_type, required_rank);
+                }
                 Ok(())
-            })?;
+            },
+        )?;
 
         let get_login_message_func = lua.create_function(|_, _: ()| {
             let config = get_config();
@@ -202,10 +212,22 @@ pub struct KawariLuaState {
     pub action_scripts: HashMap<u32, String>,
     pub command_scripts: HashMap<String, String>,
     pub gm_command_scripts: HashMap<u32, String>,
+    /// The minimum rank needed to run a GM command, if one was given when registering it.
+    pub gm_command_ranks: HashMap<u32, GameMasterRank>,
     pub effect_scripts: HashMap<u32, String>,
     pub zone_eobj_scripts: HashMap<u32, String>,
 }
 
+impl KawariLuaState {
+    /// Whether a player with `gm_rank` is allowed to run the GM command `command_type`.
+    pub fn can_run_gm_command(&self, command_type: u32, gm_rank: GameMasterRank) -> bool {
+        match self.gm_command_ranks.get(&command_type) {
+            Some(required_rank) => gm_rank as u8 >= *required_rank as u8,
+            None => true,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,4 +323,17 @@ mod tests {
         assert_eq!(lua.load("return UPPER").call::<u32>(()).unwrap(), 1);
         assert_eq!(lua.load("return MASTER").call::<u32>(()).unwrap(), 2);
     }
+
+    #[test]
+    fn test_gm_command_rank() {
+        let mut state = KawariLuaState::default();
+        state.gm_command_ranks.insert(100, GameMasterRank::Debug);
+
+        assert!(!state.can_run_gm_command(100, GameMasterRank::NormalUser));
+        assert!(!state.can_run_gm_command(100, GameMasterRank::GameMaster));
+        assert!(state.can_run_gm_command(100, GameMasterRank::Debug));
+
+        // Commands without a rank are left up to the script
+        assert!(state.can_run_gm_command(101, GameMasterRank::NormalUser));
+    }
 }
@@ -1,10 +1,11 @@
 use crate::{
     RemakeMode,
-    inventory::{CrystalKind, CurrencyKind},
+    inventory::{CrystalKind, CurrencyKind, SortKey},
+    zone_connection::ReviveKind,
 };
 use kawari::{
-    common::Position,
-    ipc::zone::{EventType, GrandCompany, SceneFlags, ServerZoneIpcSegment},
+    common::{ContainerType, CustomizeData, ObjectId, Position},
+    ipc::zone::{EventType, GrandCompany, OnlineStatus, SceneFlags, ServerZoneIpcSegment},
     packet::PacketSegment,
 };
 
@@ -20,7 +21,11 @@ pub enum LuaTask {
         warp_id: u32,
     },
     BeginLogOut,
-    FinishEvent {},
+    FinishEvent {
+        result: u8,
+        /// If None, then the arg the event was started with is used.
+        arg: Option<u32>,
+    },
     UnlockClassJob {
         classjob_id: u8,
     },
@@ -42,6 +47,9 @@ pub enum LuaTask {
     SetLevel {
         level: u16,
     },
+    UnlockAction {
+        action_id: u32,
+    },
     ChangeWeather {
         id: u8,
     },
@@ -62,14 +70,38 @@ pub enum LuaTask {
     ToggleOrchestrion {
         id: u32,
     },
+    UnlockOrchestrionAll {},
     AddItem {
         id: u32,
         quantity: u32,
         send_client_update: bool,
+        /// Whether to tell the player they obtained it, like for quest rewards.
+        announce: bool,
+    },
+    RemoveItem {
+        id: u32,
+        quantity: u32,
+    },
+    AddKeyItem {
+        id: u32,
+    },
+    RemoveKeyItem {
+        id: u32,
+    },
+    AddItemByName {
+        name: String,
+        quantity: u32,
+    },
+    SortInventory {
+        container: ContainerType,
+        key: SortKey,
     },
     UnlockContent {
         id: u16,
     },
+    CompleteContent {
+        id: u16,
+    },
     UnlockAllContent {},
     AddExp {
         amount: i32,
@@ -85,6 +117,15 @@ pub enum LuaTask {
     ToggleMount {
         id: u32,
     },
+    UnlockMount {
+        id: u32,
+    },
+    SetMount {
+        mount_id: u16,
+    },
+    SummonMinion {
+        minion_id: u32,
+    },
     MoveToPopRange {
         id: u32,
         fade_out: bool,
@@ -92,6 +133,10 @@ pub enum LuaTask {
     SetHP {
         hp: u32,
     },
+    SetEObjState {
+        eobj_id: u32,
+        state: u32,
+    },
     SetMP {
         mp: u16,
     },
@@ -136,6 +181,9 @@ pub enum LuaTask {
         id: u32,
     },
     ToggleMinionAll {},
+    UnlockMinion {
+        id: u32,
+    },
     ToggleAetherCurrent {
         id: u32,
     },
@@ -153,6 +201,9 @@ pub enum LuaTask {
     SetSex {
         sex: u8,
     },
+    SetAppearance {
+        customize: CustomizeData,
+    },
     // previously, this was kept as a separate thing apart from tasks
     // but I discovered that this doesn't mix well - for example with play_scene (segment-based) and start_event (task)
     // this is because segments were always sent before tasks and there wasn't strong ordering
@@ -219,6 +270,7 @@ pub enum LuaTask {
         id: u32,
     },
     SendMailboxStatus {},
+    ReportPlaytime {},
     SetGrandCompany {
         company: GrandCompany,
     },
@@ -231,4 +283,63 @@ pub enum LuaTask {
     Call {
         name: String,
     },
+    Emote {
+        emote_id: u32,
+        target: Option<ObjectId>,
+    },
+    DecayEquipment {},
+    Revive {
+        kind: ReviveKind,
+    },
+    SpawnNpc {
+        enpc_id: u32,
+        position: Position,
+        rotation: f32,
+    },
+    RepairItem {
+        container: ContainerType,
+        index: u16,
+        gil_cost: u32,
+    },
+    RepairAll {
+        gil_cost: u32,
+    },
+    EquipItem {
+        container: ContainerType,
+        index: u16,
+        equip_slot: u16,
+    },
+    AddToBlacklist {
+        name: String,
+    },
+    RemoveFromBlacklist {
+        name: String,
+    },
+    ToggleOnlineStatus {
+        status: OnlineStatus,
+    },
+    CreateFreeCompany {
+        name: String,
+        tag: String,
+    },
+    JoinFreeCompany {
+        name: String,
+    },
+    SetSearchComment {
+        comment: String,
+    },
+    SetHpMp {
+        hp: u32,
+        mp: u16,
+    },
+    LoseStatusEffect {
+        effect_id: u16,
+        effect_param: u16,
+        effect_source_actor_id: ObjectId,
+    },
+    MoveActor {
+        actor_id: ObjectId,
+        waypoints: Vec<Position>,
+        speed: f32,
+    },
 }
@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use std::time::{Instant, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
 use axum::Router;
 use axum::routing::get;
@@ -7,20 +7,20 @@ use kawari::common::{
     ContainerType, DEBUG_COMMAND_TRIGGER, DirectorEvent, DirectorTrigger, DutyOption, FestivalId,
     HandlerId, HandlerType, ItemOperationKind, LogMessageType, ObjectId, ObjectTypeId,
     ObjectTypeKind, PlayerStateFlags1, PlayerStateFlags2, PlayerStateFlags3, Position,
-    calculate_max_level,
+    calculate_max_level, determine_initial_pop_range,
 };
-use kawari::config::{FilesystemConfig, get_config};
+use kawari::config::get_config;
 use kawari_world::inventory::{Item, MAX_LARGE_STORAGE, Storage, get_next_free_slot};
 use physis::{TerritoryIntendedUse, equipment::EquipSlot};
 
 use kawari::ipc::chat::ClientChatIpcData;
 
 use kawari::ipc::zone::{
-    ActorControlCategory, CWLSLeaveReason, Conditions, ContentFinderUserAction, CrossRealmListing,
-    CrossRealmListings, EventType, FurnitureTranslatedForObserver, ItemInfo,
+    ActionKind, ActorControlCategory, CWLSLeaveReason, Conditions, ContentFinderUserAction,
+    CrossRealmListing, CrossRealmListings, EventType, FurnitureTranslatedForObserver, ItemInfo,
     LinkshellInviteResponse, MapEffects, MarketBoardItem, OnlineStatus, OnlineStatusMask,
     PlayerSetup, SceneFlags, SearchInfo, SocialListRequestType, TrustContent, TrustInformation,
-    WarpType,
+    WarpType, truncate_search_comment,
 };
 
 use kawari::ipc::zone::{
@@ -31,18 +31,19 @@ use kawari::ipc::zone::{
 use kawari::common::{CharacterMode, NETWORK_TIMEOUT, RECEIVE_BUFFER_SIZE};
 use kawari::constants::{AETHER_CURRENT_COMP_FLG_SET_BITMASK_SIZE, CLASSJOB_ARRAY_SIZE};
 use kawari::packet::oodle::OodleNetwork;
-use kawari::packet::{ConnectionState, ConnectionType, SegmentData, parse_packet_header};
-use kawari_world::lua::{KawariLua, KawariLuaState, LuaPlayer};
+use kawari::packet::{
+    ConnectionState, ConnectionType, SegmentData, parse_packet_header, start_packet_capture,
+};
+use kawari_world::lua::{KawariLua, LuaPlayer};
 use kawari_world::{
-    ChatConnection, CustomIpcConnection, Event, EventHandler, GameData, ObsfucationData, Roulette,
-    TeleportReason, ZoneConnection,
+    ChatConnection, ContentQueue, CustomIpcConnection, Event, EventHandler, GameData, MoveThrottle,
+    ObsfucationData, Roulette, TeleportReason, ZoneConnection,
 };
 use kawari_world::{
     ChatConnectionChannels, ChatPlayerData, ClientHandle, ClientId, FromServer, MessageInfo,
     PlayerData, ServerHandle, ToServer, WorldDatabase, server_main_loop,
 };
 
-use mlua::Function;
 use parking_lot::Mutex;
 use tokio::io::AsyncReadExt;
 use tokio::join;
@@ -60,7 +61,7 @@ fn spawn_main_loop(
     let (send, recv) = channel(64);
 
     let handle = ServerHandle {
-        chan: send,
+        chan: send.clone(),
         next_id: Default::default(),
     };
 
@@ -78,7 +79,7 @@ fn spawn_main_loop(
             parties = database.get_parties();
             linkshells = database.find_all_linkshells();
         }
-        let res = server_main_loop(game_data_new, parties, linkshells, recv).await;
+        let res = server_main_loop(game_data_new, parties, linkshells, send, recv).await;
         match res {
             Ok(()) => {}
             Err(err) => {
@@ -152,7 +153,7 @@ async fn initial_setup(
                     last_keep_alive: Instant::now(),
                     gracefully_logged_out: false,
                     obsfucation_data: ObsfucationData::default(),
-                    queued_content: None,
+                    queued_content: ContentQueue::default(),
                     conditions: Conditions::default(),
                     queued_tasks: Vec::new(),
                     old_zone_id: 0,
@@ -164,6 +165,7 @@ async fn initial_setup(
                     party_id: 0,
                     rejoining_party: false,
                     login_time: None,
+                    playtime_flushed_at: None,
                     content_settings: None,
                     current_instance_id: None,
                     glamour_information: None,
@@ -181,6 +183,11 @@ async fn initial_setup(
                     mail_index: 0,
                     spawned_in: false,
                     offered_teleport: None,
+                    spawn_pop_range: None,
+                    move_throttle: MoveThrottle::default(),
+                    last_move: None,
+                    replay: None,
+                    blacklist: None,
                 };
 
                 // Handle setup before passing off control to the zone connection.
@@ -210,6 +217,8 @@ async fn initial_setup(
                                         database.find_player_data(actor_id, &mut game_data);
                                 }
                                 connection.player_data = player_data;
+                                connection.reset_toggled_online_status();
+                                connection.apply_configured_gm_rank();
                             }
 
                             // collect actor data
@@ -598,6 +607,7 @@ async fn process_packet(
 
                             // Store when we logged in, for various purposes.
                             connection.login_time = Some(SystemTime::now());
+                            connection.playtime_flushed_at = connection.login_time;
 
                             // Mark the player as online for total player counts, player searches, etc.
                             {
@@ -851,21 +861,7 @@ async fn process_packet(
                                 connection.send_ipc_self(ipc).await;
                             }
 
-                            let level;
-                            {
-                                let mut game_data = connection.gamedata.lock();
-
-                                level = connection
-                                    .player_data
-                                    .inventory
-                                    .equipped
-                                    .calculate_item_level(&mut game_data)
-                                    as u32;
-                            }
-
-                            connection
-                                .actor_control_self(ActorControlCategory::SetItemLevel { level })
-                                .await;
+                            connection.refresh_item_level().await;
 
                             connection
                                 .handle
@@ -875,10 +871,14 @@ async fn process_packet(
                                     connection.player_data.volatile.zone_id as u16,
                                     connection.player_data.volatile.position,
                                     connection.player_data.volatile.rotation as f32,
-                                    if connection.player_data.unlock.cutscene_seen.contains(2) {
-                                        None
+                                    if connection.player_data.unlock.cutscene_seen.contains(2)
+                                        || config.world.starting_position.is_some()
+                                    {
+                                        connection.spawn_pop_range.take()
                                     } else {
-                                        Some(connection.player_data.city_state)
+                                        Some(determine_initial_pop_range(
+                                            connection.player_data.city_state,
+                                        ))
                                     }, // If seen the opening cutscene
                                 ))
                                 .await;
@@ -888,8 +888,7 @@ async fn process_packet(
                             connection.send_crossworld_linkshells(false).await;
                             connection.send_grand_company_info().await;
 
-                            // Send login message
-                            connection.send_notice(&config.world.login_message).await;
+                            connection.send_login_message().await;
 
                             let online_player_count;
                             {
@@ -917,6 +916,26 @@ async fn process_packet(
                                 ))
                                 .await;
 
+                            // The server recreates our actor on every zone load, so it needs reminding.
+                            if connection.player_data.gm_invisible {
+                                connection
+                                    .handle
+                                    .send(ToServer::SetGmInvisible(
+                                        connection.player_data.character.actor_id,
+                                        true,
+                                    ))
+                                    .await;
+                            }
+                            if !connection.player_data.volatile.recast_timers.0.is_empty() {
+                                connection
+                                    .handle
+                                    .send(ToServer::SetRecastTimers(
+                                        connection.player_data.character.actor_id,
+                                        connection.player_data.volatile.recast_timers.clone(),
+                                    ))
+                                    .await;
+                            }
+
                             // If we're in a party, we need to tell the other members we changed areas or reconnected.
                             if connection.is_in_party() {
                                 if !connection.rejoining_party {
@@ -1023,82 +1042,7 @@ async fn process_packet(
                                     connection.respawn_player(false).await;
                                 }
                                 ClientTriggerCommand::Dismount { sequence } => {
-                                    // TODO: Move all this to FromServer::ActorDismounted so all of the logic can be consolidated
-                                    connection.conditions = Conditions::default();
-                                    connection.send_conditions().await;
-
-                                    // SetMode isn't important, no, but it's included for accuracy.
-                                    connection
-                                        .set_character_mode(CharacterMode::Normal, 0)
-                                        .await;
-
-                                    // Retail indeed does send an AC, not an ACS for this.
-                                    connection
-                                        .actor_control(
-                                            connection.player_data.character.actor_id,
-                                            ActorControlCategory::PlayDismountAnimation {
-                                                unk1: 47494,
-                                                unk2: 32711,
-                                                unk3: 1510381914,
-                                            },
-                                        )
-                                        .await;
-
-                                    // TODO: This should only be sent when the player is actually riding pillion, but for now, it doesn't seem to hurt sending it unconditionally.
-                                    connection
-                                        .actor_control(
-                                            connection.player_data.character.actor_id,
-                                            ActorControlCategory::RidePillion {
-                                                target_actor_id: ObjectId::default(),
-                                                target_seat_index: 0,
-                                            },
-                                        )
-                                        .await;
-
-                                    connection
-                                        .actor_control_self(ActorControlCategory::Dismount {
-                                            sequence,
-                                        })
-                                        .await;
-
-                                    // Then these are also sent!
-                                    connection
-                                        .actor_control_self(ActorControlCategory::SetPetEntityId {
-                                            unk1: 0,
-                                        })
-                                        .await;
-
-                                    connection
-                                        .actor_control_self(ActorControlCategory::CompanionUnlock {
-                                            unk1: 0,
-                                            unk2: 0,
-                                        })
-                                        .await;
-
-                                    connection
-                                        .actor_control_self(
-                                            ActorControlCategory::SetPetParameters {
-                                                pet_id: 0,
-                                                unk2: 0,
-                                                unk3: 0,
-                                                unk4: 7,
-                                            },
-                                        )
-                                        .await;
-
-                                    // TODO: Remove this `let party_id` in an upcoming party refactor, this is temporary
-                                    let party_id = if connection.party_id != 0 {
-                                        Some(connection.party_id)
-                                    } else {
-                                        None
-                                    };
-                                    connection
-                                        .handle
-                                        .send(ToServer::Dismounted(
-                                            connection.player_data.character.actor_id,
-                                            party_id,
-                                        ))
-                                        .await;
+                                    connection.dismount(sequence).await;
                                 }
                                 ClientTriggerCommand::ShownActiveHelp { id } => {
                                     // Save this so it isn't shown again on next login
@@ -1233,16 +1177,7 @@ async fn process_packet(
                                 }
                                 ClientTriggerCommand::AbandonContent { .. } => {
                                     // Remove ourselves from this instance.
-                                    connection
-                                        .handle
-                                        .send(ToServer::LeaveContent(
-                                            connection.id,
-                                            connection.player_data.character.actor_id,
-                                            connection.old_zone_id,
-                                            connection.old_position,
-                                            connection.old_rotation,
-                                        ))
-                                        .await;
+                                    connection.leave_content().await;
                                 }
                                 ClientTriggerCommand::PrepareCastGlamour { .. } => {
                                     // The actual glamoruing happens later when the action is complete.
@@ -1395,31 +1330,13 @@ async fn process_packet(
                                         ))
                                         .await;
                                 }
-                                ClientTriggerCommand::ExamineCharacter { .. } => {
-                                    let ipc = ServerZoneIpcSegment::new(
-                                        ServerZoneIpcData::ExamineCharacterInformation {
-                                            unk1: [0; 640],
-                                            name: "test".to_string(),
-                                            unk2: [0; 272],
-                                        },
-                                    );
-                                    connection.send_ipc_self(ipc).await;
+                                ClientTriggerCommand::ExamineCharacter { target_actor_id } => {
+                                    connection.examine_player(target_actor_id).await;
                                 }
                                 ClientTriggerCommand::ToggleNoviceStatus { .. } => {
-                                    if connection.player_data.search_info.online_status
-                                        != OnlineStatus::NewAdventurer
-                                    {
-                                        connection.player_data.search_info.online_status =
-                                            OnlineStatus::NewAdventurer;
-                                    } else {
-                                        connection.player_data.search_info.online_status =
-                                            OnlineStatus::Online;
-                                    }
-                                    {
-                                        let mut database = connection.database.lock();
-                                        database.commit_search_info(&connection.player_data);
-                                    }
-                                    connection.update_online_status().await;
+                                    connection
+                                        .toggle_online_status(OnlineStatus::NewAdventurer)
+                                        .await;
                                 }
                                 ClientTriggerCommand::OpenUnk1 { .. } => {
                                     for i in 0..10 {
@@ -1778,6 +1695,10 @@ async fn process_packet(
                             jump_state,
                         } => {
                             if connection.spawned_in {
+                                if !connection.validate_move(*position).await {
+                                    continue;
+                                }
+
                                 connection.player_data.volatile.rotation = *rotation as f64;
                                 connection.player_data.volatile.position = *position;
 
@@ -1812,91 +1733,25 @@ async fn process_packet(
                             return false;
                         }
                         ClientZoneIpcData::SendChatMessage(chat_message) => {
+                            if connection
+                                .process_player_commands(&chat_message.message.to_string())
+                                .await
+                            {
+                                continue; // Don't send the message off anywhere
+                            }
+
                             // Process debug commands
                             if chat_message
                                 .message
                                 .to_string()
                                 .starts_with(DEBUG_COMMAND_TRIGGER)
                             {
-                                let msg = chat_message.message.to_string();
-                                let parts: Vec<&str> = msg.split(' ').collect();
-                                let command_name = &parts[0][1..];
-
+                                // If a Lua command exists, try using that first
+                                if connection
+                                    .handle_command(&chat_message.message.to_string(), lua_player)
+                                    .await
                                 {
-                                    let lua = connection.lua.lock();
-                                    let state = lua.0.app_data_ref::<KawariLuaState>().unwrap();
-
-                                    // If a Lua command exists, try using that first
-                                    if let Some(command_script) =
-                                        state.command_scripts.get(command_name)
-                                    {
-                                        let file_name =
-                                            FilesystemConfig::locate_script_file(command_script);
-
-                                        let mut run_script = || -> mlua::Result<()> {
-                                            lua.0.scope(|scope| {
-                                                    let connection_data = scope
-                                                    .create_userdata_ref_mut(lua_player)?;
-
-                                                    /* TODO: Instead of panicking we ought to send a message to the player
-                                                     * and the console log, and abandon execution. */
-                                                    lua.0.load(
-                                                        std::fs::read(&file_name).unwrap_or_else(|_| panic!("Failed to load script file {}!", &file_name)),
-                                                    )
-                                                    .set_name("@".to_string() + &file_name)
-                                                    .exec()?;
-
-                                                    let required_rank = lua.0.globals().get("required_rank");
-                                                    if let Err(error) = required_rank {
-                                                        tracing::info!("Script is missing required_rank! Unable to run command, sending error to user. Additional information: {}", error);
-                                                        let func: Function =
-                                                        lua.0.globals().get("onCommandRequiredRankMissingError")?;
-                                                        func.call::<()>((error.to_string(), connection_data))?;
-                                                        return Ok(());
-                                                    }
-
-                                                    /* Reset state for future commands. Without this it'll stay set to the last value
-                                                     * and allow other commands that omit required_rank to run, which is undesirable. */
-                                                    lua.0.globals().set("required_rank", mlua::Value::Nil)?;
-
-                                                    if connection.player_data.character.gm_rank as u8 >= required_rank? {
-                                                        let mut func_args = Vec::new();
-                                                        if parts.len() > 1 {
-                                                            func_args = (parts[1..]).to_vec();
-                                                            tracing::info!("Args passed to Lua command {}: {:?}", command_name, func_args);
-                                                        } else {
-                                                            tracing::info!("No additional args passed to Lua command {}.", command_name);
-                                                        }
-                                                        let func: Function =
-                                                        lua.0.globals().get("onCommand")?;
-                                                        func.call::<()>((connection_data, func_args, String::default()))?;
-
-                                                        /* `command_sender` is an optional variable scripts can define to identify themselves in print messages.
-                                                         * It's okay if this global isn't set. We also don't care what its value is, just that it exists.
-                                                         * This is reset -after- running the command intentionally. Resetting beforehand will never display the command's identifier.
-                                                         */
-                                                        let command_sender: Result<mlua::prelude::LuaValue, mlua::prelude::LuaError> = lua.0.globals().get("command_sender");
-                                                        if command_sender.is_ok() {
-                                                            lua.0.globals().set("command_sender", mlua::Value::Nil)?;
-                                                        }
-                                                        Ok(())
-                                                    } else {
-                                                        tracing::info!("User with account_id {} tried to invoke GM command {} with insufficient privileges!",
-                                                                       connection.player_data.character.service_account_id, command_name);
-                                                        let func: Function =
-                                                        lua.0.globals().get("onCommandRequiredRankInsufficientError")?;
-                                                        func.call::<()>(connection_data)?;
-                                                        Ok(())
-                                                    }
-                                                })
-                                        };
-
-                                        if let Err(err) = run_script() {
-                                            tracing::warn!("Lua error in {file_name}: {:?}", err);
-                                        }
-
-                                        continue; // Don't send the message off anywhere
-                                    }
+                                    continue; // Don't send the message off anywhere
                                 }
 
                                 // Fallback to Rust implemented commands in ZoneConnection, but if this fails then
@@ -1916,6 +1771,8 @@ async fn process_packet(
                                     .character
                                     .service_account_id
                                     as u64,
+                                sender_content_id: connection.player_data.character.content_id
+                                    as u64,
                                 sender_world_id: config.world.world_id,
                                 sender_position: connection.player_data.volatile.position,
                                 sender_name: connection.player_data.character.name.clone(),
@@ -1994,6 +1851,16 @@ async fn process_packet(
                                 .await;
                         }
                         ClientZoneIpcData::ActionRequest(request) => {
+                            if request.action_kind == ActionKind::Mount
+                                && !connection.is_mount_unlocked(request.action_key)
+                            {
+                                tracing::warn!(
+                                    "Player tried to ride mount {}, which they haven't unlocked!",
+                                    request.action_key
+                                );
+                                continue;
+                            }
+
                             connection
                                 .handle
                                 .send(ToServer::ActionRequest(
@@ -2314,9 +2181,13 @@ async fn process_packet(
                                     connection.send_ipc_self(ipc).await;
                                 }
 
-                                connection
-                                    .join_content(connection.queued_content.unwrap())
-                                    .await;
+                                if let Some(content_id) = connection.queued_content.pop() {
+                                    connection.join_content(content_id).await;
+                                } else {
+                                    tracing::warn!(
+                                        "Accepted a duty without having queued for one, ignoring!"
+                                    );
+                                }
                             }
 
                             // If we don't send this, the content finder gets stuck.
@@ -2327,7 +2198,8 @@ async fn process_packet(
                                 });
                             connection.send_ipc_self(ipc).await;
 
-                            connection.queued_content = None;
+                            // Once matched (or withdrawn), the player is no longer in the queue for anything else.
+                            connection.queued_content.clear();
                         }
                         ClientZoneIpcData::EquipGearset {
                             gearset_index,
@@ -2361,26 +2233,12 @@ async fn process_packet(
                                     .equipped
                                     .get_slot(slot as u16);
 
-                                if !from_item.is_empty_slot() && !equipped_item.is_empty_slot() {
-                                    // If there is something equipped and a replacement for it, we must swap.
+                                if !from_item.is_empty_slot() {
+                                    // Whether or not something is already equipped, the new item is swapped in. Everyone is informed at the end.
                                     connection
-                                        .swap_items(
+                                        .move_into_equipped_slot(
                                             from_container,
                                             from_slot as u16,
-                                            ContainerType::Equipped,
-                                            slot as u16,
-                                        )
-                                        .await;
-                                } else if !from_item.is_empty_slot()
-                                    && equipped_item.is_empty_slot()
-                                {
-                                    // If there is nothing equipped but a new item in that slot, we just have to move it.
-                                    // TODO: be a little smarter about this maybe?
-                                    connection
-                                        .swap_items(
-                                            from_container,
-                                            from_slot as u16,
-                                            ContainerType::Equipped,
                                             slot as u16,
                                         )
                                         .await;
@@ -2744,7 +2602,7 @@ async fn process_packet(
                                 .copied()
                                 .unwrap_or(OnlineStatus::Online); // TODO: unsure if this makes sense?
                             connection.player_data.search_info.comment =
-                                search_info.comment.clone();
+                                truncate_search_comment(&search_info.comment);
                             connection.player_data.search_info.selected_languages =
                                 search_info.selected_languages;
                             {
@@ -3598,23 +3456,10 @@ async fn process_server_msg(
                 connection.actor_control_self(actor_control).await
             }
             FromServer::ActorSummonsMinion(minion_id) => {
-                connection
-                    .handle
-                    .send(ToServer::ActorSummonsMinion(
-                        connection.player_data.character.actor_id,
-                        minion_id,
-                    ))
-                    .await;
-                connection.active_minion = minion_id;
+                connection.summon_minion(minion_id).await;
             }
             FromServer::ActorDespawnsMinion() => {
-                connection
-                    .handle
-                    .send(ToServer::ActorDespawnsMinion(
-                        connection.player_data.character.actor_id,
-                    ))
-                    .await;
-                connection.active_minion = 0;
+                connection.summon_minion(0).await;
             }
             FromServer::UpdateConfig(actor_id, config) => {
                 connection.update_config(actor_id, config).await
@@ -3645,6 +3490,29 @@ async fn process_server_msg(
                 connection.conditions = conditions;
                 connection.send_conditions().await;
             }
+            FromServer::WeatherChanged(weather_id) => {
+                connection.change_weather(weather_id as u8).await;
+                lua_player.zone_data.weather_id = weather_id;
+            }
+            FromServer::RecastTimersChanged(timers) => {
+                connection.player_data.volatile.recast_timers = timers;
+            }
+            FromServer::OnlinePlayers(players) => {
+                if players.is_empty() {
+                    connection.send_notice("No players found.").await;
+                } else {
+                    let lines: Vec<String> = players
+                        .iter()
+                        .map(|player| {
+                            format!(
+                                "{} - Lv. {} {} - {}",
+                                player.name, player.level, player.classjob_name, player.zone_name
+                            )
+                        })
+                        .collect();
+                    connection.send_notices(&lines).await;
+                }
+            }
             FromServer::ChangeZone(
                 zone_id,
                 content_finder_condition_id,
@@ -3832,16 +3700,7 @@ async fn process_server_msg(
                     .await
             }
             FromServer::LeaveContent() => {
-                connection
-                    .handle
-                    .send(ToServer::LeaveContent(
-                        connection.id,
-                        connection.player_data.character.actor_id,
-                        connection.old_zone_id,
-                        connection.old_position,
-                        connection.old_rotation,
-                    ))
-                    .await;
+                connection.leave_content().await;
             }
             FromServer::FinishEvent() => {
                 connection.event_finish(events).await;
@@ -4110,7 +3969,21 @@ async fn client_loop(
     // Of course, Rust's mutability rules disallow that.
     let mut events: Vec<(Box<dyn EventHandler>, Event)> = Vec::new();
 
+    // Dead connections don't necessarily close their socket, so periodically check if the client is still talking to us.
+    let mut keep_alive_check = tokio::time::interval(Duration::from_secs(5));
+
+    // Save playtime every so often, in case we crash or the client disconnects without logging out.
+    let mut playtime_flush = tokio::time::interval(Duration::from_secs(60));
+
+    // Send movement the throttle held back, once it's allowed to.
+    let mut move_flush = tokio::time::interval(Duration::from_millis(100));
+
     loop {
+        let replay_deadline = connection
+            .replay
+            .as_ref()
+            .and_then(|replay| replay.next_deadline());
+
         tokio::select! {
             biased; // client data should always be prioritized
             n = connection.socket.read(&mut buf) => {
@@ -4127,6 +4000,17 @@ async fn client_loop(
                 }
             }
             msg = internal_recv.recv() => process_server_msg(&mut connection, &mut lua_player, &mut events, client_handle.clone(), msg).await,
+            _ = keep_alive_check.tick() => {
+                if connection.keep_alive_expired(Instant::now()) {
+                    tracing::info!("ZoneConnection {:#?} was killed because it stopped sending keep-alives!", client_handle.id);
+                    break;
+                }
+            }
+            _ = playtime_flush.tick() => connection.flush_playtime(),
+            _ = move_flush.tick() => connection.flush_throttled_moves().await,
+            _ = tokio::time::sleep_until(replay_deadline.unwrap_or_else(Instant::now).into()), if replay_deadline.is_some() => {
+                connection.send_due_replay_packets().await
+            }
         }
     }
 
@@ -4176,12 +4060,44 @@ async fn main() {
 
     let listener = TcpListener::bind(addr).await.unwrap();
 
+    if !config.world.packet_capture_path.is_empty() {
+        match start_packet_capture(&config.world.packet_capture_path) {
+            Ok(()) => tracing::info!("Capturing packets to {}", config.world.packet_capture_path),
+            Err(err) => tracing::warn!("Failed to start capturing packets: {err}"),
+        }
+    }
+
+    if config.world.custom_ipc_token == 0 {
+        tracing::error!(
+            "No custom IPC token is configured, so the other servers can't talk to this one! Set world.custom_ipc_token in your config."
+        );
+    }
+
     let database = Arc::new(Mutex::new(WorldDatabase::new()));
     let lua = Arc::new(Mutex::new(KawariLua::new()));
-    let game_data = Arc::new(Mutex::new(GameData::new()));
+    let game_data = match GameData::new() {
+        Ok(game_data) => Arc::new(Mutex::new(game_data)),
+        Err(err) => {
+            tracing::error!("Unable to load the game data, refusing to start: {err}");
+            return;
+        }
+    };
 
     tracing::info!("Server started on {addr}");
 
+    {
+        let mut game_data = game_data.lock();
+        match game_data.get_world_name(config.world.world_id) {
+            Some(world_name) => {
+                tracing::info!("Serving world {world_name} ({})", config.world.world_id)
+            }
+            None => tracing::warn!(
+                "World {} isn't in the World Excel sheet, clients won't be able to display it!",
+                config.world.world_id
+            ),
+        }
+    }
+
     {
         let mut lua = lua.lock();
         if let Err(err) = lua.init(game_data.clone()) {
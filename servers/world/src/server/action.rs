@@ -2,12 +2,13 @@
 
 use std::{sync::Arc, time::Duration};
 
+use glam::{Vec2, Vec3};
 use mlua::Function;
 use parking_lot::Mutex;
 
 use crate::{
     ClientId, FromServer, GameData, PlayerData, StatusEffects, ToServer,
-    lua::{EffectsBuilder, KawariLua, KawariLuaState, LuaContent, LuaPlayer, LuaZone},
+    lua::{EffectsBuilder, KawariLua, KawariLuaState, LuaContent, LuaPlayer, LuaTask, LuaZone},
     server::{
         WorldServer,
         actor::{NetworkedActor, NpcState},
@@ -20,17 +21,20 @@ use crate::{
 };
 use kawari::{
     common::{
-        COMBO_TIMEOUT, CharacterMode, DEAD_FADE_OUT_TIME, ObjectId, STRIKING_DUMMY_NAME_ID,
-        TimepointData,
+        COMBO_TIMEOUT, CharacterMode, DEAD_FADE_OUT_TIME, ObjectId, Position,
+        STRIKING_DUMMY_NAME_ID, TimepointData, timestamp_msecs,
     },
-    config::FilesystemConfig,
+    config::{FilesystemConfig, get_config},
     ipc::zone::{
         ActionEffect, ActionKind, ActionRequest, ActionResult, ActorControlCategory,
         BattleNpcSubKind, CommonSpawn, EffectEntry, EffectKind, EffectResult, ObjectKind,
-        ServerZoneIpcData, ServerZoneIpcSegment, SpawnNpc,
+        ServerNoticeFlags, ServerNoticeMessage, ServerZoneIpcData, ServerZoneIpcSegment, SpawnNpc,
     },
 };
 
+/// The maximum number of effects that fit into a single ActionResult.
+const MAX_ACTION_EFFECTS: usize = 8;
+
 /// Process action-related messages.
 pub fn handle_action_messages(
     data: Arc<Mutex<WorldServer>>,
@@ -64,6 +68,21 @@ pub fn handle_action_messages(
         return true;
     }
 
+    if let ToServer::ActorsInRadius(from_actor_id, center, radius, reply) = msg {
+        let data = data.lock();
+        let actors = data
+            .find_actor_instance(*from_actor_id)
+            .map(|instance| {
+                instance.actors_in_area(*center, 0.0, AreaShape::Circle { radius: *radius })
+            })
+            .unwrap_or_default();
+
+        // The connection might've gone away in the meantime, which is fine
+        let _ = reply.try_send(actors);
+
+        return true;
+    }
+
     false
 }
 
@@ -85,15 +104,101 @@ pub fn execute_action(
         content_data: LuaContent::default(),
         base_parameters: BaseParameters::default(),
     };
+    // Make sure the action is off of recast and that we can afford it, before anything else happens.
+    // It's only paid for once it has actually been executed.
+    let mut action_cost = None;
+    if request.action_kind == ActionKind::Normal {
+        let mp_cost;
+        let recast;
+        let range;
+        let targets_area;
+        {
+            let mut game_data = game_data.lock();
+            mp_cost = game_data.get_action_cost(request.action_key).unwrap_or(0);
+            recast = game_data.get_action_recast(request.action_key).unwrap_or(0);
+            range = game_data.get_action_range(request.action_key).unwrap_or(0);
+            targets_area = game_data
+                .get_action_targets_area(request.action_key)
+                .unwrap_or(false);
+        }
+
+        let rejection;
+        {
+            let data = data.lock();
+            let Some(instance) = data.find_actor_instance(from_actor_id) else {
+                cancel_action(network.clone(), from_id);
+                return;
+            };
+
+            let target_position = instance
+                .find_actor(request.target.object_id)
+                .map(|actor| actor.position());
+
+            let Some(NetworkedActor::Player {
+                spawn,
+                remove_cooldowns,
+                recast_timers,
+                ..
+            }) = instance.find_actor(from_actor_id)
+            else {
+                cancel_action(network.clone(), from_id);
+                return;
+            };
+
+            rejection = if let Err(message) =
+                validate_action_target(spawn.common.position, target_position, range, targets_area)
+            {
+                Some(message)
+            } else if !*remove_cooldowns
+                && !recast_timers.is_ready(request.action_key, timestamp_msecs())
+            {
+                Some("That action is not yet ready.")
+            } else if spawn.common.resource_points < mp_cost {
+                Some("Not enough MP.")
+            } else {
+                None
+            };
+        }
+
+        if let Some(message) = rejection {
+            cancel_action(network.clone(), from_id);
+            reset_client_cooldown(
+                network.clone(),
+                game_data.clone(),
+                from_actor_id,
+                request.action_key,
+            );
+
+            let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ServerNoticeMessage(
+                ServerNoticeMessage {
+                    message: message.to_string(),
+                    ..Default::default()
+                },
+            ));
+
+            let mut network = network.lock();
+            network.send_to(
+                from_id,
+                FromServer::PacketSegment(ipc, from_actor_id),
+                DestinationNetwork::ZoneClients,
+            );
+            return;
+        }
+
+        action_cost = Some((mp_cost, recast));
+    }
+
     // TODO: Isn't there a better way to do this without a bunch of borrow checking issues involving data, actor, and instance below?
     // Regardless, we need to set the player's mount id in their common spawn so both pillion works and also letting players see this existing actor's mount when they spawn.
     if request.action_kind == ActionKind::Mount {
         let mut data = data.lock();
         let Some(instance) = data.find_actor_instance_mut(from_actor_id) else {
+            cancel_action(network.clone(), from_id);
             return;
         };
 
         let Some(actor) = instance.find_actor_mut(from_actor_id) else {
+            cancel_action(network.clone(), from_id);
             return;
         };
 
@@ -117,10 +222,12 @@ pub fn execute_action(
 
         let data = data.lock();
         let Some(instance) = data.find_actor_instance(from_actor_id) else {
+            cancel_action(network.clone(), from_id);
             return;
         };
 
         let Some(actor) = instance.find_actor(from_actor_id) else {
+            cancel_action(network.clone(), from_id);
             return;
         };
 
@@ -140,10 +247,12 @@ pub fn execute_action(
         let data = data.lock();
 
         let Some(instance) = data.find_actor_instance(from_actor_id) else {
+            cancel_action(network.clone(), from_id);
             return;
         };
 
         let Some(actor) = instance.find_actor(from_actor_id) else {
+            cancel_action(network.clone(), from_id);
             return;
         };
 
@@ -172,25 +281,65 @@ pub fn execute_action(
         };
     }
 
+    // Nothing handled this action, so unlock the client instead of leaving them waiting for a result.
+    if !action_was_handled(request.action_kind, &effects_builder) {
+        if action_cost.is_some() {
+            reset_client_cooldown(
+                network.clone(),
+                game_data.clone(),
+                from_actor_id,
+                request.action_key,
+            );
+        }
+
+        let messages = unhandled_action_messages(
+            request.action_key,
+            from_actor_id,
+            get_config().world.verbose_scripting,
+        );
+
+        let mut network = network.lock();
+        for msg in messages {
+            network.send_to(from_id, msg, DestinationNetwork::ZoneClients);
+        }
+        return;
+    }
+
     // tell them the action results
     if let Some(mut effects_builder) = effects_builder {
+        // The action went through, so now it's paid for.
+        if let Some((mp_cost, recast)) = action_cost {
+            charge_action(
+                network.clone(),
+                data.clone(),
+                from_actor_id,
+                request.action_key,
+                mp_cost,
+                recast,
+            );
+        }
+
         // Update our internal data model to their new HP
         {
             let mut data = data.lock();
 
-            let Some(instance) = data.find_actor_instance_mut(request.target.object_id) else {
+            let Some(instance) = data.find_actor_instance_mut(from_actor_id) else {
+                cancel_action(network.clone(), from_id);
                 return;
             };
 
-            // aggro any NPCs
+            // Figure out who is actually affected by this action
+            let targets;
             {
-                let Some(actor) = instance.find_actor_mut(request.target.object_id) else {
-                    return;
-                };
+                let mut game_data = game_data.lock();
+                targets = find_action_targets(&mut game_data, instance, from_actor_id, &request);
+            }
 
-                if let NetworkedActor::Npc {
+            // aggro any NPCs
+            for target_id in &targets {
+                if let Some(NetworkedActor::Npc {
                     newly_hated_actor, ..
-                } = actor
+                }) = instance.find_actor_mut(*target_id)
                 {
                     *newly_hated_actor = Some(from_actor_id);
                 }
@@ -198,14 +347,10 @@ pub fn execute_action(
 
             // Handle invulnerability
             {
-                let Some(actor) = instance.find_actor_mut(request.target.object_id) else {
-                    return;
-                };
-
-                if let NetworkedActor::Npc {
+                if let Some(NetworkedActor::Npc {
                     currently_invulnerable,
                     ..
-                } = actor
+                }) = instance.find_actor(request.target.object_id)
                     && *currently_invulnerable
                 {
                     effects_builder.effects = effects_builder
@@ -226,6 +371,7 @@ pub fn execute_action(
                 // TODO: don't send this for auto-attacks. it should be harmless in the mean time
 
                 let Some(actor) = instance.find_actor_mut(from_actor_id) else {
+                    cancel_action(network.clone(), from_id);
                     return;
                 };
 
@@ -280,13 +426,25 @@ pub fn execute_action(
                         damage_element,
                         ..
                     } => {
-                        let Some(actor) = instance.find_actor_mut(request.target.object_id) else {
-                            return;
-                        };
-                        let common_spawn = actor.get_common_spawn_mut();
-                        if common_spawn.name_id != STRIKING_DUMMY_NAME_ID {
-                            common_spawn.health_points =
-                                common_spawn.health_points.saturating_sub(*amount as u32);
+                        for target_id in &targets {
+                            let Some(actor) = instance.find_actor_mut(*target_id) else {
+                                continue;
+                            };
+
+                            // Other targets in the area may be invulnerable, even if the main one isn't.
+                            if let NetworkedActor::Npc {
+                                currently_invulnerable: true,
+                                ..
+                            } = actor
+                            {
+                                continue;
+                            }
+
+                            let common_spawn = actor.get_common_spawn_mut();
+                            if common_spawn.name_id != STRIKING_DUMMY_NAME_ID {
+                                common_spawn.health_points =
+                                    common_spawn.health_points.saturating_sub(*amount as u32);
+                            }
                         }
 
                         // Update from game data
@@ -296,10 +454,13 @@ pub fn execute_action(
                     EffectKind::InterruptAction {} => {
                         // TODO: this could cancel more than just casting, so we need to be more specific eventually
                         // TODO: also cancel the cast visually
-                        instance.cancel_actor_tasks(request.target.object_id);
+                        for target_id in &targets {
+                            instance.cancel_actor_tasks(*target_id);
+                        }
                     }
                     EffectKind::SummonPet { .. } => {
                         let Some(actor) = instance.find_actor(from_actor_id) else {
+                            cancel_action(network.clone(), from_id);
                             return;
                         };
 
@@ -356,17 +517,50 @@ pub fn execute_action(
                 }
             }
 
-            update_actor_hp_mp(network.clone(), instance, request.target.object_id);
+            for target_id in &targets {
+                update_actor_hp_mp(network.clone(), instance, *target_id);
+            }
         }
 
-        // TODO: send Cooldown ActorControlSelf
+        // The client can only display so many effects at once.
+        if effects_builder.effects.len() > MAX_ACTION_EFFECTS {
+            tracing::warn!(
+                "Action {} produced {} effects, but only {MAX_ACTION_EFFECTS} can be sent!",
+                request.action_key,
+                effects_builder.effects.len()
+            );
+            effects_builder.effects.truncate(MAX_ACTION_EFFECTS);
+        }
+
+        // Start the cooldown on the client
+        if request.action_kind == ActionKind::Normal {
+            let mut game_data = game_data.lock();
+            let recast = game_data.get_action_recast(request.action_key).unwrap_or(0);
+            if recast > 0 {
+                let cooldown_group = (game_data
+                    .get_action_cooldown_group(request.action_key)
+                    .unwrap_or_default() as u32)
+                    .saturating_sub(1);
+
+                let mut network = network.lock();
+                network.send_to_by_actor_id(
+                    from_actor_id,
+                    FromServer::ActorControlSelf(ActorControlCategory::SetCooldownTimerMax {
+                        cooldown_group,
+                        action_id: request.action_key,
+                        milliseconds: recast as u32 * 100 * 10,
+                    }),
+                    DestinationNetwork::ZoneClients,
+                );
+            }
+        }
 
         {
             let mut network = network.lock();
 
             // ActionResult
             {
-                let mut effects = [ActionEffect::default(); 8];
+                let mut effects = [ActionEffect::default(); MAX_ACTION_EFFECTS];
                 effects[..effects_builder.effects.len()].copy_from_slice(&effects_builder.effects);
 
                 let action_animation_id;
@@ -405,7 +599,7 @@ pub fn execute_action(
 
                 let mut data = data.lock();
 
-                let Some(instance) = data.find_actor_instance_mut(request.target.object_id) else {
+                let Some(instance) = data.find_actor_instance_mut(from_actor_id) else {
                     return;
                 };
                 network.send_in_range_inclusive_instance(
@@ -437,6 +631,7 @@ pub fn execute_action(
                     let index = gain_effect(
                         network.clone(),
                         data.clone(),
+                        lua.clone(),
                         ClientId::default(),
                         request.target.object_id,
                         effect_id,
@@ -467,6 +662,7 @@ pub fn execute_action(
                     let index = gain_effect(
                         network.clone(),
                         data.clone(),
+                        lua.clone(),
                         from_id,
                         from_actor_id,
                         effect_id,
@@ -563,6 +759,11 @@ pub fn execute_action(
         }
     }
 
+    // Using actions slowly wears down the player's equipment.
+    if request.action_kind == ActionKind::Normal {
+        lua_player.queued_tasks.push(LuaTask::DecayEquipment {});
+    }
+
     let mut network = network.lock();
     let msg = FromServer::NewTasks(lua_player.queued_tasks);
     network.send_to(from_id, msg, DestinationNetwork::ZoneClients);
@@ -588,7 +789,10 @@ pub fn execute_action(
 
         if *remove_cooldowns {
             let mut game_data = game_data.lock();
-            let cooldown_group = game_data.get_action_cooldown_group(request.action_key) as u32 - 1;
+            let cooldown_group = (game_data
+                .get_action_cooldown_group(request.action_key)
+                .unwrap_or_default() as u32)
+                .saturating_sub(1);
             network.send_to_by_actor_id(
                 from_actor_id,
                 FromServer::ActorControlSelf(ActorControlCategory::SetCooldownTimer {
@@ -602,6 +806,126 @@ pub fn execute_action(
     }
 }
 
+/// Determines which actors are affected by an action.
+///
+/// Single-target actions only ever hit their main target, while area actions hit everyone on the same side as it.
+fn find_action_targets(
+    game_data: &mut GameData,
+    instance: &Instance,
+    from_actor_id: ObjectId,
+    request: &ActionRequest,
+) -> Vec<ObjectId> {
+    let main_target = instance.find_actor(request.target.object_id);
+    let single_target = || {
+        main_target
+            .map(|_| vec![request.target.object_id])
+            .unwrap_or_default()
+    };
+
+    // Items and mounts don't have an area of effect.
+    if request.action_kind != ActionKind::Normal {
+        return single_target();
+    }
+
+    let Some((cast_type, effect_range, x_axis_modifier)) =
+        game_data.get_action_effect_range(request.action_key)
+    else {
+        return single_target();
+    };
+
+    let Some(shape) = AreaShape::from_cast_type(cast_type, effect_range, x_axis_modifier) else {
+        return single_target();
+    };
+
+    let Some(caster) = instance.find_actor(from_actor_id) else {
+        return Vec::new();
+    };
+
+    // If there is no main target (e.g. it's already dead), center it on ourselves and hit everyone who isn't on our side.
+    let (battalion, same_side) = match main_target {
+        Some(actor) => (actor.get_common_spawn().battalion, true),
+        None => (caster.get_common_spawn().battalion, false),
+    };
+
+    // Circles are centered on the main target, while cones and lines come out of the caster towards it.
+    let (origin, rotation) = match (shape, main_target) {
+        (AreaShape::Circle { .. }, Some(target)) => (target.position(), 0.0),
+        (AreaShape::Circle { .. }, None) => (caster.position(), 0.0),
+        (_, Some(target)) if request.target.object_id != from_actor_id => {
+            let from = caster.position().0;
+            let to = target.position().0;
+            (caster.position(), f32::atan2(to.x - from.x, to.z - from.z))
+        }
+        _ => (caster.position(), caster.rotation()),
+    };
+
+    instance
+        .actors_in_area(origin, rotation, shape)
+        .into_iter()
+        .filter(|id| {
+            instance
+                .find_actor(*id)
+                .is_some_and(|x| (x.get_common_spawn().battalion == battalion) == same_side)
+        })
+        .collect()
+}
+
+/// The shape of an action's area of effect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AreaShape {
+    /// Everyone within `radius` of the center.
+    Circle { radius: f32 },
+    /// Everyone within `radius` of the caster, and no more than `half_angle` (in radians) to either side of where they're facing.
+    Cone { radius: f32, half_angle: f32 },
+    /// Everyone inside a rectangle `length` long and `width` wide, stretching out from the caster in the direction they're facing.
+    Line { length: f32, width: f32 },
+}
+
+impl AreaShape {
+    /// Picks the shape from an action's CastType, EffectRange and XAxisModifier columns. Returns None for single-target actions.
+    pub fn from_cast_type(cast_type: u8, effect_range: u8, x_axis_modifier: u8) -> Option<Self> {
+        let radius = effect_range as f32;
+        match cast_type {
+            _ if effect_range == 0 => None,
+            0 | 1 => None,
+            3 => Some(Self::Cone {
+                radius,
+                half_angle: 45.0_f32.to_radians(),
+            }),
+            13 => Some(Self::Cone {
+                radius,
+                half_angle: 60.0_f32.to_radians(),
+            }),
+            4 | 8 | 12 => Some(Self::Line {
+                length: radius,
+                width: x_axis_modifier as f32,
+            }),
+            _ => Some(Self::Circle { radius }),
+        }
+    }
+
+    /// Whether `point` is inside this shape, when it's placed at `origin` facing `rotation`. Like retail, the Y axis is ignored.
+    pub fn contains(&self, origin: Vec3, rotation: f32, point: Vec3) -> bool {
+        let offset = Vec2::new(point.x - origin.x, point.z - origin.z);
+        let forward = Vec2::new(rotation.sin(), rotation.cos());
+        let distance = offset.length();
+
+        match *self {
+            Self::Circle { radius } => distance <= radius,
+            Self::Cone { radius, half_angle } => {
+                // Anyone standing right on top of the caster is always hit.
+                distance <= radius
+                    && (distance == 0.0 || forward.dot(offset) / distance >= half_angle.cos())
+            }
+            Self::Line { length, width } => {
+                let along = forward.dot(offset);
+                let across = forward.perp_dot(offset).abs();
+                (0.0..=length).contains(&along) && across <= width / 2.0
+            }
+        }
+    }
+}
+
 /// Executes an action from an enemy.
 pub fn execute_enemy_action(
     network: Arc<Mutex<NetworkState>>,
@@ -767,6 +1091,137 @@ pub fn execute_enemy_action(
     }
 }
 
+/// Spends the MP and starts the recast of an action that was just executed.
+fn charge_action(
+    network: Arc<Mutex<NetworkState>>,
+    data: Arc<Mutex<WorldServer>>,
+    from_actor_id: ObjectId,
+    action_key: u32,
+    mp_cost: u16,
+    recast: u16,
+) {
+    let mut data = data.lock();
+    let Some(instance) = data.find_actor_instance_mut(from_actor_id) else {
+        return;
+    };
+
+    let Some(NetworkedActor::Player {
+        spawn,
+        recast_timers,
+        ..
+    }) = instance.find_actor_mut(from_actor_id)
+    else {
+        return;
+    };
+
+    spawn.common.resource_points = spawn.common.resource_points.saturating_sub(mp_cost);
+    recast_timers.start(
+        action_key,
+        Duration::from_millis(recast as u64 * 100),
+        timestamp_msecs(),
+    );
+
+    // Keep the connection's copy up to date, since ours is thrown away when they leave the zone.
+    {
+        let msg = FromServer::RecastTimersChanged(recast_timers.clone());
+
+        let mut network = network.lock();
+        network.send_to_by_actor_id(from_actor_id, msg, DestinationNetwork::ZoneClients);
+    }
+
+    if mp_cost > 0 {
+        update_actor_hp_mp(network, instance, from_actor_id);
+    }
+}
+
+/// Clears the cooldown the client started on its own, for an action that didn't go through.
+fn reset_client_cooldown(
+    network: Arc<Mutex<NetworkState>>,
+    game_data: Arc<Mutex<GameData>>,
+    from_actor_id: ObjectId,
+    action_key: u32,
+) {
+    let Some(cooldown_group) = game_data.lock().get_action_cooldown_group(action_key) else {
+        return;
+    };
+
+    let mut network = network.lock();
+    network.send_to_by_actor_id(
+        from_actor_id,
+        FromServer::ActorControlSelf(ActorControlCategory::SetCooldownTimer {
+            cooldown_group: (cooldown_group as u32).saturating_sub(1),
+            unk1: 0,
+            unk2: 0,
+        }),
+        DestinationNetwork::ZoneClients,
+    );
+}
+
+/// How much further (in yalms) than an action's range its target can be. Roughly accounts for the target's hitbox, which we don't know yet.
+const ACTION_RANGE_TOLERANCE: f32 = 3.0;
+
+/// Checks that the target of an action with `range` exists and is close enough to the caster. Actions without a range are centered on the caster, so they don't need a target.
+/// Ground-targeted actions (`targets_area`) are aimed at a location instead of an actor, so they don't need one either.
+fn validate_action_target(
+    caster_position: Position,
+    target_position: Option<Position>,
+    range: u8,
+    targets_area: bool,
+) -> Result<(), &'static str> {
+    if range == 0 || targets_area {
+        return Ok(());
+    }
+
+    let Some(target_position) = target_position else {
+        return Err("Invalid target.");
+    };
+
+    if caster_position.0.distance(target_position.0) > range as f32 + ACTION_RANGE_TOLERANCE {
+        return Err("Target not in range.");
+    }
+
+    Ok(())
+}
+
+/// Whether an `ActionResult` will be sent for this action. Mounts send their own, everything else needs effects.
+fn action_was_handled(action_kind: ActionKind, effects_builder: &Option<EffectsBuilder>) -> bool {
+    effects_builder.is_some() || action_kind == ActionKind::Mount
+}
+
+/// Messages sent when no script handled `action_key`. The client is always told to cancel, so they don't stay animation-locked.
+fn unhandled_action_messages(
+    action_key: u32,
+    from_actor_id: ObjectId,
+    verbose_scripting: bool,
+) -> Vec<FromServer> {
+    let mut messages = vec![FromServer::ActorControlSelf(
+        ActorControlCategory::CancelCast {},
+    )];
+
+    if verbose_scripting {
+        messages.push(script_notice(
+            from_actor_id,
+            &format!(
+                "Action {action_key} was used, but it doesn't have a script associated with it!"
+            ),
+        ));
+    }
+
+    messages
+}
+
+/// Creates a chat log notice for `from_actor_id`, used to explain why a script didn't do anything.
+pub fn script_notice(from_actor_id: ObjectId, message: &str) -> FromServer {
+    let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ServerNoticeMessage(
+        ServerNoticeMessage {
+            flags: ServerNoticeFlags::CHAT_LOG,
+            message: message.to_string(),
+        },
+    ));
+
+    FromServer::PacketSegment(ipc, from_actor_id)
+}
+
 pub fn cancel_action(network: Arc<Mutex<NetworkState>>, from_id: ClientId) {
     let msg = FromServer::ActorControlSelf(ActorControlCategory::CancelCast {});
 
@@ -1069,3 +1524,196 @@ pub fn update_actor_hp_mp(
         kill_actor(network.clone(), instance, target_actor_id);
     }
 }
+
+/// Sets an actor's HP and/or MP, but never higher than their maximums.
+pub fn set_hp_mp(common_spawn: &mut CommonSpawn, hp: Option<u32>, mp: Option<u16>) {
+    if let Some(hp) = hp {
+        common_spawn.health_points = hp.min(common_spawn.max_health_points);
+    }
+    if let Some(mp) = mp {
+        common_spawn.resource_points = mp.min(common_spawn.max_resource_points);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gamedata::MELEE_ACTION_RANGE;
+
+    use super::*;
+
+    #[test]
+    fn test_set_hp_mp_clamps() {
+        let mut common_spawn = CommonSpawn {
+            health_points: 100,
+            max_health_points: 500,
+            resource_points: 100,
+            max_resource_points: 10000,
+            ..Default::default()
+        };
+
+        set_hp_mp(&mut common_spawn, Some(9999), None);
+        assert_eq!(common_spawn.health_points, 500);
+        assert_eq!(common_spawn.resource_points, 100);
+
+        set_hp_mp(&mut common_spawn, None, Some(20000));
+        assert_eq!(common_spawn.resource_points, 10000);
+
+        set_hp_mp(&mut common_spawn, Some(0), Some(50));
+        assert_eq!(common_spawn.health_points, 0);
+        assert_eq!(common_spawn.resource_points, 50);
+    }
+
+    #[test]
+    fn test_missing_action_target() {
+        let caster = Position::default();
+
+        assert_eq!(
+            validate_action_target(caster, None, 25, false),
+            Err("Invalid target.")
+        );
+
+        // Self-centered actions don't need one
+        assert_eq!(validate_action_target(caster, None, 0, false), Ok(()));
+
+        // Neither do ground-targeted ones
+        assert_eq!(validate_action_target(caster, None, 25, true), Ok(()));
+    }
+
+    #[test]
+    fn test_out_of_range_action_target() {
+        let caster = Position::default();
+        let nearby = Position(Vec3::new(20.0, 0.0, 0.0));
+        let far_away = Position(Vec3::new(50.0, 0.0, 0.0));
+
+        assert_eq!(
+            validate_action_target(caster, Some(nearby), 25, false),
+            Ok(())
+        );
+        assert_eq!(
+            validate_action_target(caster, Some(far_away), 25, false),
+            Err("Target not in range.")
+        );
+    }
+
+    #[test]
+    fn test_melee_action_target() {
+        let caster = Position::default();
+        let adjacent = Position(Vec3::new(2.0, 0.0, 0.0));
+        let nearby = Position(Vec3::new(20.0, 0.0, 0.0));
+
+        assert_eq!(
+            validate_action_target(caster, Some(adjacent), MELEE_ACTION_RANGE, false),
+            Ok(())
+        );
+        assert_eq!(
+            validate_action_target(caster, Some(nearby), MELEE_ACTION_RANGE, false),
+            Err("Target not in range.")
+        );
+    }
+
+    #[test]
+    fn test_unscripted_action_cancels() {
+        // Missing scripts don't produce any effects
+        assert!(!action_was_handled(ActionKind::Normal, &None));
+        assert!(!action_was_handled(ActionKind::Item, &None));
+        assert!(!action_was_handled(ActionKind::Nothing, &None));
+
+        assert!(action_was_handled(
+            ActionKind::Normal,
+            &Some(EffectsBuilder::default())
+        ));
+        assert!(action_was_handled(ActionKind::Mount, &None));
+    }
+
+    #[test]
+    fn test_unscripted_action_unlocks() {
+        let messages = unhandled_action_messages(9, ObjectId(1), false);
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(
+            messages[0],
+            FromServer::ActorControlSelf(ActorControlCategory::CancelCast {})
+        ));
+
+        // Testers are also told why nothing happened
+        let messages = unhandled_action_messages(9, ObjectId(1), true);
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(
+            messages[0],
+            FromServer::ActorControlSelf(ActorControlCategory::CancelCast {})
+        ));
+        assert!(matches!(
+            messages[1],
+            FromServer::PacketSegment(_, ObjectId(1))
+        ));
+    }
+
+    #[test]
+    fn test_area_shape_from_cast_type() {
+        assert_eq!(AreaShape::from_cast_type(1, 5, 0), None);
+        assert_eq!(AreaShape::from_cast_type(2, 0, 0), None);
+        assert_eq!(
+            AreaShape::from_cast_type(2, 5, 0),
+            Some(AreaShape::Circle { radius: 5.0 })
+        );
+        assert!(matches!(
+            AreaShape::from_cast_type(3, 8, 0),
+            Some(AreaShape::Cone { radius, .. }) if radius == 8.0
+        ));
+        assert_eq!(
+            AreaShape::from_cast_type(4, 25, 4),
+            Some(AreaShape::Line {
+                length: 25.0,
+                width: 4.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_cone_contains() {
+        let cone = AreaShape::Cone {
+            radius: 8.0,
+            half_angle: 45.0_f32.to_radians(),
+        };
+        // Facing towards +Z
+        let origin = Vec3::ZERO;
+
+        assert!(cone.contains(origin, 0.0, Vec3::new(0.0, 0.0, 5.0)));
+        assert!(cone.contains(origin, 0.0, Vec3::new(3.0, 0.0, 4.0)));
+        assert!(cone.contains(origin, 0.0, origin));
+
+        // Too far to the side, behind, or out of range
+        assert!(!cone.contains(origin, 0.0, Vec3::new(5.0, 0.0, 1.0)));
+        assert!(!cone.contains(origin, 0.0, Vec3::new(0.0, 0.0, -5.0)));
+        assert!(!cone.contains(origin, 0.0, Vec3::new(0.0, 0.0, 9.0)));
+
+        // Turning around to face -Z
+        assert!(cone.contains(origin, std::f32::consts::PI, Vec3::new(0.0, 0.0, -5.0)));
+    }
+
+    #[test]
+    fn test_line_contains() {
+        let line = AreaShape::Line {
+            length: 20.0,
+            width: 4.0,
+        };
+        // Facing towards +X
+        let origin = Vec3::ZERO;
+        let rotation = std::f32::consts::FRAC_PI_2;
+
+        assert!(line.contains(origin, rotation, Vec3::new(15.0, 0.0, 0.0)));
+        assert!(line.contains(origin, rotation, Vec3::new(15.0, 10.0, 1.5)));
+
+        // Too wide, behind, or past the end
+        assert!(!line.contains(origin, rotation, Vec3::new(15.0, 0.0, 3.0)));
+        assert!(!line.contains(origin, rotation, Vec3::new(-1.0, 0.0, 0.0)));
+        assert!(!line.contains(origin, rotation, Vec3::new(21.0, 0.0, 0.0)));
+
+        // A circle of the same size would've hit someone beside us
+        assert!(AreaShape::Circle { radius: 20.0 }.contains(
+            origin,
+            rotation,
+            Vec3::new(0.0, 0.0, 15.0)
+        ));
+        assert!(!line.contains(origin, rotation, Vec3::new(0.0, 0.0, 15.0)));
+    }
+}
@@ -1,12 +1,15 @@
-use std::collections::VecDeque;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    StatusEffects,
-    zone_connection::{BaseParameters, TeleportQuery},
+    RecastTimers, StatusEffects,
+    zone_connection::{BaseParameters, ReviveKind, TeleportQuery},
 };
 use glam::Vec3;
 use kawari::{
-    common::{DistanceRange, ObjectId, Position, Timeline},
+    common::{CharacterMode, DistanceRange, ObjectId, ObjectTypeId, Position, Timeline},
     ipc::zone::{CommonSpawn, Conditions, SpawnNpc, SpawnObject, SpawnPlayer, SpawnTreasure},
 };
 
@@ -35,6 +38,25 @@ impl NpcState {
     }
 }
 
+/// Returns a fresh copy of a dead NPC's spawn, suitable for spawning it again.
+pub fn respawned_npc(spawn: &SpawnNpc) -> SpawnNpc {
+    let mut spawn = spawn.clone();
+    spawn.common.health_points = spawn.common.max_health_points;
+    spawn.common.resource_points = spawn.common.max_resource_points;
+    spawn.common.mode = CharacterMode::Normal;
+    spawn.common.target_id = ObjectTypeId::default();
+    spawn.common.combat_tagger_id = ObjectTypeId::default();
+
+    spawn
+}
+
+/// Restores a dead player's HP according to `kind`. Their mode is left alone so the change can be broadcast with `set_character_mode`.
+pub fn revive_actor(common: &mut CommonSpawn, kind: ReviveKind) {
+    common.health_points = kind.restored_hp(common.max_health_points);
+    common.target_id = ObjectTypeId::default();
+    common.combat_tagger_id = ObjectTypeId::default();
+}
+
 #[derive(Debug, Clone)]
 pub enum NetworkedActor {
     Player {
@@ -49,6 +71,10 @@ pub enum NetworkedActor {
         executing_gimmick_jump: bool,
         // If this actor is currently inside of an instance exit range.
         inside_instance_exit: bool,
+        /// If this actor is currently inside of a zone line.
+        inside_zone_line: bool,
+        /// When this actor walked into the zone line they're inside of, until they're sent through it.
+        entered_zone_line_at: Option<Instant>,
         parameters: BaseParameters,
         dueling_opponent_id: ObjectId,
         /// Whether or not cooldowns should be cheatily removed.
@@ -57,6 +83,10 @@ pub enum NetworkedActor {
         last_combo_action: u16,
         /// Sequence into the current combo.
         combo_sequence: u8,
+        /// When this actor's actions come off of recast. This is a copy of the one in the connection's PlayerData, which is reapplied on every zone load.
+        recast_timers: RecastTimers,
+        /// Whether this GM is hiding from other players, e.g. in the online player list.
+        gm_invisible: bool,
     },
     Npc {
         state: NpcState,
@@ -74,6 +104,8 @@ pub enum NetworkedActor {
         currently_invulnerable: bool,
         /// This actor's status effects.
         status_effects: StatusEffects,
+        /// How long to wait before respawning after being despawned. If None, this NPC never comes back.
+        respawn_delay: Option<Duration>,
     },
     Object {
         object: SpawnObject,
@@ -84,6 +116,29 @@ pub enum NetworkedActor {
 }
 
 impl NetworkedActor {
+    /// Creates a player actor with default state, for a player that just loaded in.
+    pub fn new_player(spawn: SpawnPlayer) -> Self {
+        NetworkedActor::Player {
+            spawn,
+            status_effects: StatusEffects::default(),
+            teleport_query: TeleportQuery::default(),
+            distance_range: DistanceRange::Normal,
+            conditions: Conditions::default(),
+            executing_gimmick_jump: false,
+            inside_instance_exit: false,
+            // Start as if we're already inside one, so players that spawn on top of a zone line aren't immediately sent through it.
+            inside_zone_line: true,
+            entered_zone_line_at: None,
+            parameters: BaseParameters::default(),
+            dueling_opponent_id: ObjectId::default(),
+            remove_cooldowns: false,
+            last_combo_action: 0,
+            combo_sequence: 0,
+            recast_timers: RecastTimers::default(),
+            gm_invisible: false,
+        }
+    }
+
     pub fn get_common_spawn(&self) -> &CommonSpawn {
         match &self {
             NetworkedActor::Player { spawn, .. } => &spawn.common,
@@ -133,6 +188,11 @@ impl NetworkedActor {
     }
 
     pub fn in_range_of(&self, other: &NetworkedActor) -> bool {
+        self.in_spawn_range_of(other, 0.0)
+    }
+
+    /// Same as `in_range_of`, but never further than `max_spawn_distance` unless it's zero.
+    pub fn in_spawn_range_of(&self, other: &NetworkedActor, max_spawn_distance: f32) -> bool {
         // This only makes sense for players
         if let NetworkedActor::Player { distance_range, .. } = self {
             // Retail doesn't take into account Y
@@ -142,8 +202,13 @@ impl NetworkedActor {
             let mut other_pos = other.position().0;
             other_pos.y = 0.0;
 
+            let mut range = distance_range.distance();
+            if max_spawn_distance > 0.0 {
+                range = range.min(max_spawn_distance);
+            }
+
             let distance = Vec3::distance(self_pos, other_pos);
-            distance < distance_range.distance()
+            distance < range
         } else {
             false
         }
@@ -176,3 +241,113 @@ impl NetworkedActor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recast_timers() {
+        let now = 1_000_000;
+        let mut timers = RecastTimers::default();
+
+        // Nothing has been used yet
+        assert!(timers.is_ready(1, now));
+
+        // Casting twice in quick succession should be rejected the second time
+        timers.start(1, Duration::from_millis(2500), now);
+        assert!(!timers.is_ready(1, now + 100));
+
+        // Other actions are unaffected
+        assert!(timers.is_ready(2, now));
+
+        // Once the recast ends, it's usable again
+        assert!(timers.is_ready(1, now + 2500));
+
+        // ...and it's forgotten the next time something else is used
+        timers.start(2, Duration::from_millis(2500), now + 2500);
+        assert_eq!(timers.0.len(), 1);
+
+        // Actions without a recast never block
+        timers.start(3, Duration::ZERO, now);
+        assert!(timers.is_ready(3, now));
+    }
+
+    #[test]
+    fn test_respawned_npc() {
+        let mut spawn = SpawnNpc::default();
+        spawn.common.max_health_points = 100;
+        spawn.common.health_points = 0;
+        spawn.common.mode = CharacterMode::Dead;
+
+        // HP should reset, and they should no longer be dead
+        let respawned = respawned_npc(&spawn);
+        assert_eq!(respawned.common.health_points, 100);
+        assert_eq!(respawned.common.mode, CharacterMode::Normal);
+    }
+
+    #[test]
+    fn test_revive_actor() {
+        let mut common = CommonSpawn {
+            max_health_points: 1000,
+            health_points: 0,
+            mode: CharacterMode::Dead,
+            ..Default::default()
+        };
+
+        // Being raised only gives back a fraction of our HP
+        revive_actor(&mut common, ReviveKind::Raise);
+        assert_eq!(common.health_points, 100);
+
+        // But returning to the home point is a full recovery
+        common.health_points = 0;
+        revive_actor(&mut common, ReviveKind::Homepoint);
+        assert_eq!(common.health_points, 1000);
+    }
+
+    #[test]
+    fn test_players_in_range() {
+        let player_at = |x: f32, z: f32| {
+            let mut spawn = SpawnPlayer::default();
+            spawn.common.name = "Test".to_string();
+            spawn.common.position = Position(Vec3::new(x, 0.0, z));
+            NetworkedActor::new_player(spawn)
+        };
+
+        // Two players standing next to each other see each other
+        let first = player_at(0.0, 0.0);
+        let second = player_at(5.0, 5.0);
+        assert!(first.in_range_of(&second));
+        assert!(second.in_range_of(&first));
+
+        // But not once one walks far away
+        let far = player_at(1000.0, 1000.0);
+        assert!(!first.in_range_of(&far));
+
+        // Height shouldn't matter
+        let mut above = player_at(0.0, 0.0);
+        above.get_common_spawn_mut().position.0.y = 1000.0;
+        assert!(first.in_range_of(&above));
+    }
+
+    #[test]
+    fn test_max_spawn_distance() {
+        let player_at = |x: f32| {
+            let mut spawn = SpawnPlayer::default();
+            spawn.common.name = "Test".to_string();
+            spawn.common.position = Position(Vec3::new(x, 0.0, 0.0));
+            NetworkedActor::new_player(spawn)
+        };
+
+        let first = player_at(0.0);
+        let second = player_at(50.0);
+
+        // The server can shrink the client's view distance
+        assert!(first.in_spawn_range_of(&second, 0.0));
+        assert!(!first.in_spawn_range_of(&second, 25.0));
+
+        // But doesn't extend it
+        let far = player_at(200.0);
+        assert!(!first.in_spawn_range_of(&far, 1000.0));
+    }
+}
@@ -1,11 +1,20 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use bstr::BString;
+use bstr::{BString, ByteSlice};
+use glam::Vec3;
 use kawari::{
-    common::{DEBUG_COMMAND_TRIGGER, ObjectId},
+    common::{
+        DEBUG_COMMAND_TRIGGER, MESSAGE_MAX_LENGTH, ObjectId, Position, truncate_at_char_boundary,
+    },
+    config::{WorldConfig, get_config},
+    ipc::chat::{ChatChannelType, TellMessage},
     ipc::zone::{
         ActionKind, ActionRequest, BattleNpcSubKind, CharacterDataFlag, CommonSpawn, ObjectKind,
-        ServerNoticeMessage, ServerZoneIpcData, ServerZoneIpcSegment, SpawnNpc, WarpType,
+        ServerNoticeFlags, ServerNoticeMessage, ServerZoneIpcData, ServerZoneIpcSegment, SpawnNpc,
+        WarpType,
     },
 };
 use parking_lot::Mutex;
@@ -23,6 +32,117 @@ use crate::{
     },
 };
 
+/// How often a broadcast with a countdown is repeated.
+const BROADCAST_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A broadcast that's repeated every `BROADCAST_INTERVAL` until its countdown runs out.
+#[derive(Debug, Clone)]
+pub struct ScheduledBroadcast {
+    pub message: String,
+    /// How many minutes are left, as shown the next time this is sent.
+    pub minutes_left: u32,
+    pub next_send: Instant,
+}
+
+/// Sends `message` to everyone online, both in the chat log and on-screen.
+fn broadcast_message(network: &mut NetworkState, message: &str) {
+    let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ServerNoticeMessage(
+        ServerNoticeMessage {
+            flags: ServerNoticeFlags::CHAT_LOG | ServerNoticeFlags::ON_SCREEN,
+            message: message.to_string(),
+        },
+    ));
+
+    network.send_to_all(
+        FromServer::PacketSegment(ipc, ObjectId::default()),
+        DestinationNetwork::ZoneClients,
+    );
+}
+
+/// Sends the broadcasts that are due, and forgets about the ones that have finished counting down.
+pub fn send_scheduled_broadcasts(
+    network: &mut NetworkState,
+    broadcasts: &mut Vec<ScheduledBroadcast>,
+    now: Instant,
+) {
+    for broadcast in broadcasts.iter_mut() {
+        if broadcast.next_send <= now {
+            broadcast_message(
+                network,
+                &format_countdown(&broadcast.message, broadcast.minutes_left),
+            );
+
+            broadcast.minutes_left -= 1;
+            broadcast.next_send = now + BROADCAST_INTERVAL;
+        }
+    }
+
+    broadcasts.retain(|broadcast| broadcast.minutes_left > 0);
+}
+
+/// Appends how much time is left to a counting down broadcast.
+fn format_countdown(message: &str, minutes_left: u32) -> String {
+    if minutes_left == 1 {
+        format!("{message} (1 minute remaining)")
+    } else {
+        format!("{message} ({minutes_left} minutes remaining)")
+    }
+}
+
+/// Returns how far a message in this channel can be heard, or None if it reaches the entire zone.
+fn chat_radius(config: &WorldConfig, channel: ChatChannelType) -> Option<f32> {
+    let radius = match channel {
+        ChatChannelType::Say | ChatChannelType::CustomEmote => config.say_radius,
+        ChatChannelType::Yell => config.yell_radius,
+        ChatChannelType::Shout => config.shout_radius,
+        _ => 0.0,
+    };
+
+    (radius > 0.0).then_some(radius)
+}
+
+/// Whether a listener is close enough to the sender to hear them. Like spawning, this doesn't take into account Y.
+fn can_hear(radius: f32, sender: Position, listener: Position) -> bool {
+    let sender = Vec3::new(sender.0.x, 0.0, sender.0.z);
+    let listener = Vec3::new(listener.0.x, 0.0, listener.0.z);
+
+    sender.distance(listener) <= radius
+}
+
+/// Rolls a number between 1 and `max`, like /random.
+fn roll_random(max: u32) -> u32 {
+    fastrand::u32(1..=max.max(1))
+}
+
+/// The message everyone nearby sees after a roll.
+fn format_random_roll(name: &str, roll: u32, max: u32) -> String {
+    format!("{name} rolls a {roll} (out of {max}).")
+}
+
+/// Delivers a tell to the recipient's chat connection, cutting off anything that doesn't fit in a single message.
+fn send_tell(
+    network: &mut NetworkState,
+    from_actor_id: ObjectId,
+    recipient_actor_id: ObjectId,
+    message: &TellMessage,
+) {
+    let mut message = message.clone();
+    // Leave room for the null terminator
+    let max_length = MESSAGE_MAX_LENGTH - 1;
+    let length = match message.message.to_str() {
+        Ok(text) => truncate_at_char_boundary(text, max_length).len(),
+        // SEString macros aren't valid UTF-8, so there are no characters to keep whole
+        Err(_) => message.message.len().min(max_length),
+    };
+    message.message.truncate(length);
+
+    network.send_to_by_actor_id(
+        recipient_actor_id,
+        FromServer::TellMessageReceived(from_actor_id, message),
+        DestinationNetwork::ChatClients,
+    );
+}
+
 /// Process chat-related messages.
 pub fn handle_chat_messages(
     data: Arc<Mutex<WorldServer>>,
@@ -47,7 +167,10 @@ pub fn handle_chat_messages(
                     // If it's truly not existent...
                     let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ServerNoticeMessage(
                         ServerNoticeMessage {
-                            message: format!("Unknown command {}", msg.message),
+                            message: format!(
+                                "Unknown command {}, see the Debug Commands page in the documentation for what's available.",
+                                msg.message
+                            ),
                             ..Default::default()
                         },
                     ));
@@ -73,22 +196,96 @@ pub fn handle_chat_messages(
                 panic!("Client is somehow not in an instance yet?!");
             };
 
-            network.send_to_instance(
-                *from_actor_id,
-                sender_instance,
-                FromServer::Message(msg.clone()),
-                DestinationNetwork::ZoneClients,
-            );
+            let config = get_config();
+            if let Some(radius) = chat_radius(&config.world, msg.channel) {
+                for (id, actor) in &sender_instance.actors {
+                    if *id == *from_actor_id
+                        || !matches!(actor, NetworkedActor::Player { .. })
+                        || !can_hear(radius, msg.sender_position, actor.position())
+                    {
+                        continue;
+                    }
+
+                    network.send_to_by_actor_id(
+                        *id,
+                        FromServer::Message(msg.clone()),
+                        DestinationNetwork::ZoneClients,
+                    );
+                }
+            } else {
+                network.send_to_instance(
+                    *from_actor_id,
+                    sender_instance,
+                    FromServer::Message(msg.clone()),
+                    DestinationNetwork::ZoneClients,
+                );
+            }
+
+            true
+        }
+        ToServer::RandomRoll(from_actor_id, name, position, max) => {
+            let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ServerNoticeMessage(
+                ServerNoticeMessage {
+                    message: format_random_roll(name, roll_random(*max), *max),
+                    ..Default::default()
+                },
+            ));
+
+            let mut network = network.lock();
+            let data = data.lock();
+
+            let Some(sender_instance) = data.find_actor_instance(*from_actor_id) else {
+                return true;
+            };
+
+            // Rolls can be seen as far as /say can be heard, including by the one who rolled.
+            let config = get_config();
+            let radius = chat_radius(&config.world, ChatChannelType::Say);
+            for (id, actor) in &sender_instance.actors {
+                if !matches!(actor, NetworkedActor::Player { .. })
+                    || radius.is_some_and(|radius| !can_hear(radius, *position, actor.position()))
+                {
+                    continue;
+                }
+
+                network.send_to_by_actor_id(
+                    *id,
+                    FromServer::PacketSegment(ipc.clone(), *from_actor_id),
+                    DestinationNetwork::ZoneClients,
+                );
+            }
+
+            true
+        }
+        ToServer::Broadcast(message, countdown) => {
+            let mut network = network.lock();
+
+            match countdown {
+                Some(minutes) if *minutes > 0 => {
+                    broadcast_message(&mut network, &format_countdown(message, *minutes));
+
+                    if *minutes > 1 {
+                        let mut data = data.lock();
+                        data.broadcasts.push(ScheduledBroadcast {
+                            message: message.clone(),
+                            minutes_left: minutes - 1,
+                            next_send: Instant::now() + BROADCAST_INTERVAL,
+                        });
+                    }
+                }
+                _ => broadcast_message(&mut network, message),
+            }
 
             true
         }
         ToServer::TellMessageSent(from_actor_id, recipient_actor_id, message_data) => {
             let mut network = network.lock();
 
-            network.send_to_by_actor_id(
+            send_tell(
+                &mut network,
+                *from_actor_id,
                 *recipient_actor_id,
-                FromServer::TellMessageReceived(*from_actor_id, message_data.clone()),
-                DestinationNetwork::ChatClients,
+                message_data,
             );
 
             true
@@ -328,3 +525,150 @@ fn process_debug_commands(
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_broadcast_reaches_everyone() {
+        let mut network = NetworkState::default();
+
+        let receivers: Vec<_> = (1..=2)
+            .map(|id| network.connect_test_client(id, DestinationNetwork::ZoneClients))
+            .collect();
+
+        // This one disconnected before the broadcast went out
+        drop(network.connect_test_client(3, DestinationNetwork::ZoneClients));
+
+        broadcast_message(&mut network, "The server is restarting soon!");
+
+        for mut recv in receivers {
+            assert!(matches!(
+                recv.try_recv(),
+                Ok(FromServer::PacketSegment(ipc, _))
+                    if matches!(&ipc.data, ServerZoneIpcData::ServerNoticeMessage(notice)
+                        if notice.message == "The server is restarting soon!")
+            ));
+        }
+        assert_eq!(network.to_remove, vec![ClientId::from_raw(3)]);
+    }
+
+    #[test]
+    fn test_tell_reaches_recipient() {
+        let mut network = NetworkState::default();
+
+        let mut receivers: Vec<_> = (1..=2)
+            .map(|id| network.connect_test_client(id, DestinationNetwork::ChatClients))
+            .collect();
+
+        let tell = TellMessage {
+            sender_content_id: 1,
+            sender_name: "Test Player".to_string(),
+            message: BString::from("Hello!"),
+            ..Default::default()
+        };
+        send_tell(&mut network, ObjectId(1), ObjectId(2), &tell);
+
+        assert!(matches!(
+            receivers[1].try_recv(),
+            Ok(FromServer::TellMessageReceived(ObjectId(1), received))
+                if received.message == "Hello!" && received.sender_name == "Test Player"
+        ));
+
+        // The sender doesn't get their own tell echoed back
+        assert!(receivers[0].try_recv().is_err());
+
+        // Tells that are too long to fit are cut short
+        let tell = TellMessage {
+            message: BString::from("a".repeat(MESSAGE_MAX_LENGTH * 2)),
+            ..Default::default()
+        };
+        send_tell(&mut network, ObjectId(1), ObjectId(2), &tell);
+
+        assert!(matches!(
+            receivers[1].try_recv(),
+            Ok(FromServer::TellMessageReceived(_, received))
+                if received.message.len() == MESSAGE_MAX_LENGTH - 1
+        ));
+
+        // ...but never in the middle of a character
+        let tell = TellMessage {
+            message: BString::from("é".repeat(MESSAGE_MAX_LENGTH)),
+            ..Default::default()
+        };
+        send_tell(&mut network, ObjectId(1), ObjectId(2), &tell);
+
+        assert!(matches!(
+            receivers[1].try_recv(),
+            Ok(FromServer::TellMessageReceived(_, received))
+                if received.message.to_str().is_ok() && received.message.len() == MESSAGE_MAX_LENGTH - 2
+        ));
+    }
+
+    #[test]
+    fn test_broadcast_countdown() {
+        assert_eq!(
+            format_countdown("Restarting!", 5),
+            "Restarting! (5 minutes remaining)"
+        );
+        assert_eq!(
+            format_countdown("Restarting!", 1),
+            "Restarting! (1 minute remaining)"
+        );
+
+        let mut network = NetworkState::default();
+        let now = Instant::now();
+        let mut broadcasts = vec![ScheduledBroadcast {
+            message: "Restarting!".to_string(),
+            minutes_left: 2,
+            next_send: now,
+        }];
+
+        send_scheduled_broadcasts(&mut network, &mut broadcasts, now);
+        assert_eq!(broadcasts[0].minutes_left, 1);
+
+        // Not due again yet
+        send_scheduled_broadcasts(&mut network, &mut broadcasts, now);
+        assert_eq!(broadcasts[0].minutes_left, 1);
+
+        send_scheduled_broadcasts(&mut network, &mut broadcasts, now + BROADCAST_INTERVAL);
+        assert!(broadcasts.is_empty());
+    }
+
+    #[test]
+    fn test_say_radius() {
+        let config = WorldConfig::default();
+        let radius = chat_radius(&config, ChatChannelType::Say).unwrap();
+
+        let sender = Position(Vec3::ZERO);
+        let just_inside = Position(Vec3::new(radius - 0.1, 0.0, 0.0));
+        let just_outside = Position(Vec3::new(radius + 0.1, 0.0, 0.0));
+
+        assert!(can_hear(radius, sender, just_inside));
+        assert!(!can_hear(radius, sender, just_outside));
+    }
+
+    #[test]
+    fn test_random_roll() {
+        for _ in 0..1000 {
+            let roll = roll_random(999);
+            assert!((1..=999).contains(&roll));
+        }
+
+        assert_eq!(roll_random(1), 1);
+        assert_eq!(roll_random(0), 1);
+
+        assert_eq!(
+            format_random_roll("Test Player", 42, 999),
+            "Test Player rolls a 42 (out of 999)."
+        );
+    }
+
+    #[test]
+    fn test_shout_reaches_zone() {
+        let config = WorldConfig::default();
+        assert!(chat_radius(&config, ChatChannelType::Shout).is_none());
+        assert!(chat_radius(&config, ChatChannelType::Yell).is_none());
+    }
+}
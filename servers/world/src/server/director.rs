@@ -462,7 +462,11 @@ impl DirectorData {
 }
 
 /// Perform any queued director tasks
-pub fn director_tick(network: Arc<Mutex<NetworkState>>, instance: &mut Instance) {
+pub fn director_tick(
+    network: Arc<Mutex<NetworkState>>,
+    lua: Arc<Mutex<KawariLua>>,
+    instance: &mut Instance,
+) {
     let tasks = if let Some(director) = &instance.director {
         director.tasks.clone()
     } else {
@@ -641,6 +645,7 @@ pub fn director_tick(network: Arc<Mutex<NetworkState>>, instance: &mut Instance)
             } => {
                 gain_effect_instance(
                     network.clone(),
+                    lua.clone(),
                     ClientId::default(),
                     instance,
                     *actor_id,
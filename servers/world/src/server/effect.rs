@@ -2,14 +2,15 @@
 
 use std::{sync::Arc, time::Duration};
 
-use mlua::Function;
+use mlua::{Function, Lua};
 use parking_lot::Mutex;
 
 use crate::{
     ClientId, FromServer, PlayerData, StatusEffects, ToServer,
-    lua::{KawariLua, KawariLuaState, LuaContent, LuaPlayer, LuaZone},
+    lua::{KawariLua, KawariLuaState, LuaContent, LuaPlayer, LuaTask, LuaZone},
     server::{
         WorldServer,
+        action::script_notice,
         instance::{Instance, QueuedTaskData},
         network::{DestinationNetwork, NetworkState},
     },
@@ -17,6 +18,7 @@ use crate::{
 };
 use kawari::{
     common::ObjectId,
+    config::get_config,
     ipc::zone::{
         ActorControlCategory, ServerZoneIpcData, ServerZoneIpcSegment, StatusEffect,
         StatusEffectList,
@@ -42,6 +44,7 @@ pub fn handle_effect_messages(
             gain_effect(
                 network.clone(),
                 data.clone(),
+                lua.clone(),
                 *from_id,
                 *from_actor_id,
                 *effect_id,
@@ -151,6 +154,7 @@ fn process_effects_list(
 pub fn gain_effect(
     network: Arc<Mutex<NetworkState>>,
     data: Arc<Mutex<WorldServer>>,
+    lua: Arc<Mutex<KawariLua>>,
     from_id: ClientId,
     from_actor_id: ObjectId,
     effect_id: u16,
@@ -166,6 +170,7 @@ pub fn gain_effect(
 
     gain_effect_instance(
         network,
+        lua,
         from_id,
         instance,
         from_actor_id,
@@ -178,8 +183,10 @@ pub fn gain_effect(
 }
 
 /// Gives the actor a new effect. You can also optionally send an ACS, if needed.
+/// The effect script's onGain is run once it's applied, but not when an effect the actor already has is refreshed.
 pub fn gain_effect_instance(
     network: Arc<Mutex<NetworkState>>,
+    lua: Arc<Mutex<KawariLua>>,
     from_id: ClientId,
     instance: &mut Instance,
     from_actor_id: ObjectId,
@@ -198,6 +205,7 @@ pub fn gain_effect_instance(
     };
 
     let index = status_effects.len() as u8;
+    let refreshed = status_effects.get(effect_id).is_some();
     status_effects.add(effect_id, effect_param, effect_duration);
 
     if inform_players {
@@ -218,6 +226,19 @@ pub fn gain_effect_instance(
         process_effects_list(network.clone(), instance, from_actor_id);
     }
 
+    // Give the effect's Lua script a chance to react, now that it's been applied.
+    if !refreshed {
+        run_effect_script(
+            network.clone(),
+            &lua.lock(),
+            from_id,
+            from_actor_id,
+            effect_id,
+            "onGain",
+            "gained",
+        );
+    }
+
     // Scheduling doesn't make sense when the effect never ends.
     if effect_duration == 0.0 {
         return index;
@@ -237,6 +258,79 @@ pub fn gain_effect_instance(
     index
 }
 
+/// Runs `function_name` from the effect's script, and sends any tasks it queued to the client.
+fn run_effect_script(
+    network: Arc<Mutex<NetworkState>>,
+    lua: &KawariLua,
+    from_id: ClientId,
+    from_actor_id: ObjectId,
+    effect_id: u16,
+    function_name: &str,
+    verb: &str,
+) {
+    let Some(queued_tasks) = call_effect_function(&lua.0, effect_id, function_name) else {
+        tracing::warn!("Effect {effect_id} isn't scripted yet! Ignoring...");
+
+        if get_config().world.verbose_scripting {
+            let mut network = network.lock();
+            let msg = script_notice(
+                from_actor_id,
+                &format!(
+                    "Effect {effect_id} was {verb}, but it doesn't have a script associated with it!"
+                ),
+            );
+            network.send_to(from_id, msg, DestinationNetwork::ZoneClients);
+        }
+        return;
+    };
+
+    // Inform the client of any new Lua tasks
+    let mut network = network.lock();
+    let msg = FromServer::NewTasks(queued_tasks);
+    network.send_to(from_id, msg, DestinationNetwork::ZoneClients);
+}
+
+/// Calls `function_name` from the effect's script, if it defines one. Returns `None` if the effect isn't scripted.
+fn call_effect_function(lua: &Lua, effect_id: u16, function_name: &str) -> Option<Vec<LuaTask>> {
+    let state = lua.app_data_ref::<KawariLuaState>().unwrap();
+    let effect_script = state.effect_scripts.get(&(effect_id as u32))?;
+
+    let mut lua_player = LuaPlayer {
+        player_data: PlayerData::default(),
+        status_effects: StatusEffects::default(),
+        queued_tasks: Vec::new(),
+        zone_data: LuaZone::default(),
+        content_data: LuaContent::default(),
+        base_parameters: BaseParameters::default(),
+    };
+
+    lua.scope(|scope| {
+        let connection_data = scope.create_userdata_ref_mut(&mut lua_player).unwrap();
+
+        // Don't let a previously loaded effect script's function linger
+        lua.globals().set(function_name, mlua::Nil).unwrap();
+
+        lua.load(std::fs::read(effect_script).expect("Failed to locate scripts directory!"))
+            .set_name("@".to_string() + effect_script)
+            .exec()
+            .unwrap();
+
+        // Not every effect needs to react to both being gained and lost
+        if let Some(func) = lua
+            .globals()
+            .get::<Option<Function>>(function_name)
+            .unwrap()
+        {
+            func.call::<()>(connection_data).unwrap();
+        }
+
+        Ok(())
+    })
+    .unwrap();
+
+    Some(lua_player.queued_tasks)
+}
+
 /// Removes an effect from the actor.
 pub fn remove_effect(
     network: Arc<Mutex<NetworkState>>,
@@ -296,48 +390,54 @@ pub fn remove_effect(
     }
 
     // Also run the effect's Lua script in case it wants to do something!
-    {
-        let lua = lua.lock();
-        let state = lua.0.app_data_ref::<KawariLuaState>().unwrap();
-
-        let mut lua_player = LuaPlayer {
-            player_data: PlayerData::default(),
-            status_effects: StatusEffects::default(),
-            queued_tasks: Vec::new(),
-            zone_data: LuaZone::default(),
-            content_data: LuaContent::default(),
-            base_parameters: BaseParameters::default(),
-        };
-
-        let key = effect_id as u32;
-        if let Some(effect_script) = state.effect_scripts.get(&key) {
-            lua.0
-                .scope(|scope| {
-                    let connection_data = scope.create_userdata_ref_mut(&mut lua_player).unwrap();
-
-                    lua.0
-                        .load(
-                            std::fs::read(effect_script)
-                                .expect("Failed to locate scripts directory!"),
-                        )
-                        .set_name("@".to_string() + effect_script)
-                        .exec()
-                        .unwrap();
-
-                    let func: Function = lua.0.globals().get("onLose").unwrap();
-
-                    func.call::<()>(connection_data).unwrap();
-
-                    Ok(())
-                })
-                .unwrap();
-        } else {
-            tracing::warn!("Effect {effect_id} isn't scripted yet! Ignoring...");
-        }
+    run_effect_script(
+        network,
+        &lua.lock(),
+        from_id,
+        from_actor_id,
+        effect_id,
+        "onLose",
+        "lost",
+    );
+}
 
-        // Inform the client of any new Lua tasks
-        let mut network = network.lock();
-        let msg = FromServer::NewTasks(lua_player.queued_tasks);
-        network.send_to(from_id, msg, DestinationNetwork::ZoneClients);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_on_gain_called_once() {
+        let script = std::env::temp_dir().join(format!("kawari-effect-{}.lua", std::process::id()));
+        std::fs::write(
+            &script,
+            "function onGain(player) gained = (gained or 0) + 1 player:gain_effect(4209, 20, 0.0) end",
+        )
+        .unwrap();
+
+        let lua = Lua::new();
+        let mut state = KawariLuaState::default();
+        state
+            .effect_scripts
+            .insert(50, script.to_str().unwrap().to_string());
+        lua.set_app_data(state);
+
+        let tasks = call_effect_function(&lua, 50, "onGain").unwrap();
+        assert_eq!(lua.globals().get::<u32>("gained").unwrap(), 1);
+        assert!(matches!(
+            tasks.as_slice(),
+            [LuaTask::GainStatusEffect {
+                effect_id: 4209,
+                ..
+            }]
+        ));
+
+        // onLose isn't defined, which is fine.
+        assert!(call_effect_function(&lua, 50, "onLose").unwrap().is_empty());
+        assert_eq!(lua.globals().get::<u32>("gained").unwrap(), 1);
+
+        // Effects without a script are skipped.
+        assert!(call_effect_function(&lua, 51, "onGain").is_none());
+
+        std::fs::remove_file(script).unwrap();
     }
 }
@@ -8,20 +8,20 @@ use std::{
 use crate::{
     ClientId, GameData, Navmesh, StatusEffects,
     server::{
-        action::cancel_action,
+        action::{AreaShape, cancel_action},
         actor::{NetworkedActor, NpcState},
         director::DirectorData,
         network::NetworkState,
+        npc_behavior::ActorPath,
         zone::Zone,
     },
-    zone_connection::{BaseParameters, TeleportQuery},
 };
 use kawari::{
-    common::{DistanceRange, ENTRANCE_CIRCLE_IDS, ObjectId, Position},
+    common::{ENTRANCE_CIRCLE_IDS, ObjectId, Position, timestamp_secs},
     config::{FilesystemConfig, get_config},
     ipc::zone::{
-        ActionRequest, Conditions, ServerZoneIpcSegment, SpawnNpc, SpawnObject, SpawnPlayer,
-        SpawnTreasure,
+        ActionRequest, BattleNpcSubKind, ObjectKind, ServerZoneIpcSegment, SpawnNpc, SpawnObject,
+        SpawnPlayer, SpawnTreasure,
     },
 };
 use parking_lot::Mutex;
@@ -81,6 +81,11 @@ pub enum QueuedTaskData {
     },
     /// Reset a player's action combo status.
     ResetCombo,
+    /// Respawn a previously despawned NPC.
+    RespawnNpc {
+        spawn: SpawnNpc,
+        respawn_delay: Duration,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -105,6 +110,10 @@ pub struct Instance {
     pub navmesh: Navmesh,
     pub zone: Zone,
     pub weather_id: u16,
+    /// The weather period that `weather_id` was calculated for.
+    pub weather_period: u32,
+    /// If a GM forced the weather, in which case it stays as-is until they clear it.
+    pub forced_weather: bool,
     pub content_finder_condition_id: u16,
     /// If Some, then this is the path of the navmesh we need to generate.
     pub generate_navmesh: NavmeshGenerationStep,
@@ -113,6 +122,8 @@ pub struct Instance {
     /// Director for this instance.
     pub director: Option<DirectorData>,
     pub enemy_ai_disabled: bool,
+    /// NPCs that are walking along waypoints given to them by a script.
+    pub actor_paths: HashMap<ObjectId, ActorPath>,
 }
 
 impl Instance {
@@ -120,6 +131,7 @@ impl Instance {
         let mut instance = Instance {
             zone: Zone::load(game_data, id),
             weather_id: game_data.get_weather(id as u32).unwrap_or_default() as u16,
+            weather_period: GameData::get_weather_period(timestamp_secs()),
             ..Default::default()
         };
 
@@ -158,8 +170,19 @@ impl Instance {
         }
 
         // Load initial NPCs into instance
+        let respawn_delay = if config.world.enemy_respawn_time > 0 {
+            Some(Duration::from_secs(config.world.enemy_respawn_time))
+        } else {
+            None
+        };
         for npc in instance.zone.get_npcs(game_data) {
-            instance.insert_npc(ObjectId(fastrand::u32(..)), npc);
+            let actor_id = ObjectId(fastrand::u32(..));
+            let is_enemy = npc.common.object_kind == ObjectKind::BattleNpc(BattleNpcSubKind::Enemy);
+
+            instance.insert_npc(actor_id, npc);
+            if is_enemy {
+                instance.set_respawn_delay(actor_id, respawn_delay);
+            }
         }
 
         instance
@@ -228,10 +251,18 @@ impl Instance {
                 newly_hated_actor: None,
                 currently_invulnerable: false,
                 status_effects: StatusEffects::default(),
+                respawn_delay: None,
             },
         );
     }
 
+    /// Sets how long this NPC takes to respawn after it's despawned.
+    pub fn set_respawn_delay(&mut self, id: ObjectId, delay: Option<Duration>) {
+        if let Some(NetworkedActor::Npc { respawn_delay, .. }) = self.find_actor_mut(id) {
+            *respawn_delay = delay;
+        }
+    }
+
     pub fn generate_actor_id() -> ObjectId {
         // TODO: ensure we don't collide with another actor
         ObjectId(fastrand::u32(..))
@@ -256,28 +287,44 @@ impl Instance {
             .collect()
     }
 
+    /// Finds all (alive) players and NPCs inside `shape`, when it's placed at `origin` facing `rotation`. Like retail, the Y axis is ignored.
+    pub fn actors_in_area(
+        &self,
+        origin: Position,
+        rotation: f32,
+        shape: AreaShape,
+    ) -> Vec<ObjectId> {
+        self.actors
+            .iter()
+            .filter(|(_, y)| {
+                matches!(y, NetworkedActor::Player { .. })
+                    || matches!(y, NetworkedActor::Npc { .. })
+            })
+            .filter(|(_, y)| y.get_common_spawn().health_points > 0)
+            .filter(|(_, y)| shape.contains(origin.0, rotation, y.position().0))
+            .map(|(x, _)| *x)
+            .collect()
+    }
+
+    /// Whether the weather has to be recalculated at `unix_seconds`, because it moved into a new period.
+    pub fn needs_weather_update(&self, unix_seconds: u32) -> bool {
+        !self.forced_weather && GameData::get_weather_period(unix_seconds) != self.weather_period
+    }
+
+    /// Sets the weather calculated for `unix_seconds`, returning true if it changed.
+    pub fn update_weather(&mut self, weather_id: u16, unix_seconds: u32) -> bool {
+        self.weather_period = GameData::get_weather_period(unix_seconds);
+
+        std::mem::replace(&mut self.weather_id, weather_id) != weather_id
+    }
+
     pub fn insert_empty_actor(&mut self, actor_id: ObjectId) {
         if self.actors.contains_key(&actor_id) {
             return;
         }
 
-        self.actors.insert(
-            actor_id,
-            NetworkedActor::Player {
-                spawn: SpawnPlayer::default(),
-                status_effects: StatusEffects::default(),
-                teleport_query: TeleportQuery::default(),
-                distance_range: DistanceRange::Normal,
-                conditions: Conditions::default(),
-                executing_gimmick_jump: false,
-                inside_instance_exit: false,
-                parameters: BaseParameters::default(),
-                dueling_opponent_id: ObjectId::default(),
-                remove_cooldowns: false,
-                last_combo_action: 0,
-                combo_sequence: 0,
-            },
-        );
+        self.actors
+            .insert(actor_id, NetworkedActor::new_player(SpawnPlayer::default()));
     }
 
     pub fn insert_object(&mut self, actor_id: ObjectId, object: SpawnObject) {
@@ -8,45 +8,48 @@ use std::{
     sync::Arc,
     time::{Duration, Instant},
 };
-use tokio::sync::mpsc::Receiver;
+use tokio::sync::mpsc::{Receiver, Sender};
 
 use crate::{
     GameData, Navmesh, SpawnAllocator,
     lua::KawariLua,
     server::{
         action::{
-            execute_action, execute_enemy_action, handle_action_messages, kill_actor,
+            execute_action, execute_enemy_action, handle_action_messages, kill_actor, set_hp_mp,
             update_actor_hp_mp,
         },
-        actor::{NetworkedActor, NpcState},
-        chat::handle_chat_messages,
+        actor::{NetworkedActor, NpcState, respawned_npc, revive_actor},
+        chat::{ScheduledBroadcast, handle_chat_messages, send_scheduled_broadcasts},
         director::{DirectorData, director_tick, handle_director_messages},
         effect::{handle_effect_messages, remove_effect, send_effects_list},
         instance::{Instance, NavmeshGenerationStep, QueuedTaskData},
         linkshell::handle_linkshell_messages,
         network::{DestinationNetwork, NetworkState},
+        npc_behavior::ActorPath,
         party::{
             NUM_TARGET_SIGNS, get_party_id_from_actor_id, handle_party_messages,
             send_party_positions, update_party_position, update_party_waymark,
             update_party_waymarks,
         },
-        social::handle_social_messages,
+        social::{handle_social_messages, list_online_players},
         zone::{
-            MapGimmick, change_zone_to_player, change_zone_warp_to_entrance,
-            change_zone_warp_to_pop_range, handle_zone_messages,
+            MapGimmick, Zone, can_summon, change_zone_to_player, change_zone_warp_to_entrance,
+            change_zone_warp_to_pop_range, enter_zone_jump, handle_zone_messages,
         },
     },
+    zone_connection::ReviveKind,
 };
 use kawari::{
     common::{
         CharacterMode, DEAD_DESPAWN_TIME, HandlerId, HandlerType, InvisibilityFlags,
         MAX_SPAWNED_ACTORS, MAX_SPAWNED_OBJECTS, ObjectId, ObjectTypeId, ObjectTypeKind, Position,
-        SharedGroupTimelineState, determine_initial_pop_range, euler_to_direction, is_private_area,
+        SharedGroupTimelineState, euler_to_direction, is_private_area, timestamp_secs,
     },
     config::{FilesystemConfig, get_config},
     ipc::zone::{
-        ActorControlCategory, ClientTriggerCommand, Condition, Conditions, EnmityList, Hater,
-        HaterList, PlayerEnmity, ServerZoneIpcData, ServerZoneIpcSegment, WarpType, WaymarkPreset,
+        ActorControlCategory, ClientTriggerCommand, Condition, Conditions, EnmityList,
+        GameMasterRank, Hater, HaterList, PlayerEnmity, ServerZoneIpcData, ServerZoneIpcSegment,
+        SpawnObject, WarpType, WaymarkPreset,
     },
 };
 
@@ -65,6 +68,7 @@ pub use party::{Party, PartyMember};
 mod npc_behavior;
 mod social;
 mod zone;
+pub use zone::ZoneCache;
 
 #[derive(Default, Debug, Clone)]
 struct ClientState {
@@ -82,6 +86,8 @@ impl ClientState {
 #[derive(Default, Debug)]
 struct WorldServer {
     instances: Vec<Instance>,
+    /// Announcements that are still counting down.
+    broadcasts: Vec<ScheduledBroadcast>,
     // TODO: Eventually remove these once we can reliably and ergonomically run misc. tasks on slower intervals!
     rested_exp_counter: i32,
     party_positions_counter: i32,
@@ -218,18 +224,25 @@ impl WorldServer {
         self.instances.last_mut()
     }
 
-    fn find_actor_by_name(&self, name: &str) -> ObjectId {
+    fn find_actor_by_name(&self, name: &str) -> Option<ObjectId> {
         for instance in &self.instances {
             for (id, actor) in &instance.actors {
                 if let Some(spawn) = actor.get_player_spawn()
                     && spawn.common.name == name
                 {
-                    return *id;
+                    return Some(*id);
                 }
             }
         }
 
-        ObjectId::default()
+        None
+    }
+
+    /// Returns the GM rank of the player with this actor ID, if they're currently spawned in.
+    fn find_player_gm_rank(&self, actor_id: ObjectId) -> Option<GameMasterRank> {
+        let instance = self.find_actor_instance(actor_id)?;
+        let spawn = instance.find_actor(actor_id)?.get_player_spawn()?;
+        Some(spawn.gm_rank)
     }
 
     /// Removes instances without players in them, which wastes resources.
@@ -324,25 +337,44 @@ fn set_shared_group_timeline_state(
         });
     }
 
-    // Update internal data model for new spawns
-    {
-        let Some(actor) = instance.find_actor_mut(from_actor_id) else {
-            return;
-        };
+    let Some(NetworkedActor::Object { object }) = instance.find_actor_mut(from_actor_id) else {
+        return;
+    };
+    let category = update_eobj_state(object, state);
 
-        let NetworkedActor::Object { object } = actor else {
-            return;
-        };
+    // Inform actors
+    network.send_ac_in_range_inclusive_instance(instance, from_actor_id, category);
+}
+
+/// Updates the internal data model of `object` so new spawns see `state`, and returns the ActorControl to inform everyone else with.
+fn update_eobj_state(
+    object: &mut SpawnObject,
+    state: SharedGroupTimelineState,
+) -> ActorControlCategory {
+    object.args1 = state.bits();
+
+    ActorControlCategory::SetSharedGroupTimelineState { state }
+}
 
-        object.args1 = state.bits();
+/// Tells every player in `instance` about its current weather.
+fn send_weather(network: &mut NetworkState, instance: &Instance) {
+    for (id, actor) in &instance.actors {
+        if let NetworkedActor::Player { .. } = actor {
+            network.send_to_by_actor_id(
+                *id,
+                FromServer::WeatherChanged(instance.weather_id),
+                DestinationNetwork::ZoneClients,
+            );
+        }
     }
+}
 
-    // Inform actors
-    network.send_ac_in_range_inclusive_instance(
-        instance,
-        from_actor_id,
-        ActorControlCategory::SetSharedGroupTimelineState { state },
-    );
+/// How long to wait for the client to ask to go through a zone line, before we send them through it ourselves.
+const ZONE_LINE_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Whether a player that walked into a zone line at `entered_at` still hasn't been sent through it by the client.
+fn zone_line_jump_due(entered_at: Instant, now: Instant) -> bool {
+    now.saturating_duration_since(entered_at) >= ZONE_LINE_GRACE_PERIOD
 }
 
 fn server_logic_tick(
@@ -353,6 +385,29 @@ fn server_logic_tick(
 ) {
     let mut actors_to_update_hp_mp = Vec::new();
     let mut actors_to_fake_zone_jump = Vec::new();
+    let mut actors_to_zone_jump = Vec::new();
+    let max_spawn_distance = get_config().world.max_spawn_distance;
+    let now = timestamp_secs();
+    let tick_instant = Instant::now();
+
+    // Work out any new weather up front, since the game data can't be locked while holding the server data.
+    let zones_needing_weather: Vec<u16> = data
+        .lock()
+        .instances
+        .iter()
+        .filter(|instance| instance.needs_weather_update(now))
+        .map(|instance| instance.zone.id)
+        .collect();
+    let mut new_weathers = HashMap::new();
+    if !zones_needing_weather.is_empty() {
+        let mut game_data = gamedata.lock();
+        for zone_id in zones_needing_weather {
+            let weather_id = game_data
+                .get_weather_at(zone_id as u32, now)
+                .unwrap_or_default() as u16;
+            new_weathers.insert(zone_id, weather_id);
+        }
+    }
 
     {
         let mut data = data.lock();
@@ -361,6 +416,12 @@ fn server_logic_tick(
 
         data.cleanup_dead_instances();
 
+        // Repeat any broadcasts that are counting down
+        if !data.broadcasts.is_empty() {
+            let mut network = network.lock();
+            send_scheduled_broadcasts(&mut network, &mut data.broadcasts, Instant::now());
+        }
+
         // Send a periodic update to all parties about where their members are in the world.
         // TODO: On retail this is sent once every 5 seconds, so sending this at a slower interval would be more ideal.
         if party_positions_counter == 0 {
@@ -369,6 +430,18 @@ fn server_logic_tick(
         }
 
         for instance in &mut data.instances {
+            // Weather changes every eight Eorzean hours
+            if instance.needs_weather_update(now)
+                && let Some(weather_id) = new_weathers.get(&instance.zone.id)
+                && instance.update_weather(*weather_id, now)
+            {
+                let mut network = network.lock();
+                send_weather(&mut network, instance);
+            }
+
+            // The logic tick runs every half-second
+            npc_behavior::follow_actor_paths(network.clone(), instance, 0.5);
+
             let mut haters = HashMap::new();
             npc_behavior::npc_behavior(
                 network.clone(),
@@ -381,6 +454,7 @@ fn server_logic_tick(
             let mut actors_now_gimmick_jumping = Vec::new();
             let mut actors_now_inside_instance_exits = Vec::new();
             let mut actors_now_outside_instance_entrances = Vec::new();
+            let mut actors_zone_line_changed = Vec::new();
 
             // Player area stuffs
             for (id, actor) in &instance.actors {
@@ -389,6 +463,8 @@ fn server_logic_tick(
                     conditions,
                     executing_gimmick_jump,
                     inside_instance_exit: inside_instance_entrance,
+                    inside_zone_line,
+                    entered_zone_line_at,
                     ..
                 } = actor
                 else {
@@ -532,6 +608,18 @@ fn server_logic_tick(
                     actors_now_outside_instance_entrances.push(*id);
                 }
 
+                // Walking into a zone line takes you through it. The client usually asks for this itself, so only do it if it hasn't after a while.
+                let zone_line = instance.zone.find_zone_line(actor.position().0);
+                if zone_line.is_some() != *inside_zone_line {
+                    actors_zone_line_changed.push((*id, zone_line.is_some()));
+                } else if let Some(exit_box) = zone_line
+                    && let Some(entered_at) = entered_zone_line_at
+                    && !executing_gimmick_jump
+                    && zone_line_jump_due(*entered_at, tick_instant)
+                {
+                    actors_to_zone_jump.push((*id, exit_box));
+                }
+
                 let is_in_duel_area = overlapping_ranges.iter().filter(|x| x.duel).count() > 0;
                 let has_duel_condition = conditions.has_condition(Condition::InDuelingArea);
 
@@ -590,7 +678,7 @@ fn server_logic_tick(
                     }
 
                     // If the actor _should_ be in the view of the other.
-                    let in_range = actor.in_range_of(other_actor);
+                    let in_range = actor.in_spawn_range_of(other_actor, max_spawn_distance);
                     let has_been_spawned = state.has_spawned(*other_id);
 
                     // There are four states:
@@ -681,6 +769,20 @@ fn server_logic_tick(
                 *inside_instance_exit = false;
             }
 
+            for (actor, inside) in &actors_zone_line_changed {
+                let Some(NetworkedActor::Player {
+                    inside_zone_line,
+                    entered_zone_line_at,
+                    ..
+                }) = instance.find_actor_mut(*actor)
+                else {
+                    continue;
+                };
+
+                *inside_zone_line = *inside;
+                *entered_zone_line_at = inside.then_some(tick_instant);
+            }
+
             // NOTE: I know this isn't retail accurate
             for (id, actor) in &mut instance.actors {
                 if let NetworkedActor::Player { spawn, .. } = actor {
@@ -767,7 +869,7 @@ fn server_logic_tick(
             }
 
             // Process any director tasks for this instance.
-            director_tick(network.clone(), instance);
+            director_tick(network.clone(), lua.clone(), instance);
         }
         // Ensure the rested EXP counter only happens every 10 seconds.
         data.rested_exp_counter += 1;
@@ -807,12 +909,31 @@ fn server_logic_tick(
             0,
         );
     }
+
+    for (id, exit_box) in actors_to_zone_jump {
+        let mut game_data = gamedata.lock();
+        let mut data = data.lock();
+        let mut network = network.lock();
+        let Some(from_id) = network.find_by_actor(id) else {
+            continue;
+        };
+        enter_zone_jump(
+            &mut data,
+            &mut network,
+            &mut game_data,
+            from_id,
+            id,
+            exit_box,
+            None,
+        );
+    }
 }
 
 pub async fn server_main_loop(
     game_data: GameData,
     parties: HashMap<u64, Party>,
     linkshells: HashMap<u64, Vec<ObjectId>>,
+    send: Sender<ToServer>,
     mut recv: Receiver<ToServer>,
 ) -> Result<(), std::io::Error> {
     let data = Arc::new(Mutex::new(WorldServer::default()));
@@ -824,6 +945,15 @@ pub async fn server_main_loop(
     let game_data = Arc::new(Mutex::new(game_data));
     let lua = Arc::new(Mutex::new(KawariLua::new()));
 
+    // Read these zones now, so the first player to enter them doesn't have to wait
+    {
+        let mut game_data = game_data.lock();
+        for zone_id in &get_config().world.preload_zones {
+            tracing::info!("Preloading zone {zone_id}...");
+            Zone::load(&mut game_data, *zone_id);
+        }
+    }
+
     // Run Init.lua and set up other Lua state
     {
         let mut lua = lua.lock();
@@ -932,11 +1062,32 @@ pub async fn server_main_loop(
                                 }
                             }
                             QueuedTaskData::DeadDespawn { actor_id } => {
-                                // despawn
+                                // The main loop takes care of actually removing it, and bringing it back later.
+                                if send
+                                    .send(ToServer::ActorDespawned(*actor_id))
+                                    .await
+                                    .is_err()
+                                {
+                                    tracing::warn!(
+                                        "Main loop has shut down, {actor_id} won't despawn!"
+                                    );
+                                }
+                            }
+                            QueuedTaskData::RespawnNpc {
+                                spawn,
+                                respawn_delay,
+                            } => {
                                 let mut data = data.lock();
-                                if let Some(instance) = data.find_actor_instance_mut(*actor_id) {
-                                    let mut network = network.lock();
-                                    network.remove_actor(instance, *actor_id);
+                                if let Some(instance) = data.instances.get_mut(*instance_index) {
+                                    // Prefer the layout's copy, since the old one may have wandered off.
+                                    let spawn = instance
+                                        .zone
+                                        .get_battle_npc(spawn.common.layout_id)
+                                        .unwrap_or_else(|| spawn.clone());
+
+                                    let actor_id = Instance::generate_actor_id();
+                                    instance.insert_npc(actor_id, respawned_npc(&spawn));
+                                    instance.set_respawn_delay(actor_id, Some(*respawn_delay));
                                 }
                             }
                             QueuedTaskData::CastEventAction { target } => {
@@ -1101,7 +1252,7 @@ pub async fn server_main_loop(
                     zone_id,
                     position,
                     rotation,
-                    city_state_opening,
+                    spawn_pop_range,
                 ) => {
                     tracing::info!("Player {from_id:?} is now spawning into {zone_id}....");
 
@@ -1125,14 +1276,11 @@ pub async fn server_main_loop(
 
                     let exit_position;
                     let exit_rotation;
-                    if let Some(city_state) = city_state_opening {
-                        // If spawning for the initial opening, we need to spawn them at this pop range *as soon as possible*
+                    if let Some(pop_range_id) = spawn_pop_range {
+                        // If spawning for the initial opening (or to a safe area), we need to spawn them at this pop range *as soon as possible*
                         // The reason being is that this helps loading times and the initial camera rotation.
                         // Doing it in the opening Lua script happens far too late, as EnterTerritoryEvent will only be fired after ZoneInit is sent.
-                        if let Some((object, _)) = instance
-                            .zone
-                            .find_pop_range(determine_initial_pop_range(city_state))
-                        {
+                        if let Some((object, _)) = instance.zone.find_pop_range(*pop_range_id) {
                             let (_, rotation, translation) =
                                 Affine3A::from(object.transform).to_scale_rotation_translation();
                             exit_position = Position(translation);
@@ -2211,6 +2359,36 @@ pub async fn server_main_loop(
                         DestinationNetwork::ZoneClients,
                     );
                 }
+                ToServer::ActorDespawned(actor_id) => {
+                    let mut data = data.lock();
+                    let Some(instance) = data.find_actor_instance_mut(actor_id) else {
+                        continue;
+                    };
+
+                    // Overworld enemies come back after a while, but content is left alone.
+                    if instance.director.is_none()
+                        && let Some(NetworkedActor::Npc {
+                            spawn,
+                            respawn_delay: Some(respawn_delay),
+                            ..
+                        }) = instance.find_actor(actor_id)
+                    {
+                        let spawn = spawn.clone();
+                        let respawn_delay = *respawn_delay;
+                        instance.insert_task(
+                            ClientId::default(),
+                            ObjectId::default(),
+                            respawn_delay,
+                            QueuedTaskData::RespawnNpc {
+                                spawn,
+                                respawn_delay,
+                            },
+                        );
+                    }
+
+                    let mut network = network.lock();
+                    network.remove_actor(instance, actor_id);
+                }
                 ToServer::Kill(_from_id, from_actor_id) => {
                     let mut data = data.lock();
                     let Some(instance) = data.find_actor_instance_mut(from_actor_id) else {
@@ -2228,10 +2406,58 @@ pub async fn server_main_loop(
                         continue;
                     };
 
-                    actor.get_common_spawn_mut().health_points = hp;
+                    // Dropping to zero HP is handled like any other death.
+                    set_hp_mp(actor.get_common_spawn_mut(), Some(hp), None);
 
                     update_actor_hp_mp(network.clone(), instance, from_actor_id);
                 }
+                ToServer::SetEObjState(from_actor_id, base_id, state) => {
+                    let mut network = network.lock();
+                    let mut data = data.lock();
+                    let Some(instance) = data.find_actor_instance_mut(from_actor_id) else {
+                        continue;
+                    };
+
+                    let Some(object_id) = instance.find_object_by_eobj_id(base_id) else {
+                        tracing::warn!("Failed to find eobj {base_id} to change its state!");
+                        continue;
+                    };
+
+                    let Some(NetworkedActor::Object { object }) =
+                        instance.find_actor_mut(object_id)
+                    else {
+                        continue;
+                    };
+                    let category = update_eobj_state(
+                        object,
+                        SharedGroupTimelineState::from_bits_retain(state),
+                    );
+
+                    network.send_ac_in_range_instance(instance, object_id, category);
+                }
+                ToServer::ForceWeather(from_actor_id, weather_id) => {
+                    let mut game_data = game_data.lock();
+                    let mut data = data.lock();
+                    let mut network = network.lock();
+                    let Some(instance) = data.find_actor_instance_mut(from_actor_id) else {
+                        continue;
+                    };
+
+                    instance.forced_weather = weather_id.is_some();
+                    match weather_id {
+                        Some(weather_id) => instance.weather_id = weather_id,
+                        None => {
+                            let now = timestamp_secs();
+                            let weather_id = game_data
+                                .get_weather_at(instance.zone.id as u32, now)
+                                .unwrap_or_default()
+                                as u16;
+                            instance.update_weather(weather_id, now);
+                        }
+                    }
+
+                    send_weather(&mut network, instance);
+                }
                 ToServer::SetMP(_from_id, from_actor_id, mp) => {
                     let mut data = data.lock();
                     let Some(instance) = data.find_actor_instance_mut(from_actor_id) else {
@@ -2242,7 +2468,21 @@ pub async fn server_main_loop(
                         continue;
                     };
 
-                    actor.get_common_spawn_mut().resource_points = mp;
+                    set_hp_mp(actor.get_common_spawn_mut(), None, Some(mp));
+
+                    update_actor_hp_mp(network.clone(), instance, from_actor_id);
+                }
+                ToServer::SetHpMp(_from_id, from_actor_id, hp, mp) => {
+                    let mut data = data.lock();
+                    let Some(instance) = data.find_actor_instance_mut(from_actor_id) else {
+                        continue;
+                    };
+
+                    let Some(actor) = instance.find_actor_mut(from_actor_id) else {
+                        continue;
+                    };
+
+                    set_hp_mp(actor.get_common_spawn_mut(), Some(hp), Some(mp));
 
                     update_actor_hp_mp(network.clone(), instance, from_actor_id);
                 }
@@ -2263,6 +2503,12 @@ pub async fn server_main_loop(
                         actor.get_common_spawn_mut().max_resource_points = new_parameters.mp as u16;
                         actor.get_common_spawn_mut().class_job = class_job;
 
+                        // Don't let the current HP/MP exceed the new maximum, e.g. after a level sync or changing classjobs.
+                        let common = actor.get_common_spawn_mut();
+                        common.health_points = common.health_points.min(common.max_health_points);
+                        common.resource_points =
+                            common.resource_points.min(common.max_resource_points);
+
                         if let NetworkedActor::Player { parameters, .. } = actor {
                             *parameters = new_parameters.clone();
                         }
@@ -2285,6 +2531,9 @@ pub async fn server_main_loop(
                     );
                 }
                 ToServer::ReloadScripts => {
+                    // Drop-ins may have changed too, so new instances have to read their zone again
+                    game_data.lock().zone_cache.clear();
+
                     let mut lua = lua.lock();
                     if let Err(err) = lua.init(game_data.clone()) {
                         tracing::warn!("Failed to load Init.lua: {:?}", err);
@@ -2375,12 +2624,70 @@ pub async fn server_main_loop(
 
                     *remove_cooldowns = true;
                 }
+                ToServer::SetGmInvisible(actor_id, invisible) => {
+                    let mut data = data.lock();
+
+                    let Some(instance) = data.find_actor_instance_mut(actor_id) else {
+                        continue;
+                    };
+
+                    let Some(actor) = instance.find_actor_mut(actor_id) else {
+                        continue;
+                    };
+
+                    let NetworkedActor::Player { gm_invisible, .. } = actor else {
+                        continue;
+                    };
+
+                    *gm_invisible = invisible;
+                }
+                ToServer::SetRecastTimers(actor_id, timers) => {
+                    let mut data = data.lock();
+
+                    let Some(instance) = data.find_actor_instance_mut(actor_id) else {
+                        continue;
+                    };
+
+                    let Some(actor) = instance.find_actor_mut(actor_id) else {
+                        continue;
+                    };
+
+                    let NetworkedActor::Player { recast_timers, .. } = actor else {
+                        continue;
+                    };
+
+                    *recast_timers = timers;
+                }
+                ToServer::ListOnlinePlayers(from_id, filter) => {
+                    let players = {
+                        let mut game_data = game_data.lock();
+                        let data = data.lock();
+
+                        let requester_rank = data.find_player_gm_rank(from_id).unwrap_or_default();
+                        list_online_players(
+                            &data.instances,
+                            &mut game_data,
+                            requester_rank,
+                            filter.as_deref(),
+                        )
+                    };
+
+                    let mut network = network.lock();
+                    network.send_to_by_actor_id(
+                        from_id,
+                        FromServer::OnlinePlayers(players),
+                        DestinationNetwork::ZoneClients,
+                    );
+                }
                 ToServer::Jump(from_id, name) => {
                     let mut data = data.lock();
                     let mut network = network.lock();
                     let mut game_data = game_data.lock();
 
-                    let to_actor_id = data.find_actor_by_name(&name);
+                    let Some(to_actor_id) = data.find_actor_by_name(&name) else {
+                        tracing::warn!("Can't jump to {name}, they aren't online!");
+                        continue;
+                    };
 
                     change_zone_to_player(
                         &mut network,
@@ -2395,7 +2702,20 @@ pub async fn server_main_loop(
                     let mut network = network.lock();
                     let mut game_data = game_data.lock();
 
-                    let actor_id = data.find_actor_by_name(&name);
+                    let Some(actor_id) = data.find_actor_by_name(&name) else {
+                        tracing::warn!("Can't call {name}, they aren't online!");
+                        continue;
+                    };
+
+                    let summoner_rank = data.find_player_gm_rank(from_actor_id).unwrap_or_default();
+                    let target_rank = data.find_player_gm_rank(actor_id).unwrap_or_default();
+                    if !can_summon(summoner_rank, target_rank) {
+                        tracing::warn!(
+                            "{from_actor_id} ({summoner_rank}) tried to call {name} ({target_rank}) without a sufficient GM rank!"
+                        );
+                        continue;
+                    }
+
                     if let Some(client_id) = network.find_by_actor(actor_id) {
                         change_zone_to_player(
                             &mut network,
@@ -2422,6 +2742,68 @@ pub async fn server_main_loop(
                         );
                     }
                 }
+                ToServer::Revive(_from_id, from_actor_id, kind) => {
+                    let mut data = data.lock();
+                    let Some(instance) = data.find_actor_instance_mut(from_actor_id) else {
+                        continue;
+                    };
+
+                    let Some(actor) = instance.find_actor_mut(from_actor_id) else {
+                        continue;
+                    };
+
+                    // You can only be revived if you're actually dead.
+                    let common = actor.get_common_spawn_mut();
+                    if common.mode != CharacterMode::Dead {
+                        continue;
+                    }
+                    revive_actor(common, kind);
+
+                    {
+                        let mut network = network.lock();
+                        set_character_mode(
+                            instance,
+                            &mut network,
+                            from_actor_id,
+                            CharacterMode::Normal,
+                            0,
+                        );
+
+                        if kind == ReviveKind::Raise {
+                            network.send_ac_in_range_inclusive_instance(
+                                instance,
+                                from_actor_id,
+                                ActorControlCategory::ZoneIn {
+                                    warp_finish_anim: 0,
+                                    raise_anim: 1,
+                                    unk1: 0,
+                                },
+                            );
+                        }
+                    }
+
+                    update_actor_hp_mp(network.clone(), instance, from_actor_id);
+                }
+                ToServer::SpawnNpc(from_actor_id, spawn) => {
+                    let mut data = data.lock();
+
+                    let Some(instance) = data.find_actor_instance_mut(from_actor_id) else {
+                        continue;
+                    };
+
+                    instance.insert_npc(Instance::generate_actor_id(), spawn);
+                }
+                ToServer::MoveActor(actor_id, waypoints, speed) => {
+                    let mut data = data.lock();
+
+                    let Some(instance) = data.find_actor_instance_mut(actor_id) else {
+                        continue;
+                    };
+
+                    instance
+                        .actor_paths
+                        .insert(actor_id, ActorPath::new(&waypoints, speed));
+                }
                 ToServer::FatalError(err) => return Err(err),
                 _ => {
                     tracing::error!("Received a ToServer message we don't handle yet: {msg:#?}");
@@ -2479,3 +2861,72 @@ pub async fn server_main_loop(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weather_cycle_boundary() {
+        let period_start = 1400 * 1000;
+        let instance = Instance {
+            weather_period: GameData::get_weather_period(period_start),
+            ..Default::default()
+        };
+
+        // Still in the same period
+        assert!(!instance.needs_weather_update(period_start + 1399));
+
+        // Crossed into the next one
+        assert!(instance.needs_weather_update(period_start + 1400));
+
+        // Unless a GM forced it
+        let instance = Instance {
+            forced_weather: true,
+            ..instance
+        };
+        assert!(!instance.needs_weather_update(period_start + 1400));
+    }
+
+    #[test]
+    fn test_zone_line_jump_due() {
+        let entered_at = Instant::now();
+
+        // The client still has time to ask for it
+        assert!(!zone_line_jump_due(entered_at, entered_at));
+        assert!(!zone_line_jump_due(
+            entered_at,
+            entered_at + ZONE_LINE_GRACE_PERIOD - Duration::from_millis(1)
+        ));
+
+        // It didn't, so we do it
+        assert!(zone_line_jump_due(
+            entered_at,
+            entered_at + ZONE_LINE_GRACE_PERIOD
+        ));
+    }
+
+    #[test]
+    fn test_toggle_eobj_state() {
+        let mut object = SpawnObject::default();
+
+        let category = update_eobj_state(&mut object, SharedGroupTimelineState::TIMELINE_1);
+        assert_eq!(object.args1, 1);
+        assert_eq!(
+            category,
+            ActorControlCategory::SetSharedGroupTimelineState {
+                state: SharedGroupTimelineState::TIMELINE_1
+            }
+        );
+
+        // And closing it again
+        let category = update_eobj_state(&mut object, SharedGroupTimelineState::NONE);
+        assert_eq!(object.args1, 0);
+        assert_eq!(
+            category,
+            ActorControlCategory::SetSharedGroupTimelineState {
+                state: SharedGroupTimelineState::NONE
+            }
+        );
+    }
+}
@@ -105,6 +105,70 @@ impl NetworkState {
         }
     }
 
+    /// Spawns a player who just loaded into `instance` for everyone nearby, and everyone nearby for them.
+    pub fn spawn_entered_player(
+        &mut self,
+        instance: &Instance,
+        actor_id: ObjectId,
+        max_spawn_distance: f32,
+    ) {
+        let Some(actor) = instance.find_actor(actor_id) else {
+            return;
+        };
+        let Some(entered_id) = self.find_by_actor(actor_id) else {
+            return;
+        };
+
+        let mut visible_to_entered = Vec::new();
+        for (id, (handle, state)) in &mut self.clients {
+            let id = *id;
+
+            // Don't tell itself
+            if handle.actor_id == actor_id {
+                continue;
+            }
+
+            // Skip any clients not in this instance, or that haven't finished loading yet
+            let Some(other_actor) = instance.find_actor(handle.actor_id) else {
+                continue;
+            };
+            if !other_actor.is_valid() {
+                continue;
+            }
+
+            if actor.in_spawn_range_of(other_actor, max_spawn_distance) {
+                visible_to_entered.push(handle.actor_id);
+            }
+
+            if !other_actor.in_spawn_range_of(actor, max_spawn_distance)
+                || state.has_spawned(actor_id)
+            {
+                continue;
+            }
+
+            if let Some(msg) = Self::spawn_existing_actor_message(state, actor_id, actor)
+                && handle.send(msg).is_err()
+            {
+                self.to_remove.push(id);
+            }
+        }
+
+        let (handle, state) = self.clients.get_mut(&entered_id).unwrap();
+        for other_id in visible_to_entered {
+            if state.has_spawned(other_id) {
+                continue;
+            }
+
+            if let Some(msg) =
+                Self::spawn_existing_actor_message(state, other_id, &instance.actors[&other_id])
+                && handle.send(msg).is_err()
+            {
+                self.to_remove.push(entered_id);
+                break;
+            }
+        }
+    }
+
     /// Sends a `message` to every client in this instance but *not* including it.
     pub fn send_to_instance(
         &mut self,
@@ -198,6 +262,25 @@ impl NetworkState {
         }
     }
 
+    /// Sends the `message` to every connected client.
+    pub fn send_to_all(&mut self, message: FromServer, destination: DestinationNetwork) {
+        let clients = match destination {
+            DestinationNetwork::ZoneClients => &mut self.clients,
+            DestinationNetwork::ChatClients => &mut self.chat_clients,
+        };
+
+        for (id, (handle, _)) in clients {
+            // Some may have disconnected in the meantime, which is cleaned up later.
+            if handle.send(message.clone()).is_err() {
+                if destination == DestinationNetwork::ZoneClients {
+                    self.to_remove.push(*id);
+                } else {
+                    self.to_remove_chat.push(*id);
+                }
+            }
+        }
+    }
+
     /// Sends the `message` to `client_id`.
     pub fn send_to(
         &mut self,
@@ -416,3 +499,87 @@ impl NetworkState {
             .map(|x| *x.0)
     }
 }
+
+#[cfg(test)]
+impl NetworkState {
+    /// Connects a client to `destination`, whose actor, content and account ids are all `id`. Its client id is `id` too, or `100 + id` for chat clients so they don't clash with zone clients.
+    pub fn connect_test_client(
+        &mut self,
+        id: u32,
+        destination: DestinationNetwork,
+    ) -> tokio::sync::mpsc::Receiver<FromServer> {
+        let (client_id, clients) = match destination {
+            DestinationNetwork::ZoneClients => (id as usize, &mut self.clients),
+            DestinationNetwork::ChatClients => (100 + id as usize, &mut self.chat_clients),
+        };
+
+        let (send, recv) = tokio::sync::mpsc::channel(16);
+        let handle = ClientHandle {
+            id: ClientId::from_raw(client_id),
+            channel: send,
+            actor_id: ObjectId(id),
+            content_id: id as u64,
+            account_id: id as u64,
+        };
+        clients.insert(handle.id, (handle, ClientState::default()));
+
+        recv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+    use tokio::sync::mpsc::Receiver;
+
+    use kawari::{
+        common::Position,
+        ipc::zone::{CommonSpawn, SpawnPlayer},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_entered_player_spawns_for_nearby() {
+        let mut instance = Instance::default();
+        let mut network = NetworkState::default();
+
+        let mut receivers: Vec<Receiver<FromServer>> = Vec::new();
+        for (id, x) in [(1, 0.0), (2, 5.0), (3, 1000.0)] {
+            instance.actors.insert(
+                ObjectId(id),
+                NetworkedActor::new_player(SpawnPlayer {
+                    common: CommonSpawn {
+                        name: format!("Player {id}"),
+                        position: Position(Vec3::new(x, 0.0, 0.0)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }),
+            );
+
+            receivers.push(network.connect_test_client(id, DestinationNetwork::ZoneClients));
+        }
+
+        // Player 2 just loaded in next to player 1, while player 3 is far away
+        network.spawn_entered_player(&instance, ObjectId(2), 0.0);
+
+        let spawned = |recv: &mut Receiver<FromServer>| {
+            let mut spawned = Vec::new();
+            while let Ok(msg) = recv.try_recv() {
+                if let FromServer::ActorSpawn(id, _) = msg {
+                    spawned.push(id);
+                }
+            }
+            spawned
+        };
+
+        assert_eq!(spawned(&mut receivers[0]), [ObjectId(2)]);
+        assert_eq!(spawned(&mut receivers[1]), [ObjectId(1)]);
+        assert!(spawned(&mut receivers[2]).is_empty());
+
+        // The next tick shouldn't spawn them a second time
+        let (_, state) = &network.clients[&ClientId::from_raw(1)];
+        assert!(state.has_spawned(ObjectId(2)));
+    }
+}
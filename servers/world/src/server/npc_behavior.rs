@@ -30,6 +30,103 @@ use crate::{
     },
 };
 
+/// Returns the rotation needed to face `to_pos` when standing at `from_pos`.
+fn rotate(from_pos: Vec3, to_pos: Vec3) -> f32 {
+    let rotation = f32::atan2(to_pos.x - from_pos.x, to_pos.z - from_pos.z);
+    if rotation >= PI { -PI } else { rotation }
+}
+
+/// Waypoints a script told an NPC to walk along, like a patrolling guard.
+#[derive(Debug, Clone)]
+pub struct ActorPath {
+    waypoints: VecDeque<Vec3>,
+    /// In yalms per second.
+    speed: f32,
+}
+
+impl ActorPath {
+    pub fn new(waypoints: &[Position], speed: f32) -> Self {
+        Self {
+            waypoints: waypoints.iter().map(|waypoint| waypoint.0).collect(),
+            speed,
+        }
+    }
+
+    /// Walks from `position` for `delta` seconds, passing through as many waypoints as needed. Returns the new position and rotation, or None if there's nowhere left to go.
+    fn advance(&mut self, mut position: Vec3, delta: f32) -> Option<(Vec3, f32)> {
+        let mut remaining = self.speed * delta;
+        let mut rotation = None;
+        while let Some(next) = self.waypoints.front().copied() {
+            let distance = position.distance(next);
+            if distance > 0.0 {
+                rotation = Some(rotate(position, next));
+            }
+
+            if distance > remaining {
+                position += (next - position) / distance * remaining;
+                break;
+            }
+
+            remaining -= distance;
+            position = next;
+            self.waypoints.pop_front();
+        }
+
+        rotation.map(|rotation| (position, rotation))
+    }
+}
+
+/// Moves NPCs along their paths by however far they walk in `delta` seconds.
+pub fn follow_actor_paths(network: Arc<Mutex<NetworkState>>, instance: &mut Instance, delta: f32) {
+    let mut actor_moves = Vec::new();
+
+    instance.actor_paths.retain(|id, path| {
+        // The NPC could have been despawned or killed while walking
+        let Some(NetworkedActor::Npc {
+            state,
+            navmesh_target,
+            spawn,
+            ..
+        }) = instance.actors.get_mut(id)
+        else {
+            return false;
+        };
+        if *state == NpcState::Dead {
+            return false;
+        }
+
+        // Chasing after a target takes priority, but they can go back to walking afterwards
+        if navmesh_target.is_some() {
+            return true;
+        }
+
+        let Some((position, rotation)) = path.advance(spawn.common.position.0, delta) else {
+            return false;
+        };
+        spawn.common.position = Position(position);
+        spawn.common.rotation = rotation;
+
+        actor_moves.push((
+            *id,
+            FromServer::ActorMove(
+                *id,
+                Position(position),
+                rotation,
+                MoveAnimationType::RUNNING,
+                MoveAnimationState::None,
+                JumpState::NoneOrFalling,
+            ),
+        ));
+
+        true
+    });
+
+    let mut network = network.lock();
+    for (id, msg) in actor_moves {
+        network.send_in_range_instance(id, instance, msg, DestinationNetwork::ZoneClients);
+    }
+}
+
 /// Updates NPCs in this instance.
 pub fn npc_behavior(
     network: Arc<Mutex<NetworkState>>,
@@ -76,11 +173,6 @@ pub fn npc_behavior(
 
                 let distance = Vec3::distance(spawn.common.position.0, target_pos);
 
-                let rotate = |from_pos: Vec3, to_pos: Vec3| {
-                    let rotation = f32::atan2(to_pos.x - from_pos.x, to_pos.z - from_pos.z);
-                    if rotation >= PI { -PI } else { rotation }
-                };
-
                 let position;
                 let rotation;
                 // If we are in distance, rotate towards target
@@ -440,3 +532,49 @@ pub fn npc_behavior(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_actor_path_advances() {
+        let mut path = ActorPath::new(
+            &[
+                Position(Vec3::new(0.0, 0.0, 3.0)),
+                Position(Vec3::new(3.0, 0.0, 3.0)),
+            ],
+            4.0,
+        );
+
+        // Each half-second tick walks two yalms, turning at the corner.
+        let mut position = Vec3::ZERO;
+        let mut positions = Vec::new();
+        while let Some((new_position, _)) = path.advance(position, 0.5) {
+            position = new_position;
+            positions.push(position);
+        }
+
+        assert_eq!(
+            positions,
+            [
+                Vec3::new(0.0, 0.0, 2.0),
+                Vec3::new(1.0, 0.0, 3.0),
+                Vec3::new(3.0, 0.0, 3.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_despawned_actor_path() {
+        let network = Arc::new(Mutex::new(NetworkState::default()));
+        let mut instance = Instance::default();
+        instance.actor_paths.insert(
+            ObjectId(1),
+            ActorPath::new(&[Position(Vec3::new(0.0, 0.0, 3.0))], 4.0),
+        );
+
+        follow_actor_paths(network, &mut instance, 0.5);
+        assert!(instance.actor_paths.is_empty());
+    }
+}
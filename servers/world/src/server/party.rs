@@ -74,6 +74,11 @@ impl Party {
             .count()
     }
 
+    /// Returns true if no more members can join this party.
+    pub fn is_full(&self) -> bool {
+        self.members.len() >= PartyMemberEntry::NUM_ENTRIES
+    }
+
     pub fn remove_member(&mut self, member_to_remove: ObjectId) {
         self.members.retain(|x| x.actor_id != member_to_remove);
     }
@@ -89,11 +94,17 @@ impl Party {
     }
 
     pub fn auto_promote_member(&mut self) -> ObjectId {
-        for member in &self.members {
-            if member.is_valid() && member.is_online() && member.actor_id != self.leader_id {
-                self.leader_id = member.actor_id;
-                break;
-            }
+        if let Some(member) = self
+            .members
+            .iter()
+            .find(|x| x.is_valid() && x.is_online() && x.actor_id != self.leader_id)
+        {
+            self.leader_id = member.actor_id;
+        } else if self.get_member_by_actor_id(self.leader_id).is_none()
+            && let Some(member) = self.members.iter().find(|x| x.is_valid())
+        {
+            // If the leader is no longer in the party and nobody else is online, we still need *someone* to lead it.
+            self.leader_id = member.actor_id;
         }
 
         self.leader_id
@@ -448,7 +459,7 @@ pub fn handle_party_messages(
             }
 
             if let Some(party) = network.parties.get(&party_id) {
-                if party.members.len() >= PartyMemberEntry::NUM_ENTRIES {
+                if party.is_full() {
                     tracing::error!(
                         "Tried to add a party member to a full party! What happened? {party:#?}"
                     );
@@ -1406,3 +1417,275 @@ pub fn handle_party_messages(
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::Receiver;
+
+    use kawari::ipc::zone::{CommonSpawn, SpawnPlayer};
+
+    use super::*;
+    use crate::server::instance::Instance;
+
+    /// The receiving ends of a test player's zone and chat connections.
+    struct TestClient {
+        zone: Receiver<FromServer>,
+        chat: Receiver<FromServer>,
+    }
+
+    impl TestClient {
+        /// Returns the status and leader of every party update this client received.
+        fn party_updates(&mut self) -> Vec<(PartyUpdateStatus, Option<ObjectId>)> {
+            let mut updates = Vec::new();
+            while let Ok(msg) = self.zone.try_recv() {
+                if let FromServer::PartyUpdate(_, status, info) = msg {
+                    updates.push((status, info.map(|(_, _, leader_id, _)| leader_id)));
+                }
+            }
+            updates
+        }
+
+        /// Returns the last party chat channel this client was told to use.
+        fn chat_channel(&mut self) -> Option<u32> {
+            let mut chat_channel = None;
+            while let Ok(msg) = self.chat.try_recv() {
+                if let FromServer::SetPartyChatChannel(id) = msg {
+                    chat_channel = Some(id);
+                }
+            }
+            chat_channel
+        }
+    }
+
+    /// Creates `count` connected players, whose actor, content and account ids all start from 1.
+    fn setup(
+        count: u32,
+    ) -> (
+        Arc<Mutex<WorldServer>>,
+        Arc<Mutex<NetworkState>>,
+        Vec<TestClient>,
+    ) {
+        let mut instance = Instance::default();
+        let mut network = NetworkState::default();
+        let mut clients = Vec::new();
+
+        for id in 1..=count {
+            instance.actors.insert(
+                ObjectId(id),
+                NetworkedActor::new_player(SpawnPlayer {
+                    content_id: id as u64,
+                    account_id: id as u64,
+                    common: CommonSpawn {
+                        name: format!("Player {id}"),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }),
+            );
+
+            clients.push(TestClient {
+                zone: network.connect_test_client(id, DestinationNetwork::ZoneClients),
+                chat: network.connect_test_client(id, DestinationNetwork::ChatClients),
+            });
+        }
+
+        let data = WorldServer {
+            instances: vec![instance],
+            ..Default::default()
+        };
+
+        (
+            Arc::new(Mutex::new(data)),
+            Arc::new(Mutex::new(network)),
+            clients,
+        )
+    }
+
+    /// Has player 1 invite everyone else into a new party, and returns its id.
+    fn form_party(
+        data: &Arc<Mutex<WorldServer>>,
+        network: &Arc<Mutex<NetworkState>>,
+        count: u32,
+    ) -> u64 {
+        handle_party_messages(
+            data.clone(),
+            network.clone(),
+            &ToServer::AddPartyMember(0, ObjectId(1), 2),
+        );
+        let party_id = *network.lock().parties.keys().next().unwrap();
+
+        for content_id in 3..=count as u64 {
+            handle_party_messages(
+                data.clone(),
+                network.clone(),
+                &ToServer::AddPartyMember(party_id, ObjectId(1), content_id),
+            );
+        }
+
+        party_id
+    }
+
+    fn leave(
+        data: &Arc<Mutex<WorldServer>>,
+        network: &Arc<Mutex<NetworkState>>,
+        party_id: u64,
+        id: u32,
+    ) {
+        handle_party_messages(
+            data.clone(),
+            network.clone(),
+            &ToServer::PartyMemberLeft(
+                party_id,
+                id as u64,
+                id as u64,
+                ObjectId(id),
+                format!("Player {id}"),
+            ),
+        );
+    }
+
+    #[test]
+    fn test_party_invite() {
+        let (data, network, mut clients) = setup(3);
+        let party_id = form_party(&data, &network, 3);
+
+        {
+            let network = network.lock();
+            let party = &network.parties[&party_id];
+            assert_eq!(party.leader_id, ObjectId(1));
+            assert_eq!(party.get_online_member_count(), 3);
+            assert!(party.get_member_by_content_id(3).is_some());
+        }
+
+        // The leader and the first invitee hear about both joins, while the last one only hears about their own.
+        let joined = (PartyUpdateStatus::JoinParty, Some(ObjectId(1)));
+        assert_eq!(clients[0].party_updates(), [joined, joined]);
+        assert_eq!(clients[1].party_updates(), [joined, joined]);
+        assert_eq!(clients[2].party_updates(), [joined]);
+
+        let chatchannel_id = network.lock().parties[&party_id].chatchannel_id;
+        for client in &mut clients {
+            assert_eq!(client.chat_channel(), Some(chatchannel_id));
+        }
+    }
+
+    #[test]
+    fn test_party_invite_when_full() {
+        let (data, network, _clients) = setup(9);
+        let party_id = form_party(&data, &network, 9);
+
+        // The ninth player should have been turned away
+        let network = network.lock();
+        let party = &network.parties[&party_id];
+        assert!(party.is_full());
+        assert!(party.get_member_by_content_id(9).is_none());
+    }
+
+    #[test]
+    fn test_party_leave() {
+        let (data, network, mut clients) = setup(3);
+        let party_id = form_party(&data, &network, 3);
+        for client in &mut clients {
+            client.party_updates();
+            client.chat_channel();
+        }
+
+        leave(&data, &network, party_id, 3);
+
+        // A normal member leaving keeps the same leader
+        assert_eq!(network.lock().parties[&party_id].leader_id, ObjectId(1));
+        assert_eq!(network.lock().parties[&party_id].get_member_count(), 2);
+
+        let left = (PartyUpdateStatus::MemberLeftParty, Some(ObjectId(1)));
+        assert_eq!(clients[0].party_updates(), [left]);
+        assert_eq!(clients[1].party_updates(), [left]);
+        assert_eq!(
+            clients[2].party_updates(),
+            [(PartyUpdateStatus::MemberLeftParty, None)]
+        );
+        assert_eq!(clients[2].chat_channel(), Some(0));
+        assert_eq!(clients[0].chat_channel(), None);
+    }
+
+    #[test]
+    fn test_party_leader_leaves() {
+        let (data, network, mut clients) = setup(3);
+        let party_id = form_party(&data, &network, 3);
+        for client in &mut clients {
+            client.party_updates();
+        }
+
+        leave(&data, &network, party_id, 1);
+
+        // The next member is promoted, and everyone left is told about it
+        assert_eq!(network.lock().parties[&party_id].leader_id, ObjectId(2));
+
+        let left = (PartyUpdateStatus::MemberLeftParty, Some(ObjectId(2)));
+        assert_eq!(clients[1].party_updates(), [left]);
+        assert_eq!(clients[2].party_updates(), [left]);
+    }
+
+    #[test]
+    fn test_party_leave_disbands() {
+        let (data, network, mut clients) = setup(2);
+        let party_id = form_party(&data, &network, 2);
+        for client in &mut clients {
+            client.party_updates();
+            client.chat_channel();
+        }
+
+        leave(&data, &network, party_id, 2);
+
+        // Parties with less than two members are disbanded
+        assert!(!network.lock().parties.contains_key(&party_id));
+
+        let disbanded = (PartyUpdateStatus::DisbandingParty, None);
+        assert_eq!(clients[0].party_updates(), [disbanded]);
+        assert_eq!(clients[1].party_updates(), [disbanded]);
+        assert_eq!(clients[0].chat_channel(), Some(0));
+        assert_eq!(clients[1].chat_channel(), Some(0));
+    }
+
+    #[test]
+    fn test_party_change_leader() {
+        let (data, network, mut clients) = setup(3);
+        let party_id = form_party(&data, &network, 3);
+        for client in &mut clients {
+            client.party_updates();
+        }
+
+        handle_party_messages(
+            data.clone(),
+            network.clone(),
+            &ToServer::PartyChangeLeader(
+                party_id,
+                1,
+                1,
+                "Player 1".to_string(),
+                3,
+                "Player 3".to_string(),
+            ),
+        );
+
+        assert_eq!(network.lock().parties[&party_id].leader_id, ObjectId(3));
+
+        let promoted = (PartyUpdateStatus::PromoteLeader, Some(ObjectId(3)));
+        for client in &mut clients {
+            assert_eq!(client.party_updates(), [promoted]);
+        }
+    }
+
+    #[test]
+    fn test_auto_promote_offline_member() {
+        let (data, network, _clients) = setup(3);
+        let party_id = form_party(&data, &network, 3);
+
+        // Even if nobody else is online, someone still has to lead the party
+        let mut network = network.lock();
+        let party = network.parties.get_mut(&party_id).unwrap();
+        party.set_member_offline(ObjectId(2));
+        party.set_member_offline(ObjectId(3));
+        party.remove_member(ObjectId(1));
+        assert_eq!(party.auto_promote_member(), ObjectId(2));
+    }
+}
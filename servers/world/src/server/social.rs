@@ -2,11 +2,84 @@ use std::sync::Arc;
 
 use parking_lot::Mutex;
 
+use glam::Vec3;
+
 use crate::{
-    FromServer, ToServer,
-    server::{DestinationNetwork, WorldServer, network::NetworkState},
+    FromServer, GameData, OnlinePlayer, TerritoryNameKind, ToServer,
+    server::{
+        DestinationNetwork, WorldServer, actor::NetworkedActor, instance::Instance,
+        network::NetworkState,
+    },
+};
+use kawari::{
+    common::{LogMessageType, Position},
+    ipc::zone::{CommonSpawn, GameMasterRank, InviteType, ServerZoneIpcData, ServerZoneIpcSegment},
 };
-use kawari::{common::LogMessageType, ipc::zone::InviteType};
+
+/// How far away (in yalms) another player can be examined from.
+// TODO: This is a guess, find out what retail uses.
+const EXAMINE_RANGE: f32 = 30.0;
+
+/// Whether a player at `from` is close enough to examine someone at `to`.
+fn in_examine_range(from: Position, to: Position) -> bool {
+    Vec3::distance(from.0, to.0) <= EXAMINE_RANGE
+}
+
+/// Returns the zone id and spawn of every player someone with `requester_rank` can see online. Only GMs can see GM invisible players.
+fn visible_players(
+    instances: &[Instance],
+    requester_rank: GameMasterRank,
+) -> Vec<(u16, &CommonSpawn)> {
+    let requester_is_gm = requester_rank != GameMasterRank::NormalUser;
+
+    instances
+        .iter()
+        .flat_map(|instance| {
+            instance.actors.values().filter_map(|actor| match actor {
+                NetworkedActor::Player {
+                    spawn,
+                    gm_invisible,
+                    ..
+                } if requester_is_gm || !gm_invisible => Some((instance.zone.id, &spawn.common)),
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+/// Whether `player` has `filter` somewhere in their name or zone name, ignoring case.
+fn matches_who_filter(player: &OnlinePlayer, filter: &str) -> bool {
+    let filter = filter.to_lowercase();
+    player.name.to_lowercase().contains(&filter)
+        || player.zone_name.to_lowercase().contains(&filter)
+}
+
+/// Lists the players someone with `requester_rank` can see online, sorted by name.
+pub fn list_online_players(
+    instances: &[Instance],
+    game_data: &mut GameData,
+    requester_rank: GameMasterRank,
+    filter: Option<&str>,
+) -> Vec<OnlinePlayer> {
+    let mut players: Vec<OnlinePlayer> = visible_players(instances, requester_rank)
+        .into_iter()
+        .map(|(zone_id, common)| OnlinePlayer {
+            name: common.name.clone(),
+            zone_name: game_data
+                .get_territory_name(zone_id as u32, TerritoryNameKind::Place)
+                .unwrap_or_default(),
+            classjob_id: common.class_job,
+            classjob_name: game_data
+                .get_classjob_abbreviation(common.class_job)
+                .unwrap_or_default(),
+            level: common.level,
+        })
+        .filter(|player| filter.is_none_or(|filter| matches_who_filter(player, filter)))
+        .collect();
+    players.sort_by(|a, b| a.name.cmp(&b.name));
+
+    players
+}
 
 /// Process social invitation and moogle mail-related messages.
 pub fn handle_social_messages(
@@ -162,6 +235,129 @@ pub fn handle_social_messages(
 
             true
         }
+        ToServer::ExaminePlayer(from_actor_id, target_actor_id) => {
+            let ipc;
+            {
+                let data = data.lock();
+                let Some(instance) = data.find_actor_instance(*from_actor_id) else {
+                    return true;
+                };
+
+                // The target has to be a player in the same instance as us, otherwise they logged out or left the zone.
+                let (Some(from_actor), Some(target_actor)) = (
+                    instance.find_actor(*from_actor_id),
+                    instance.find_actor(*target_actor_id),
+                ) else {
+                    tracing::info!(
+                        "{from_actor_id} tried to examine {target_actor_id}, but they're not here!"
+                    );
+                    return true;
+                };
+
+                let Some(target_spawn) = target_actor.get_player_spawn() else {
+                    return true;
+                };
+
+                if !in_examine_range(from_actor.position(), target_actor.position()) {
+                    tracing::info!(
+                        "{from_actor_id} tried to examine {target_actor_id}, but they're too far away!"
+                    );
+                    return true;
+                }
+
+                // TODO: Fill in the target's gear and appearance, once we know where they're located in unk1.
+                ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ExamineCharacterInformation {
+                    unk1: [0; 640],
+                    name: target_spawn.common.name.clone(),
+                    unk2: [0; 272],
+                });
+            }
+
+            let mut network = network.lock();
+            network.send_to_by_actor_id(
+                *from_actor_id,
+                FromServer::PacketSegment(ipc, *from_actor_id),
+                DestinationNetwork::ZoneClients,
+            );
+
+            true
+        }
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use kawari::{common::ObjectId, ipc::zone::SpawnPlayer};
+
+    use super::*;
+
+    fn player_named(name: &str) -> NetworkedActor {
+        NetworkedActor::new_player(SpawnPlayer {
+            common: CommonSpawn {
+                name: name.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_invisible_gm_hidden_from_who() {
+        let mut gm = player_named("Hidden GM");
+        if let NetworkedActor::Player { gm_invisible, .. } = &mut gm {
+            *gm_invisible = true;
+        }
+
+        let mut instance = Instance::default();
+        instance
+            .actors
+            .insert(ObjectId(1), player_named("Regular Player"));
+        instance.actors.insert(ObjectId(2), gm);
+        let instances = [instance];
+
+        let names = |rank| -> Vec<String> {
+            let mut names: Vec<String> = visible_players(&instances, rank)
+                .iter()
+                .map(|(_, common)| common.name.clone())
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(GameMasterRank::NormalUser), ["Regular Player"]);
+        assert_eq!(
+            names(GameMasterRank::GameMaster),
+            ["Hidden GM", "Regular Player"]
+        );
+    }
+
+    #[test]
+    fn test_who_filter() {
+        let player = OnlinePlayer {
+            name: "Test Player".to_string(),
+            zone_name: "New Gridania".to_string(),
+            ..Default::default()
+        };
+
+        assert!(matches_who_filter(&player, "test"));
+        assert!(matches_who_filter(&player, "gridania"));
+        assert!(!matches_who_filter(&player, "limsa"));
+    }
+
+    #[test]
+    fn test_examine_nearby_player() {
+        let from = Position(Vec3::new(0.0, 0.0, 0.0));
+        let to = Position(Vec3::new(5.0, 0.0, 5.0));
+
+        assert!(in_examine_range(from, to));
+    }
+
+    #[test]
+    fn test_examine_out_of_range() {
+        let from = Position(Vec3::new(0.0, 0.0, 0.0));
+        let to = Position(Vec3::new(100.0, 0.0, 0.0));
+
+        assert!(!in_examine_range(from, to));
+    }
+}
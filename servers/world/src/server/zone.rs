@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Instant};
 
 use glam::{Affine3A, EulerRot, Vec3};
 use parking_lot::Mutex;
@@ -13,18 +13,18 @@ use physis::{
 };
 
 use crate::{
-    ClientId, FromServer, GameData, StatusEffects, TerritoryNameKind, ToServer,
+    ClientId, FromServer, GameData, TerritoryNameKind, ToServer,
     lua::LuaZone,
     server::{
         NetworkedActor, WorldServer,
         instance::{Instance, QueuedTaskData},
         network::{DestinationNetwork, NetworkState},
     },
-    zone_connection::{BaseParameters, TeleportQuery},
+    zone_connection::BaseParameters,
 };
 use kawari::{
     common::{
-        DistanceRange, DropIn, DropInLayer, DropInObjectData, ENTRANCE_CIRCLE_IDS, EOBJ_EXIT,
+        DropIn, DropInLayer, DropInObjectData, ENTRANCE_CIRCLE_IDS, EOBJ_EXIT,
         EOBJ_HOUSING_ENTRANCE, EOBJ_SHORTCUT, EOBJ_SHORTCUT_EXPLORER_MODE, HandlerType,
         InvisibilityFlags, ObjectId, Position, WARP_DELAY, euler_to_direction,
         internal_housing_row,
@@ -32,12 +32,12 @@ use kawari::{
     config::get_config,
     ipc::zone::{
         ActorControlCategory, ActorSetPos, BattleNpcSubKind, CharacterDataFlag, CommonSpawn,
-        Conditions, DisplayFlag, ObjectKind, ServerZoneIpcData, ServerZoneIpcSegment, SpawnNpc,
+        DisplayFlag, GameMasterRank, ObjectKind, ServerZoneIpcData, ServerZoneIpcSegment, SpawnNpc,
         SpawnObject, SpawnTreasure, WarpType,
     },
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum MapGimmick {
     /// Seen for final boss triggers in Sastasha
     Generic {},
@@ -58,7 +58,7 @@ pub enum MapGimmick {
 }
 
 /// Simpler form of a MapRange object designed for collision detection.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MapRange {
     /// Trigger box shape.
     pub trigger_box_shape: TriggerBoxShape,
@@ -78,22 +78,48 @@ pub struct MapRange {
     pub discovery_id: Option<u8>,
     /// Whether this map range represents an instance exit.
     pub entrance: bool,
+    /// If this map range is a zone line, the instance ID of its exit box.
+    pub exit_box: Option<u32>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct HousingPlot {
     entrance_position: Vec3,
 }
 
+/// Zones that were loaded before, so they don't have to be read again for every new instance.
+#[derive(Default, Clone)]
+pub struct ZoneCache {
+    zones: HashMap<u16, Zone>,
+}
+
+impl ZoneCache {
+    /// Returns a copy of `zone_id`, calling `load` to read it if it isn't cached yet. The copy shares its layer groups with the cache.
+    pub fn get_or_load(&mut self, zone_id: u16, load: impl FnOnce() -> Zone) -> Zone {
+        self.zones.entry(zone_id).or_insert_with(load).clone()
+    }
+
+    /// If `zone_id` was already loaded.
+    pub fn contains(&self, zone_id: u16) -> bool {
+        self.zones.contains_key(&zone_id)
+    }
+
+    /// Forgets every zone, so they're read again the next time they're needed.
+    pub fn clear(&mut self) {
+        self.zones.clear();
+    }
+}
+
 /// Represents a loaded zone
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct Zone {
     pub id: u16,
     pub internal_name: String,
     pub region_name: String,
     pub place_name: String,
     pub intended_use: u8,
-    pub layer_groups: Vec<Lgb>,
+    /// Shared with every other copy of this zone, see [`ZoneCache`].
+    pub layer_groups: Arc<Vec<Lgb>>,
     pub navimesh_path: String,
     pub map_id: u16,
     cached_npc_base_ids: HashMap<u32, u32>,
@@ -108,7 +134,23 @@ pub struct Zone {
 }
 
 impl Zone {
+    /// Loads the zone, or copies it from the cache if it was loaded before.
     pub fn load(game_data: &mut GameData, id: u16) -> Self {
+        // Reading the zone needs the rest of the game data, so the cache has to be taken out in the meantime.
+        let mut zone_cache = std::mem::take(&mut game_data.zone_cache);
+        let zone = zone_cache.get_or_load(id, || {
+            let start = Instant::now();
+            let zone = Self::read(game_data, id);
+            tracing::info!("Loaded zone {id} in {:?}", start.elapsed());
+
+            zone
+        });
+        game_data.zone_cache = zone_cache;
+
+        zone
+    }
+
+    fn read(game_data: &mut GameData, id: u16) -> Self {
         let mut zone = Self {
             id,
             ..Default::default()
@@ -153,11 +195,13 @@ impl Zone {
                 lgb.ok()
             };
 
-            for path in &lvb.sections[0].lgb_paths {
-                if let Some(lgb) = load_lgb(path) {
-                    zone.layer_groups.push(lgb);
-                }
-            }
+            zone.layer_groups = Arc::new(
+                lvb.sections[0]
+                    .lgb_paths
+                    .iter()
+                    .filter_map(|path| load_lgb(path))
+                    .collect(),
+            );
 
             for layer_set in &lvb.sections[0].layer_sets.layer_sets {
                 if layer_set.territory_type_id == id {
@@ -211,7 +255,7 @@ impl Zone {
         }
 
         // create NPC ID cache
-        for layer_group in &zone.layer_groups {
+        for layer_group in zone.layer_groups.iter() {
             for chunk in &layer_group.chunks {
                 for layer in &chunk.layers {
                     if !layer.header.has_layer_set(zone.layer_set as u32) {
@@ -241,6 +285,21 @@ impl Zone {
                                     None
                                 },
                                 entrance: false,
+                                exit_box: None,
+                            });
+                        }
+                        if let LayerEntryData::ExitRange(exit_range) = &object.data {
+                            zone.map_ranges.push(MapRange {
+                                trigger_box_shape: exit_range.parent_data.trigger_box_shape,
+                                position: translation,
+                                scale,
+                                sanctuary: false,
+                                duel: false,
+                                gimmick: None,
+                                instance_id: object.instance_id,
+                                discovery_id: None,
+                                entrance: false,
+                                exit_box: Some(object.instance_id),
                             });
                         }
                         if let LayerEntryData::EventRange(event_range) = &object.data {
@@ -259,6 +318,7 @@ impl Zone {
                                 discovery_id: None,
                                 // Set later!
                                 entrance: false,
+                                exit_box: None,
                             });
                         }
                     }
@@ -397,7 +457,7 @@ impl Zone {
         instance_id: u32,
     ) -> Option<(&InstanceObject, &ExitRangeInstanceObject)> {
         // TODO: also check position!
-        for layer_group in &self.layer_groups {
+        for layer_group in self.layer_groups.iter() {
             for layer in &layer_group.chunks[0].layers {
                 if !layer.header.has_layer_set(self.layer_set as u32) {
                     continue;
@@ -416,11 +476,18 @@ impl Zone {
         None
     }
 
+    /// Returns the exit box of the zone line at `position`, if there is one.
+    pub fn find_zone_line(&self, position: Vec3) -> Option<u32> {
+        self.get_overlapping_map_ranges(position)
+            .iter()
+            .find_map(|range| range.exit_box)
+    }
+
     pub fn find_pop_range(
         &self,
         instance_id: u32,
     ) -> Option<(&InstanceObject, &PopRangeInstanceObject)> {
-        for layer_group in &self.layer_groups {
+        for layer_group in self.layer_groups.iter() {
             for layer in &layer_group.chunks[0].layers {
                 if !layer.header.has_layer_set(self.layer_set as u32) {
                     continue;
@@ -456,7 +523,7 @@ impl Zone {
     fn find_entrance_from_base_id(&self, base_id: u32) -> Option<&InstanceObject> {
         // First, we need to find the EventObject for the entrance:
         let mut bound_id = None;
-        for layer_group in &self.layer_groups {
+        for layer_group in self.layer_groups.iter() {
             for layer in &layer_group.chunks[0].layers {
                 if !layer.header.has_layer_set(self.layer_set as u32) {
                     continue;
@@ -476,7 +543,7 @@ impl Zone {
         bound_id?;
 
         // Then find the linked instance object, which is usually a SGB.
-        for layer_group in &self.layer_groups {
+        for layer_group in self.layer_groups.iter() {
             for layer in &layer_group.chunks[0].layers {
                 if !layer.header.has_layer_set(self.layer_set as u32) {
                     continue;
@@ -514,7 +581,7 @@ impl Zone {
     ) -> Vec<SpawnObject> {
         let mut object_spawns = Vec::new();
 
-        for layer_group in &self.layer_groups {
+        for layer_group in self.layer_groups.iter() {
             for layer in &layer_group.chunks[0].layers {
                 if !layer.header.has_layer_set(self.layer_set as u32) {
                     continue;
@@ -686,9 +753,12 @@ impl Zone {
                             &param_grow,
                             &modifiers,
                         );
-                        base_parameters.calculate_potencies(level, &param_grow, None); // TODO: If NPCs have classjob modifiers and such, change that None!
+                        base_parameters.calculate_potencies();
 
-                        usable_hp = base_parameters.hp;
+                        // TODO: If NPCs have classjob modifiers and such, change that None!
+                        usable_hp = game_data
+                            .calculate_max_hp(level, None, base_parameters.vitality)
+                            .expect("Failed to read param grow");
                     }
 
                     let spawn = SpawnNpc {
@@ -726,22 +796,12 @@ impl Zone {
                     }
                 }
                 if let DropInObjectData::EventNpc { base_id } = object.data {
-                    let (model_chara, customize, equip) = game_data.find_enpc(base_id).unwrap();
-
-                    let spawn = SpawnNpc {
-                        common: CommonSpawn {
-                            base_id,
-                            name_id: base_id,
-                            model_chara,
-                            object_kind: ObjectKind::EventNpc,
-                            position: object.position,
-                            rotation: object.rotation,
-                            look: customize,
-                            layout_id: object.instance_id,
-                            ..game_data.get_npc_equip(equip as u32).unwrap_or_default()
-                        },
-                        ..Default::default()
-                    };
+                    let mut spawn = game_data.get_npc_appearance(base_id).unwrap().to_spawn(
+                        base_id,
+                        object.position,
+                        object.rotation,
+                    );
+                    spawn.common.layout_id = object.instance_id;
 
                     self.cached_npcs.insert(object.instance_id, spawn.clone());
                     npc_spawns.push(spawn);
@@ -992,6 +1052,12 @@ pub fn change_zone_warp_to_entrance(
     );
 }
 
+/// Whether a GM of `summoner` rank is allowed to summon a player of `target` rank to them.
+/// Normal users can never summon, and GMs can't pull in someone who outranks them.
+pub fn can_summon(summoner: GameMasterRank, target: GameMasterRank) -> bool {
+    summoner != GameMasterRank::NormalUser && summoner as u8 >= target as u8
+}
+
 /// Teleports one player to another.
 pub fn change_zone_to_player(
     network: &mut NetworkState,
@@ -1091,6 +1157,69 @@ fn do_change_zone(
     }
 }
 
+/// Sends `actor_id` through the zone line `exitbox_id` in their current zone.
+pub fn enter_zone_jump(
+    data: &mut WorldServer,
+    network: &mut NetworkState,
+    game_data: &mut GameData,
+    from_id: ClientId,
+    actor_id: ObjectId,
+    exitbox_id: u32,
+    warp_type_info: Option<(WarpType, u8, u8, u8)>,
+) {
+    // Whoever asked first, we don't want the tick to send them through it again.
+    if let Some(NetworkedActor::Player {
+        entered_zone_line_at,
+        ..
+    }) = data
+        .find_actor_instance_mut(actor_id)
+        .and_then(|instance| instance.find_actor_mut(actor_id))
+    {
+        *entered_zone_line_at = None;
+    }
+
+    // first, find the zone jump in the current zone
+    let mut destination_zone_id;
+    let destination_instance_id;
+    if let Some(current_instance) = data.find_actor_instance(actor_id) {
+        let Some((_, new_exit_box)) = current_instance.zone.find_exit_box(exitbox_id) else {
+            tracing::warn!("Couldn't find exit box {exitbox_id}?!");
+            return;
+        };
+        destination_zone_id = new_exit_box.territory_type;
+
+        // Seen when attempting to enter underwater portals in Ruby Sea
+        if new_exit_box.territory_type == 0
+            && new_exit_box.zone_id == 0
+            && new_exit_box.exit_type == physis::layer::ExitType::Unk
+        {
+            destination_zone_id = current_instance.zone.id;
+        }
+
+        destination_instance_id = new_exit_box.destination_instance_id;
+    } else {
+        tracing::warn!("Actor isn't in the instance it was expected in. This is a bug!");
+        return;
+    }
+
+    let (warp_type, param4, hide_character, unk1) =
+        warp_type_info.unwrap_or((WarpType::Normal, 0, 0, 0));
+
+    change_zone_warp_to_pop_range(
+        data,
+        network,
+        game_data,
+        Some(destination_zone_id),
+        destination_instance_id,
+        actor_id,
+        from_id,
+        warp_type,
+        param4,
+        hide_character,
+        unk1,
+    );
+}
+
 /// Process zone-related messages.
 pub fn handle_zone_messages(
     data: Arc<Mutex<WorldServer>>,
@@ -1108,20 +1237,16 @@ pub fn handle_zone_messages(
 
             // replace the connection's actor in the table
             let instance = data.find_actor_instance_mut(*from_actor_id).unwrap();
-            *instance.find_actor_mut(*from_actor_id).unwrap() = NetworkedActor::Player {
-                spawn: player_spawn.clone(),
-                status_effects: StatusEffects::default(),
-                teleport_query: TeleportQuery::default(),
-                distance_range: DistanceRange::Normal,
-                conditions: Conditions::default(),
-                executing_gimmick_jump: false,
-                inside_instance_exit: false,
-                parameters: BaseParameters::default(),
-                dueling_opponent_id: ObjectId::default(),
-                remove_cooldowns: false,
-                last_combo_action: 0,
-                combo_sequence: 0,
-            };
+            *instance.find_actor_mut(*from_actor_id).unwrap() =
+                NetworkedActor::new_player(player_spawn.clone());
+
+            // Let everyone already here (party members included) see them right away, instead of waiting for the next tick.
+            let mut network = network.lock();
+            network.spawn_entered_player(
+                instance,
+                *from_actor_id,
+                get_config().world.max_spawn_distance,
+            );
 
             true
         }
@@ -1172,52 +1297,16 @@ pub fn handle_zone_messages(
         ToServer::EnterZoneJump(from_id, actor_id, exitbox_id, warp_type_info) => {
             let mut data = data.lock();
             let mut network = network.lock();
-
-            // first, find the zone jump in the current zone
-            let mut destination_zone_id;
-            let destination_instance_id;
-            if let Some(current_instance) = data.find_actor_instance(*actor_id) {
-                let Some((_, new_exit_box)) = current_instance.zone.find_exit_box(*exitbox_id)
-                else {
-                    tracing::warn!("Couldn't find exit box {exitbox_id}?!");
-                    return true;
-                };
-                destination_zone_id = new_exit_box.territory_type;
-
-                // Seen when attempting to enter underwater portals in Ruby Sea
-                if new_exit_box.territory_type == 0
-                    && new_exit_box.zone_id == 0
-                    && new_exit_box.exit_type == physis::layer::ExitType::Unk
-                {
-                    destination_zone_id = current_instance.zone.id;
-                }
-
-                destination_instance_id = new_exit_box.destination_instance_id;
-            } else {
-                tracing::warn!("Actor isn't in the instance it was expected in. This is a bug!");
-                return true;
-            }
-
-            let (warp_type, param4, hide_character, unk1) =
-                if let Some((w_type, param, hide, unk)) = warp_type_info {
-                    (*w_type, *param, *hide, *unk)
-                } else {
-                    (WarpType::Normal, 0, 0, 0)
-                };
-
             let mut game_data = game_data.lock();
-            change_zone_warp_to_pop_range(
+
+            enter_zone_jump(
                 &mut data,
                 &mut network,
                 &mut game_data,
-                Some(destination_zone_id),
-                destination_instance_id,
-                *actor_id,
                 *from_id,
-                warp_type,
-                param4,
-                hide_character,
-                unk1,
+                *actor_id,
+                *exitbox_id,
+                *warp_type_info,
             );
 
             true
@@ -1478,3 +1567,93 @@ pub fn handle_zone_messages(
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preloaded_zone_cached() {
+        let mut cache = ZoneCache::default();
+        let preloaded = cache.get_or_load(132, || Zone {
+            id: 132,
+            ..Default::default()
+        });
+        assert!(cache.contains(132));
+        assert!(!cache.contains(133));
+
+        // The second load shouldn't read anything.
+        let loaded = cache.get_or_load(132, || panic!("Zone 132 was read again!"));
+        assert_eq!(loaded.id, 132);
+        assert!(Arc::ptr_eq(&preloaded.layer_groups, &loaded.layer_groups));
+
+        // Reloading should read it again.
+        cache.clear();
+        assert!(!cache.contains(132));
+    }
+
+    #[test]
+    fn test_cached_zones_share_data() {
+        let mut cache = ZoneCache::default();
+        let mut first = cache.get_or_load(132, || Zone {
+            id: 132,
+            map_ranges: vec![MapRange {
+                trigger_box_shape: TriggerBoxShape::Box,
+                position: Vec3::ZERO,
+                scale: Vec3::ONE,
+                sanctuary: false,
+                duel: false,
+                gimmick: None,
+                instance_id: 1234,
+                discovery_id: None,
+                entrance: false,
+                exit_box: None,
+            }],
+            ..Default::default()
+        });
+
+        let second = cache.get_or_load(132, Zone::default);
+        assert!(Arc::ptr_eq(&first.layer_groups, &second.layer_groups));
+
+        // But each instance can still change its own copy, like content marking their entrance.
+        first.map_ranges[0].entrance = true;
+        assert!(!second.map_ranges[0].entrance);
+        assert!(!cache.get_or_load(132, Zone::default).map_ranges[0].entrance);
+    }
+
+    #[test]
+    fn test_find_zone_line() {
+        let zone = Zone {
+            map_ranges: vec![MapRange {
+                trigger_box_shape: TriggerBoxShape::Box,
+                position: Vec3::new(100.0, 0.0, 100.0),
+                scale: Vec3::new(5.0, 5.0, 5.0),
+                sanctuary: false,
+                duel: false,
+                gimmick: None,
+                instance_id: 1234,
+                discovery_id: None,
+                entrance: false,
+                exit_box: Some(1234),
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(zone.find_zone_line(Vec3::new(102.0, 1.0, 98.0)), Some(1234));
+        assert_eq!(zone.find_zone_line(Vec3::ZERO), None);
+    }
+
+    #[test]
+    fn test_summon_rank_gating() {
+        assert!(!can_summon(
+            GameMasterRank::NormalUser,
+            GameMasterRank::NormalUser
+        ));
+        assert!(can_summon(
+            GameMasterRank::GameMaster,
+            GameMasterRank::NormalUser
+        ));
+        assert!(can_summon(GameMasterRank::Debug, GameMasterRank::Debug));
+        assert!(!can_summon(GameMasterRank::Support, GameMasterRank::Debug));
+    }
+}
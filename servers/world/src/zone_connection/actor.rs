@@ -1,20 +1,45 @@
 //! Everything to do with spawning, managing and moving actors - including the player.
 
-use crate::{ToServer, ZoneConnection, common::SpawnKind};
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::channel;
+
+use crate::{
+    ToServer, ZoneConnection,
+    common::SpawnKind,
+    zone_connection::{ActorMovement, ReviveKind},
+};
 use kawari::{
     common::{
-        CharacterMode, EquipDisplayFlag, JumpState, MoveAnimationState, MoveAnimationType,
-        ObjectId, ObjectTypeId, Position,
+        CharacterMode, CustomizeData, EquipDisplayFlag, JumpState, MoveAnimationState,
+        MoveAnimationType, ObjectId, ObjectTypeId, ObjectTypeKind, Position, WEAKNESS_DURATION,
+        WEAKNESS_STATUS_ID,
     },
     config::get_config,
     ipc::zone::{
-        ActorControl, ActorControlCategory, ActorControlSelf, ActorControlTarget, ActorMove,
-        CommonSpawn, Config, DisplayFlag, ObjectKind, PlayerSubKind, ServerZoneIpcData,
-        ServerZoneIpcSegment, SpawnObject, SpawnPlayer, SpawnTreasure,
+        ActionKind, ActionRequest, ActorControl, ActorControlCategory, ActorControlSelf,
+        ActorControlTarget, ActorMove, ActorSetPos, ClientTrigger, ClientTriggerCommand,
+        CommonSpawn, Conditions, Config, DisplayFlag, GameMasterRank, ObjectKind, PlayerSubKind,
+        ServerZoneIpcData, ServerZoneIpcSegment, SpawnObject, SpawnPlayer, SpawnTreasure,
     },
 };
 
 impl ZoneConnection {
+    /// Finds all (alive) players and NPCs within `radius` of `center`, in the instance we're in. Like retail, the Y axis is ignored.
+    pub async fn actors_in_radius(&mut self, center: Position, radius: f32) -> Vec<ObjectId> {
+        let (send, mut recv) = channel(1);
+        self.handle
+            .send(ToServer::ActorsInRadius(
+                self.player_data.character.actor_id,
+                center,
+                radius,
+                send,
+            ))
+            .await;
+
+        recv.recv().await.unwrap_or_default()
+    }
+
     pub async fn set_actor_position(
         &mut self,
         actor_id: ObjectId,
@@ -24,6 +49,44 @@ impl ZoneConnection {
         anim_state: MoveAnimationState,
         jump_state: JumpState,
     ) {
+        let movement = ActorMovement {
+            position,
+            rotation,
+            anim_type,
+            anim_state,
+            jump_state,
+        };
+        if !self.move_throttle.should_send(
+            actor_id,
+            movement,
+            Instant::now(),
+            self.config.movement_updates_per_second,
+        ) {
+            return;
+        }
+
+        self.send_actor_move(actor_id, movement).await;
+    }
+
+    /// Sends any movement the throttle held back, once the actor's window is over. Otherwise they'd appear to stop short of where they really stopped.
+    pub async fn flush_throttled_moves(&mut self) {
+        let due = self
+            .move_throttle
+            .take_due(Instant::now(), self.config.movement_updates_per_second);
+        for (actor_id, movement) in due {
+            self.send_actor_move(actor_id, movement).await;
+        }
+    }
+
+    async fn send_actor_move(&mut self, actor_id: ObjectId, movement: ActorMovement) {
+        let ActorMovement {
+            position,
+            rotation,
+            anim_type,
+            anim_state,
+            jump_state,
+        } = movement;
+
         const SPEED_WALKING: u8 = 20;
         const SPEED_RUNNING: u8 = 60;
 
@@ -81,7 +144,56 @@ impl ZoneConnection {
         self.send_ipc_from(actor_id, ipc).await;
     }
 
+    /// Spawns the Event NPC `enpc_id` into our instance, so everyone nearby can see them.
+    pub async fn spawn_npc(&mut self, enpc_id: u32, position: Position, rotation: f32) {
+        let appearance;
+        {
+            let mut game_data = self.gamedata.lock();
+            appearance = game_data.get_npc_appearance(enpc_id);
+        }
+
+        let Some(appearance) = appearance else {
+            tracing::warn!("Can't spawn ENpc {enpc_id}, it doesn't exist!");
+            return;
+        };
+
+        self.handle
+            .send(ToServer::SpawnNpc(
+                self.player_data.character.actor_id,
+                appearance.to_spawn(enpc_id, position, rotation),
+            ))
+            .await;
+    }
+
+    /// Brings us back from the dead. If we're returning to our home point, we're also teleported there and gain Weakness.
+    pub async fn revive(&mut self, kind: ReviveKind) {
+        self.handle
+            .send(ToServer::Revive(
+                self.id,
+                self.player_data.character.actor_id,
+                kind,
+            ))
+            .await;
+
+        if kind == ReviveKind::Homepoint {
+            let mut aetheryte_id = self.player_data.aetheryte.homepoint as u32;
+            if aetheryte_id == 0 {
+                // If they never set a homepoint, bring them to the closest thing in the zone they're in.
+                let mut game_data = self.gamedata.lock();
+                aetheryte_id = game_data
+                    .get_default_aetheryte_for_zone(self.player_data.volatile.zone_id as u16)
+                    .unwrap_or_default();
+            }
+
+            self.warp_aetheryte(aetheryte_id, false, false).await;
+            self.gain_effect(WEAKNESS_STATUS_ID, 0, WEAKNESS_DURATION)
+                .await;
+        }
+    }
+
     pub async fn delete_actor(&mut self, actor_id: ObjectId, spawn_index: u8) {
+        self.move_throttle.forget(actor_id);
+
         let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::DeleteActor {
             spawn_index,
             actor_id,
@@ -100,6 +212,12 @@ impl ZoneConnection {
         self.player_data.gm_invisible = invisible;
         self.actor_control_self(ActorControlCategory::ToggleInvisibility { invisible })
             .await;
+        self.handle
+            .send(ToServer::SetGmInvisible(
+                self.player_data.character.actor_id,
+                invisible,
+            ))
+            .await;
     }
 
     pub async fn actor_control_self(&mut self, category: ActorControlCategory) {
@@ -157,7 +275,10 @@ impl ZoneConnection {
             account_id: self.player_data.character.service_account_id as u64,
             content_id: self.player_data.character.content_id as u64,
             current_world_id: config.world.world_id,
-            home_world_id: config.world.world_id,
+            home_world_id: self
+                .player_data
+                .character
+                .home_world_id_or(config.world.world_id),
             gm_rank: self.player_data.character.gm_rank,
             online_status: self.get_actual_online_status(),
             common: common.clone(),
@@ -172,6 +293,7 @@ impl ZoneConnection {
         }
 
         self.spawned_in = true;
+        self.last_move = None;
 
         spawn
     }
@@ -187,6 +309,7 @@ impl ZoneConnection {
 
         let mut database = self.database.lock();
         let chara_make = database.get_chara_make(self.player_data.character.content_id as u64);
+        let fc_tag = database.find_fc_tag(self.player_data.character.content_id);
         let mut look = chara_make.customize;
 
         // There seems to be no display flag for this, so clear the bit out
@@ -210,11 +333,12 @@ impl ZoneConnection {
         CommonSpawn {
             class_job: self.player_data.classjob.current_class as u8,
             name: self.player_data.character.name.clone(),
+            fc_tag,
             health_points: base_parameters.hp,
             max_health_points: base_parameters.hp,
             resource_points: base_parameters.mp as u16,
             max_resource_points: base_parameters.mp as u16,
-            level: self.current_level(&game_data) as u8,
+            level: self.current_level(&game_data).unwrap_or_default() as u8,
             object_kind: ObjectKind::Player(PlayerSubKind::Player),
             look,
             display_flags,
@@ -268,9 +392,330 @@ impl ZoneConnection {
             .await;
     }
 
+    /// Checks whether the player could've actually moved to `position` since their last update. If not, they're snapped back to where they were.
+    pub async fn validate_move(&mut self, position: Position) -> bool {
+        let now = Instant::now();
+        let max_speed = self.config.max_movement_speed;
+        let exempt = self.player_data.character.gm_rank != GameMasterRank::NormalUser;
+
+        if let Some((moved_at, last_position)) = self.last_move
+            && max_speed > 0.0
+            && !exempt
+            && !is_plausible_move(last_position, position, now - moved_at, max_speed)
+        {
+            tracing::warn!(
+                "Player {} moved from {:?} to {:?} too quickly, snapping them back!",
+                self.player_data.character.actor_id,
+                last_position,
+                position
+            );
+
+            let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ActorSetPos(ActorSetPos {
+                rotation: self.player_data.volatile.rotation as f32,
+                position: last_position,
+                ..Default::default()
+            }));
+            self.send_ipc_self(ipc).await;
+
+            // Sending ActorSetPos clears this, but they're still where they were
+            self.last_move = Some((now, last_position));
+
+            return false;
+        }
+
+        self.last_move = Some((now, position));
+
+        true
+    }
+
+    /// Puts the player on `mount_id` for everyone nearby to see, or dismounts them if it's zero.
+    /// Returns false if the player hasn't unlocked this mount.
+    pub async fn set_mount(&mut self, mount_id: u16) -> bool {
+        if mount_id == 0 {
+            if self.player_data.volatile.current_mount != 0 {
+                self.dismount(0).await;
+            }
+            return true;
+        }
+
+        if !self.is_mount_unlocked(mount_id as u32) {
+            tracing::warn!("Player tried to ride mount {mount_id}, which they haven't unlocked!");
+            return false;
+        }
+
+        self.handle
+            .send(ToServer::ActionRequest(
+                self.id,
+                self.player_data.character.actor_id,
+                ActionRequest {
+                    action_key: mount_id as u32,
+                    exec_proc: 0,
+                    action_kind: ActionKind::Mount,
+                    ..Default::default()
+                },
+            ))
+            .await;
+
+        true
+    }
+
+    /// Summons `minion_id` for everyone nearby to see, despawning the previous minion if there was one. Zero despawns the current minion, and summoning the one that's already out does nothing.
+    /// Returns false if the player hasn't unlocked this minion.
+    pub async fn summon_minion(&mut self, minion_id: u32) -> bool {
+        if minion_id != 0 && !self.player_data.unlock.minions.contains(minion_id) {
+            tracing::warn!(
+                "Player tried to summon minion {minion_id}, which they haven't unlocked!"
+            );
+            return false;
+        }
+
+        // It's already out, so there's nothing to respawn.
+        if self.active_minion == minion_id {
+            return true;
+        }
+
+        if swap_minion(&mut self.active_minion, minion_id).is_some() {
+            self.handle
+                .send(ToServer::ActorDespawnsMinion(
+                    self.player_data.character.actor_id,
+                ))
+                .await;
+        }
+
+        if minion_id != 0 {
+            self.handle
+                .send(ToServer::ActorSummonsMinion(
+                    self.player_data.character.actor_id,
+                    minion_id,
+                ))
+                .await;
+        }
+
+        true
+    }
+
+    /// Gets the player off their mount, and any passengers too.
+    pub async fn dismount(&mut self, sequence: u32) {
+        // TODO: Move all this to FromServer::ActorDismounted so all of the logic can be consolidated
+        self.conditions = Conditions::default();
+        self.send_conditions().await;
+
+        // SetMode isn't important, no, but it's included for accuracy.
+        self.set_character_mode(CharacterMode::Normal, 0).await;
+
+        // Retail indeed does send an AC, not an ACS for this.
+        self.actor_control(
+            self.player_data.character.actor_id,
+            ActorControlCategory::PlayDismountAnimation {
+                unk1: 47494,
+                unk2: 32711,
+                unk3: 1510381914,
+            },
+        )
+        .await;
+
+        // TODO: This should only be sent when the player is actually riding pillion, but for now, it doesn't seem to hurt sending it unconditionally.
+        self.actor_control(
+            self.player_data.character.actor_id,
+            ActorControlCategory::RidePillion {
+                target_actor_id: ObjectId::default(),
+                target_seat_index: 0,
+            },
+        )
+        .await;
+
+        self.actor_control_self(ActorControlCategory::Dismount { sequence })
+            .await;
+
+        // Then these are also sent!
+        self.actor_control_self(ActorControlCategory::SetPetEntityId { unk1: 0 })
+            .await;
+
+        self.actor_control_self(ActorControlCategory::CompanionUnlock { unk1: 0, unk2: 0 })
+            .await;
+
+        self.actor_control_self(ActorControlCategory::SetPetParameters {
+            pet_id: 0,
+            unk2: 0,
+            unk3: 0,
+            unk4: 7,
+        })
+        .await;
+
+        // TODO: Remove this `let party_id` in an upcoming party refactor, this is temporary
+        let party_id = if self.party_id != 0 {
+            Some(self.party_id)
+        } else {
+            None
+        };
+        self.handle
+            .send(ToServer::Dismounted(
+                self.player_data.character.actor_id,
+                party_id,
+            ))
+            .await;
+    }
+
+    /// Changes the player's appearance like the aesthetician or a fantasia would, and respawns them so it's visible.
+    /// Returns false if `customize` isn't a combination that can exist.
+    pub async fn set_appearance(&mut self, customize: CustomizeData) -> bool {
+        let valid_tribe;
+        {
+            let mut game_data = self.gamedata.lock();
+            valid_tribe = game_data.is_valid_tribe(customize.subrace);
+        }
+
+        if !valid_tribe || !customize.has_valid_race() {
+            tracing::warn!(
+                "Rejecting appearance with race {}, tribe {} and gender {}!",
+                customize.race,
+                customize.subrace,
+                customize.gender
+            );
+            return false;
+        }
+
+        {
+            let mut database = self.database.lock();
+            let mut chara_make =
+                database.get_chara_make(self.player_data.character.content_id as u64);
+            chara_make.customize = customize;
+
+            database.set_chara_make(
+                self.player_data.character.content_id as u64,
+                &chara_make.to_json(),
+            );
+        }
+        self.respawn_player(false).await;
+
+        true
+    }
+
+    /// Performs an emote as if the player did it themselves, optionally towards `target`.
+    pub async fn do_emote(&mut self, emote_id: u32, target: Option<ObjectId>) {
+        let valid;
+        {
+            let mut game_data = self.gamedata.lock();
+            valid = game_data.is_emote_valid(emote_id);
+        }
+
+        if !valid {
+            tracing::warn!("Refusing to perform invalid emote {emote_id}!");
+            return;
+        }
+
+        let target = target
+            .map(|object_id| ObjectTypeId {
+                object_id,
+                object_type: ObjectTypeKind::None,
+            })
+            .unwrap_or_default();
+        let category = ActorControlCategory::Emote {
+            emote: emote_id,
+            hide_text: false,
+        };
+
+        // The client normally plays the emote on its own, so we have to tell it.
+        self.actor_control_target(self.player_data.character.actor_id, target, category)
+            .await;
+
+        // Then let the server show it to everyone else, and keep looping emotes around for new spawns.
+        self.handle
+            .send(ToServer::ClientTrigger(
+                self.id,
+                self.player_data.character.actor_id,
+                ClientTrigger {
+                    trigger: ClientTriggerCommand::Emote {
+                        emote: emote_id,
+                        hide_text: false,
+                    },
+                    target: Some(target),
+                    content_id: None,
+                },
+            ))
+            .await;
+    }
+
     pub async fn spawn_treasure(&mut self, spawn: SpawnTreasure) {
         let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::SpawnTreasure(spawn.clone()));
 
         self.send_ipc_from(spawn.entity_id, ipc).await;
     }
 }
+
+/// How much longer (in seconds) than the time between updates a player is given to cover a distance, to account for lag.
+const MOVEMENT_LAG_TOLERANCE: f32 = 1.0;
+
+/// Whether moving from `from` to `to` within `elapsed` is possible at `max_speed` yalms per second. Like spawning, this doesn't take into account Y so falling is never a problem.
+fn is_plausible_move(from: Position, to: Position, elapsed: Duration, max_speed: f32) -> bool {
+    let mut from = from.0;
+    from.y = 0.0;
+
+    let mut to = to.0;
+    to.y = 0.0;
+
+    let max_distance = max_speed * (elapsed.as_secs_f32() + MOVEMENT_LAG_TOLERANCE);
+    from.distance(to) <= max_distance
+}
+
+/// Sets `active_minion` to `minion_id`, and returns the minion that was replaced and needs to be despawned.
+fn swap_minion(active_minion: &mut u32, minion_id: u32) -> Option<u32> {
+    if *active_minion == minion_id {
+        return None;
+    }
+
+    let previous = std::mem::replace(active_minion, minion_id);
+    (previous != 0).then_some(previous)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    #[test]
+    fn test_plausible_move() {
+        let from = Position(Vec3::ZERO);
+
+        // Running for a second
+        let to = Position(Vec3::new(6.0, 0.0, 0.0));
+        assert!(is_plausible_move(from, to, Duration::from_secs(1), 30.0));
+
+        // Falling off a cliff is fine
+        let to = Position(Vec3::new(0.0, -500.0, 0.0));
+        assert!(is_plausible_move(
+            from,
+            to,
+            Duration::from_millis(100),
+            30.0
+        ));
+
+        // But crossing the entire zone in an instant is not
+        let to = Position(Vec3::new(1000.0, 0.0, 0.0));
+        assert!(!is_plausible_move(
+            from,
+            to,
+            Duration::from_millis(100),
+            30.0
+        ));
+    }
+
+    #[test]
+    fn test_summon_second_minion() {
+        let mut active_minion = 0;
+
+        assert_eq!(swap_minion(&mut active_minion, 1), None);
+        assert_eq!(active_minion, 1);
+
+        // The first minion has to go away
+        assert_eq!(swap_minion(&mut active_minion, 2), Some(1));
+        assert_eq!(active_minion, 2);
+
+        // Summoning the same one again does nothing
+        assert_eq!(swap_minion(&mut active_minion, 2), None);
+
+        assert_eq!(swap_minion(&mut active_minion, 0), Some(2));
+        assert_eq!(active_minion, 0);
+    }
+}
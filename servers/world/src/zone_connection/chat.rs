@@ -7,23 +7,33 @@ use mlua::Function;
 use physis::equipment::EquipSlot;
 
 use crate::{
-    Event, EventHandler, ItemInfoQuery, MessageInfo, ZoneConnection,
-    inventory::{Item, Storage},
+    Event, EventHandler, ItemInfoQuery, MessageInfo, ToServer, ZoneConnection,
+    inventory::{AddItemError, Item, Storage},
     lua::{KawariLuaState, LuaPlayer},
 };
 use kawari::{
     common::{
-        DirectorEvent, ERR_INVENTORY_ADD_FAILED, FateState, HandlerId, HandlerType, ObjectTypeId,
+        DEBUG_COMMAND_TRIGGER, DirectorEvent, FateState, HandlerId, HandlerType, ObjectTypeId,
     },
     config::FilesystemConfig,
     ipc::zone::{
-        ActorControlCategory, ChatMessage, Condition, Conditions, GameMasterRank,
+        ActorControlCategory, ChatMessage, Condition, Conditions, GameMasterRank, OnlineStatus,
         ServerNoticeFlags, ServerNoticeMessage, ServerZoneIpcData, ServerZoneIpcSegment,
     },
+    packet::ReplayOptions,
 };
 
 impl ZoneConnection {
     pub async fn send_message(&mut self, message: MessageInfo) {
+        // Messages from anyone we've blacklisted never make it to the client.
+        let content_id = self.player_data.character.content_id;
+        let blacklist = self
+            .blacklist
+            .get_or_insert_with(|| self.database.lock().get_blacklist(content_id));
+        if blacklist.contains(&(message.sender_content_id as i64)) {
+            return;
+        }
+
         let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ChatMessage(ChatMessage {
             sender_account_id: message.sender_account_id,
             sender_actor_id: message.sender_actor_id,
@@ -47,6 +57,111 @@ impl ZoneConnection {
         self.send_ipc_self(ipc).await;
     }
 
+    /// Sends several notices to the chat log at once.
+    pub async fn send_notices(&mut self, messages: &[String]) {
+        let ipcs = messages
+            .iter()
+            .map(|message| {
+                ServerZoneIpcSegment::new(ServerZoneIpcData::ServerNoticeMessage(
+                    ServerNoticeMessage {
+                        flags: ServerNoticeFlags::CHAT_LOG,
+                        message: message.clone(),
+                    },
+                ))
+            })
+            .collect();
+
+        self.send_ipcs_self(ipcs).await;
+    }
+
+    /// Greets the player with the configured `login_message`.
+    pub async fn send_login_message(&mut self) {
+        let lines =
+            format_login_message(&self.config.login_message, &self.player_data.character.name);
+        for line in lines {
+            self.send_notice(&line).await;
+        }
+    }
+
+    /// Handles commands that any player can use, not just GMs. Returns true if the message was one of them.
+    pub async fn process_player_commands(&mut self, chat_message: &str) -> bool {
+        if let Some(max) = parse_random_command(chat_message) {
+            self.roll_random(max).await;
+            return true;
+        }
+
+        if let Some(status) = parse_status_command(chat_message) {
+            self.toggle_online_status(status).await;
+            return true;
+        }
+
+        if let Some(filter) = parse_who_command(chat_message) {
+            self.handle
+                .send(ToServer::ListOnlinePlayers(
+                    self.player_data.character.actor_id,
+                    filter.map(str::to_string),
+                ))
+                .await;
+            return true;
+        }
+
+        false
+    }
+
+    /// Rolls a random number for everyone nearby to see.
+    pub async fn roll_random(&mut self, max: u32) {
+        self.handle
+            .send(ToServer::RandomRoll(
+                self.player_data.character.actor_id,
+                self.player_data.character.name.clone(),
+                self.player_data.volatile.position,
+                max,
+            ))
+            .await;
+    }
+
+    /// Runs the Lua command script matching `input` (e.g. `!cf 1`), passing along its arguments.
+    /// Returns false if there's no script for this command, so it can be handled elsewhere.
+    pub async fn handle_command(&mut self, input: &str, lua_player: &mut LuaPlayer) -> bool {
+        let Some((command, args)) = tokenize_command(input) else {
+            return false;
+        };
+
+        let lua = self.lua.lock();
+        let file_name;
+        {
+            let state = lua.0.app_data_ref::<KawariLuaState>().unwrap();
+            let Some(command_script) = state.command_scripts.get(&command) else {
+                return false;
+            };
+            file_name = FilesystemConfig::locate_script_file(command_script);
+        }
+
+        if args.is_empty() {
+            tracing::info!("No additional args passed to Lua command {command}.");
+        } else {
+            tracing::info!("Args passed to Lua command {command}: {args:?}");
+        }
+
+        /* TODO: Instead of panicking we ought to send a message to the player
+         * and the console log, and abandon execution. */
+        let script = std::fs::read(&file_name)
+            .unwrap_or_else(|_| panic!("Failed to load script file {}!", &file_name));
+
+        if let Err(err) = run_command_script(
+            &lua.0,
+            &script,
+            &file_name,
+            self.player_data.character.gm_rank,
+            lua_player,
+            args,
+        ) {
+            tracing::warn!("Lua error in {file_name}: {:?}", err);
+        }
+
+        true
+    }
+
     pub async fn run_gm_command(
         &mut self,
         command: u32,
@@ -57,6 +172,23 @@ impl ZoneConnection {
         name: String,
         lua_player: &mut LuaPlayer,
     ) {
+        let can_run = {
+            let lua = self.lua.lock();
+            let state = lua.0.app_data_ref::<KawariLuaState>().unwrap();
+            state.can_run_gm_command(command, self.player_data.character.gm_rank)
+        };
+
+        if !can_run {
+            tracing::info!(
+                "User with account_id {} tried to invoke GM command {} with insufficient privileges!",
+                self.player_data.character.service_account_id,
+                command
+            );
+            self.send_notice("You do not have permission to use this command.")
+                .await;
+            return;
+        }
+
         let lua = self.lua.lock();
         let state = lua.0.app_data_ref::<KawariLuaState>().unwrap();
 
@@ -175,29 +307,68 @@ impl ZoneConnection {
                 true
             }
             "!item" => {
-                if let Some((_, name)) = chat_message.split_once(' ') {
-                    let mut result = None;
-                    {
-                        let mut gamedata = self.gamedata.lock();
+                if let Some((_, name)) = chat_message.split_once(' ')
+                    && self
+                        .add_item(ItemInfoQuery::ByName(name.to_string()), 1, true)
+                        .await
+                        == Err(AddItemError::UnknownItem)
+                {
+                    self.send_notice(&format!("[item] No items named {name:#?} were found!"))
+                        .await;
+                }
 
-                        if let Some(item_info) =
-                            gamedata.get_item_info(ItemInfoQuery::ByName(name.to_string()))
-                        {
-                            result = self
-                                .player_data
-                                .inventory
-                                .add_in_next_free_slot(Item::new(&item_info, 1));
-                        }
-                    }
+                true
+            }
+            "!aetherytes" => {
+                let aetherytes;
+                {
+                    let mut gamedata = self.gamedata.lock();
+                    aetherytes = gamedata.list_aetherytes(
+                        &self.player_data.aetheryte.unlocked,
+                        self.config.teleport_cost,
+                    );
+                }
 
-                    if result.is_some() {
-                        self.send_inventory().await;
-                    } else {
-                        tracing::error!(ERR_INVENTORY_ADD_FAILED);
-                        self.send_notice(ERR_INVENTORY_ADD_FAILED).await;
-                    }
+                if aetherytes.is_empty() {
+                    self.send_notice("[aetherytes] You haven't unlocked any aetherytes.")
+                        .await;
+                }
+
+                let lines: Vec<String> = aetherytes
+                    .iter()
+                    .map(|(id, name, cost)| format!("[aetherytes] {id}: {name} ({cost} gil)"))
+                    .collect();
+                self.send_notices(&lines).await;
+
+                true
+            }
+            "!finditem" => {
+                // Only the best few matches, so broad searches don't flood the chat log.
+                const MAX_RESULTS: usize = 10;
+
+                let Some((_, name)) = chat_message.split_once(' ') else {
+                    self.send_notice("[finditem] Usage: !finditem <name>").await;
+                    return true;
+                };
+
+                let items;
+                {
+                    let mut gamedata = self.gamedata.lock();
+                    items = gamedata.get_all_items_matching(name);
                 }
 
+                if items.is_empty() {
+                    self.send_notice(&format!("[finditem] No items named {name:#?} were found!"))
+                        .await;
+                }
+
+                let lines: Vec<String> = items
+                    .iter()
+                    .take(MAX_RESULTS)
+                    .map(|item| format!("[finditem] {}: {}", item.id, item.name))
+                    .collect();
+                self.send_notices(&lines).await;
+
                 true
             }
             "!reload" => {
@@ -205,6 +376,57 @@ impl ZoneConnection {
                 self.send_notice("Scripts reloaded!").await;
                 true
             }
+            "!reloadconfig" => {
+                let (applied, requires_restart) = self.reload_config();
+                if applied.is_empty() {
+                    self.send_notice("Config reloaded, nothing changed.").await;
+                } else {
+                    self.send_notice(&format!("Config reloaded, changed: {}", applied.join(", ")))
+                        .await;
+                }
+                if !requires_restart.is_empty() {
+                    self.send_notice(&format!(
+                        "These changes require a restart: {}",
+                        requires_restart.join(", ")
+                    ))
+                    .await;
+                }
+                true
+            }
+            "!broadcast" => {
+                if let Some((countdown, message)) = parse_broadcast_command(&chat_message) {
+                    self.handle
+                        .send(ToServer::Broadcast(message.to_string(), countdown))
+                        .await;
+                } else {
+                    self.send_notice(
+                        "[broadcast] Usage: !broadcast <minutes (optional)> <message>",
+                    )
+                    .await;
+                }
+
+                true
+            }
+            "!nearby" => {
+                // Roughly the range of most area actions.
+                const DEFAULT_RADIUS: f32 = 10.0;
+
+                let radius = parts
+                    .get(1)
+                    .and_then(|radius| radius.parse().ok())
+                    .unwrap_or(DEFAULT_RADIUS);
+                let actors = self
+                    .actors_in_radius(self.player_data.volatile.position, radius)
+                    .await;
+
+                let lines: Vec<String> = actors
+                    .iter()
+                    .map(|actor_id| format!("[nearby] {}", actor_id.0))
+                    .collect();
+                self.send_notices(&lines).await;
+
+                true
+            }
             "!finishevent" => {
                 self.event_finish(events).await;
                 self.send_notice("Current event forcefully finished.").await;
@@ -390,6 +612,33 @@ impl ZoneConnection {
                 .await;
                 true
             }
+            "!replay" => {
+                let Some(path) = parts.get(1) else {
+                    self.send_notice("[replay] Usage: !replay <path|stop> [speed] [loop]")
+                        .await;
+                    return true;
+                };
+
+                if *path == "stop" {
+                    if !self.stop_replay() {
+                        self.send_notice("[replay] Nothing is being replayed.")
+                            .await;
+                    }
+                    return true;
+                }
+
+                let options = ReplayOptions {
+                    speed: parts
+                        .get(2)
+                        .and_then(|speed| speed.parse().ok())
+                        .unwrap_or(1.0),
+                    loop_forever: parts.get(3) == Some(&"loop"),
+                    ..Default::default()
+                };
+                self.replay_packets(path, options).await;
+
+                true
+            }
             "!settime" => {
                 // TODO: Figure out how UTC is converted to Eorzean time and make this friendly by allowing for strings such as "6:30PM" or "18:30"
                 // TODO: Write the GM command equivalent which would just set the time offset directly as an i64/u64 (whichever this actually is)
@@ -403,3 +652,272 @@ impl ZoneConnection {
         }
     }
 }
+
+/// Splits a command like `!nudge 5 up` into its name and arguments. Arguments wrapped in double quotes can contain spaces.
+fn tokenize_command(input: &str) -> Option<(String, Vec<String>)> {
+    let input = input.strip_prefix(DEBUG_COMMAND_TRIGGER)?;
+
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                // Quoted arguments are kept even if they're empty.
+                if !current.is_empty() || quoted {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                quoted = false;
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() || quoted {
+        tokens.push(current);
+    }
+
+    let mut tokens = tokens.into_iter();
+    let command = tokens.next().filter(|command| !command.is_empty())?;
+
+    Some((command, tokens.collect()))
+}
+
+/// Runs a command `script`, first checking that `gm_rank` meets the script's `required_rank`.
+/// `args` are given to its `onCommand` function as a table.
+fn run_command_script(
+    lua: &mlua::Lua,
+    script: &[u8],
+    file_name: &str,
+    gm_rank: GameMasterRank,
+    lua_player: &mut LuaPlayer,
+    args: Vec<String>,
+) -> mlua::Result<()> {
+    lua.scope(|scope| {
+        let connection_data = scope.create_userdata_ref_mut(lua_player)?;
+
+        lua.load(script)
+            .set_name("@".to_string() + file_name)
+            .exec()?;
+
+        let required_rank = lua.globals().get("required_rank");
+        if let Err(error) = required_rank {
+            tracing::info!(
+                "Script is missing required_rank! Unable to run command, sending error to user. Additional information: {}",
+                error
+            );
+            let func: Function = lua.globals().get("onCommandRequiredRankMissingError")?;
+            func.call::<()>((error.to_string(), connection_data))?;
+            return Ok(());
+        }
+
+        /* Reset state for future commands. Without this it'll stay set to the last value
+         * and allow other commands that omit required_rank to run, which is undesirable. */
+        lua.globals().set("required_rank", mlua::Value::Nil)?;
+
+        if gm_rank as u8 >= required_rank? {
+            let func: Function = lua.globals().get("onCommand")?;
+            func.call::<()>((connection_data, args, String::default()))?;
+
+            /* `command_sender` is an optional variable scripts can define to identify themselves in print messages.
+             * It's okay if this global isn't set. We also don't care what its value is, just that it exists.
+             * This is reset -after- running the command intentionally. Resetting beforehand will never display the command's identifier.
+             */
+            let command_sender: mlua::Result<mlua::Value> = lua.globals().get("command_sender");
+            if command_sender.is_ok() {
+                lua.globals().set("command_sender", mlua::Value::Nil)?;
+            }
+        } else {
+            tracing::info!("Player tried to invoke command {file_name} with insufficient privileges!");
+            let func: Function = lua.globals().get("onCommandRequiredRankInsufficientError")?;
+            func.call::<()>(connection_data)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// The highest number that can be rolled, which is also the default.
+const MAX_RANDOM_ROLL: u32 = 999;
+
+/// If this is a `!random [max]` or `!dice [max]` command, returns what to roll up to.
+fn parse_random_command(message: &str) -> Option<u32> {
+    let mut parts = message.split_whitespace();
+    let command = parts.next()?.strip_prefix(DEBUG_COMMAND_TRIGGER)?;
+    if command != "random" && command != "dice" {
+        return None;
+    }
+
+    let max = parts
+        .next()
+        .and_then(|max| max.parse::<u32>().ok())
+        .unwrap_or(MAX_RANDOM_ROLL);
+
+    Some(max.clamp(1, MAX_RANDOM_ROLL))
+}
+
+/// If this is a command toggling an online status, like `!afk`, returns which status.
+fn parse_status_command(message: &str) -> Option<OnlineStatus> {
+    match message.trim().strip_prefix(DEBUG_COMMAND_TRIGGER)? {
+        "afk" => Some(OnlineStatus::AwayFromKeyboard),
+        "busy" => Some(OnlineStatus::Busy),
+        "lfp" => Some(OnlineStatus::LookingForParty),
+        "rp" => Some(OnlineStatus::RolePlaying),
+        _ => None,
+    }
+}
+
+/// If this is a `!who [filter]` command, returns the filter (if any) to list online players by.
+fn parse_who_command(message: &str) -> Option<Option<&str>> {
+    let rest = message
+        .strip_prefix(DEBUG_COMMAND_TRIGGER)?
+        .strip_prefix("who")?;
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+
+    let filter = rest.trim();
+    Some((!filter.is_empty()).then_some(filter))
+}
+
+/// If this is a `!broadcast [minutes] <message>` command, returns the countdown (if any) and the message to announce.
+fn parse_broadcast_command(message: &str) -> Option<(Option<u32>, &str)> {
+    let (_, rest) = message.split_once(' ')?;
+    let rest = rest.trim();
+
+    let (countdown, message) = match rest.split_once(' ') {
+        Some((minutes, message)) if minutes.parse::<u32>().is_ok() => {
+            (minutes.parse::<u32>().ok(), message.trim())
+        }
+        _ => (None, rest),
+    };
+
+    (!message.is_empty()).then_some((countdown, message))
+}
+
+/// Fills in the login message `template` for the character `name`, returning each non-empty line.
+fn format_login_message(template: &str, name: &str) -> Vec<String> {
+    template
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.replace("{name}", name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_broadcast_command() {
+        assert_eq!(
+            parse_broadcast_command("!broadcast Maintenance is starting!"),
+            Some((None, "Maintenance is starting!"))
+        );
+        assert_eq!(
+            parse_broadcast_command("!broadcast 10 Restarting for an update."),
+            Some((Some(10), "Restarting for an update."))
+        );
+        assert_eq!(parse_broadcast_command("!broadcast"), None);
+        assert_eq!(parse_broadcast_command("!broadcast 10"), Some((None, "10")));
+    }
+
+    #[test]
+    fn test_status_command() {
+        assert_eq!(
+            parse_status_command("!afk"),
+            Some(OnlineStatus::AwayFromKeyboard)
+        );
+        assert_eq!(parse_status_command("!busy"), Some(OnlineStatus::Busy));
+        assert_eq!(parse_status_command("!afkk"), None);
+    }
+
+    #[test]
+    fn test_who_command() {
+        assert_eq!(parse_who_command("!who"), Some(None));
+        assert_eq!(parse_who_command("!who Gridania"), Some(Some("Gridania")));
+        assert_eq!(
+            parse_who_command("!who  Test Player "),
+            Some(Some("Test Player"))
+        );
+        assert_eq!(parse_who_command("!whoami"), None);
+    }
+
+    #[test]
+    fn test_login_message() {
+        assert_eq!(
+            format_login_message("Welcome to Kawari, {name}!", "Test Player"),
+            vec!["Welcome to Kawari, Test Player!"]
+        );
+        assert_eq!(
+            format_login_message("Hi {name}\n\nMaintenance is at noon.", "Test Player"),
+            vec!["Hi Test Player", "Maintenance is at noon."]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_command() {
+        assert_eq!(
+            tokenize_command("!nudge 5 up"),
+            Some(("nudge".to_string(), vec!["5".to_string(), "up".to_string()]))
+        );
+        assert_eq!(
+            tokenize_command("!item   \"Potion of Strength\" 2"),
+            Some((
+                "item".to_string(),
+                vec!["Potion of Strength".to_string(), "2".to_string()]
+            ))
+        );
+        assert_eq!(
+            tokenize_command("!reload"),
+            Some(("reload".to_string(), Vec::new()))
+        );
+        assert_eq!(tokenize_command("!"), None);
+        assert_eq!(tokenize_command("hello"), None);
+    }
+
+    #[test]
+    fn test_run_command_script() {
+        let lua = mlua::Lua::new();
+        let script = b"required_rank = 0
+            function onCommand(player, args, name)
+                received = #args .. ':' .. args[1] .. ',' .. args[2]
+            end";
+
+        let (_, args) = tokenize_command("!stub first \"second arg\"").unwrap();
+        run_command_script(
+            &lua,
+            script,
+            "Stub.lua",
+            GameMasterRank::NormalUser,
+            &mut LuaPlayer::default(),
+            args,
+        )
+        .unwrap();
+
+        assert_eq!(
+            lua.globals().get::<String>("received").unwrap(),
+            "2:first,second arg"
+        );
+    }
+
+    #[test]
+    fn test_parse_random_command() {
+        assert_eq!(parse_random_command("!random"), Some(999));
+        assert_eq!(parse_random_command("!dice 20"), Some(20));
+        assert_eq!(parse_random_command("!random 5000"), Some(999));
+        assert_eq!(parse_random_command("!random abc"), Some(999));
+        assert_eq!(parse_random_command("!randomize"), None);
+        assert_eq!(parse_random_command("random"), None);
+    }
+
+    #[test]
+    fn test_empty_login_message() {
+        assert!(format_login_message("", "Test Player").is_empty());
+        assert!(format_login_message("  \n", "Test Player").is_empty());
+    }
+}
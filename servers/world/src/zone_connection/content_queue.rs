@@ -0,0 +1,82 @@
+use std::collections::VecDeque;
+
+/// The duties a player is currently queued for in the Duty Finder, in the order they registered for them.
+#[derive(Debug, Default, Clone)]
+pub struct ContentQueue {
+    contents: VecDeque<u16>,
+}
+
+impl ContentQueue {
+    /// Queues up `content_id`, if it isn't already in the queue.
+    pub fn push(&mut self, content_id: u16) {
+        if !self.contents.contains(&content_id) {
+            self.contents.push_back(content_id);
+        }
+    }
+
+    /// Removes and returns the content at the front of the queue, if there is any.
+    pub fn pop(&mut self) -> Option<u16> {
+        self.contents.pop_front()
+    }
+
+    /// Returns the content at the front of the queue without removing it.
+    pub fn front(&self) -> Option<u16> {
+        self.contents.front().copied()
+    }
+
+    /// Removes `content_id` from the queue, returning false if it wasn't queued.
+    pub fn cancel(&mut self, content_id: u16) -> bool {
+        let Some(index) = self.contents.iter().position(|id| *id == content_id) else {
+            return false;
+        };
+
+        self.contents.remove(index);
+        true
+    }
+
+    /// Removes every content from the queue.
+    pub fn clear(&mut self) {
+        self.contents.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.contents.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_content() {
+        let mut queue = ContentQueue::default();
+        queue.push(4);
+        assert_eq!(queue.front(), Some(4));
+        assert_eq!(queue.pop(), Some(4));
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_cancel_middle_content() {
+        let mut queue = ContentQueue::default();
+        queue.push(4);
+        queue.push(5);
+        queue.push(6);
+        queue.push(5); // Already queued, so it shouldn't be added again
+        assert_eq!(queue.len(), 3);
+
+        assert!(queue.cancel(5));
+        assert!(!queue.cancel(5));
+        assert_eq!(queue.len(), 2);
+
+        assert_eq!(queue.pop(), Some(4));
+        assert_eq!(queue.pop(), Some(6));
+        assert_eq!(queue.pop(), None);
+    }
+}
@@ -49,6 +49,16 @@ impl ZoneConnection {
 
     /// Finishes the current event, including resetting any conditions set during the start of said event.
     pub async fn event_finish(&mut self, events: &mut Vec<(Box<dyn EventHandler>, Event)>) {
+        self.event_finish_with(events, 1, None).await;
+    }
+
+    /// Same as `event_finish`, but lets you choose what `result` and `arg` are sent to the client.
+    pub async fn event_finish_with(
+        &mut self,
+        events: &mut Vec<(Box<dyn EventHandler>, Event)>,
+        result: u8,
+        arg: Option<u32>,
+    ) {
         if let Some(event) = events.pop() {
             let event_type = event.1.event_type;
             let event_arg = event.1.event_arg;
@@ -65,8 +75,8 @@ impl ZoneConnection {
                 let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::EventFinish {
                     handler_id: HandlerId(event_id),
                     event_type,
-                    result: 1,
-                    arg: event_arg,
+                    result,
+                    arg: arg.unwrap_or(event_arg),
                 });
                 self.send_ipc_self(ipc).await;
             }
@@ -2,10 +2,17 @@
 
 use crate::{
     ItemInfoQuery, ToServer, ZoneConnection,
-    inventory::{DesiredHousingInventoryPages, EQUIP_RESTRICTED, Storage},
+    inventory::{
+        AddItemError, CONDITION_LOSS_PER_ACTION, CrystalsStorage, CurrencyKind, CurrencyStorage,
+        DesiredHousingInventoryPages, EQUIP_RESTRICTED, Item, Storage, can_equip_in_slot,
+        can_glamour,
+    },
 };
 use kawari::{
-    common::{ContainerType, ItemOperationKind, LegacyEquipmentModelId, ObjectId, WeaponModelId},
+    common::{
+        ContainerType, HandlerId, ITEM_CONDITION_MAX, ItemOperationKind, LegacyEquipmentModelId,
+        LogMessageType, ObjectId, WeaponModelId,
+    },
     ipc::zone::{
         ActorControlCategory, ContainerInfo, CurrencyInfo, Equip, ItemInfo, ItemOperation,
         ServerZoneIpcData, ServerZoneIpcSegment,
@@ -43,9 +50,35 @@ impl ZoneConnection {
             .await;
     }
 
+    /// Recalculates the item level of our equipment, and informs the client of it.
+    ///
+    /// This should be called after any change to the equipped container, but note that `update_equip` already does this for anything going through `inform_equip`.
+    pub async fn refresh_item_level(&mut self) {
+        let level;
+        {
+            let mut game_data = self.gamedata.lock();
+
+            level = self
+                .player_data
+                .inventory
+                .equipped
+                .calculate_item_level(&mut game_data) as u32;
+        }
+
+        self.actor_control_self(ActorControlCategory::SetItemLevel { level })
+            .await;
+    }
+
     pub async fn send_inventory(&mut self) {
-        for (container_type, container) in (&self.player_data.inventory.clone()).into_iter() {
-            self.send_container(container, container_type).await;
+        // This is sent all at once (especially during login) so batch them into as few packets as possible.
+        let mut ipcs = Vec::new();
+        for (container_type, container) in (&self.player_data.inventory).into_iter() {
+            ipcs.append(&mut container_ipcs(
+                container,
+                container_type,
+                self.player_data.item_sequence,
+            ));
+            self.player_data.item_sequence += 1;
         }
 
         // Inform the client of dummy container states that are not implemented
@@ -54,15 +87,18 @@ impl ZoneConnection {
             ContainerType::Unk2,
             ContainerType::ArmoryWaist,
         ] {
-            let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ContainerInfo(ContainerInfo {
-                sequence: self.player_data.item_sequence,
-                num_items: 0,
-                container: container_type,
-                ..Default::default()
-            }));
-            self.send_ipc_self(ipc).await;
+            ipcs.push(ServerZoneIpcSegment::new(ServerZoneIpcData::ContainerInfo(
+                ContainerInfo {
+                    sequence: self.player_data.item_sequence,
+                    num_items: 0,
+                    container: container_type,
+                    ..Default::default()
+                },
+            )));
             self.player_data.item_sequence += 1;
         }
+
+        self.send_ipcs_self(ipcs).await;
     }
 
     /// Sends the updateitem and containerinfo packets for the equipped container.
@@ -98,46 +134,8 @@ impl ZoneConnection {
     }
 
     pub async fn send_container(&mut self, container: &dyn Storage, container_type: ContainerType) {
-        let mut num_items = 0;
-        for slot_index in 0..container.max_slots() {
-            let item = container.get_slot(slot_index as u16);
-            // Don't tell the client about things they don't have
-            if item.is_empty_slot() {
-                continue;
-            }
-
-            let ipc = match container_type {
-                ContainerType::Currency | ContainerType::Crystals => ServerZoneIpcSegment::new(
-                    ServerZoneIpcData::CurrencyCrystalInfo(CurrencyInfo {
-                        sequence: self.player_data.item_sequence,
-                        container: container_type,
-                        quantity: item.quantity,
-                        catalog_id: item.item_id,
-                        slot: slot_index as u16,
-                        ..Default::default()
-                    }),
-                ),
-                _ => ServerZoneIpcSegment::new(ServerZoneIpcData::UpdateItem(ItemInfo {
-                    sequence: self.player_data.item_sequence,
-                    container: container_type,
-                    slot: slot_index as u16,
-                    ..(*item).into()
-                })),
-            };
-
-            self.send_ipc_self(ipc).await;
-
-            num_items += 1;
-        }
-
-        // Inform the client of container state
-        let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ContainerInfo(ContainerInfo {
-            container: container_type,
-            num_items,
-            sequence: self.player_data.item_sequence,
-            ..Default::default()
-        }));
-        self.send_ipc_self(ipc).await;
+        let ipcs = container_ipcs(container, container_type, self.player_data.item_sequence);
+        self.send_ipcs_self(ipcs).await;
 
         self.player_data.item_sequence += 1;
     }
@@ -197,19 +195,7 @@ impl ZoneConnection {
 
         // TODO: get a capture of another player equipping stuff to see if we get this as well, but it seems unlikely.
         if self.player_data.character.actor_id == actor_id {
-            let level;
-            {
-                let mut game_data = self.gamedata.lock();
-
-                level = self
-                    .player_data
-                    .inventory
-                    .equipped
-                    .calculate_item_level(&mut game_data) as u32;
-            }
-
-            self.actor_control_self(ActorControlCategory::SetItemLevel { level })
-                .await;
+            self.refresh_item_level().await;
 
             // This seems to be pattern/crest related, it's seen when (un)equipping stuff.
             self.actor_control_self(ActorControlCategory::Unknown {
@@ -255,40 +241,18 @@ impl ZoneConnection {
         dst_container: ContainerType,
         dst_index: u16,
     ) {
-        let Some(src_item) = self
-            .player_data
-            .inventory
-            .get_item(src_container, src_index)
-        else {
+        let Some((dst_item, src_item)) = self.player_data.inventory.swap_items(
+            src_container,
+            src_index,
+            dst_container,
+            dst_index,
+        ) else {
             tracing::warn!(
-                "Unable to swap items: src_container was an invalid container for this operation: {src_container}!"
+                "Unable to swap items: {src_container} or {dst_container} is an invalid container for this operation!"
             );
             return;
         };
-
-        // move src item into dst slot
-        let Some(dst_slot) = self
-            .player_data
-            .inventory
-            .get_item_mut(dst_container, dst_index)
-        else {
-            tracing::warn!(
-                "Unable to swap items: dst_container was an invalid container for this operation: {dst_container}!"
-            );
-            return;
-        };
-
-        let dst_item = *dst_slot;
         let was_empty = dst_item.quantity == 0;
-        dst_slot.clone_from(&src_item);
-
-        // move dst item into src slot
-        let src_slot = self
-            .player_data
-            .inventory
-            .get_item_mut(src_container, src_index)
-            .unwrap(); // This unwrap should be fine since we've reached this point.
-        src_slot.clone_from(&dst_item);
 
         // Then inform the client of the updated slots, we have to do this since this is caused server-side.
         {
@@ -339,6 +303,375 @@ impl ZoneConnection {
         self.send_ipc_self(ipc).await;
     }
 
+    /// Equips the item in `src_storage` into `equip_slot`, swapping out the existing piece (if any.) Returns false if the item doesn't belong in that slot.
+    pub async fn equip_item(
+        &mut self,
+        src_storage: ContainerType,
+        src_index: u16,
+        equip_slot: u16,
+    ) -> bool {
+        if !self
+            .move_into_equipped_slot(src_storage, src_index, equip_slot)
+            .await
+        {
+            return false;
+        }
+
+        // This also refreshes our item level, see update_equip.
+        self.inform_equip().await;
+
+        true
+    }
+
+    /// Checks and moves the item for `equip_item`, but doesn't inform anyone of our new appearance. Useful when equipping several items at once.
+    pub async fn move_into_equipped_slot(
+        &mut self,
+        src_storage: ContainerType,
+        src_index: u16,
+        equip_slot: u16,
+    ) -> bool {
+        let Some(item) = self.player_data.inventory.get_item(src_storage, src_index) else {
+            tracing::warn!("Can't equip from {src_storage}, it's not a valid container!");
+            return false;
+        };
+
+        if item.is_empty_slot() {
+            return false;
+        }
+
+        let category_slot;
+        {
+            let mut game_data = self.gamedata.lock();
+            category_slot = game_data
+                .get_item_info(ItemInfoQuery::ById(item.item_id))
+                .and_then(|info| game_data.get_equipslot_category(info.equip_category as u8));
+        }
+
+        let Some(category_slot) = category_slot else {
+            tracing::warn!("Item {} can't be equipped!", item.item_id);
+            return false;
+        };
+
+        if !can_equip_in_slot(category_slot, equip_slot) {
+            tracing::warn!(
+                "Item {} can't be equipped in slot {equip_slot}, it belongs in {category_slot}!",
+                item.item_id
+            );
+            return false;
+        }
+
+        self.swap_items(src_storage, src_index, ContainerType::Equipped, equip_slot)
+            .await;
+
+        true
+    }
+
+    /// Projects `glamour_item_id` onto the item in `storage` at `index`, or removes its glamour if zero.
+    ///
+    /// Returns false if there's no item there, or the glamour doesn't share its equip category.
+    pub async fn apply_glamour(
+        &mut self,
+        storage: ContainerType,
+        index: u16,
+        glamour_item_id: u32,
+    ) -> bool {
+        let Some(item) = self.player_data.inventory.get_item(storage, index) else {
+            tracing::warn!("Can't glamour in {storage}, it's not a valid container!");
+            return false;
+        };
+
+        if item.is_empty_slot() {
+            return false;
+        }
+
+        if glamour_item_id != 0 {
+            let compatible;
+            {
+                let mut game_data = self.gamedata.lock();
+                let item_info = game_data.get_item_info(ItemInfoQuery::ById(item.item_id));
+                let glamour_info = game_data.get_item_info(ItemInfoQuery::ById(glamour_item_id));
+                compatible = match (item_info, glamour_info) {
+                    (Some(item_info), Some(glamour_info)) => can_glamour(
+                        item_info.equip_category as u8,
+                        glamour_info.equip_category as u8,
+                    ),
+                    _ => false,
+                };
+            }
+
+            if !compatible {
+                tracing::warn!(
+                    "Item {glamour_item_id} can't be used as a glamour for {}!",
+                    item.item_id
+                );
+                return false;
+            }
+        }
+
+        let Some(item) = self.player_data.inventory.get_item_mut(storage, index) else {
+            return false;
+        };
+        item.glamour_id = glamour_item_id;
+        let item = *item;
+
+        let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::UpdateInventorySlot(ItemInfo {
+            sequence: self.player_data.item_sequence,
+            container: storage,
+            slot: index,
+            ..item.into()
+        }));
+        self.send_ipc_self(ipc).await;
+        self.player_data.item_sequence += 1;
+
+        self.inform_equip().await;
+
+        true
+    }
+
+    /// Adds `quantity` of the item matching `query` to our inventory. If there's no room for it, the player is told why.
+    pub async fn add_item(
+        &mut self,
+        query: ItemInfoQuery,
+        quantity: u32,
+        send_client_update: bool,
+    ) -> Result<(), AddItemError> {
+        let new_item;
+        {
+            let mut game_data = self.gamedata.lock();
+            new_item = game_data
+                .get_item_info(query)
+                .map(|x| Item::new(&x, quantity));
+        }
+
+        let Some(new_item) = new_item else {
+            return Err(AddItemError::UnknownItem);
+        };
+
+        match self
+            .player_data
+            .inventory
+            .try_add_in_next_free_slot(new_item)
+        {
+            Ok(_) => {
+                if send_client_update {
+                    self.send_inventory().await;
+                }
+                Ok(())
+            }
+            Err(err) => {
+                tracing::warn!("Can't add item {}: {err}", new_item.item_id);
+                self.send_notice(&err.to_string()).await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Tells the player they obtained `quantity` of `item_id` in their chat log, like when receiving quest rewards.
+    pub async fn send_item_obtained_message(&mut self, item_id: u32, quantity: u32) {
+        let stackable;
+        {
+            let mut game_data = self.gamedata.lock();
+            stackable = game_data
+                .get_item_info(ItemInfoQuery::ById(item_id))
+                .is_some_and(|x| x.stack_size > 1);
+        }
+
+        let ipc = ServerZoneIpcSegment::new(item_obtained_log_message(
+            self.event_handler_id.unwrap_or_default(),
+            item_id,
+            quantity,
+            stackable,
+        ));
+        self.send_ipc_self(ipc).await;
+    }
+
+    /// Removes `quantity` of `item_id` from anywhere in the inventory. Returns false if the player doesn't have enough.
+    pub async fn remove_item(&mut self, item_id: u32, quantity: u32) -> bool {
+        let Some(changed) = self.player_data.inventory.remove_item(item_id, quantity) else {
+            return false;
+        };
+
+        for info in changed {
+            let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::UpdateInventorySlot(ItemInfo {
+                sequence: self.player_data.item_sequence,
+                ..info
+            }));
+            self.send_ipc_self(ipc).await;
+            self.player_data.item_sequence += 1;
+        }
+
+        true
+    }
+
+    /// Gives the player a key item, and informs the client. Returns false if they already have it.
+    pub async fn add_key_item(&mut self, id: u32) -> bool {
+        let Some(info) = self.player_data.inventory.add_key_item(id) else {
+            return false;
+        };
+
+        self.send_key_item_update(info).await;
+        true
+    }
+
+    /// Takes away a key item, and informs the client. Returns false if they didn't have it.
+    pub async fn remove_key_item(&mut self, id: u32) -> bool {
+        let Some(info) = self.player_data.inventory.remove_key_item(id) else {
+            return false;
+        };
+
+        self.send_key_item_update(info).await;
+        true
+    }
+
+    async fn send_key_item_update(&mut self, info: ItemInfo) {
+        let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::UpdateInventorySlot(ItemInfo {
+            sequence: self.player_data.item_sequence,
+            ..info
+        }));
+        self.send_ipc_self(ipc).await;
+        self.player_data.item_sequence += 1;
+    }
+
+    /// Wears down the condition of our equipment, this is done after each action.
+    pub async fn decay_equipment(&mut self) {
+        let changed = self
+            .player_data
+            .inventory
+            .equipped
+            .decay_condition(CONDITION_LOSS_PER_ACTION);
+
+        let any_broken = changed.iter().any(|slot| {
+            self.player_data
+                .inventory
+                .equipped
+                .get_slot(*slot)
+                .is_broken()
+        });
+        self.send_equipped_slots(&changed).await;
+
+        // Broken equipment no longer counts towards our item level.
+        if any_broken {
+            self.refresh_item_level().await;
+        }
+    }
+
+    /// How much gil selling `quantity` of the item at `index` in `storage` to a shop would give, or zero if there's nothing to sell.
+    pub fn preview_sale(&mut self, storage: ContainerType, index: u16, quantity: u32) -> u32 {
+        let Some(item) = self.player_data.inventory.get_item(storage, index) else {
+            return 0;
+        };
+        if item.is_empty_slot() {
+            return 0;
+        }
+
+        let mut game_data = self.gamedata.lock();
+        game_data
+            .get_item_info(ItemInfoQuery::ById(item.item_id))
+            .map(|item_info| sale_price(item_info.price_low, quantity.min(item.quantity)))
+            .unwrap_or_default()
+    }
+
+    /// Restores the condition of the item in `container` at `index`, charging `gil_cost` if it's not zero.
+    pub async fn repair_item(&mut self, container: ContainerType, index: u16, gil_cost: u32) {
+        let Some(item) = self.player_data.inventory.get_item(container, index) else {
+            tracing::warn!("Can't repair in {container}, it's not a valid container!");
+            return;
+        };
+
+        if item.is_empty_slot() || item.condition == ITEM_CONDITION_MAX {
+            return;
+        }
+
+        if !self.pay_for_repair(gil_cost).await {
+            return;
+        }
+
+        let Some(item) = self.player_data.inventory.get_item_mut(container, index) else {
+            return;
+        };
+        item.repair();
+        let item = *item;
+
+        let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::UpdateInventorySlot(ItemInfo {
+            sequence: self.player_data.item_sequence,
+            container,
+            slot: index,
+            ..item.into()
+        }));
+        self.send_ipc_self(ipc).await;
+        self.player_data.item_sequence += 1;
+
+        if container == ContainerType::Equipped {
+            self.refresh_item_level().await;
+        }
+    }
+
+    /// Restores the condition of all of our equipment, charging `gil_cost` if it's not zero.
+    pub async fn repair_all(&mut self, gil_cost: u32) {
+        // Don't charge for a repair that wouldn't do anything.
+        if self
+            .player_data
+            .inventory
+            .equipped
+            .damaged_slots()
+            .is_empty()
+        {
+            return;
+        }
+
+        if !self.pay_for_repair(gil_cost).await {
+            return;
+        }
+
+        let changed = self.player_data.inventory.equipped.repair_all();
+        self.send_equipped_slots(&changed).await;
+        self.refresh_item_level().await;
+    }
+
+    /// Deducts `gil_cost` from the player. If they can't afford it, they're told so and this returns false.
+    async fn pay_for_repair(&mut self, gil_cost: u32) -> bool {
+        if gil_cost == 0 {
+            return true;
+        }
+
+        if !self.player_data.inventory.currency.try_spend_gil(gil_cost) {
+            self.send_notice(&format!("You need {gil_cost} gil to repair that."))
+                .await;
+            return false;
+        }
+
+        let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::UpdateInventorySlot(ItemInfo {
+            sequence: self.player_data.item_sequence,
+            container: ContainerType::Currency,
+            slot: CurrencyStorage::GIL_SLOT,
+            ..self.player_data.inventory.currency.gil.into()
+        }));
+        self.send_ipc_self(ipc).await;
+        self.player_data.item_sequence += 1;
+
+        true
+    }
+
+    /// Informs the client of the current state of the equipped `slots`, all in one packet.
+    async fn send_equipped_slots(&mut self, slots: &[u16]) {
+        let mut ipcs = Vec::new();
+        for slot in slots {
+            let item = *self.player_data.inventory.equipped.get_slot(*slot);
+
+            ipcs.push(ServerZoneIpcSegment::new(
+                ServerZoneIpcData::UpdateInventorySlot(ItemInfo {
+                    sequence: self.player_data.item_sequence,
+                    container: ContainerType::Equipped,
+                    slot: *slot,
+                    ..item.into()
+                }),
+            ));
+            self.player_data.item_sequence += 1;
+        }
+
+        self.send_ipcs_self(ipcs).await;
+    }
+
     /// Changes the class based on the weapon equipped.
     pub async fn change_class_based_on_weapon(&mut self) {
         // Check the weapon's compatible class jobs:
@@ -444,3 +777,222 @@ impl ZoneConnection {
         }
     }
 }
+
+/// Creates the packets describing every item in `container`, followed by its container info.
+fn container_ipcs(
+    container: &dyn Storage,
+    container_type: ContainerType,
+    sequence: u32,
+) -> Vec<ServerZoneIpcSegment> {
+    let mut ipcs = Vec::new();
+    let mut num_items = 0;
+    for slot_index in 0..container.max_slots() {
+        let item = container.get_slot(slot_index as u16);
+        // Don't tell the client about things they don't have
+        if item.is_empty_slot() {
+            continue;
+        }
+
+        let ipc = match container_type {
+            ContainerType::Currency | ContainerType::Crystals => {
+                let quantity = capped_quantity(container_type, item);
+                if quantity != item.quantity {
+                    tracing::warn!(
+                        "{} of item {} is over the cap, sending {quantity} instead!",
+                        item.quantity,
+                        item.item_id
+                    );
+                }
+
+                ServerZoneIpcSegment::new(ServerZoneIpcData::CurrencyCrystalInfo(CurrencyInfo {
+                    sequence,
+                    container: container_type,
+                    quantity,
+                    catalog_id: item.item_id,
+                    slot: slot_index as u16,
+                    ..Default::default()
+                }))
+            }
+            _ => ServerZoneIpcSegment::new(ServerZoneIpcData::UpdateItem(ItemInfo {
+                sequence,
+                container: container_type,
+                slot: slot_index as u16,
+                ..(*item).into()
+            })),
+        };
+
+        ipcs.push(ipc);
+
+        num_items += 1;
+    }
+
+    // Inform the client of container state
+    let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::ContainerInfo(ContainerInfo {
+        container: container_type,
+        num_items,
+        sequence,
+        ..Default::default()
+    }));
+    ipcs.push(ipc);
+
+    ipcs
+}
+
+/// Creates the "You obtained" log message for `quantity` of `item_id`.
+fn item_obtained_log_message(
+    handler_id: HandlerId,
+    item_id: u32,
+    quantity: u32,
+    stackable: bool,
+) -> ServerZoneIpcData {
+    let (message_type, item_quantity) = if stackable && quantity > 1 {
+        (LogMessageType::ItemsObtained, quantity)
+    } else {
+        (LogMessageType::ItemObtained, 0)
+    };
+
+    ServerZoneIpcData::LogMessage {
+        handler_id,
+        message_type: message_type as u32,
+        params_count: 2,
+        item_id,
+        item_quantity,
+    }
+}
+
+/// Shops pay the same for an item regardless of its condition, just like retail.
+fn sale_price(price_low: u32, quantity: u32) -> u32 {
+    quantity.saturating_mul(price_low)
+}
+
+/// Returns the quantity of a currency or crystal, but no more than can actually be held.
+fn capped_quantity(container_type: ContainerType, item: &Item) -> u32 {
+    let max_quantity = match container_type {
+        ContainerType::Currency => CurrencyKind::from_repr(item.item_id).map(|x| x.max_quantity()),
+        ContainerType::Crystals => Some(CrystalsStorage::MAX_QUANTITY),
+        _ => None,
+    };
+
+    match max_quantity {
+        Some(max_quantity) => item.quantity.min(max_quantity),
+        None => item.quantity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        inventory::{CrystalKind, Inventory},
+        zone_connection::MAX_SEGMENTS_PER_PACKET,
+    };
+
+    #[test]
+    fn test_inventory_segment_count() {
+        let mut inventory = Inventory::default();
+        for slot in 0..30 {
+            inventory.pages[0].slots[slot] = Item {
+                item_id: 4551,
+                quantity: 1,
+                ..Default::default()
+            };
+        }
+        inventory.currency.gil.quantity = 1000;
+
+        let mut segments = 0;
+        let mut containers = 0;
+        for (container_type, container) in (&inventory).into_iter() {
+            let ipcs = container_ipcs(container, container_type, 0);
+
+            // Empty slots aren't sent, but the container info always is
+            if container_type == ContainerType::Inventory0 {
+                assert_eq!(ipcs.len(), 31);
+            }
+            assert!(matches!(
+                ipcs.last().map(|ipc| &ipc.data),
+                Some(ServerZoneIpcData::ContainerInfo(_))
+            ));
+
+            segments += ipcs.len();
+            containers += 1;
+        }
+
+        // 30 items, 1 gil and a container info for each container
+        assert_eq!(segments, 31 + containers);
+
+        // Instead of a packet for each one
+        assert!(segments.div_ceil(MAX_SEGMENTS_PER_PACKET) < segments / 10);
+    }
+
+    #[test]
+    fn test_item_obtained_message_type() {
+        let ServerZoneIpcData::LogMessage {
+            message_type,
+            item_quantity,
+            ..
+        } = item_obtained_log_message(HandlerId::default(), 4551, 5, true)
+        else {
+            panic!("Expected a LogMessage!");
+        };
+        assert_eq!(message_type, LogMessageType::ItemsObtained as u32);
+        assert_eq!(item_quantity, 5);
+
+        // Only one, or not stackable at all
+        for (quantity, stackable) in [(1, true), (1, false)] {
+            let ServerZoneIpcData::LogMessage {
+                message_type,
+                item_quantity,
+                ..
+            } = item_obtained_log_message(HandlerId::default(), 4551, quantity, stackable)
+            else {
+                panic!("Expected a LogMessage!");
+            };
+            assert_eq!(message_type, LogMessageType::ItemObtained as u32);
+            assert_eq!(item_quantity, 0);
+        }
+    }
+
+    #[test]
+    fn test_over_cap_currency() {
+        let gil = Item {
+            item_id: CurrencyKind::Gil as u32,
+            quantity: CurrencyKind::Gil.max_quantity() + 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            capped_quantity(ContainerType::Currency, &gil),
+            CurrencyKind::Gil.max_quantity()
+        );
+
+        let poetics = Item {
+            item_id: CurrencyKind::TomestonePoetics as u32,
+            quantity: 1500,
+            ..Default::default()
+        };
+        assert_eq!(capped_quantity(ContainerType::Currency, &poetics), 1500);
+
+        let shards = Item {
+            item_id: CrystalKind::FireShard as u32,
+            quantity: 10_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            capped_quantity(ContainerType::Crystals, &shards),
+            CrystalsStorage::MAX_QUANTITY
+        );
+    }
+
+    #[test]
+    fn test_sale_preview_matches_credit() {
+        let preview = sale_price(4, 99);
+        assert_eq!(preview, 396);
+
+        // This is what the shop does when the sale goes through
+        let mut currency = CurrencyStorage::default();
+        assert_eq!(currency.add_gil(preview), preview);
+        assert_eq!(currency.gil.quantity, preview);
+
+        assert_eq!(sale_price(u32::MAX, 2), u32::MAX);
+    }
+}
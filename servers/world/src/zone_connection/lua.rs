@@ -3,14 +3,11 @@
 use crate::{
     Event, ItemInfoQuery, ToServer, ZoneConnection,
     event::EventHandler,
-    inventory::{CrystalsStorage, CurrencyStorage, Item},
+    inventory::{AddItemError, CrystalsStorage, Item},
     lua::{LuaPlayer, LuaTask},
 };
 use kawari::{
-    common::{
-        ContainerType, DirectorEvent, ERR_INVENTORY_ADD_FAILED, HandlerId, InstanceContentType,
-        ObjectTypeId, ObjectTypeKind,
-    },
+    common::{ContainerType, DirectorEvent, HandlerId, ObjectTypeId, ObjectTypeKind},
     constants::{
         ADVENTURE_BITMASK_SIZE, AETHER_CURRENT_BITMASK_SIZE,
         AETHER_CURRENT_COMP_FLG_SET_BITMASK_SIZE, BUDDY_EQUIP_BITMASK_SIZE,
@@ -64,8 +61,8 @@ impl ZoneConnection {
                     self.warp(*warp_id).await;
                 }
                 LuaTask::BeginLogOut => self.begin_log_out().await,
-                LuaTask::FinishEvent {} => {
-                    self.event_finish(events).await;
+                LuaTask::FinishEvent { result, arg } => {
+                    self.event_finish_with(events, *result, *arg).await;
                     run_finish_event = true;
                 }
                 LuaTask::UnlockClassJob { classjob_id } => {
@@ -133,6 +130,15 @@ impl ZoneConnection {
                     })
                     .await;
                 }
+                LuaTask::UnlockAction { action_id } => {
+                    let unlock_link;
+                    {
+                        let mut game_data = self.gamedata.lock();
+                        unlock_link = game_data.get_action_unlock_link(*action_id).unwrap_or(0);
+                    }
+
+                    self.unlock_action(*action_id, unlock_link).await;
+                }
                 LuaTask::UnlockAll {} => {
                     self.player_data.unlock.unlocks.set_all();
                 }
@@ -167,34 +173,35 @@ impl ZoneConnection {
                     }
                 }
                 LuaTask::SetLevel { level } => {
-                    self.set_current_level(*level);
+                    let Some(old_level) = self.set_current_level(*level) else {
+                        continue;
+                    };
+
+                    self.learn_actions_for_level(old_level, *level).await;
                     self.update_class_info().await;
                     self.send_stats().await; // Needed because stats change based on level.
                 }
                 LuaTask::ChangeWeather { id } => {
-                    self.change_weather(*id).await;
+                    self.force_weather(*id).await;
                 }
                 LuaTask::ModifyCurrency {
                     id,
                     amount,
                     send_client_update,
                 } => {
-                    let slot = self.player_data.inventory.currency.get_item_for_id(*id);
-
-                    if *amount > 0 {
-                        slot.quantity = slot.quantity.saturating_add(*amount as u32);
-                    } else {
-                        slot.quantity = slot.quantity.saturating_sub(-(*amount) as u32);
-                    }
+                    let Some((slot_index, slot)) =
+                        self.player_data.inventory.currency.modify(*id, *amount)
+                    else {
+                        tracing::warn!("We don't know where to store {id}, ignoring!");
+                        continue;
+                    };
 
                     if *send_client_update {
-                        let slot = *slot;
-
                         let ipc = ServerZoneIpcSegment::new(
                             ServerZoneIpcData::UpdateInventorySlot(ItemInfo {
                                 sequence: self.player_data.item_sequence,
                                 container: ContainerType::Currency,
-                                slot: CurrencyStorage::get_slot_for_id(*id),
+                                slot: slot_index,
                                 ..slot.into()
                             }),
                         );
@@ -234,73 +241,90 @@ impl ZoneConnection {
                 LuaTask::ToggleOrchestrion { id } => {
                     self.toggle_orchestrion(*id).await;
                 }
+                LuaTask::UnlockOrchestrionAll {} => {
+                    self.unlock_all_orchestrion().await;
+                }
                 LuaTask::AddItem {
                     id,
                     quantity,
                     send_client_update,
+                    announce,
                 } => {
-                    let new_item: Option<Item>;
+                    match self
+                        .add_item(ItemInfoQuery::ById(*id), *quantity, *send_client_update)
+                        .await
                     {
-                        let mut game_data = self.gamedata.lock();
-                        new_item = game_data
-                            .get_item_info(ItemInfoQuery::ById(*id))
-                            .map(|x| Item::new(&x, *quantity));
-                    }
-                    if let Some(new_item) = new_item {
-                        if self
-                            .player_data
-                            .inventory
-                            .add_in_next_free_slot(new_item)
-                            .is_some()
-                        {
-                            if *send_client_update {
-                                self.send_inventory().await;
+                        Ok(()) => {
+                            if *announce {
+                                self.send_item_obtained_message(*id, *quantity).await;
                             }
-                        } else {
-                            tracing::error!(ERR_INVENTORY_ADD_FAILED);
-                            self.send_notice(ERR_INVENTORY_ADD_FAILED).await;
                         }
-                    } else {
-                        tracing::error!(ERR_INVENTORY_ADD_FAILED);
-                        self.send_notice(ERR_INVENTORY_ADD_FAILED).await;
+                        Err(AddItemError::UnknownItem) => {
+                            tracing::warn!("Can't give item {id}, it doesn't exist!");
+                            self.send_notice(&format!("No item with id {id} was found!"))
+                                .await;
+                        }
+                        // The player was already told why.
+                        Err(_) => {}
+                    }
+                }
+                LuaTask::RemoveItem { id, quantity } => {
+                    if !self.remove_item(*id, *quantity).await {
+                        tracing::warn!(
+                            "Can't remove {quantity} of item {id}, the player doesn't have enough!"
+                        );
+                    }
+                }
+                LuaTask::AddKeyItem { id } => {
+                    if !self.add_key_item(*id).await {
+                        tracing::warn!(
+                            "Can't give key item {id}, the player already has it or it isn't a key item!"
+                        );
+                    }
+                }
+                LuaTask::RemoveKeyItem { id } => {
+                    if !self.remove_key_item(*id).await {
+                        tracing::warn!("Can't remove key item {id}, the player doesn't have it!");
+                    }
+                }
+                LuaTask::AddItemByName { name, quantity } => {
+                    if self
+                        .add_item(ItemInfoQuery::ByName(name.clone()), *quantity, true)
+                        .await
+                        == Err(AddItemError::UnknownItem)
+                    {
+                        self.send_notice(&format!("No items named {name:#?} were found!"))
+                            .await;
+                    }
+                }
+                LuaTask::SortInventory { container, key } => {
+                    let changed = self.player_data.inventory.sort_container(*container, *key);
+                    for slot in changed {
+                        let Some(item) = self.player_data.inventory.get_item(*container, slot)
+                        else {
+                            continue;
+                        };
+
+                        let ipc = ServerZoneIpcSegment::new(
+                            ServerZoneIpcData::UpdateInventorySlot(ItemInfo {
+                                sequence: self.player_data.item_sequence,
+                                container: *container,
+                                slot,
+                                ..item.into()
+                            }),
+                        );
+                        self.send_ipc_self(ipc).await;
                     }
                 }
                 LuaTask::UnlockContent { id } => {
                     {
                         let mut game_data = self.gamedata.lock();
                         if let Some(instance_content_type) = game_data.find_type_for_content(*id) {
-                            // Each id has to be subtracted by it's offset in the InstanceContent Excel sheet. For example, all guildheists start at ID 10000.
-                            match instance_content_type {
-                                InstanceContentType::Dungeon => {
-                                    self.player_data
-                                        .content
-                                        .unlocked_dungeons
-                                        .set(*id as u32 - 1);
-                                }
-                                InstanceContentType::Raid => {
-                                    self.player_data
-                                        .content
-                                        .unlocked_raids
-                                        .set(*id as u32 - 30001);
-                                }
-                                InstanceContentType::Guildhests => {
-                                    self.player_data
-                                        .content
-                                        .unlocked_guildhests
-                                        .set(*id as u32 - 10001);
-                                }
-                                InstanceContentType::Trial => {
-                                    self.player_data
-                                        .content
-                                        .unlocked_trials
-                                        .set(*id as u32 - 20001);
-                                }
-                                _ => {
-                                    tracing::warn!(
-                                        "Not sure what to do about {instance_content_type:?} {id}!"
-                                    );
-                                }
-                            };
+                            if !self.player_data.content.unlock(&instance_content_type, *id) {
+                                tracing::warn!(
+                                    "Not sure what to do about {instance_content_type:?} {id}!"
+                                );
+                            }
                         } else {
                             tracing::warn!("Unknown content {id}!");
                         }
@@ -312,6 +336,9 @@ impl ZoneConnection {
                     })
                     .await;
                 }
+                LuaTask::CompleteContent { id } => {
+                    self.complete_content(*id).await;
+                }
                 LuaTask::UnlockAllContent {} => {
                     self.player_data.content.unlocked_special_content.set_all();
                     self.player_data.content.unlocked_raids.set_all();
@@ -366,6 +393,19 @@ impl ZoneConnection {
                     })
                     .await;
                 }
+                LuaTask::UnlockMount { id } => {
+                    self.unlock_mount(*id).await;
+                }
+                LuaTask::SetMount { mount_id } => {
+                    if !self.set_mount(*mount_id).await {
+                        self.send_notice("You haven't unlocked this mount.").await;
+                    }
+                }
+                LuaTask::SummonMinion { minion_id } => {
+                    if !self.summon_minion(*minion_id).await {
+                        self.send_notice("You haven't unlocked this minion.").await;
+                    }
+                }
                 LuaTask::MoveToPopRange { id, fade_out } => {
                     self.handle
                         .send(ToServer::MoveToPopRange(
@@ -385,6 +425,9 @@ impl ZoneConnection {
                         ))
                         .await;
                 }
+                LuaTask::SetEObjState { eobj_id, state } => {
+                    self.set_eobj_state(*eobj_id, *state).await;
+                }
                 LuaTask::SetMP { mp } => {
                     self.handle
                         .send(ToServer::SetMP(
@@ -498,6 +541,9 @@ impl ZoneConnection {
                         self.toggle_minion(i).await;
                     }
                 }
+                LuaTask::UnlockMinion { id } => {
+                    self.unlock_minion(*id).await;
+                }
                 LuaTask::ToggleAetherCurrent { id } => {
                     self.toggle_aether_current(*id).await;
                 }
@@ -562,6 +608,11 @@ impl ZoneConnection {
                     }
                     self.respawn_player(false).await;
                 }
+                LuaTask::SetAppearance { customize } => {
+                    if !self.set_appearance(customize.clone()).await {
+                        self.send_notice("That appearance isn't possible.").await;
+                    }
+                }
                 LuaTask::SendSegment { segment } => {
                     self.send_segment(segment.clone()).await;
                 }
@@ -598,6 +649,23 @@ impl ZoneConnection {
                 } => {
                     self.gain_effect(*effect_id, *effect_param, *duration).await;
                 }
+                LuaTask::LoseStatusEffect {
+                    effect_id,
+                    effect_param,
+                    effect_source_actor_id,
+                } => {
+                    self.lose_effect(*effect_id, *effect_param, *effect_source_actor_id)
+                        .await;
+                }
+                LuaTask::MoveActor {
+                    actor_id,
+                    waypoints,
+                    speed,
+                } => {
+                    self.handle
+                        .send(ToServer::MoveActor(*actor_id, waypoints.clone(), *speed))
+                        .await;
+                }
                 LuaTask::RegisterForContent { content_id } => {
                     self.register_for_content([*content_id, 0, 0, 0, 0]).await;
                 }
@@ -655,15 +723,7 @@ impl ZoneConnection {
                 }
                 LuaTask::AbandonContent {} => {
                     // Signal to the global server to leave this content.
-                    self.handle
-                        .send(ToServer::LeaveContent(
-                            self.id,
-                            self.player_data.character.actor_id,
-                            self.old_zone_id,
-                            self.old_position,
-                            self.old_rotation,
-                        ))
-                        .await;
+                    self.leave_content().await;
                 }
                 LuaTask::SetHomepoint { homepoint } => {
                     self.player_data.aetheryte.homepoint = *homepoint as i32;
@@ -674,6 +734,39 @@ impl ZoneConnection {
                     })
                     .await;
                 }
+                LuaTask::Emote { emote_id, target } => {
+                    self.do_emote(*emote_id, *target).await;
+                }
+                LuaTask::SpawnNpc {
+                    enpc_id,
+                    position,
+                    rotation,
+                } => {
+                    self.spawn_npc(*enpc_id, *position, *rotation).await;
+                }
+                LuaTask::Revive { kind } => {
+                    self.revive(*kind).await;
+                }
+                LuaTask::DecayEquipment {} => {
+                    self.decay_equipment().await;
+                }
+                LuaTask::RepairItem {
+                    container,
+                    index,
+                    gil_cost,
+                } => {
+                    self.repair_item(*container, *index, *gil_cost).await;
+                }
+                LuaTask::RepairAll { gil_cost } => {
+                    self.repair_all(*gil_cost).await;
+                }
+                LuaTask::EquipItem {
+                    container,
+                    index,
+                    equip_slot,
+                } => {
+                    self.equip_item(*container, *index, *equip_slot).await;
+                }
                 LuaTask::ReturnToHomepoint {} => {
                     self.warp_aetheryte(self.player_data.aetheryte.homepoint as u32, false, false)
                         .await;
@@ -698,19 +791,13 @@ impl ZoneConnection {
                         else {
                             return true;
                         };
-                        let Some(dst_slot) = self
-                            .player_data
-                            .inventory
-                            .get_item_mut(dst_container_type, dst_container_index as u16)
-                        else {
-                            return true;
-                        };
 
-                        dst_slot.glamour_id = src_slot.item_id;
-
-                        // The client needs to be informed about the new glamoured item, but this is extreme...
-                        self.send_inventory().await;
-                        self.inform_equip().await;
+                        self.apply_glamour(
+                            dst_container_type,
+                            dst_container_index as u16,
+                            src_slot.item_id,
+                        )
+                        .await;
 
                         self.send_conditions().await; // So the client gets unstuck.
                     }
@@ -719,19 +806,8 @@ impl ZoneConnection {
                         dst_container_index,
                     }) = self.glamour_information
                     {
-                        let Some(dst_slot) = self
-                            .player_data
-                            .inventory
-                            .get_item_mut(dst_container_type, dst_container_index as u16)
-                        else {
-                            return true;
-                        };
-
-                        dst_slot.glamour_id = 0;
-
-                        // The client needs to be informed about the new glamoured item, but this is extreme...
-                        self.send_inventory().await;
-                        self.inform_equip().await;
+                        self.apply_glamour(dst_container_type, dst_container_index as u16, 0)
+                            .await;
 
                         self.send_conditions().await; // So the client gets unstuck.
                     }
@@ -795,6 +871,9 @@ impl ZoneConnection {
                 LuaTask::SendMailboxStatus {} => {
                     self.send_mailbox_status().await;
                 }
+                LuaTask::ReportPlaytime {} => {
+                    self.send_playtime().await;
+                }
                 LuaTask::SetGrandCompany { company } => {
                     self.set_grand_company(*company);
                     self.send_grand_company_info().await;
@@ -816,6 +895,75 @@ impl ZoneConnection {
                         ))
                         .await;
                 }
+                LuaTask::AddToBlacklist { name } => {
+                    let mut database = self.database.lock();
+                    if let Some(their_ids) = database.find_character_ids(None, Some(name.clone())) {
+                        database.add_to_blacklist(
+                            their_ids.content_id,
+                            self.player_data.character.content_id,
+                        );
+                        self.blacklist = None;
+                    } else {
+                        tracing::warn!("Can't blacklist {name}, they don't exist!");
+                    }
+                }
+                LuaTask::CreateFreeCompany { name, tag } => {
+                    let created;
+                    {
+                        let mut database = self.database.lock();
+                        created = database
+                            .create_free_company(name, tag, self.player_data.character.content_id)
+                            .is_some();
+                    }
+
+                    if created {
+                        // So everyone sees our new tag.
+                        self.respawn_player(false).await;
+                    } else {
+                        tracing::warn!("Can't create Free Company {name}, the name is taken!");
+                    }
+                }
+                LuaTask::JoinFreeCompany { name } => {
+                    let joined;
+                    {
+                        let mut database = self.database.lock();
+                        joined = database.find_free_company_id(name).is_some_and(|fc_id| {
+                            database.join_free_company(fc_id, self.player_data.character.content_id)
+                        });
+                    }
+
+                    if joined {
+                        self.respawn_player(false).await;
+                    } else {
+                        tracing::warn!("Can't join Free Company {name}, it doesn't exist!");
+                    }
+                }
+                LuaTask::SetHpMp { hp, mp } => {
+                    self.handle
+                        .send(ToServer::SetHpMp(
+                            self.id,
+                            self.player_data.character.actor_id,
+                            *hp,
+                            *mp,
+                        ))
+                        .await;
+                }
+                LuaTask::SetSearchComment { comment } => {
+                    self.set_search_comment(comment);
+                }
+                LuaTask::ToggleOnlineStatus { status } => {
+                    self.toggle_online_status(*status).await;
+                }
+                LuaTask::RemoveFromBlacklist { name } => {
+                    let mut database = self.database.lock();
+                    if let Some(their_ids) = database.find_character_ids(None, Some(name.clone())) {
+                        database.remove_from_blacklist(
+                            their_ids.content_id,
+                            self.player_data.character.content_id,
+                        );
+                        self.blacklist = None;
+                    }
+                }
             }
         }
         player.queued_tasks.clear();
@@ -832,8 +980,10 @@ impl ZoneConnection {
         false
     }
 
-    /// Reloads Global.lua
+    /// Reloads Global.lua, and picks up any changes to the world config.
     pub async fn reload_scripts(&mut self) {
+        self.reload_config();
+
         {
             let mut lua = self.lua.lock();
             if let Err(err) = lua.init(self.gamedata.clone()) {
@@ -149,11 +149,17 @@ impl ZoneConnection {
                 // Here we deliberately ignore the item id provided by the client as much as possible in case they're tampering with things. We also assume they mean the entire stack if they send a bogus amount (say, 65535 when they only possess 1 or 100...).
                 if item.1.item_id != 0 && item.1.item_quantity > 0 {
                     if let Some(currency_kind) = CurrencyKind::from_repr(item.1.item_id) {
-                        let currency = self
+                        let Some(currency) = self
                             .player_data
                             .inventory
                             .currency
-                            .get_item_for_id(currency_kind);
+                            .get_item_for_id(currency_kind)
+                        else {
+                            tracing::warn!(
+                                "Client attempted to mail a currency we don't store: {currency_kind}"
+                            );
+                            return;
+                        };
                         item.0.item_id = currency.item_id;
                         item.0.quantity = if item.1.item_quantity >= currency.quantity {
                             currency.quantity
@@ -317,12 +323,13 @@ impl ZoneConnection {
             // TODO: Should we enforce gil being in the last attachment slot only? It should never appear anywhere else, but this system should be able to handle it..
             // NOTE: We don't do saturated adds here because we don't want to put gil or crystals into the void. If the player's inventory is "full" according to stack_size, we should not accept the attachment!
             if let Some(currency_kind) = CurrencyKind::from_repr(item.item_id) {
-                let slot = self
+                if let Some(slot) = self
                     .player_data
                     .inventory
                     .currency
-                    .get_item_for_id(currency_kind);
-                if slot.quantity + item.quantity <= item.stack_size {
+                    .get_item_for_id(currency_kind)
+                    && slot.quantity + item.quantity <= item.stack_size
+                {
                     slot.quantity += item.quantity;
                     item_taken = true;
                 }
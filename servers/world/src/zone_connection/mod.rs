@@ -1,11 +1,13 @@
 use std::{
+    collections::HashSet,
     sync::Arc,
-    time::{Instant, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
 
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use tokio::net::TcpStream;
+use strum_macros::FromRepr;
+use tokio::{io::AsyncWriteExt, net::TcpStream};
 
 use crate::{
     Content, GameData, Recipe, Unlock,
@@ -17,7 +19,7 @@ use crate::{
 };
 use kawari::{
     common::{HandlerId, ObjectId, Position, timestamp_secs},
-    config::WorldConfig,
+    config::{WorldConfig, get_config},
     ipc::zone::{
         ApartmentList, ApartmentListEntry, CWLSMemberListEntry, ClientTriggerCommand,
         ClientZoneIpcSegment, Condition, Conditions, ContentRegistrationFlags,
@@ -27,8 +29,8 @@ use kawari::{
     opcodes::ServerZoneIpcType,
     packet::{
         CompressionType, ConnectionState, ConnectionType, IpcSegmentHeader, PacketSegment,
-        SegmentData, SegmentType, ServerIpcSegmentHeader, parse_packet, send_keep_alive,
-        send_packet,
+        ReplayOptions, SegmentData, SegmentType, ServerIpcSegmentHeader, parse_packet,
+        parse_packet_header, read_packet_capture, replay_schedule, send_keep_alive, send_packet,
     },
 };
 
@@ -40,6 +42,8 @@ use super::{
 
 mod actor;
 mod chat;
+mod content_queue;
+pub use content_queue::ContentQueue;
 mod effect;
 mod event;
 mod friends;
@@ -47,6 +51,8 @@ mod item;
 mod linkshell;
 mod lua;
 mod mail;
+mod move_throttle;
+pub use move_throttle::{ActorMovement, MoveThrottle};
 mod party;
 mod quest;
 mod social;
@@ -56,9 +62,43 @@ pub use stats::BaseParameters;
 mod unlock;
 mod zone;
 
+/// The most segments we put in a single packet when batching them.
+const MAX_SEGMENTS_PER_PACKET: usize = 32;
+
+/// A packet capture being replayed to the client. It's driven by the connection loop, so the client is still handled in the meantime.
+#[derive(Debug, Clone)]
+pub struct PacketReplay {
+    /// The raw packets to send, and when to send them relative to the start of the replay.
+    schedule: Vec<(Duration, Vec<u8>)>,
+    /// When the current run through the schedule started.
+    started: Instant,
+    /// Index of the next packet in the schedule.
+    next: usize,
+    /// Whether to start over again once the last packet is sent.
+    loop_forever: bool,
+}
+
+impl PacketReplay {
+    /// When the next packet is due to be sent.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.schedule
+            .get(self.next)
+            .map(|(delay, _)| self.started + *delay)
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct TeleportQuery {
     pub aetheryte_id: u16,
+    /// Whether this teleport costs nothing, such as to the homepoint or a favorite aetheryte.
+    pub free: bool,
+}
+
+impl TeleportQuery {
+    /// How much gil this teleport costs, given the configured `teleport_cost`.
+    pub fn cost(&self, teleport_cost: u32) -> u32 {
+        if self.free { 0 } else { teleport_cost }
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -69,6 +109,26 @@ pub enum TeleportReason {
     Aetheryte,
 }
 
+/// How a dead player is brought back to life.
+#[derive(Debug, Clone, Copy, PartialEq, FromRepr)]
+#[repr(u8)]
+pub enum ReviveKind {
+    /// Returning to the home point, which costs Weakness.
+    Homepoint = 0,
+    /// Being raised where they fell.
+    Raise = 1,
+}
+
+impl ReviveKind {
+    /// How much HP the player comes back with, out of `max_hp`.
+    pub fn restored_hp(&self, max_hp: u32) -> u32 {
+        match self {
+            Self::Homepoint => max_hp,
+            Self::Raise => (max_hp / 10).max(1),
+        }
+    }
+}
+
 /// Quest information stored in the database.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PersistentQuest {
@@ -143,6 +203,8 @@ pub struct ZoneConnection {
     pub rejoining_party: bool,
     /// The player's currently active quests.
     pub login_time: Option<SystemTime>,
+    /// The point up to which this session's playtime has been written to the database.
+    pub playtime_flushed_at: Option<SystemTime>,
     pub glamour_information: Option<ClientTriggerCommand>,
 
     pub last_keep_alive: Instant,
@@ -152,8 +214,7 @@ pub struct ZoneConnection {
 
     pub obsfucation_data: ObsfucationData,
 
-    // TODO: support more than one content in the queue
-    pub queued_content: Option<u16>,
+    pub queued_content: ContentQueue,
     pub content_settings: Option<ContentRegistrationFlags>,
     pub current_instance_id: Option<u16>,
 
@@ -192,6 +253,16 @@ pub struct ZoneConnection {
     pub spawned_in: bool,
     /// The last teleport offered to this player. Only one can be kept at a time.
     pub offered_teleport: Option<TeleportQuery>,
+    /// The pop range to place the player at when they next spawn, instead of their saved position.
+    pub spawn_pop_range: Option<u32>,
+    /// Limits how often other actors' movement is sent to the client.
+    pub move_throttle: MoveThrottle,
+    /// When and where the player last moved to, used to catch impossible movement. Cleared whenever we move the player ourselves.
+    pub last_move: Option<(Instant, Position)>,
+    /// The packet capture currently being replayed to the client, if any.
+    pub replay: Option<PacketReplay>,
+    /// The content ids this player has blacklisted, loaded on the first message and cleared whenever it changes.
+    pub blacklist: Option<HashSet<i64>>,
 }
 
 impl ZoneConnection {
@@ -218,20 +289,119 @@ impl ZoneConnection {
         Box::pin(self.send_segment(segment)).await;
     }
 
+    /// Sends multiple IPC segments to the player, batched into as few packets as possible.
+    pub async fn send_ipcs_self(&mut self, ipcs: Vec<ServerZoneIpcSegment>) {
+        let segments: Vec<_> = ipcs
+            .into_iter()
+            .map(|ipc| PacketSegment {
+                source_actor: self.player_data.character.actor_id,
+                target_actor: self.player_data.character.actor_id,
+                segment_type: SegmentType::Ipc,
+                data: SegmentData::Ipc(ipc),
+            })
+            .collect();
+
+        // Ditto as above
+        Box::pin(self.send_segments(&segments)).await;
+    }
+
     pub async fn send_segment(&mut self, segment: PacketSegment<ServerZoneIpcSegment>) {
         // Ditto as above
-        Box::pin(send_packet(
-            &mut self.socket,
-            &mut self.state,
-            ConnectionType::Zone,
-            if self.config.enable_packet_compression {
-                CompressionType::Oodle
-            } else {
-                CompressionType::Uncompressed
-            },
-            &[segment],
-        ))
-        .await;
+        Box::pin(self.send_segments(&[segment])).await;
+    }
+
+    /// Sends `segments` to the player, packing as many as possible into each packet.
+    pub async fn send_segments(&mut self, segments: &[PacketSegment<ServerZoneIpcSegment>]) {
+        // The client's next position update will be wherever we warped them to, so don't compare against where they were.
+        let warps_player = segments.iter().any(|segment| {
+            let SegmentData::Ipc(ipc) = &segment.data else {
+                return false;
+            };
+            matches!(ipc.data, ServerZoneIpcData::ActorSetPos(_))
+        });
+        if warps_player {
+            self.last_move = None;
+        }
+
+        for chunk in segments.chunks(MAX_SEGMENTS_PER_PACKET) {
+            // Ditto as above
+            Box::pin(send_packet(
+                &mut self.socket,
+                &mut self.state,
+                ConnectionType::Zone,
+                self.config.packet_compression_type(),
+                chunk,
+            ))
+            .await;
+        }
+    }
+
+    /// Starts replaying the packets we sent in a capture file back to our client, replacing any replay that's already running.
+    pub async fn replay_packets(&mut self, path: &str, options: ReplayOptions) {
+        let Ok(data) = std::fs::read(path) else {
+            self.send_notice(&format!("Failed to read packet capture {path}!"))
+                .await;
+            return;
+        };
+
+        let packets = read_packet_capture(&data);
+
+        // Oodle is stateful, so those packets can't be replayed into a different session.
+        let schedule: Vec<_> = replay_schedule(&packets, &options)
+            .into_iter()
+            .filter(|(_, packet)| {
+                packet.connection_type == ConnectionType::Zone
+                    && parse_packet_header(&packet.data).compression_type != CompressionType::Oodle
+            })
+            .map(|(delay, packet)| (delay, packet.data.clone()))
+            .collect();
+
+        if schedule.is_empty() {
+            self.replay = None;
+            return;
+        }
+
+        self.replay = Some(PacketReplay {
+            schedule,
+            started: Instant::now(),
+            next: 0,
+            loop_forever: options.loop_forever,
+        });
+    }
+
+    /// Stops the current replay, returning false if there wasn't one.
+    pub fn stop_replay(&mut self) -> bool {
+        self.replay.take().is_some()
+    }
+
+    /// Sends every packet of the current replay that's due by now.
+    pub async fn send_due_replay_packets(&mut self) {
+        let Some(mut replay) = self.replay.take() else {
+            return;
+        };
+
+        let now = Instant::now();
+        while let Some(deadline) = replay.next_deadline()
+            && deadline <= now
+        {
+            if let Err(err) = self.socket.write_all(&replay.schedule[replay.next].1).await {
+                tracing::warn!("Stopping replay, failed to send packet: {err}");
+                return;
+            }
+
+            replay.next += 1;
+        }
+
+        if replay.next >= replay.schedule.len() {
+            if !replay.loop_forever {
+                return;
+            }
+
+            replay.started = now;
+            replay.next = 0;
+        }
+
+        self.replay = Some(replay);
     }
 
     pub async fn initialize(&mut self, actor_id: ObjectId) {
@@ -275,26 +445,7 @@ impl ZoneConnection {
             self.player_data.volatile.rotation = self.old_rotation as f64;
         }
 
-        // Update playtime
-        {
-            // By default, just write back the original playtime if something goes wrong.
-            let mut database = self.database.lock();
-            let mut time_played_minutes =
-                database.find_playtime(self.player_data.character.content_id as u64);
-            if let Some(login_time) = self.login_time {
-                match SystemTime::now().duration_since(login_time) {
-                    Ok(session_length) => {
-                        time_played_minutes += (session_length.as_secs() / 60) as i64;
-                    }
-                    Err(e) => {
-                        tracing::error!(
-                            "Unable to update the session's playtime, due to the following error: {e}",
-                        );
-                    }
-                }
-            }
-            self.player_data.character.time_played_minutes = time_played_minutes;
-        }
+        self.flush_playtime();
 
         // Write the player back to the database
         {
@@ -326,6 +477,15 @@ impl ZoneConnection {
         self.send_ipc_self(ipc).await;
     }
 
+    /// Whether the client hasn't sent anything for longer than the configured `keep_alive_timeout`.
+    pub fn keep_alive_expired(&self, now: Instant) -> bool {
+        keep_alive_expired(
+            self.last_keep_alive,
+            now,
+            Duration::from_secs(self.config.keep_alive_timeout),
+        )
+    }
+
     pub async fn send_keep_alive(&mut self, id: u32, timestamp: u32) {
         send_keep_alive::<ServerZoneIpcSegment>(
             &mut self.socket,
@@ -338,7 +498,9 @@ impl ZoneConnection {
     }
 
     pub async fn register_for_content(&mut self, content_ids: [u16; 5]) {
-        self.queued_content = Some(content_ids[0]);
+        for content_id in content_ids.iter().filter(|id| **id != 0) {
+            self.queued_content.push(*content_id);
+        }
 
         // update
         {
@@ -366,6 +528,26 @@ impl ZoneConnection {
         }
     }
 
+    /// Writes the playtime accumulated since the last flush to the database.
+    /// This is done periodically, so a crash or ungraceful disconnect loses at most a few minutes.
+    pub fn flush_playtime(&mut self) {
+        let Some(flushed_at) = self.playtime_flushed_at else {
+            return;
+        };
+
+        let content_id = self.player_data.character.content_id as u64;
+        let mut database = self.database.lock();
+        let (time_played_minutes, flushed_at) = accumulate_playtime(
+            database.find_playtime(content_id),
+            flushed_at,
+            SystemTime::now(),
+        );
+        database.commit_playtime(content_id, time_played_minutes);
+
+        self.player_data.character.time_played_minutes = time_played_minutes;
+        self.playtime_flushed_at = Some(flushed_at);
+    }
+
     pub async fn send_playtime(&mut self) {
         if let Some(login_time) = self.login_time {
             let time_played_minutes;
@@ -375,12 +557,15 @@ impl ZoneConnection {
                     database.find_playtime(self.player_data.character.content_id as u64);
             }
 
-            // In case something goes wrong with calculating the current session's playtime, we'll send the old total by default.
-            let mut total_play_time = time_played_minutes;
+            // Include whatever hasn't been flushed to the database yet.
+            let (total_play_time, _) = accumulate_playtime(
+                time_played_minutes,
+                self.playtime_flushed_at.unwrap_or(login_time),
+                SystemTime::now(),
+            );
+
             match SystemTime::now().duration_since(login_time) {
                 Ok(session_length) => {
-                    total_play_time = (session_length.as_secs() / 60) as i64 + time_played_minutes;
-
                     // Retail doesn't do this, but it's a nice QoL thing to have.
                     self.send_notice(
                         &format!(
@@ -474,4 +659,204 @@ impl ZoneConnection {
             db.commit_grand_companies(&self.player_data);
         }
     }
+
+    /// Overrides the player's GM rank with the one from `gm_accounts`, if it's being used.
+    pub fn apply_configured_gm_rank(&mut self) {
+        let service_account_id = self.player_data.character.service_account_id as u64;
+        if let Some(gm_rank) = self.config.gm_rank_for_account(service_account_id) {
+            self.player_data.character.gm_rank = gm_rank;
+        }
+    }
+
+    /// Re-reads the world config, and applies whatever can be changed without restarting.
+    /// Returns the names of the fields that changed, and those that were left alone because they need a restart.
+    pub fn reload_config(&mut self) -> (Vec<&'static str>, Vec<&'static str>) {
+        let (applied, requires_restart) = apply_world_config(&mut self.config, get_config().world);
+
+        if !applied.is_empty() {
+            tracing::info!("Reloaded world config, changed: {}", applied.join(", "));
+        }
+        if !requires_restart.is_empty() {
+            tracing::warn!(
+                "These world config changes require a restart to take effect: {}",
+                requires_restart.join(", ")
+            );
+        }
+
+        (applied, requires_restart)
+    }
+}
+
+/// Replaces `config` with `new`, except for fields that can't change while the server is running.
+/// Returns the names of the fields that were changed, and those that were kept because they need a restart.
+fn apply_world_config(
+    config: &mut WorldConfig,
+    mut new: WorldConfig,
+) -> (Vec<&'static str>, Vec<&'static str>) {
+    let mut applied = Vec::new();
+    let mut requires_restart = Vec::new();
+
+    macro_rules! compare {
+        ($list:ident, $($field:ident),*) => {
+            $(
+                if config.$field != new.$field {
+                    $list.push(stringify!($field));
+                }
+            )*
+        };
+    }
+
+    // These are only read when the server starts or when a client first connects.
+    compare!(
+        requires_restart,
+        port,
+        healthcheck_port,
+        listen_address,
+        server_name,
+        world_id,
+        enable_packet_obsfucation,
+        packet_capture_path,
+        preload_zones,
+        language
+    );
+    compare!(
+        applied,
+        enable_packet_compression,
+        packet_compression_mode,
+        login_message,
+        generate_navmesh,
+        active_festivals,
+        accept_new_characters,
+        exp_bonus,
+        enemy_respawn_time,
+        say_radius,
+        yell_radius,
+        shout_radius,
+        teleport_cost,
+        exp_multiplier,
+        starting_items,
+        starting_gil,
+        starting_position,
+        keep_alive_timeout,
+        movement_updates_per_second,
+        max_spawn_distance,
+        max_movement_speed,
+        receive_buffer_size,
+        custom_ipc_token,
+        verbose_scripting,
+        gm_accounts
+    );
+
+    new.port = config.port;
+    new.healthcheck_port = config.healthcheck_port;
+    new.listen_address = std::mem::take(&mut config.listen_address);
+    new.server_name = std::mem::take(&mut config.server_name);
+    new.world_id = config.world_id;
+    new.enable_packet_obsfucation = config.enable_packet_obsfucation;
+    new.packet_capture_path = std::mem::take(&mut config.packet_capture_path);
+    new.preload_zones = std::mem::take(&mut config.preload_zones);
+    new.language = std::mem::take(&mut config.language);
+    *config = new;
+
+    (applied, requires_restart)
+}
+
+/// Whether more than `timeout` has passed between `last_keep_alive` and `now`.
+fn keep_alive_expired(last_keep_alive: Instant, now: Instant, timeout: Duration) -> bool {
+    now.saturating_duration_since(last_keep_alive) > timeout
+}
+
+/// Adds the whole minutes between `flushed_at` and `now` to `time_played_minutes`.
+/// Returns the new total, and the point in time it now accounts for so leftover seconds carry over to the next flush.
+fn accumulate_playtime(
+    time_played_minutes: i64,
+    flushed_at: SystemTime,
+    now: SystemTime,
+) -> (i64, SystemTime) {
+    let elapsed_minutes = now
+        .duration_since(flushed_at)
+        .map(|elapsed| elapsed.as_secs() / 60)
+        .unwrap_or_default();
+
+    (
+        time_played_minutes + elapsed_minutes as i64,
+        flushed_at + Duration::from_secs(elapsed_minutes * 60),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playtime_across_sessions() {
+        let login = SystemTime::UNIX_EPOCH;
+
+        // First session, flushed once mid-way: 90 seconds only counts one minute, but the rest carries over.
+        let (total, flushed_at) = accumulate_playtime(0, login, login + Duration::from_secs(90));
+        assert_eq!(total, 1);
+        let (total, _) = accumulate_playtime(total, flushed_at, login + Duration::from_secs(150));
+        assert_eq!(total, 2);
+
+        // Second session picks up from the committed total.
+        let login = login + Duration::from_secs(3600);
+        let (total, _) = accumulate_playtime(total, login, login + Duration::from_secs(600));
+        assert_eq!(total, 12);
+
+        // Clock going backwards shouldn't lose the existing total.
+        let (total, _) = accumulate_playtime(total, login, login - Duration::from_secs(60));
+        assert_eq!(total, 12);
+    }
+
+    #[test]
+    fn test_reload_config() {
+        let mut config = WorldConfig::default();
+        assert_eq!(config.packet_compression_type(), CompressionType::Oodle);
+
+        let new = WorldConfig {
+            enable_packet_compression: false,
+            port: 1234,
+            language: "fr".to_string(),
+            ..Default::default()
+        };
+        let (applied, requires_restart) = apply_world_config(&mut config, new);
+
+        assert_eq!(applied, vec!["enable_packet_compression"]);
+        assert_eq!(
+            config.packet_compression_type(),
+            CompressionType::Uncompressed
+        );
+
+        // The port and language aren't changed until a restart
+        assert_eq!(requires_restart, vec!["port", "language"]);
+        assert_eq!(config.port, WorldConfig::default().port);
+        assert_eq!(config.language, WorldConfig::default().language);
+    }
+
+    #[test]
+    fn test_keep_alive_expired() {
+        let timeout = Duration::from_secs(60);
+        let last_keep_alive = Instant::now();
+
+        // Fresh
+        assert!(!keep_alive_expired(
+            last_keep_alive,
+            last_keep_alive + Duration::from_secs(30),
+            timeout
+        ));
+
+        // Stale
+        assert!(keep_alive_expired(
+            last_keep_alive,
+            last_keep_alive + Duration::from_secs(61),
+            timeout
+        ));
+
+        // A keep-alive received after we last checked
+        assert!(!keep_alive_expired(
+            last_keep_alive + Duration::from_secs(5),
+            last_keep_alive,
+            timeout
+        ));
+    }
 }
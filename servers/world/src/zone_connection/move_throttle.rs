@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use kawari::common::{JumpState, MoveAnimationState, MoveAnimationType, ObjectId, Position};
+
+/// Moving less than this (in yalms) isn't worth telling the client about.
+const POSITION_EPSILON: f32 = 0.01;
+/// Turning less than this (in radians) isn't worth telling the client about.
+const ROTATION_EPSILON: f32 = 0.01;
+/// Moving further than this (in yalms) at once is treated as a teleport, and is always sent.
+const TELEPORT_DISTANCE: f32 = 10.0;
+
+/// Everything about an actor's movement that's sent to the client.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ActorMovement {
+    pub position: Position,
+    pub rotation: f32,
+    pub anim_type: MoveAnimationType,
+    pub anim_state: MoveAnimationState,
+    pub jump_state: JumpState,
+}
+
+impl ActorMovement {
+    fn same_animation(&self, other: &ActorMovement) -> bool {
+        self.anim_type == other.anim_type
+            && self.anim_state == other.anim_state
+            && self.jump_state == other.jump_state
+    }
+}
+
+/// Limits how often actor movement is sent to the client, so lots of moving actors don't flood the connection.
+#[derive(Debug, Default, Clone)]
+pub struct MoveThrottle {
+    /// When we last sent movement for each actor, and what it was.
+    last_sent: HashMap<ObjectId, (Instant, ActorMovement)>,
+    /// The latest movement we held back for each actor, which is sent once their window is over.
+    pending: HashMap<ObjectId, ActorMovement>,
+}
+
+impl MoveThrottle {
+    /// Returns true if this movement should be sent, given at most `max_per_second` updates per actor.
+    /// If `max_per_second` is zero, every movement that changes something is sent. Animation and jump changes are always sent.
+    pub fn should_send(
+        &mut self,
+        actor_id: ObjectId,
+        movement: ActorMovement,
+        now: Instant,
+        max_per_second: u32,
+    ) -> bool {
+        if let Some((sent_at, sent)) = self.last_sent.get(&actor_id)
+            && sent.same_animation(&movement)
+        {
+            let distance = sent.position.0.distance(movement.position.0);
+            let turned = (sent.rotation - movement.rotation).abs();
+
+            if distance < POSITION_EPSILON && turned < ROTATION_EPSILON {
+                // The client already has them here, so anything held back is stale.
+                self.pending.remove(&actor_id);
+                return false;
+            }
+
+            // Teleports always get through, otherwise the actor would appear to rubber-band.
+            if distance < TELEPORT_DISTANCE && max_per_second > 0 {
+                let interval = Duration::from_secs(1) / max_per_second;
+                if now.saturating_duration_since(*sent_at) < interval {
+                    self.pending.insert(actor_id, movement);
+                    return false;
+                }
+            }
+        }
+
+        self.pending.remove(&actor_id);
+        self.last_sent.insert(actor_id, (now, movement));
+        true
+    }
+
+    /// Returns the held back movement of any actor whose window is over, and marks it as sent.
+    pub fn take_due(
+        &mut self,
+        now: Instant,
+        max_per_second: u32,
+    ) -> Vec<(ObjectId, ActorMovement)> {
+        let interval = Duration::from_secs(1)
+            .checked_div(max_per_second)
+            .unwrap_or_default();
+
+        let due: Vec<ObjectId> = self
+            .pending
+            .keys()
+            .filter(|actor_id| {
+                self.last_sent
+                    .get(actor_id)
+                    .is_none_or(|(sent_at, _)| now.saturating_duration_since(*sent_at) >= interval)
+            })
+            .copied()
+            .collect();
+
+        due.into_iter()
+            .filter_map(|actor_id| {
+                let movement = self.pending.remove(&actor_id)?;
+                self.last_sent.insert(actor_id, (now, movement));
+                Some((actor_id, movement))
+            })
+            .collect()
+    }
+
+    /// Forgets about this actor, such as when they despawn.
+    pub fn forget(&mut self, actor_id: ObjectId) {
+        self.last_sent.remove(&actor_id);
+        self.pending.remove(&actor_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    fn at(x: f32) -> ActorMovement {
+        ActorMovement {
+            position: Position(Vec3::new(x, 0.0, 0.0)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_throttle_tiny_moves() {
+        let mut throttle = MoveThrottle::default();
+        let start = Instant::now();
+
+        // 100 small steps spread out over a single second
+        let mut sent = 0;
+        for i in 0..100 {
+            let now = start + Duration::from_millis(i * 10);
+            if throttle.should_send(ObjectId(1), at(i as f32 * 0.05), now, 10) {
+                sent += 1;
+            }
+        }
+
+        assert_eq!(sent, 10);
+    }
+
+    #[test]
+    fn test_throttle_unchanged_and_teleport() {
+        let mut throttle = MoveThrottle::default();
+        let now = Instant::now();
+        let later = now + Duration::from_secs(5);
+
+        assert!(throttle.should_send(ObjectId(1), at(0.0), now, 10));
+
+        // Standing still isn't sent, no matter how long it's been.
+        assert!(!throttle.should_send(ObjectId(1), at(0.0), later, 10));
+
+        // Teleporting is sent right away.
+        assert!(throttle.should_send(ObjectId(1), at(100.0), now, 10));
+
+        // Other actors have their own limit.
+        assert!(throttle.should_send(ObjectId(2), at(0.0), now, 10));
+    }
+
+    #[test]
+    fn test_throttle_flushes_last_position() {
+        let mut throttle = MoveThrottle::default();
+        let now = Instant::now();
+
+        assert!(throttle.should_send(ObjectId(1), at(0.0), now, 10));
+        assert!(!throttle.should_send(ObjectId(1), at(1.0), now, 10));
+        assert!(!throttle.should_send(ObjectId(1), at(2.0), now, 10));
+
+        // Nothing is due until the window is over.
+        assert!(throttle.take_due(now, 10).is_empty());
+
+        // Then only where they stopped is sent, and only once.
+        let later = now + Duration::from_millis(100);
+        assert_eq!(throttle.take_due(later, 10), vec![(ObjectId(1), at(2.0))]);
+        assert!(throttle.take_due(later, 10).is_empty());
+    }
+
+    #[test]
+    fn test_throttle_animation_changes() {
+        let mut throttle = MoveThrottle::default();
+        let now = Instant::now();
+
+        assert!(throttle.should_send(ObjectId(1), at(0.0), now, 10));
+
+        // Jumping in place is sent right away.
+        let jumping = ActorMovement {
+            anim_type: MoveAnimationType::JUMPING,
+            jump_state: JumpState::Ascending,
+            ..at(0.0)
+        };
+        assert!(throttle.should_send(ObjectId(1), jumping, now, 10));
+    }
+}
@@ -1,6 +1,6 @@
 //! Quests!
 
-use crate::{ZoneConnection, inventory::Storage, zone_connection::PersistentQuest};
+use crate::{ZoneConnection, zone_connection::PersistentQuest};
 use kawari::{
     common::adjust_quest_id,
     constants::{COMPLETED_LEVEQUEST_BITMASK_SIZE, COMPLETED_QUEST_BITMASK_SIZE},
@@ -124,7 +124,7 @@ impl ZoneConnection {
 
         // Add gil
         // TODO: send log message
-        self.player_data.inventory.currency.get_slot_mut(0).quantity += rewards.1;
+        self.player_data.inventory.currency.add_gil(rewards.1);
         self.send_inventory().await;
 
         // Add exp
@@ -1,14 +1,31 @@
 //! Other social features, as well as invite sending and replies.
 use crate::{ToServer, ZoneConnection};
 use kawari::{
-    common::{LogMessageType, timestamp_secs},
+    common::{LogMessageType, ObjectId, timestamp_secs},
     ipc::zone::{
         InviteReply, InviteType, InviteUpdateType, OnlineStatus, OnlineStatusMask, PlayerEntry,
         SearchUIClassJobMask, SearchUIGrandCompanies, ServerZoneIpcData, ServerZoneIpcSegment,
-        SocialList, SocialListRequestType, SocialListUILanguages,
+        SocialList, SocialListRequestType, SocialListUILanguages, truncate_search_comment,
     },
 };
 
+/// Statuses players toggle for themselves, e.g. with `!afk`. Unlike being a New Adventurer, these don't carry over between logins.
+const TOGGLEABLE_ONLINE_STATUSES: [OnlineStatus; 4] = [
+    OnlineStatus::AwayFromKeyboard,
+    OnlineStatus::Busy,
+    OnlineStatus::LookingForParty,
+    OnlineStatus::RolePlaying,
+];
+
+/// Turns `status` on, or back to plain Online if `current` already is that status.
+fn toggle_status(current: OnlineStatus, status: OnlineStatus) -> OnlineStatus {
+    if current == status {
+        OnlineStatus::Online
+    } else {
+        status
+    }
+}
+
 pub fn fetch_entries<T>(
     next_index: &mut u16,
     data: &mut Vec<T>,
@@ -38,6 +55,16 @@ where
 }
 
 impl ZoneConnection {
+    /// Asks the server for another player's examine information. The server replies directly to us if they're nearby.
+    pub async fn examine_player(&mut self, target: ObjectId) {
+        self.handle
+            .send(ToServer::ExaminePlayer(
+                self.player_data.character.actor_id,
+                target,
+            ))
+            .await;
+    }
+
     pub async fn send_invite_update(
         &mut self,
         from_account_id: u64,
@@ -374,6 +401,35 @@ impl ZoneConnection {
         OnlineStatus::Offline
     }
 
+    /// Toggles the player's chosen `status`, and lets everyone know.
+    pub async fn toggle_online_status(&mut self, status: OnlineStatus) {
+        self.player_data.search_info.online_status =
+            toggle_status(self.player_data.search_info.online_status, status);
+        {
+            let mut database = self.database.lock();
+            database.commit_search_info(&self.player_data);
+        }
+        self.update_online_status().await;
+    }
+
+    /// Changes the player's search comment, cutting it down if it's too long.
+    pub fn set_search_comment(&mut self, comment: &str) {
+        self.player_data.search_info.comment = truncate_search_comment(comment);
+
+        let mut database = self.database.lock();
+        database.commit_search_info(&self.player_data);
+    }
+
+    /// Clears any status from `TOGGLEABLE_ONLINE_STATUSES` left over from the last time the player was online.
+    pub fn reset_toggled_online_status(&mut self) {
+        if TOGGLEABLE_ONLINE_STATUSES.contains(&self.player_data.search_info.online_status) {
+            self.player_data.search_info.online_status = OnlineStatus::Online;
+
+            let mut database = self.database.lock();
+            database.commit_search_info(&self.player_data);
+        }
+    }
+
     /// Updates the online status not just on yourself but also informing other players.
     pub async fn update_online_status(&mut self) {
         // TODO: re-review this now that OnlineStatusMask can be calculated independently from any ZoneConnection
@@ -546,3 +602,24 @@ impl ZoneConnection {
         self.send_ipc_self(ipc).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle_status() {
+        assert_eq!(
+            toggle_status(OnlineStatus::Online, OnlineStatus::AwayFromKeyboard),
+            OnlineStatus::AwayFromKeyboard
+        );
+        assert_eq!(
+            toggle_status(OnlineStatus::AwayFromKeyboard, OnlineStatus::Busy),
+            OnlineStatus::Busy
+        );
+        assert_eq!(
+            toggle_status(OnlineStatus::Busy, OnlineStatus::Busy),
+            OnlineStatus::Online
+        );
+    }
+}
@@ -32,7 +32,12 @@ impl<const MAX_SIZE: usize, const START_INDEX: usize>
     }
 
     /// Attempts to reserve a new spawn index, if there isn't a free space returns `None`.
+    /// If the object already has an index, that one is returned instead of taking up another.
     pub fn reserve(&mut self, object_id: ObjectId) -> Option<u8> {
+        if let Some(index) = self.index_of(object_id) {
+            return Some(index);
+        }
+
         for (i, space) in self.pool.iter_mut().enumerate() {
             if space.is_none() {
                 *space = Some(object_id);
@@ -55,6 +60,14 @@ impl<const MAX_SIZE: usize, const START_INDEX: usize>
         None
     }
 
+    /// Returns the spawn index reserved for this object, if any.
+    pub fn index_of(&self, object_id: ObjectId) -> Option<u8> {
+        self.pool
+            .iter()
+            .position(|space| *space == Some(object_id))
+            .map(|i| i as u8 + START_INDEX as u8)
+    }
+
     /// Checks if the object exists in the pool.
     pub fn contains(&self, object_id: ObjectId) -> bool {
         self.pool.contains(&Some(object_id))
@@ -91,6 +104,40 @@ mod tests {
         assert_eq!(allocator.reserve(ObjectId(2)), None); // Reserving a 3rd object should fail still
     }
 
+    #[test]
+    fn test_reuse_after_despawn() {
+        let mut allocator = SpawnAllocator::<3, 1>::new();
+        assert_eq!(allocator.reserve(ObjectId(0)), Some(1));
+        assert_eq!(allocator.reserve(ObjectId(1)), Some(2));
+        assert_eq!(allocator.reserve(ObjectId(2)), Some(3));
+
+        // An actor walking out of range gives back its index to the next one walking in
+        assert_eq!(allocator.free(ObjectId(1)), Some(2));
+        assert_eq!(allocator.reserve(ObjectId(3)), Some(2));
+
+        // And walking back in again isn't possible until another spot opens up
+        assert_eq!(allocator.reserve(ObjectId(1)), None);
+        assert_eq!(allocator.free(ObjectId(0)), Some(1));
+        assert_eq!(allocator.reserve(ObjectId(1)), Some(1));
+    }
+
+    #[test]
+    fn test_no_double_allocation() {
+        let mut allocator = SpawnAllocator::<3, 1>::new();
+        assert_eq!(allocator.reserve(ObjectId(0)), Some(1));
+
+        // Reserving again keeps the same index, and doesn't use up another slot
+        assert_eq!(allocator.reserve(ObjectId(0)), Some(1));
+        assert_eq!(allocator.reserve(ObjectId(1)), Some(2));
+        assert_eq!(allocator.reserve(ObjectId(2)), Some(3));
+        assert_eq!(allocator.index_of(ObjectId(2)), Some(3));
+
+        // Freeing twice only frees once
+        assert_eq!(allocator.free(ObjectId(1)), Some(2));
+        assert_eq!(allocator.free(ObjectId(1)), None);
+        assert_eq!(allocator.index_of(ObjectId(1)), None);
+    }
+
     #[test]
     fn test_starting_index_allocate() {
         let mut allocator = SpawnAllocator::<2, 1>::new();
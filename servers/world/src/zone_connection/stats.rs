@@ -7,7 +7,7 @@ use crate::{
 };
 use icarus::ParamGrow::ParamGrowRow;
 use kawari::{
-    common::{MAXIMUM_RESTED_EXP, ObjectId},
+    common::{MAXIMUM_LEVEL, MAXIMUM_RESTED_EXP, ObjectId},
     ipc::zone::{
         ActorControlCategory, PlayerStats, ServerZoneIpcData, ServerZoneIpcSegment, UpdateClassInfo,
     },
@@ -204,44 +204,13 @@ impl BaseParameters {
         self.tenacity = param_grow.BaseSpeed as u32;
         self.attack_power = *self.get_mut(4); // TODO: don't hardcode PrimaryStat
         self.skill_speed = self.tenacity;
-
-        // This is fixed and isn't modified by any items in retail, so it's safe to be set here.
-        self.mp = param_grow.MpModifier as u32;
     }
 
     // This should be called after item stat calculations.
-    pub fn calculate_potencies(
-        &mut self,
-        _level: u32,
-        param_grow: &ParamGrowRow,
-        modifiers: Option<&Modifiers>,
-    ) {
+    pub fn calculate_potencies(&mut self) {
         self.physical_damage = self.strength;
         self.attack_magic_potency = self.intelligence;
         self.healing_magic_potency = self.mind;
-
-        // To calculate HP, we use a formula loosely inspired by Akh Morning and take some liberties to keep it fairly simple, at least for now.
-        // TODO: This formula isn't the greatest for 1-50, as near the end of that range it's fairly low compared to retail. For level 80+ though it's pretty close.
-        // In clearer terms without all the casts: (100 + hp_modifier + (total_vit - base_vit) * (level_mod / 100)) * job_hp_modifier
-        let classjob_hp_mod;
-        let classjob_vit_mod;
-
-        if let Some(modifiers) = modifiers {
-            classjob_hp_mod = modifiers.hp as f32 / 100.0;
-            classjob_vit_mod = modifiers.vitality as f32 / 100.0;
-        } else {
-            classjob_hp_mod = 1.0;
-            classjob_vit_mod = 1.0;
-        };
-
-        let hp_mod = param_grow.HpModifier as f32;
-        let base_vit = (param_grow.BaseSpeed as f32) * classjob_vit_mod; // TODO: Tribe adjustments, if we care about such a minimal change?
-        let lv_mod = param_grow.LevelModifier as f32;
-
-        self.hp = (100.0
-            + hp_mod
-            + ((self.vitality as f32 - base_vit) * (lv_mod / 100.0)) * classjob_hp_mod)
-            .round() as u32;
     }
 
     /// Calculates amount of physical damage to apply based on potency.
@@ -311,12 +280,22 @@ impl ZoneConnection {
         {
             let game_data = self.gamedata.lock();
 
+            let (Some(current_level), Some(current_exp)) =
+                (self.current_level(&game_data), self.current_exp(&game_data))
+            else {
+                tracing::warn!(
+                    "Classjob {} doesn't have a level, not sending class info!",
+                    self.player_data.classjob.current_class
+                );
+                return;
+            };
+
             ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::UpdateClassInfo(UpdateClassInfo {
                 class_id: self.player_data.classjob.current_class as u8,
-                class_level: self.current_level(&game_data),
-                current_level: self.current_level(&game_data),
+                class_level: current_level,
+                current_level,
                 synced_level: self.synced_level.unwrap_or_default() as u16,
-                current_exp: self.current_exp(&game_data),
+                current_exp,
                 ..Default::default()
             }));
         }
@@ -343,17 +322,25 @@ impl ZoneConnection {
         })
         .await;
 
-        let ipc;
+        let current_level;
         {
             let game_data = self.gamedata.lock();
+            current_level = self.current_level(&game_data);
+        }
 
-            ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::UnkClassRelated {
+        if let Some(current_level) = current_level {
+            let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::UnkClassRelated {
                 classjob_id: self.player_data.classjob.current_class as u8,
-                class_level: self.current_level(&game_data),
-                current_level: self.current_level(&game_data),
+                class_level: current_level,
+                current_level,
             });
+            self.send_ipc_self(ipc).await;
+        } else {
+            tracing::warn!(
+                "Classjob {} doesn't have a level, not sending class info!",
+                self.player_data.classjob.current_class
+            );
         }
-        self.send_ipc_self(ipc).await;
 
         // Commit back our classjob data after changing. Done here so it gets picked up by any paths that change classjob.
         {
@@ -376,7 +363,8 @@ impl ZoneConnection {
         let level = self
             .synced_level
             .map(|x| x as u16)
-            .unwrap_or(self.current_level(&game_data));
+            .or(self.current_level(&game_data))
+            .unwrap_or(1); // ParamGrow starts at level 1, so fall back to that for invalid classjobs.
 
         let item_level_sync;
         {
@@ -413,7 +401,13 @@ impl ZoneConnection {
                 None
             },
         );
-        base_parameters.calculate_potencies(level as u32, &param_grow, Some(&modifiers));
+        base_parameters.calculate_potencies();
+
+        // HP depends on the vitality from our gear, but MP is fixed and isn't modified by any items in retail.
+        base_parameters.hp = game_data
+            .calculate_max_hp(level as u32, Some(&modifiers), base_parameters.vitality)
+            .unwrap_or_default();
+        base_parameters.mp = game_data.calculate_max_mp(level as u32).unwrap_or_default();
 
         base_parameters
     }
@@ -470,7 +464,7 @@ impl ZoneConnection {
         let current_level;
         {
             let gamedata = self.gamedata.lock();
-            current_level = self.current_level(&gamedata);
+            current_level = self.current_level(&gamedata).unwrap_or_default();
         }
 
         let base_parameters = self.base_parameters();
@@ -485,40 +479,51 @@ impl ZoneConnection {
             .await;
     }
 
-    pub fn current_level(&self, game_data: &GameData) -> u16 {
-        let index = game_data
-            .get_exp_array_index(self.player_data.classjob.current_class as u16)
-            .expect("Failed to find EXP array index?!");
-        self.player_data.classjob.levels.0[index as usize]
+    /// Returns the level of the current classjob, or None if it doesn't have one (e.g. the ID is invalid.)
+    pub fn current_level(&self, game_data: &GameData) -> Option<u16> {
+        let index = game_data.get_exp_array_index(self.player_data.classjob.current_class as u16);
+        class_array_get(&self.player_data.classjob.levels.0, index).copied()
     }
 
-    pub fn set_current_level(&mut self, level: u16) {
-        self.set_level_for(self.player_data.classjob.current_class as u8, level);
+    /// Sets the level of the current classjob, returning the previous level. See `set_level_for`.
+    pub fn set_current_level(&mut self, level: u16) -> Option<u16> {
+        self.set_level_for(self.player_data.classjob.current_class as u8, level)
     }
 
-    pub fn set_level_for(&mut self, classjob_id: u8, level: u16) {
+    /// Sets the level of `classjob_id`, returning the previous level. If it doesn't have one, nothing is changed and None is returned.
+    pub fn set_level_for(&mut self, classjob_id: u8, level: u16) -> Option<u16> {
         let game_data = self.gamedata.lock();
 
-        let index = game_data
-            .get_exp_array_index(classjob_id as u16)
-            .expect("Failed to find EXP array index?!");
-        self.player_data.classjob.levels.0[index as usize] = level;
+        let index = game_data.get_exp_array_index(classjob_id as u16);
+        let Some(entry) = class_array_get_mut(&mut self.player_data.classjob.levels.0, index)
+        else {
+            tracing::warn!("Classjob {classjob_id} doesn't have a level, not setting it!");
+            return None;
+        };
+
+        Some(std::mem::replace(entry, level))
     }
 
-    pub fn current_exp(&self, game_data: &GameData) -> i32 {
-        let index = game_data
-            .get_exp_array_index(self.player_data.classjob.current_class as u16)
-            .expect("Failed to find EXP array index?!");
-        self.player_data.classjob.exp.0[index as usize]
+    /// Returns the EXP of the current classjob, or None if it doesn't have any (e.g. the ID is invalid.)
+    pub fn current_exp(&self, game_data: &GameData) -> Option<i32> {
+        let index = game_data.get_exp_array_index(self.player_data.classjob.current_class as u16);
+        class_array_get(&self.player_data.classjob.exp.0, index).copied()
     }
 
-    pub fn set_current_exp(&mut self, exp: i32) {
+    /// Sets the EXP of the current classjob, returning the previous amount. If it doesn't have any, nothing is changed and None is returned.
+    pub fn set_current_exp(&mut self, exp: i32) -> Option<i32> {
         let game_data = self.gamedata.lock();
 
-        let index = game_data
-            .get_exp_array_index(self.player_data.classjob.current_class as u16)
-            .expect("Failed to find EXP array index?!");
-        self.player_data.classjob.exp.0[index as usize] = exp;
+        let index = game_data.get_exp_array_index(self.player_data.classjob.current_class as u16);
+        let Some(entry) = class_array_get_mut(&mut self.player_data.classjob.exp.0, index) else {
+            tracing::warn!(
+                "Classjob {} doesn't have EXP, not setting it!",
+                self.player_data.classjob.current_class
+            );
+            return None;
+        };
+
+        Some(std::mem::replace(entry, exp))
     }
 
     pub async fn update_hp_mp(&mut self, actor_id: ObjectId, hp: u32, mp: u16) {
@@ -529,6 +534,38 @@ impl ZoneConnection {
 
     /// Adds EXP to the current classjob, handles level-up and so on.
     pub async fn add_exp(&mut self, exp: i32) {
+        let exp = scale_exp(exp, self.config.exp_multiplier);
+        if exp == 0 {
+            return;
+        }
+
+        let index;
+        {
+            let game_data = self.gamedata.lock();
+            index = game_data.get_exp_array_index(self.player_data.classjob.current_class as u16);
+        }
+
+        let Some(index) = index else {
+            tracing::warn!(
+                "Classjob {} doesn't have EXP, not adding any!",
+                self.player_data.classjob.current_class
+            );
+            return;
+        };
+
+        let index = index as usize;
+        let (Some(&current_level), Some(&current_exp)) = (
+            self.player_data.classjob.levels.0.get(index),
+            self.player_data.classjob.exp.0.get(index),
+        ) else {
+            tracing::warn!("EXP array index {index} is out of bounds, not adding any!");
+            return;
+        };
+
+        if current_level >= MAXIMUM_LEVEL {
+            return;
+        }
+
         let (bonus_percent, exp) = self.use_exp_bonus(exp);
 
         self.actor_control_self(ActorControlCategory::EXPFloatingMessage {
@@ -540,36 +577,20 @@ impl ZoneConnection {
 
         self.send_rested_exp().await; // If the EXP bonus was used, we need to update in case.
 
-        let index;
-        let mut level_up = 0;
+        let new_level;
         {
             let mut game_data = self.gamedata.lock();
 
-            index = game_data
-                .get_exp_array_index(self.player_data.classjob.current_class as u16)
-                .expect("Failed to find EXP array index?!");
-
-            self.player_data.classjob.exp.0[index as usize] += exp;
-
-            // Keep going until we have leftover EXP
-            loop {
-                let curr_exp = self.player_data.classjob.exp.0[index as usize];
-                let max_exp = game_data
-                    .get_max_exp(self.player_data.classjob.levels.0[index as usize] as u32);
-                let difference = curr_exp - max_exp;
-                if difference >= 0 {
-                    level_up += 1;
-                    self.player_data.classjob.exp.0[index as usize] = difference;
-                } else {
-                    break;
-                }
-            }
+            let new_exp;
+            (new_level, new_exp) = apply_exp(current_level, current_exp, exp, |level| {
+                game_data.get_exp_to_next_level(level)
+            });
+            self.player_data.classjob.exp.0[index] = new_exp;
         }
 
-        if level_up > 0 {
-            let curr_level = self.player_data.classjob.levels.0[index as usize];
-            let new_level = curr_level + level_up;
+        if new_level > current_level {
             self.set_current_level(new_level);
+            self.learn_actions_for_level(current_level, new_level).await;
 
             self.actor_control_self(ActorControlCategory::LevelUpMessage {
                 classjob_id: self.player_data.classjob.current_class as u32,
@@ -615,10 +636,10 @@ impl ZoneConnection {
             // We need to basically convert EXP to "seconds" - which is what rested EXP is counted in.
 
             let mut gamedata = self.gamedata.lock();
-            let current_level = self.current_level(&gamedata);
+            let current_level = self.current_level(&gamedata).unwrap_or(1);
 
             // This is the size of the bar in EXP.
-            let max_exp = gamedata.get_max_exp(current_level as u32);
+            let max_exp = gamedata.get_exp_to_next_level(current_level) as i32;
             assert!(max_exp > 0);
 
             // This is the size of the bar in seconds.
@@ -641,3 +662,113 @@ impl ZoneConnection {
         (bonus_percent, exp)
     }
 }
+
+/// Adds `exp` on top of `current_exp` at `level`, leveling up each time it crosses the threshold given by `exp_to_next`.
+/// Leveling stops at `MAXIMUM_LEVEL`, where any leftover EXP is discarded. Returns the new level and EXP.
+fn apply_exp(
+    level: u16,
+    current_exp: i32,
+    exp: i32,
+    mut exp_to_next: impl FnMut(u16) -> u32,
+) -> (u16, i32) {
+    let mut level = level;
+    let mut current_exp = current_exp.saturating_add(exp);
+
+    while level < MAXIMUM_LEVEL {
+        // A threshold of zero means there's no next level, so don't get stuck here.
+        let threshold = exp_to_next(level) as i32;
+        if threshold == 0 || current_exp < threshold {
+            break;
+        }
+
+        current_exp -= threshold;
+        level += 1;
+    }
+
+    if level >= MAXIMUM_LEVEL {
+        current_exp = 0;
+    }
+
+    (level, current_exp)
+}
+
+/// Applies the configured EXP `multiplier` to `exp`, rounding to the nearest point.
+fn scale_exp(exp: i32, multiplier: f32) -> i32 {
+    // Float to int casts saturate, so huge multipliers can't overflow here.
+    (exp as f32 * multiplier.max(0.0)).round() as i32
+}
+
+/// Gets the entry for a classjob in a per-class array (like levels or EXP) using its EXP array `index`, if it has one.
+fn class_array_get<T>(array: &[T], index: Option<i8>) -> Option<&T> {
+    array.get(usize::try_from(index?).ok()?)
+}
+
+/// Same as `class_array_get`, but mutable.
+fn class_array_get_mut<T>(array: &mut [T], index: Option<i8>) -> Option<&mut T> {
+    array.get_mut(usize::try_from(index?).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every level needs 100 more EXP than the last, starting at 300 for level 1.
+    fn sample_exp_to_next(level: u16) -> u32 {
+        200 + level as u32 * 100
+    }
+
+    #[test]
+    fn test_single_level_up() {
+        assert_eq!(apply_exp(1, 0, 250, sample_exp_to_next), (1, 250));
+        assert_eq!(apply_exp(1, 250, 100, sample_exp_to_next), (2, 50));
+    }
+
+    #[test]
+    fn test_multi_level_up() {
+        // 300 + 400 + 500 to reach level 4, with 50 left over.
+        assert_eq!(apply_exp(1, 0, 1250, sample_exp_to_next), (4, 50));
+    }
+
+    #[test]
+    fn test_level_cap() {
+        assert_eq!(
+            apply_exp(MAXIMUM_LEVEL - 1, 0, i32::MAX / 2, sample_exp_to_next),
+            (MAXIMUM_LEVEL, 0)
+        );
+
+        // Levels without a threshold shouldn't loop forever.
+        assert_eq!(apply_exp(50, 0, 1000, |_| 0), (50, 1000));
+    }
+
+    #[test]
+    fn test_exp_multiplier() {
+        assert_eq!(scale_exp(250, 1.0), 250);
+        assert_eq!(scale_exp(250, 2.0), 500);
+        assert_eq!(scale_exp(3, 1.5), 5);
+        assert_eq!(scale_exp(250, 0.0), 0);
+
+        // Absurd multipliers still stop at the level cap.
+        let exp = scale_exp(10_000, 1_000_000.0);
+        assert_eq!(exp, i32::MAX);
+        assert_eq!(
+            apply_exp(MAXIMUM_LEVEL - 1, 100, exp, sample_exp_to_next),
+            (MAXIMUM_LEVEL, 0)
+        );
+    }
+
+    #[test]
+    fn test_invalid_classjob_level() {
+        let mut levels = vec![1u16, 50, 90];
+
+        assert_eq!(class_array_get(&levels, Some(1)), Some(&50));
+
+        // Classjobs without an EXP array index, corrupt indices or ones past the end shouldn't panic.
+        assert_eq!(class_array_get(&levels, None), None);
+        assert_eq!(class_array_get(&levels, Some(-5)), None);
+        assert_eq!(class_array_get(&levels, Some(100)), None);
+        assert!(class_array_get_mut(&mut levels, Some(100)).is_none());
+
+        *class_array_get_mut(&mut levels, Some(2)).unwrap() = 100;
+        assert_eq!(levels, vec![1, 50, 100]);
+    }
+}
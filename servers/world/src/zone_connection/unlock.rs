@@ -1,7 +1,11 @@
 //! Utility functions for managing unlock data and bitmasks.
 
-use crate::ZoneConnection;
-use kawari::{common::value_to_flag_byte_index_value, ipc::zone::ActorControlCategory};
+use crate::{Bitmask, ZoneConnection};
+use kawari::{
+    common::{DirectorEvent, value_to_flag_byte_index_value},
+    constants::{MOUNT_BITMASK_SIZE, ORCHESTRION_ROLL_BITMASK_SIZE},
+    ipc::zone::ActorControlCategory,
+};
 
 impl ZoneConnection {
     pub async fn toggle_orchestrion(&mut self, orchestrion_id: u32) {
@@ -30,6 +34,108 @@ impl ZoneConnection {
         .await;
     }
 
+    /// Unlocks every orchestrion roll, informing the client of each one that wasn't already unlocked.
+    pub async fn unlock_all_orchestrion(&mut self) {
+        let max_orchestrion_id = ORCHESTRION_ROLL_BITMASK_SIZE as u32 * 8;
+        let newly_unlocked = self
+            .player_data
+            .unlock
+            .orchestrion_rolls
+            .set_many(0..max_orchestrion_id);
+
+        for orchestrion_id in newly_unlocked {
+            let item_id;
+            {
+                let mut game_data = self.gamedata.lock();
+                item_id = game_data
+                    .find_orchestrion_item_id(orchestrion_id)
+                    .unwrap_or(0);
+            }
+
+            self.actor_control_self(ActorControlCategory::ToggleOrchestrionUnlock {
+                song_id: orchestrion_id,
+                unlocked: true,
+                item_id,
+            })
+            .await;
+        }
+    }
+
+    /// Marks this content as cleared, and informs the client via our current director.
+    pub async fn complete_content(&mut self, content_id: u16) {
+        let instance_content_type;
+        {
+            let mut game_data = self.gamedata.lock();
+            instance_content_type = game_data.find_type_for_content(content_id);
+        }
+
+        let Some(instance_content_type) = instance_content_type else {
+            tracing::warn!("Unknown content {content_id}!");
+            return;
+        };
+
+        match self
+            .player_data
+            .content
+            .clear(&instance_content_type, content_id)
+        {
+            Some(true) => {
+                // TODO: grant first-clear rewards, once we know where they come from
+                tracing::info!("First clear of {instance_content_type:?} {content_id}!");
+            }
+            Some(false) => {}
+            None => {
+                tracing::warn!("Not sure what to do about {instance_content_type:?} {content_id}!");
+            }
+        }
+
+        self.actor_control_self(ActorControlCategory::DirectorEvent {
+            handler_id: self.content_handler_id,
+            event: DirectorEvent::DutyCompleted,
+            arg1: 0,
+            arg2: 0,
+        })
+        .await;
+
+        // Persist it now, in case the player disconnects before leaving the content.
+        {
+            let mut db = self.database.lock();
+            db.commit_player_data(&self.player_data);
+        }
+    }
+
+    /// Records this action as learned, and unlocks it for the client if it's gated behind an UnlockLink.
+    pub async fn unlock_action(&mut self, action_id: u32, unlock_link: u32) {
+        if !self.player_data.unlock.learned_actions.learn(action_id) || unlock_link == 0 {
+            return;
+        }
+
+        self.player_data.unlock.unlocks.set(unlock_link);
+
+        self.actor_control_self(ActorControlCategory::ToggleUnlock {
+            id: unlock_link,
+            unlocked: true,
+        })
+        .await;
+    }
+
+    /// Grants every action the current class learns after `from_level`, up until `to_level`.
+    pub async fn learn_actions_for_level(&mut self, from_level: u16, to_level: u16) {
+        let actions;
+        {
+            let mut game_data = self.gamedata.lock();
+            actions = game_data.get_actions_learned_between(
+                self.player_data.classjob.current_class as u8,
+                from_level,
+                to_level,
+            );
+        }
+
+        for (action_id, unlock_link) in actions {
+            self.unlock_action(action_id, unlock_link).await;
+        }
+    }
+
     pub async fn toggle_glasses_style(&mut self, glasses_style_id: u32) {
         let should_unlock = self
             .player_data
@@ -152,6 +258,46 @@ impl ZoneConnection {
         .await;
     }
 
+    /// Unlocks this minion, unlike `toggle_minion` it's never locked again.
+    pub async fn unlock_minion(&mut self, minion_id: u32) {
+        self.player_data.unlock.minions.set(minion_id);
+
+        self.actor_control_self(ActorControlCategory::ToggleMinionUnlock {
+            minion_id,
+            unlocked: true,
+        })
+        .await;
+    }
+
+    /// Unlocks this mount, unlike toggling it's never locked again.
+    pub async fn unlock_mount(&mut self, mount_id: u32) {
+        let order;
+        {
+            let mut game_data = self.gamedata.lock();
+            order = game_data.find_mount_order(mount_id).unwrap_or(0);
+        }
+
+        self.player_data.unlock.mounts.set(order as u32);
+
+        self.actor_control_self(ActorControlCategory::ToggleMountUnlock {
+            order: order as u32,
+            id: mount_id,
+            unlocked: true,
+        })
+        .await;
+    }
+
+    /// Whether the player has unlocked the mount with `mount_id`.
+    pub fn is_mount_unlocked(&self, mount_id: u32) -> bool {
+        let order;
+        {
+            let mut game_data = self.gamedata.lock();
+            order = game_data.find_mount_order(mount_id);
+        }
+
+        mount_unlocked(&self.player_data.unlock.mounts, order)
+    }
+
     pub async fn toggle_aether_current(&mut self, aether_current_id: u32) {
         let aether_current_set;
         {
@@ -272,3 +418,27 @@ impl ZoneConnection {
         }
     }
 }
+
+/// Whether the mount at `order` is set in `mounts`. Mounts without an order can't be unlocked.
+fn mount_unlocked(mounts: &Bitmask<MOUNT_BITMASK_SIZE>, order: Option<i16>) -> bool {
+    match order {
+        Some(order) if order >= 0 => mounts.contains(order as u32),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locked_mount() {
+        let mut mounts = Bitmask::<MOUNT_BITMASK_SIZE>::default();
+        mounts.set(1);
+
+        assert!(mount_unlocked(&mounts, Some(1)));
+        assert!(!mount_unlocked(&mounts, Some(2)));
+        assert!(!mount_unlocked(&mounts, Some(-1)));
+        assert!(!mount_unlocked(&mounts, None));
+    }
+}
@@ -2,21 +2,21 @@
 
 use crate::{
     ObsfucationData, TeleportReason, ToServer, ZoneConnection,
-    inventory::BuyBackList,
+    inventory::{BuyBackList, CurrencyStorage},
     lua::{LuaContent, LuaZone},
     zone_connection::TeleportQuery,
 };
 use kawari::{
     common::{
-        FestivalId, HandlerId, HandlerType, HouseId, HouseUnit, HousingFlag, LandData, Position,
-        timestamp_secs,
+        ContainerType, FestivalId, HandlerId, HandlerType, HouseId, HouseUnit, HousingFlag,
+        LandData, Position, timestamp_secs,
     },
     config::get_config,
     constants::OBFUSCATION_ENABLED_MODE,
     ipc::zone::{
         ActorControlCategory, Condition, ContentRegistrationFlags, FurnitureList, House,
-        HouseExterior, HouseList, HouseStatus, HousingInteriorDetails, PlotSize, ServerZoneIpcData,
-        ServerZoneIpcSegment, WarpType, WeatherChange, ZoneInit, ZoneInitFlags,
+        HouseExterior, HouseList, HouseStatus, HousingInteriorDetails, ItemInfo, PlotSize,
+        ServerZoneIpcData, ServerZoneIpcSegment, WarpType, WeatherChange, ZoneInit, ZoneInitFlags,
     },
     packet::{ConnectionState, PacketSegment, ScramblerKeyGenerator, SegmentData, SegmentType},
 };
@@ -228,19 +228,7 @@ impl ZoneConnection {
             self.send_ipc_self(ipc).await;
         }
 
-        let level;
-        {
-            let mut game_data = self.gamedata.lock();
-
-            level = self
-                .player_data
-                .inventory
-                .equipped
-                .calculate_item_level(&mut game_data) as u32;
-        }
-
-        self.actor_control_self(ActorControlCategory::SetItemLevel { level })
-            .await;
+        self.refresh_item_level().await;
 
         // send some weird thing to make the zone load correctly
         if !bound_by_duty {
@@ -435,6 +423,7 @@ impl ZoneConnection {
 
                     self.synced_level = None;
                     if let Some(synced_level) = synced_level
+                        && let Some(current_level) = current_level
                         && current_level > synced_level as u16
                     {
                         self.synced_level = Some(synced_level);
@@ -520,6 +509,17 @@ impl ZoneConnection {
         housing_aethernet: bool,
         taking_offered_teleport: bool,
     ) {
+        // Housing aethernet shards are always free.
+        if !housing_aethernet {
+            let query = TeleportQuery {
+                aetheryte_id: aetheryte_id as u16,
+                free: self.player_data.aetheryte.is_free_destination(aetheryte_id),
+            };
+            if !self.pay_for_teleport(&query).await {
+                return;
+            }
+        }
+
         self.teleport_reason = TeleportReason::Aetheryte;
         self.handle
             .send(ToServer::WarpAetheryte(
@@ -533,6 +533,7 @@ impl ZoneConnection {
         if self.party_id != 0 && !housing_aethernet && !taking_offered_teleport {
             let teleport_info = TeleportQuery {
                 aetheryte_id: aetheryte_id as u16,
+                ..Default::default()
             };
 
             self.handle
@@ -550,6 +551,31 @@ impl ZoneConnection {
         }
     }
 
+    /// Deducts the gil cost of `query` from the player. If they can't afford it, they're told so and this returns false.
+    async fn pay_for_teleport(&mut self, query: &TeleportQuery) -> bool {
+        let cost = query.cost(self.config.teleport_cost);
+        if cost == 0 {
+            return true;
+        }
+
+        if !self.player_data.inventory.currency.try_spend_gil(cost) {
+            self.send_notice(&format!("You need {cost} gil to teleport there."))
+                .await;
+            return false;
+        }
+
+        let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::UpdateInventorySlot(ItemInfo {
+            sequence: self.player_data.item_sequence,
+            container: ContainerType::Currency,
+            slot: CurrencyStorage::GIL_SLOT,
+            ..self.player_data.inventory.currency.gil.into()
+        }));
+        self.send_ipc_self(ipc).await;
+        self.player_data.item_sequence += 1;
+
+        true
+    }
+
     pub async fn change_weather(&mut self, new_weather_id: u8) {
         let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::WeatherId(WeatherChange {
             weather_id: new_weather_id,
@@ -559,6 +585,27 @@ impl ZoneConnection {
         self.send_ipc_self(ipc).await;
     }
 
+    /// Changes the weather for everyone in the current instance, until it's cleared by passing zero.
+    pub async fn force_weather(&mut self, weather_id: u8) {
+        self.handle
+            .send(ToServer::ForceWeather(
+                self.player_data.character.actor_id,
+                (weather_id != 0).then_some(weather_id as u16),
+            ))
+            .await;
+    }
+
+    /// Changes the state of `eobj_id` in the current instance for everyone, such as opening a door.
+    pub async fn set_eobj_state(&mut self, eobj_id: u32, state: u32) {
+        self.handle
+            .send(ToServer::SetEObjState(
+                self.player_data.character.actor_id,
+                eobj_id,
+                state,
+            ))
+            .await;
+    }
+
     pub async fn discover_location(&mut self, map_id: u32, map_part_id: u32) {
         let ipc = ServerZoneIpcSegment::new(ServerZoneIpcData::LocationDiscovered {
             map_id,
@@ -582,6 +629,19 @@ impl ZoneConnection {
             .await;
     }
 
+    /// Leaves the current instanced content, returning to where we were before joining it.
+    pub async fn leave_content(&mut self) {
+        self.handle
+            .send(ToServer::LeaveContent(
+                self.id,
+                self.player_data.character.actor_id,
+                self.old_zone_id,
+                self.old_position,
+                self.old_rotation,
+            ))
+            .await;
+    }
+
     /// Ensure the player is placed in a valid zone, and if they aren't they are teleported back to their homepoint.
     pub async fn ensure_valid_zone(&mut self) {
         let zone_id = self.player_data.volatile.zone_id;
@@ -594,8 +654,25 @@ impl ZoneConnection {
                 || game_data.is_zone_associated_with_content(zone_id as u16);
         }
         if should_reset {
-            // TODO: teleport them to their homepoint instead
-            self.player_data.volatile.zone_id = 132;
+            let homepoint;
+            {
+                let mut game_data = self.gamedata.lock();
+                homepoint = game_data
+                    .get_aetheryte(self.player_data.aetheryte.homepoint as u32, false)
+                    .filter(|(_, zone_id)| game_data.is_zone_valid(*zone_id))
+                    .or_else(|| {
+                        // Without a usable homepoint, fall back to New Gridania's aetheryte.
+                        let aetheryte_id = game_data.get_default_aetheryte_for_zone(132)?;
+                        game_data.get_aetheryte(aetheryte_id, false)
+                    });
+            }
+
+            if let Some((pop_range_id, zone_id)) = homepoint {
+                self.player_data.volatile.zone_id = zone_id as i32;
+                self.spawn_pop_range = Some(pop_range_id);
+            } else {
+                self.player_data.volatile.zone_id = 132;
+            }
             self.player_data.volatile.position = Position::default();
 
             self.send_notice("Moved you to a safe area to prevent a crash!")